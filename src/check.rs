@@ -0,0 +1,246 @@
+//! Implements `huml-lsp --check`, also spelled `huml-lsp check`, which lints HUML files
+//! from the command line, e.g. in CI, without going through a language server client.
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use huml_lsp::lsp::{
+    common::{
+        diagnostic::{Diagnostic, DiagnosticData, DiagnosticSeverity},
+        text_document::PositionEncodingKind,
+    },
+    settings::{PartialServerSettings, ServerSettings},
+};
+use serde::Serialize;
+
+const USAGE: &str = "Usage: huml-lsp --check [--format text|json] <file>...";
+
+/// How the diagnostics found by `--check` are printed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    /// One `path:line:column: severity: message` line per diagnostic.
+    Text,
+    /// A JSON array holding the LSP diagnostics of each file.
+    Json,
+}
+
+/// The diagnostics of the files linted by `--check`.
+#[derive(Debug)]
+pub struct CheckReport {
+    files: Vec<FileDiagnostics>,
+}
+
+#[derive(Serialize, Debug)]
+struct FileDiagnostics {
+    path: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl CheckReport {
+    /// Lints the `(path, source)` pairs of `files` with the rules enabled in `settings`.
+    pub fn new<'a>(
+        files: impl IntoIterator<Item = (&'a str, &'a str)>,
+        settings: &ServerSettings,
+    ) -> Self {
+        let files = files
+            .into_iter()
+            .map(|(path, source)| FileDiagnostics {
+                path: path.to_string(),
+                // Columns are reported in bytes, as they always were
+                diagnostics: Diagnostic::lint(source, path, settings, PositionEncodingKind::Utf8),
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Returns the number of diagnostics of error severity.
+    pub fn error_count(&self) -> usize {
+        self.files
+            .iter()
+            .flat_map(|file| &file.diagnostics)
+            .filter(|diagnostic| diagnostic.severity() == Some(DiagnosticSeverity::Error))
+            .count()
+    }
+
+    /// Returns `1` if any error was found, `0` otherwise.
+    pub fn exit_code(&self) -> u8 {
+        u8::from(self.error_count() > 0)
+    }
+
+    /// Prints the diagnostics to `output` in `format`.
+    pub fn write(&self, format: OutputFormat, output: &mut impl Write) -> io::Result<()> {
+        match format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut *output, &self.files)?;
+                writeln!(output)
+            }
+            OutputFormat::Text => {
+                for file in &self.files {
+                    for diagnostic in &file.diagnostics {
+                        let start = diagnostic.range().start();
+                        write!(
+                            output,
+                            "{}:{}:{}: {}: {}",
+                            file.path,
+                            start.line() + 1,
+                            start.character() + 1,
+                            severity_label(diagnostic.severity()),
+                            diagnostic.message()
+                        )?;
+                        match diagnostic.data_as::<DiagnosticData>() {
+                            Some(data) => writeln!(output, " [{}]", data.rule())?,
+                            None => writeln!(output)?,
+                        }
+                    }
+                }
+                writeln!(
+                    output,
+                    "{} error(s) found in {} file(s)",
+                    self.error_count(),
+                    self.files.len()
+                )
+            }
+        }
+    }
+}
+
+fn severity_label(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::Error) | None => "error",
+        Some(DiagnosticSeverity::Warning) => "warning",
+        Some(DiagnosticSeverity::Information) => "info",
+        Some(DiagnosticSeverity::Hint) => "hint",
+    }
+}
+
+/// Runs `--check` with the command line `args` following it.
+///
+/// Settings are read from the settings file of the current directory, if any. Exits
+/// with `1` if any error was found, and `2` if the files couldn't be checked.
+pub fn run(args: &[String]) -> ExitCode {
+    let mut format = OutputFormat::Text;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("text") => OutputFormat::Text,
+                    Some("json") => OutputFormat::Json,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            path => paths.push(path),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    }
+
+    let mut sources = Vec::with_capacity(paths.len());
+    for path in &paths {
+        match fs::read_to_string(path) {
+            Ok(source) => sources.push(source),
+            Err(e) => {
+                eprintln!("Failed to read {path}: {e}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let report = CheckReport::new(
+        paths
+            .iter()
+            .copied()
+            .zip(sources.iter().map(String::as_str)),
+        &load_settings(),
+    );
+    if let Err(e) = report.write(format, &mut io::stdout().lock()) {
+        eprintln!("Failed to print diagnostics: {e}");
+        return ExitCode::from(2);
+    }
+    ExitCode::from(report.exit_code())
+}
+
+/// Loads the settings file of the current directory, falling back to the defaults.
+pub fn load_settings() -> ServerSettings {
+    let settings = env::current_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| {
+            PartialServerSettings::from_workspace_folder(&dir).map_err(|e| e.to_string())
+        });
+    match settings {
+        Ok(settings) => {
+            let mut settings = ServerSettings::resolve(settings.as_ref());
+            if let Err(e) = settings.load_schema_file() {
+                eprintln!("Ignoring schema: {e}");
+            }
+            settings
+        }
+        Err(e) => {
+            eprintln!("Ignoring settings: {e}");
+            ServerSettings::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str) -> CheckReport {
+        CheckReport::new([("config.huml", source)], &ServerSettings::default())
+    }
+
+    fn output(report: &CheckReport, format: OutputFormat) -> String {
+        let mut output = vec![];
+        report.write(format, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn should_fail_for_file_with_an_error() {
+        let report = check("name: \"huml\"\nport: oops\n");
+
+        assert_eq!(report.error_count(), 1);
+        assert_eq!(report.exit_code(), 1);
+        assert_eq!(
+            output(&report, OutputFormat::Text),
+            "config.huml:2:7: error: invalid value `oops`; strings must be quoted\n\
+             1 error(s) found in 1 file(s)\n"
+        );
+    }
+
+    #[test]
+    fn should_pass_for_file_with_warnings_only() {
+        let report = check("enabled: True\n");
+
+        assert_eq!(report.error_count(), 0);
+        assert_eq!(report.exit_code(), 0);
+        assert!(
+            output(&report, OutputFormat::Text)
+                .starts_with("config.huml:1:10: warning: booleans are written `true`")
+        );
+    }
+
+    #[test]
+    fn should_print_json_diagnostics() {
+        let report = check("a:1\n");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&output(&report, OutputFormat::Json)).unwrap();
+
+        assert_eq!(json[0]["path"], "config.huml");
+        assert_eq!(
+            json[0]["diagnostics"][0]["message"],
+            "expected exactly one space after ':'"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Implements `huml-lsp format`, also spelled `huml-lsp --format`, which formats a HUML
+//! file from the command line, printing it or rewriting it in place.
+
+use std::{
+    fs,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use huml_lsp::{
+    huml::emit::{self, EmitOptions},
+    lsp::settings::ServerSettings,
+};
+use thiserror::Error;
+
+use crate::check::{self, CheckReport, OutputFormat};
+
+const USAGE: &str = "Usage: huml-lsp format [--write] <file>";
+
+/// The reasons a file can't be formatted.
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("Failed to read {path}: {source}")]
+    Read { path: String, source: io::Error },
+    #[error("Failed to write {path}: {source}")]
+    Write { path: String, source: io::Error },
+    #[error("{path} has syntax errors")]
+    Invalid { path: String, report: CheckReport },
+}
+
+/// Formats the file at `path` with the indent width of `settings`. The formatted text is
+/// printed to `output`, or written back to the file if `write` is set, in which case the
+/// file is only written if it changes.
+///
+/// Returns `true` if the file wasn't formatted already. Fails without writing anything if
+/// the file has syntax errors.
+pub fn format_file(
+    path: &str,
+    write: bool,
+    settings: &ServerSettings,
+    output: &mut impl Write,
+) -> Result<bool, FormatError> {
+    let source = fs::read_to_string(path).map_err(|source| FormatError::Read {
+        path: path.to_string(),
+        source,
+    })?;
+    let options = EmitOptions {
+        indent_width: settings.indent_width(),
+        ..EmitOptions::default()
+    };
+    let Some(formatted) = emit::format(&source, &options) else {
+        return Err(FormatError::Invalid {
+            path: path.to_string(),
+            report: CheckReport::new([(path, source.as_str())], settings),
+        });
+    };
+
+    let changed = formatted != source;
+    let result = match write {
+        true if changed => fs::write(path, &formatted),
+        true => Ok(()),
+        false => output.write_all(formatted.as_bytes()),
+    };
+    result.map_err(|source| FormatError::Write {
+        path: path.to_string(),
+        source,
+    })?;
+    Ok(changed)
+}
+
+/// Runs `format` with the command line `args` following it.
+///
+/// Settings are read from the settings file of the current directory, if any. Exits
+/// with `1` if the file has syntax errors, and `2` if it couldn't be formatted otherwise.
+pub fn run(args: &[String]) -> ExitCode {
+    let (write, path) = match args {
+        [flag, path] if flag == "--write" => (true, path),
+        [path] if !path.starts_with("--") => (false, path),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match format_file(
+        path,
+        write,
+        &check::load_settings(),
+        &mut io::stdout().lock(),
+    ) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(FormatError::Invalid { path, report }) => {
+            eprintln!("Not formatting {path}, which has syntax errors:");
+            if let Err(e) = report.write(OutputFormat::Text, &mut io::stderr().lock()) {
+                eprintln!("Failed to print diagnostics: {e}");
+            }
+            ExitCode::from(1)
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        path::{Path, PathBuf},
+    };
+
+    use super::*;
+
+    fn temp_file(name: &str, text: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("huml-lsp-format-{name}.huml"));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn format(path: &Path, write: bool) -> (Result<bool, FormatError>, String) {
+        let mut output = vec![];
+        let path = path.to_str().unwrap();
+        let result = format_file(path, write, &ServerSettings::default(), &mut output);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn should_leave_formatted_file_as_is() {
+        let path = temp_file("formatted", "a: 1\nb::\n  c: 2\n");
+
+        let (result, output) = format(&path, false);
+        assert!(!result.unwrap());
+        assert_eq!(output, "a: 1\nb::\n  c: 2\n");
+        let (result, output) = format(&path, true);
+        assert!(!result.unwrap());
+        assert!(output.is_empty());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a: 1\nb::\n  c: 2\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_print_or_write_formatted_file() {
+        let path = temp_file("messy", "a:   1\nb::\n    c: 2\n");
+
+        let (result, output) = format(&path, false);
+        assert!(result.unwrap());
+        assert_eq!(output, "a: 1\nb::\n  c: 2\n");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "a:   1\nb::\n    c: 2\n"
+        );
+
+        let (result, output) = format(&path, true);
+        assert!(result.unwrap());
+        assert!(output.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a: 1\nb::\n  c: 2\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_refuse_to_format_file_with_syntax_errors() {
+        let path = temp_file("invalid", "a:   oops\n");
+
+        let (result, output) = format(&path, true);
+
+        let Err(FormatError::Invalid { report, .. }) = result else {
+            panic!("Expected the file to be invalid");
+        };
+        assert_eq!(report.exit_code(), 1);
+        assert!(output.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a:   oops\n");
+        fs::remove_file(path).unwrap();
+    }
+}
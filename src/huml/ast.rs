@@ -0,0 +1,447 @@
+/// A location in the source text.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Location {
+    /// Zero-based line number.
+    pub line: usize,
+    /// Zero-based byte offset from the start of the line.
+    pub column: usize,
+    /// Zero-based byte offset from the start of the source.
+    pub offset: usize,
+}
+
+/// A span of source text. The start is inclusive and the end is exclusive.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    pub fn new(start: Location, end: Location) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns a span starting at the start of `self` and ending at the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+
+    /// Returns `true` if the span covers more than one line.
+    pub fn is_multiline(&self) -> bool {
+        self.start.line != self.end.line
+    }
+
+    /// Returns `true` if the span starts on `line` and covers the byte `column`, its end
+    /// included, so that a cursor right after the spanned text is within it.
+    pub fn contains(&self, line: usize, column: usize) -> bool {
+        self.start.line == line && (self.start.column..=self.end.column).contains(&column)
+    }
+
+    /// Returns `true` if the byte `column` of `line` is within the span, which may cover
+    /// several lines. The start is included and the end excluded.
+    pub fn covers(&self, line: usize, column: usize) -> bool {
+        let position = (line, column);
+        (self.start.line, self.start.column) <= position
+            && position < (self.end.line, self.end.column)
+    }
+}
+
+/// A parsed HUML document.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Document {
+    /// The version declared by the `%HUML` directive, if present.
+    pub version: Option<Version>,
+    /// The root value of the document, or `None` if the document holds no value.
+    pub root: Option<Node>,
+    /// The comments of the document, in the order they appear in the source.
+    pub comments: Vec<Comment>,
+}
+
+impl Document {
+    /// Returns the key found at the byte `column` of `line`, if any.
+    pub fn key_at(&self, line: usize, column: usize) -> Option<&Key> {
+        find_key(self.root.as_ref()?, line, column)
+    }
+
+    /// Returns the innermost key or value found at the byte `column` of `line`, the end of
+    /// their spans excluded.
+    ///
+    /// Returns `None` if the position is outside of the root value, or in the indentation,
+    /// indicators or blank lines of a block dict or list, which hold no element.
+    pub fn node_at(&self, line: usize, column: usize) -> Option<NodePath<'_>> {
+        let mut node = self
+            .root
+            .as_ref()
+            .filter(|root| root.span.covers(line, column))?;
+        let mut ancestors = vec![];
+        loop {
+            let child = match &node.kind {
+                NodeKind::Dict(entries) => {
+                    if let Some(entry) = entries.iter().find(|e| e.key.span.covers(line, column)) {
+                        ancestors.push(node);
+                        let target = PathTarget::Key(&entry.key);
+                        return Some(NodePath { target, ancestors });
+                    }
+                    entries
+                        .iter()
+                        .map(|entry| &entry.value)
+                        .find(|value| value.span.covers(line, column))
+                }
+                NodeKind::List(items) => items.iter().find(|item| item.span.covers(line, column)),
+                NodeKind::Scalar(_) => None,
+            };
+            match child {
+                Some(child) => {
+                    ancestors.push(node);
+                    node = child;
+                }
+                None if matches!(node.kind, NodeKind::Scalar(_))
+                    || node.layout == Layout::Inline =>
+                {
+                    let target = PathTarget::Value(node);
+                    return Some(NodePath { target, ancestors });
+                }
+                None => return None,
+            }
+        }
+    }
+
+    /// Returns the first key declared with the same path as the key found at the byte
+    /// `column` of `line`, e.g. the first `server.port` of a document declaring it twice.
+    /// Returns `None` if there is no key at the position, or if it's the first one.
+    pub fn first_definition_at(&self, line: usize, column: usize) -> Option<&Key> {
+        let key = self.key_at(line, column)?;
+        let first = *self.occurrences_at(line, column).first()?;
+        (!std::ptr::eq(first, key)).then_some(first)
+    }
+
+    /// Returns the keys declared with the same path as the key found at the byte `column`
+    /// of `line`, that key included, in the order they're declared. Returns an empty list
+    /// if there is no key at the position.
+    pub fn occurrences_at(&self, line: usize, column: usize) -> Vec<&Key> {
+        let mut keys = vec![];
+        if let Some(root) = &self.root {
+            collect_key_paths(root, &mut vec![], &mut keys);
+        }
+        let Some((path, _)) = keys.iter().find(|(_, key)| key.span.contains(line, column)) else {
+            return vec![];
+        };
+        keys.iter()
+            .filter(|(other, _)| other == path)
+            .map(|(_, key)| *key)
+            .collect()
+    }
+}
+
+/// The innermost element found at a position of a document by [`Document::node_at`],
+/// along with the dicts and lists holding it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodePath<'a> {
+    pub target: PathTarget<'a>,
+    /// The dicts and lists holding the target, from the root to its parent.
+    pub ancestors: Vec<&'a Node>,
+}
+
+/// The element a [`NodePath`] leads to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathTarget<'a> {
+    /// The key of a dict entry.
+    Key(&'a Key),
+    /// A scalar, or an inline dict or list when the position is on its brackets or
+    /// separators.
+    Value(&'a Node),
+}
+
+/// Adds the keys of the tree rooted at `node` to `keys`, in the order they're declared,
+/// along with their path from the root. List items are part of the path by their index.
+fn collect_key_paths<'a>(
+    node: &'a Node,
+    path: &mut Vec<String>,
+    keys: &mut Vec<(Vec<String>, &'a Key)>,
+) {
+    match &node.kind {
+        NodeKind::Dict(entries) => {
+            for entry in entries {
+                path.push(entry.key.name.clone());
+                keys.push((path.clone(), &entry.key));
+                collect_key_paths(&entry.value, path, keys);
+                path.pop();
+            }
+        }
+        NodeKind::List(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push(format!("[{index}]"));
+                collect_key_paths(item, path, keys);
+                path.pop();
+            }
+        }
+        NodeKind::Scalar(_) => (),
+    }
+}
+
+/// Finds the key found at the byte `column` of `line` in the tree rooted at `node`.
+fn find_key(node: &Node, line: usize, column: usize) -> Option<&Key> {
+    match &node.kind {
+        NodeKind::Dict(entries) => entries.iter().find_map(|entry| {
+            if entry.key.span.contains(line, column) {
+                Some(&entry.key)
+            } else {
+                find_key(&entry.value, line, column)
+            }
+        }),
+        NodeKind::List(items) => items.iter().find_map(|item| find_key(item, line, column)),
+        NodeKind::Scalar(_) => None,
+    }
+}
+
+/// Where a node visited by a [`Visitor`] is found in the document.
+#[derive(Clone, Copy, Debug)]
+pub struct VisitContext<'a, 'p> {
+    /// The dict or list holding the node, or `None` for the root.
+    pub parent: Option<&'a Node>,
+    /// The keys leading from the root to the node, the key of its own entry included.
+    pub keys: &'p [&'a Key],
+}
+
+/// Walks the tree of a document with [`walk`], which calls the methods of the visitor in
+/// document order. Every method does nothing by default, so visitors only implement the
+/// ones they need.
+pub trait Visitor<'a> {
+    /// Visits a dict, before its entries.
+    fn visit_dict(&mut self, _node: &'a Node, _entries: &'a [Entry], _context: VisitContext) {}
+
+    /// Visits a list, before its items.
+    fn visit_list(&mut self, _node: &'a Node, _items: &'a [Node], _context: VisitContext) {}
+
+    /// Visits an entry of a dict, before its value. The context is the one of the dict.
+    fn visit_key_value(&mut self, _entry: &'a Entry, _context: VisitContext) {}
+
+    /// Visits a scalar value.
+    fn visit_scalar(&mut self, _node: &'a Node, _scalar: &'a Scalar, _context: VisitContext) {}
+}
+
+/// Calls the methods of `visitor` with every node of `document`, parents before their
+/// children. The spans of the nodes locate them in the source.
+pub fn walk<'a>(document: &'a Document, visitor: &mut impl Visitor<'a>) {
+    if let Some(root) = &document.root {
+        walk_node(root, None, &mut vec![], visitor);
+    }
+}
+
+fn walk_node<'a>(
+    node: &'a Node,
+    parent: Option<&'a Node>,
+    keys: &mut Vec<&'a Key>,
+    visitor: &mut impl Visitor<'a>,
+) {
+    let context = VisitContext { parent, keys };
+    match &node.kind {
+        NodeKind::Scalar(scalar) => visitor.visit_scalar(node, scalar, context),
+        NodeKind::Dict(entries) => {
+            visitor.visit_dict(node, entries, context);
+            for entry in entries {
+                visitor.visit_key_value(entry, VisitContext { parent, keys });
+                keys.push(&entry.key);
+                walk_node(&entry.value, Some(node), keys, visitor);
+                keys.pop();
+            }
+        }
+        NodeKind::List(items) => {
+            visitor.visit_list(node, items, context);
+            for item in items {
+                walk_node(item, Some(node), keys, visitor);
+            }
+        }
+    }
+}
+
+/// The version declared by a `%HUML` directive.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Version {
+    /// The declared version, e.g. `v0.1.0`.
+    pub value: String,
+    /// The span of the whole directive.
+    pub span: Span,
+}
+
+/// A value in the document along with the span of source it was parsed from.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Span,
+    pub layout: Layout,
+}
+
+/// How a value is laid out in the source.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layout {
+    /// The value is written on a single line, e.g. a scalar or `:: 1, 2, 3`.
+    Inline,
+    /// The value spans multiple lines, e.g. a nested block or a `"""` string.
+    Block,
+}
+
+/// The different kinds of values a HUML document is made of.
+#[derive(Clone, PartialEq, Debug)]
+pub enum NodeKind {
+    /// A single value, e.g. a string or a number.
+    Scalar(Scalar),
+    /// A collection of key-value pairs, declared with `::`.
+    Dict(Vec<Entry>),
+    /// An ordered collection of values, declared with `::`.
+    List(Vec<Node>),
+}
+
+/// A key-value pair in a dict.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entry {
+    pub key: Key,
+    pub value: Node,
+}
+
+impl Entry {
+    /// Returns the span covering both the key and the value of the entry.
+    pub fn span(&self) -> Span {
+        self.key.span.to(self.value.span)
+    }
+}
+
+/// The key of a dict entry.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Key {
+    /// The name of the key, with quotes and escapes resolved.
+    pub name: String,
+    pub span: Span,
+}
+
+/// A scalar value.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Scalar {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Null,
+}
+
+impl Scalar {
+    /// Returns the name of the type of the scalar, e.g. `int` for `1`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::Integer(_) => "int",
+            Self::Float(_) => "float",
+            Self::Boolean(_) => "bool",
+            Self::Null => "null",
+        }
+    }
+}
+
+/// A `#` comment, either on its own line or trailing a value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Comment {
+    /// The text of the comment, starting with `#`.
+    pub text: String,
+    pub span: Span,
+    /// `true` if the comment follows a value on the same line, `false` if it's on a
+    /// line of its own.
+    pub trailing: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    fn target_at(source: &str, line: usize, column: usize) -> Option<(String, usize)> {
+        let document = huml::parse(source).document;
+        let path = document.node_at(line, column)?;
+        let span = match path.target {
+            PathTarget::Key(key) => key.span,
+            PathTarget::Value(node) => node.span,
+        };
+        let text = source[span.start.offset..span.end.offset].to_string();
+        Some((text, path.ancestors.len()))
+    }
+
+    #[test]
+    fn should_find_keys_and_scalars_at_position() {
+        let source = "server::\n  port: 8080\n  hosts:: \"a\", \"b\"\n";
+
+        assert_eq!(target_at(source, 0, 0), Some(("server".to_string(), 1)));
+        assert_eq!(target_at(source, 1, 2), Some(("port".to_string(), 2)));
+        assert_eq!(target_at(source, 1, 8), Some(("8080".to_string(), 2)));
+        assert_eq!(target_at(source, 2, 16), Some(("\"b\"".to_string(), 3)));
+    }
+
+    #[test]
+    fn should_include_start_and_exclude_end_of_spans() {
+        let source = "port: 8080\n";
+
+        assert_eq!(target_at(source, 0, 6), Some(("8080".to_string(), 1)));
+        assert_eq!(target_at(source, 0, 9), Some(("8080".to_string(), 1)));
+        assert_eq!(target_at(source, 0, 10), None);
+        assert_eq!(target_at(source, 0, 4), None);
+    }
+
+    #[test]
+    fn should_find_inline_vectors_at_brackets_and_separators() {
+        let source = "empty:: []\nports:: 80, 443\n";
+
+        assert_eq!(target_at(source, 0, 8), Some(("[]".to_string(), 1)));
+        assert_eq!(target_at(source, 0, 9), Some(("[]".to_string(), 1)));
+        assert_eq!(target_at(source, 1, 10), Some(("80, 443".to_string(), 1)));
+    }
+
+    #[test]
+    fn should_find_nothing_in_indentation_and_whitespace() {
+        let source = "server::\n  port: 8080\n\n  host: \"a\"\n";
+
+        assert_eq!(target_at(source, 1, 0), None);
+        assert_eq!(target_at(source, 2, 0), None);
+        assert_eq!(target_at(source, 3, 1), None);
+        assert_eq!(target_at(source, 5, 0), None);
+    }
+
+    #[derive(Default)]
+    struct ScalarCollector {
+        scalars: Vec<(String, Span)>,
+        dicts: usize,
+    }
+
+    impl<'a> Visitor<'a> for ScalarCollector {
+        fn visit_dict(&mut self, _: &'a Node, _: &'a [Entry], _: VisitContext) {
+            self.dicts += 1;
+        }
+
+        fn visit_scalar(&mut self, node: &'a Node, _: &'a Scalar, context: VisitContext) {
+            let path: Vec<_> = context.keys.iter().map(|key| key.name.as_str()).collect();
+            self.scalars.push((path.join("."), node.span));
+        }
+    }
+
+    #[test]
+    fn should_visit_scalars_in_document_order() {
+        let source = "name: \"huml\"\nserver::\n  ports:: 80, 443\n  tls::\n    on: true\n";
+        let document = huml::parse(source).document;
+        let mut collector = ScalarCollector::default();
+
+        walk(&document, &mut collector);
+
+        let scalars: Vec<_> = collector
+            .scalars
+            .iter()
+            .map(|(path, span)| (path.as_str(), &source[span.start.offset..span.end.offset]))
+            .collect();
+        assert_eq!(
+            scalars,
+            [
+                ("name", "\"huml\""),
+                ("server.ports", "80"),
+                ("server.ports", "443"),
+                ("server.tls.on", "true"),
+            ]
+        );
+        assert_eq!(collector.dicts, 3);
+    }
+}
@@ -0,0 +1,904 @@
+use crate::huml::{
+    self, Fix,
+    ast::{Comment, Document, Entry, Key, Layout, Location, Node, NodeKind, Scalar, Span},
+    parser::MULTILINE_QUOTE,
+};
+
+/// Options controlling how a document is printed.
+#[derive(Clone, Debug)]
+pub struct EmitOptions {
+    /// The number of spaces each nested block is indented with.
+    pub indent_width: usize,
+    /// How dicts and lists are laid out.
+    pub vector_layout: VectorLayout,
+    /// Whether the comments of the document are printed.
+    pub preserve_comments: bool,
+}
+
+impl Default for EmitOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 2,
+            vector_layout: VectorLayout::default(),
+            preserve_comments: true,
+        }
+    }
+}
+
+/// How dicts and lists are laid out when printed. Empty vectors are always printed
+/// inline, as `[]` or `{}`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum VectorLayout {
+    /// Keep the layout each vector was parsed with.
+    #[default]
+    Preserve,
+    /// Print vectors holding only scalars on a single line, as long as the line fits
+    /// within `max_width` characters. Other vectors are printed as blocks.
+    PreferInline { max_width: usize },
+    /// Print every vector as a block.
+    PreferBlock,
+}
+
+/// Prints a document as HUML source text.
+///
+/// Parsing the printed text yields a document with the same structure, version and
+/// comments as `document`. Comments are placed using their spans, so they are only
+/// kept in place for documents that come from [`huml::parse`].
+pub fn to_string(document: &Document, options: &EmitOptions) -> String {
+    Emitter::new(document, options, None).emit_document()
+}
+
+/// Formats HUML source text by parsing it and printing it back in canonical form.
+///
+/// Unlike [`to_string`], scalars and keys are copied as written (e.g. `0xFF` stays
+/// `0xFF`), except for booleans which are lowercased, and blank lines separating entries or comments are preserved, collapsed to
+/// one. Comments on their own lines are indented like the line following them, or like
+/// the block they close, and trailing comments are separated from values by one space.
+///
+/// Returns `None` if the source has syntax errors.
+pub fn format(source: &str, options: &EmitOptions) -> Option<String> {
+    let parsed = huml::parse(source);
+    if !parsed.is_valid() {
+        return None;
+    }
+    Some(Emitter::new(&parsed.document, options, Some(source)).emit_document())
+}
+
+/// Returns the fixes replacing the tabs indenting the lines of `source` with spaces,
+/// tabs advancing to the next multiple of `indent_width` columns. HUML only allows spaces
+/// as indentation, so this turns text pasted from a tab-indented source into valid HUML.
+///
+/// Unlike [`format`], this works on documents with syntax errors. The lines of
+/// multiline strings are content, so they are kept verbatim.
+pub fn expand_indentation_tabs(source: &str, indent_width: usize) -> Vec<Fix> {
+    let mut fixes = vec![];
+    let mut in_multiline_string = false;
+    let mut offset = 0;
+    for (line, text) in source.split_inclusive('\n').enumerate() {
+        let line_offset = offset;
+        offset += text.len();
+        let content = text.trim_start_matches([' ', '\t']);
+        let trimmed = content.trim_end();
+        if in_multiline_string {
+            in_multiline_string = trimmed != MULTILINE_QUOTE;
+            continue;
+        }
+        in_multiline_string = trimmed.ends_with(MULTILINE_QUOTE);
+
+        let whitespace = &text[..text.len() - content.len()];
+        if !whitespace.contains('\t') {
+            continue;
+        }
+        let width = whitespace.chars().fold(0, |width, char| match char {
+            '\t' => (width / indent_width.max(1) + 1) * indent_width,
+            _ => width + 1,
+        });
+        let location = |column| Location {
+            line,
+            column,
+            offset: line_offset + column,
+        };
+        fixes.push(Fix {
+            span: Span::new(location(0), location(whitespace.len())),
+            text: " ".repeat(width),
+        });
+    }
+    fixes
+}
+
+struct Emitter<'a> {
+    document: &'a Document,
+    options: &'a EmitOptions,
+    /// The source the document was parsed from, if any. Scalars and keys are copied
+    /// from it instead of being printed from their values.
+    source: Option<&'a str>,
+    /// The comments left to print, in source order.
+    comments: &'a [Comment],
+    /// The offset in the source right after the last printed element, used to preserve
+    /// the blank lines of the source.
+    last_offset: Option<usize>,
+    output: String,
+}
+
+impl<'a> Emitter<'a> {
+    fn new(document: &'a Document, options: &'a EmitOptions, source: Option<&'a str>) -> Self {
+        let comments = if options.preserve_comments {
+            document.comments.as_slice()
+        } else {
+            &[]
+        };
+        Self {
+            document,
+            options,
+            source,
+            comments,
+            last_offset: None,
+            output: String::new(),
+        }
+    }
+
+    fn emit_document(mut self) -> String {
+        if let Some(version) = &self.document.version {
+            self.start_line(version.span.start.offset, 0);
+            self.output.push_str(&format!("%HUML {}", version.value));
+            self.end_line(version.span.end);
+        }
+
+        if let Some(root) = &self.document.root {
+            // Only lists can be inlined at the root, as `key: value` starts a dict block
+            let is_inline = match &root.kind {
+                NodeKind::Scalar(_) => true,
+                NodeKind::Dict(entries) => entries.is_empty(),
+                NodeKind::List(_) => self.is_inline(root),
+            };
+            if is_inline {
+                self.start_line(root.span.start.offset, 0);
+                self.emit_inline_value(root, 0);
+            } else {
+                self.emit_block(root, 0, None, None);
+            }
+        }
+
+        self.emit_comments_before(None, 0, None);
+        self.output
+    }
+
+    /// Prints the entries of a dict or the items of a list, one per line, followed by
+    /// the comments closing the block.
+    ///
+    /// `limit` is the offset of the element following the block in the source, and
+    /// `header_column` the column of the key or `-` marker the block is nested under.
+    fn emit_block(
+        &mut self,
+        node: &Node,
+        indent: usize,
+        limit: Option<usize>,
+        header_column: Option<usize>,
+    ) {
+        match &node.kind {
+            NodeKind::Dict(entries) => {
+                for (idx, entry) in entries.iter().enumerate() {
+                    let next = entries.get(idx + 1).map(|next| next.key.span.start.offset);
+                    self.emit_entry(entry, indent, next.or(limit));
+                }
+            }
+            NodeKind::List(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    let next = items.get(idx + 1).map(|next| next.span.start.offset);
+                    self.emit_item(item, indent, next.or(limit));
+                }
+            }
+            NodeKind::Scalar(_) => unreachable!("Scalars are never printed as blocks"),
+        }
+
+        // Comments after the last line of the block belong to it, as long as they are
+        // nested under its header
+        self.emit_comments_before(limit, indent, header_column);
+    }
+
+    fn emit_entry(&mut self, entry: &Entry, indent: usize, limit: Option<usize>) {
+        self.start_line(entry.key.span.start.offset, indent);
+        let key = self.key(&entry.key);
+        self.output.push_str(&key);
+        if matches!(entry.value.kind, NodeKind::Scalar(_)) {
+            self.output.push_str(": ");
+            self.emit_scalar(&entry.value, indent);
+        } else {
+            self.output.push_str("::");
+            self.emit_vector(&entry.value, indent, entry.key.span, limit);
+        }
+    }
+
+    fn emit_item(&mut self, item: &Node, indent: usize, limit: Option<usize>) {
+        self.start_line(item.span.start.offset, indent);
+        if matches!(item.kind, NodeKind::Scalar(_)) {
+            self.output.push_str("- ");
+            self.emit_scalar(item, indent);
+        } else {
+            self.output.push_str("- ::");
+            let marker = Span::new(item.span.start, item.span.start);
+            self.emit_vector(item, indent, marker, limit);
+        }
+    }
+
+    /// Prints the vector following a `::` indicator on a line indented by `indent`.
+    /// `header` is the span of the key or `-` marker preceding the indicator.
+    fn emit_vector(&mut self, node: &Node, indent: usize, header: Span, limit: Option<usize>) {
+        if self.is_inline(node) {
+            self.output.push(' ');
+            self.emit_inline_value(node, indent);
+        } else {
+            self.end_line(header.end);
+            let indent = indent + self.options.indent_width;
+            self.emit_block(node, indent, limit, Some(header.start.column));
+        }
+    }
+
+    /// Prints a scalar or an inline vector on the current line.
+    fn emit_inline_value(&mut self, node: &Node, indent: usize) {
+        if matches!(node.kind, NodeKind::Scalar(_)) {
+            self.emit_scalar(node, indent);
+        } else {
+            let vector = self.inline_vector(node);
+            self.output.push_str(&vector);
+            self.end_line(node.span.end);
+        }
+    }
+
+    /// Prints a scalar on the current line. Multiline strings continue on the following
+    /// lines, nested under the line indented by `indent`.
+    fn emit_scalar(&mut self, node: &Node, indent: usize) {
+        let (NodeKind::Scalar(Scalar::String(value)), Layout::Block) = (&node.kind, node.layout)
+        else {
+            let scalar = self.scalar(node);
+            self.output.push_str(&scalar);
+            self.end_line(node.span.end);
+            return;
+        };
+
+        self.output.push_str(MULTILINE_QUOTE);
+        let opening_end = Location {
+            column: node.span.start.column + MULTILINE_QUOTE.len(),
+            offset: node.span.start.offset + MULTILINE_QUOTE.len(),
+            ..node.span.start
+        };
+        self.end_line(opening_end);
+        for line in value.split('\n') {
+            if !line.is_empty() {
+                self.push_indent(indent + self.options.indent_width);
+                self.output.push_str(line);
+            }
+            self.output.push('\n');
+        }
+        self.push_indent(indent);
+        self.output.push_str(MULTILINE_QUOTE);
+        self.end_line(node.span.end);
+    }
+
+    /// Starts a new line indented by `indent` for the element starting at `offset` in
+    /// the source. The comments preceding the element are printed first.
+    fn start_line(&mut self, offset: usize, indent: usize) {
+        self.emit_comments_before(Some(offset), indent, None);
+        self.separate(offset);
+        self.push_indent(indent);
+    }
+
+    /// Ends the current line, printing the comment trailing the source line `end` is on.
+    fn end_line(&mut self, end: Location) {
+        self.last_offset = Some(end.offset);
+        if let Some(comment) = self
+            .comments
+            .first()
+            .filter(|comment| comment.trailing && comment.span.start.line == end.line)
+        {
+            self.output.push(' ');
+            self.output.push_str(&comment.text);
+            self.last_offset = Some(comment.span.end.offset);
+            self.comments = &self.comments[1..];
+        }
+        self.output.push('\n');
+    }
+
+    /// Prints the comments found before `offset` in the source, or all of them if
+    /// `offset` is `None`, on their own lines indented by `indent`. Stops at the first
+    /// comment that isn't indented past `parent_column` in the source.
+    fn emit_comments_before(
+        &mut self,
+        offset: Option<usize>,
+        indent: usize,
+        parent_column: Option<usize>,
+    ) {
+        while let Some(comment) = self.comments.first() {
+            let start = comment.span.start;
+            if offset.is_some_and(|offset| start.offset >= offset)
+                || parent_column.is_some_and(|parent_column| start.column <= parent_column)
+            {
+                break;
+            }
+
+            self.separate(start.offset);
+            self.push_indent(indent);
+            self.output.push_str(&comment.text);
+            self.output.push('\n');
+            self.last_offset = Some(comment.span.end.offset);
+            self.comments = &self.comments[1..];
+        }
+    }
+
+    /// Prints a blank line if the element starting at `offset` was separated from the
+    /// previously printed one by blank lines in the source.
+    fn separate(&mut self, offset: usize) {
+        let (Some(source), Some(last_offset)) = (self.source, self.last_offset) else {
+            return;
+        };
+        if has_blank_line(&source[last_offset..offset]) {
+            self.output.push('\n');
+        }
+    }
+
+    /// Returns `true` if the vector should be printed on a single line, following the
+    /// current line.
+    fn is_inline(&self, node: &Node) -> bool {
+        let is_inline_scalar =
+            |node: &Node| matches!(node.kind, NodeKind::Scalar(_)) && node.layout == Layout::Inline;
+        let (is_empty, holds_inline_scalars) = match &node.kind {
+            NodeKind::Dict(entries) => (
+                entries.is_empty(),
+                entries.iter().all(|entry| is_inline_scalar(&entry.value)),
+            ),
+            NodeKind::List(items) => (items.is_empty(), items.iter().all(is_inline_scalar)),
+            NodeKind::Scalar(_) => return false,
+        };
+        // Comments between the items of a block would be lost on a single line
+        let holds_comments = self
+            .comments
+            .first()
+            .is_some_and(|comment| comment.span.start.offset < node.span.end.offset);
+        if is_empty {
+            return true;
+        }
+        if !holds_inline_scalars || holds_comments {
+            return false;
+        }
+
+        match self.options.vector_layout {
+            VectorLayout::Preserve => node.layout == Layout::Inline,
+            VectorLayout::PreferInline { max_width } => {
+                let line_start = self.output.rfind('\n').map_or(0, |idx| idx + 1);
+                let line_width = self.output[line_start..].chars().count()
+                    + " ".len()
+                    + self.inline_vector(node).chars().count();
+                line_width <= max_width
+            }
+            VectorLayout::PreferBlock => false,
+        }
+    }
+
+    fn inline_vector(&self, node: &Node) -> String {
+        match &node.kind {
+            NodeKind::Dict(entries) if entries.is_empty() => "{}".to_string(),
+            NodeKind::List(items) if items.is_empty() => "[]".to_string(),
+            NodeKind::Dict(entries) => entries
+                .iter()
+                .map(|entry| format!("{}: {}", self.key(&entry.key), self.scalar(&entry.value)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            NodeKind::List(items) => items
+                .iter()
+                .map(|item| self.scalar(item))
+                .collect::<Vec<_>>()
+                .join(", "),
+            NodeKind::Scalar(_) => unreachable!("Scalars aren't vectors"),
+        }
+    }
+
+    fn key(&self, key: &Key) -> String {
+        if let Some(source) = self.source {
+            return source[key.span.start.offset..key.span.end.offset].to_string();
+        }
+
+        let is_bare = key
+            .name
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if is_bare {
+            key.name.clone()
+        } else {
+            quote(&key.name)
+        }
+    }
+
+    fn scalar(&self, node: &Node) -> String {
+        // Booleans are always printed canonically, since the parser accepts any case
+        if let Some(source) = self.source
+            && !matches!(node.kind, NodeKind::Scalar(Scalar::Boolean(_)))
+        {
+            return source[node.span.start.offset..node.span.end.offset].to_string();
+        }
+
+        match &node.kind {
+            NodeKind::Scalar(Scalar::String(value)) => quote(value),
+            NodeKind::Scalar(Scalar::Integer(value)) => value.to_string(),
+            NodeKind::Scalar(Scalar::Float(value)) if value.is_nan() => "nan".to_string(),
+            NodeKind::Scalar(Scalar::Float(value)) if value.is_infinite() => {
+                if value.is_sign_positive() {
+                    "inf"
+                } else {
+                    "-inf"
+                }
+                .to_string()
+            }
+            // Debug keeps the fractional part of whole floats, e.g. `1.0`
+            NodeKind::Scalar(Scalar::Float(value)) => format!("{value:?}"),
+            NodeKind::Scalar(Scalar::Boolean(value)) => value.to_string(),
+            NodeKind::Scalar(Scalar::Null) => "null".to_string(),
+            NodeKind::Dict(_) | NodeKind::List(_) => unreachable!("Vectors aren't scalars"),
+        }
+    }
+
+    fn push_indent(&mut self, indent: usize) {
+        self.output.extend(std::iter::repeat_n(' ', indent));
+    }
+}
+
+/// Returns `true` if any line of `text` is blank, not counting its first and last lines
+/// which are parts of the lines surrounding it.
+fn has_blank_line(text: &str) -> bool {
+    let lines: Vec<_> = text.split('\n').collect();
+    lines.len() > 2
+        && lines[1..lines.len() - 1]
+            .iter()
+            .any(|line| line.trim().is_empty())
+}
+
+/// Prints a string as a double quoted literal, escaping it as needed.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\u{c}' => quoted.push_str("\\f"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_with_indent(source: &str, indent_width: usize) -> Option<String> {
+        let options = EmitOptions {
+            indent_width,
+            ..EmitOptions::default()
+        };
+        format(source, &options)
+    }
+
+    const MESSY_DOCUMENT: &str = concat!(
+        "%HUML v0.1.0\n",
+        "\n",
+        "name:   \"huml\"   \n",
+        "server::\n",
+        "    port:  0x1F90\n",
+        "    tags::   \"a\",\"b\"\n",
+        "\n",
+        "\n",
+        "    hosts::\n",
+        "       -   \"localhost\"\n",
+        "       - ::\n",
+        "            empty:: {}\n",
+        "text:  \"\"\"\n",
+        "        first\n",
+        "\n",
+        "          second\n",
+        "  \"\"\"\n",
+    );
+
+    /// Documents covering the syntax of HUML, printed with every combination of options
+    const SAMPLE_DOCUMENTS: &[&str] = &[
+        MESSY_DOCUMENT,
+        "\"just a string\"\n",
+        "1, 2.5, -inf, nan  # numbers\n",
+        "[]\n",
+        concat!(
+            "# Servers\n",
+            "- ::\n",
+            "  host: \"alpha\"  # primary\n",
+            "  ports:: 80, 443\n",
+            "\n",
+            "# Backup\n",
+            "- ::  # disabled\n",
+            "    host: \"beta\"\n",
+            "    # no ports\n",
+            "    ports:: []\n",
+            "- \"\"\"\n",
+            "  notes\n",
+            "  \"\"\"\n",
+            "# end\n",
+        ),
+        concat!(
+            "%HUML v0.1.0  # version\n",
+            "\"quoted key\": \"tab\\t and \\\"quotes\\\"\"\n",
+            "numbers::\n",
+            "  - 0xFF\n",
+            "  - 1_000\n",
+            "  - 1.5e3\n",
+            "nested::\n",
+            "  deeper::\n",
+            "    point:: x: 1, y: 2\n",
+            "    # closing the deeper block\n",
+            "  # closing the nested block\n",
+            "flags:: true, false, null\n",
+        ),
+    ];
+
+    fn options_matrix() -> Vec<EmitOptions> {
+        let mut matrix = vec![];
+        for indent_width in [2, 4] {
+            for vector_layout in [
+                VectorLayout::Preserve,
+                VectorLayout::PreferInline { max_width: 40 },
+                VectorLayout::PreferBlock,
+            ] {
+                for preserve_comments in [true, false] {
+                    matrix.push(EmitOptions {
+                        indent_width,
+                        vector_layout,
+                        preserve_comments,
+                    });
+                }
+            }
+        }
+        matrix
+    }
+
+    /// The structure of a value, without spans, layouts or comments
+    #[derive(PartialEq, Debug)]
+    enum Shape {
+        /// Debug representation of the scalar, so that `nan` equals itself
+        Scalar(String),
+        Dict(Vec<(String, Shape)>),
+        List(Vec<Shape>),
+    }
+
+    fn shape(node: &Node) -> Shape {
+        match &node.kind {
+            NodeKind::Scalar(scalar) => Shape::Scalar(format!("{scalar:?}")),
+            NodeKind::Dict(entries) => Shape::Dict(
+                entries
+                    .iter()
+                    .map(|entry| (entry.key.name.clone(), shape(&entry.value)))
+                    .collect(),
+            ),
+            NodeKind::List(items) => Shape::List(items.iter().map(shape).collect()),
+        }
+    }
+
+    fn parse_valid(source: &str) -> Document {
+        let parsed = huml::parse(source);
+        assert!(
+            parsed.is_valid(),
+            "Unexpected issues {:?} in:\n{source}",
+            parsed.issues
+        );
+        parsed.document
+    }
+
+    fn comment_texts(document: &Document) -> Vec<&str> {
+        document
+            .comments
+            .iter()
+            .map(|comment| comment.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn should_round_trip_document_structure() {
+        for source in SAMPLE_DOCUMENTS {
+            let document = parse_valid(source);
+            for options in options_matrix() {
+                let emitted = to_string(&document, &options);
+                let reparsed = parse_valid(&emitted);
+
+                assert_eq!(
+                    reparsed.root.as_ref().map(shape),
+                    document.root.as_ref().map(shape),
+                    "Structure changed with {options:?}:\n{emitted}"
+                );
+                assert_eq!(
+                    reparsed.version.map(|version| version.value),
+                    document
+                        .version
+                        .as_ref()
+                        .map(|version| version.value.clone())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_preserve_comments_when_requested() {
+        for source in SAMPLE_DOCUMENTS {
+            let document = parse_valid(source);
+            for options in options_matrix() {
+                let emitted = to_string(&document, &options);
+                let reparsed = parse_valid(&emitted);
+
+                let expected = if options.preserve_comments {
+                    comment_texts(&document)
+                } else {
+                    vec![]
+                };
+                assert_eq!(
+                    comment_texts(&reparsed),
+                    expected,
+                    "Comments changed with {options:?}:\n{emitted}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_emit_stable_output() {
+        for source in SAMPLE_DOCUMENTS {
+            let document = parse_valid(source);
+            for options in options_matrix() {
+                let emitted = to_string(&document, &options);
+                let reemitted = to_string(&parse_valid(&emitted), &options);
+
+                assert_eq!(reemitted, emitted, "Unstable output with {options:?}");
+                assert!(
+                    emitted.lines().all(|line| line == line.trim_end()),
+                    "Trailing whitespace with {options:?}:\n{emitted}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_apply_vector_layout() {
+        let document = parse_valid("list::\n  - 1\n  - 2\ninline:: a: 1, b: 2\n");
+        let emit = |vector_layout| {
+            let options = EmitOptions {
+                vector_layout,
+                ..EmitOptions::default()
+            };
+            to_string(&document, &options)
+        };
+
+        assert_eq!(
+            emit(VectorLayout::Preserve),
+            "list::\n  - 1\n  - 2\ninline:: a: 1, b: 2\n"
+        );
+        assert_eq!(
+            emit(VectorLayout::PreferInline { max_width: 80 }),
+            "list:: 1, 2\ninline:: a: 1, b: 2\n"
+        );
+        assert_eq!(
+            emit(VectorLayout::PreferInline { max_width: 12 }),
+            "list:: 1, 2\ninline::\n  a: 1\n  b: 2\n"
+        );
+        assert_eq!(
+            emit(VectorLayout::PreferBlock),
+            "list::\n  - 1\n  - 2\ninline::\n  a: 1\n  b: 2\n"
+        );
+    }
+
+    #[test]
+    fn should_place_comments() {
+        let source = concat!(
+            "# leading\n",
+            "a: 1  # trailing\n",
+            "b::  # header\n",
+            "  # first\n",
+            "  c: 2\n",
+            "  # closing\n",
+            "# last\n",
+        );
+        let document = parse_valid(source);
+        let options = EmitOptions {
+            indent_width: 4,
+            ..EmitOptions::default()
+        };
+
+        assert_eq!(
+            to_string(&document, &options),
+            concat!(
+                "# leading\n",
+                "a: 1 # trailing\n",
+                "b:: # header\n",
+                "    # first\n",
+                "    c: 2\n",
+                "    # closing\n",
+                "# last\n",
+            )
+        );
+
+        let list = "- ::\n  a: 1\n  # inside\n# between\n- 2\n";
+        assert_eq!(to_string(&parse_valid(list), &EmitOptions::default()), list);
+    }
+
+    #[test]
+    fn should_lowercase_booleans() {
+        assert_eq!(
+            format_with_indent("a: True\nb:: TRUE, \"False\"\n", 2).unwrap(),
+            "a: true\nb:: true, \"False\"\n"
+        );
+    }
+
+    #[test]
+    fn should_normalize_messy_document() {
+        assert_eq!(
+            format_with_indent(MESSY_DOCUMENT, 2).unwrap(),
+            concat!(
+                "%HUML v0.1.0\n",
+                "\n",
+                "name: \"huml\"\n",
+                "server::\n",
+                "  port: 0x1F90\n",
+                "  tags:: \"a\", \"b\"\n",
+                "\n",
+                "  hosts::\n",
+                "    - \"localhost\"\n",
+                "    - ::\n",
+                "      empty:: {}\n",
+                "text: \"\"\"\n",
+                "  first\n",
+                "\n",
+                "    second\n",
+                "\"\"\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_be_idempotent() {
+        for indent_width in [2, 4] {
+            let formatted = format_with_indent(MESSY_DOCUMENT, indent_width).unwrap();
+            assert_eq!(
+                format_with_indent(&formatted, indent_width).as_deref(),
+                Some(formatted.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn should_not_format_invalid_documents() {
+        assert_eq!(format_with_indent("key: value\n", 2), None);
+        assert_eq!(format_with_indent("key::\n# no block\n", 2), None);
+    }
+
+    const COMMENTED_DOCUMENT: &str = concat!(
+        "# Database settings\n",
+        "database::   # primary\n",
+        "    host:   \"localhost\"   # local only\n",
+        "\n",
+        "      # Credentials\n",
+        "    user:  \"admin\"\n",
+        "    options::  {}    # nothing yet\n",
+        "    # end of database\n",
+        "\n",
+        "# Replicas\n",
+        "replicas::\n",
+        "    # first replica\n",
+        "    - \"replica-1\"\n",
+        "# trailing document comment\n",
+    );
+
+    #[test]
+    fn should_keep_comments_when_formatting() {
+        let formatted = format_with_indent(COMMENTED_DOCUMENT, 2).unwrap();
+
+        assert_eq!(
+            formatted,
+            concat!(
+                "# Database settings\n",
+                "database:: # primary\n",
+                "  host: \"localhost\" # local only\n",
+                "\n",
+                "  # Credentials\n",
+                "  user: \"admin\"\n",
+                "  options:: {} # nothing yet\n",
+                "  # end of database\n",
+                "\n",
+                "# Replicas\n",
+                "replicas::\n",
+                "  # first replica\n",
+                "  - \"replica-1\"\n",
+                "# trailing document comment\n",
+            )
+        );
+        assert_eq!(format_with_indent(&formatted, 2), Some(formatted));
+    }
+
+    #[test]
+    fn should_keep_every_comment_next_to_its_line() {
+        // Each comment, paired with the line it trails or the line following it
+        fn comment_anchors(source: &str) -> Vec<(String, String)> {
+            let lines: Vec<_> = source.lines().map(str::trim).collect();
+            let mut anchors = vec![];
+            for (idx, line) in lines.iter().enumerate() {
+                if line.starts_with('#') {
+                    let next = lines[idx + 1..].iter().find(|line| !line.is_empty());
+                    let next = next.copied().unwrap_or_default();
+                    anchors.push((line.to_string(), format!("before {next}")));
+                } else if let Some((content, comment)) = line.split_once(" #") {
+                    anchors.push((format!("#{comment}"), format!("after {content}")));
+                }
+            }
+            anchors
+        }
+        let normalize = |source: &str| {
+            comment_anchors(source)
+                .into_iter()
+                .map(|(comment, anchor)| (comment, anchor.split_whitespace().collect::<String>()))
+                .collect::<Vec<_>>()
+        };
+
+        for indent_width in [2, 4] {
+            let formatted = format_with_indent(COMMENTED_DOCUMENT, indent_width).unwrap();
+            assert_eq!(normalize(&formatted), normalize(COMMENTED_DOCUMENT));
+        }
+    }
+
+    #[test]
+    fn should_print_values() {
+        let document = parse_valid(concat!(
+            "\"quoted key\": \"tab\\there\"\n",
+            "hex: 0xFF\n",
+            "float: 1e3\n",
+            "nan: nan\n",
+            "list:: 1, true, null\n",
+        ));
+
+        assert_eq!(
+            to_string(&document, &EmitOptions::default()),
+            concat!(
+                "\"quoted key\": \"tab\\there\"\n",
+                "hex: 255\n",
+                "float: 1000.0\n",
+                "nan: nan\n",
+                "list:: 1, true, null\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_expand_indentation_tabs_outside_multiline_strings() {
+        let source = "a::\n\tb: 1\n\tc::\n\t\td: \"\tx\"\n  \te: \"\"\"\n\t\tkept\n\t\"\"\"\n";
+
+        let fixes = expand_indentation_tabs(source, 4);
+
+        let edits: Vec<_> = fixes
+            .iter()
+            .map(|fix| (fix.span.start.line, fix.span.end.column, fix.text.as_str()))
+            .collect();
+        assert_eq!(
+            edits,
+            [
+                (1, 1, "    "),
+                (2, 1, "    "),
+                (3, 2, "        "),
+                (4, 3, "    "),
+            ]
+        );
+        assert_eq!(
+            &source[fixes[3].span.start.offset..fixes[3].span.end.offset],
+            "  \t"
+        );
+        assert!(expand_indentation_tabs("a::\n  b: 1\n", 2).is_empty());
+    }
+}
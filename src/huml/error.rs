@@ -0,0 +1,119 @@
+use crate::huml::ast::Span;
+
+/// A problem found in a HUML document, along with the span of source it applies to.
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+#[error("{message}")]
+pub struct Issue {
+    /// A human readable description of the problem.
+    pub message: String,
+    /// The span of source text the problem applies to.
+    pub span: Span,
+    /// The validation rule that found the problem, or `None` for syntax errors.
+    pub rule: Option<Rule>,
+    /// Other places of the source involved in the problem.
+    pub related: Vec<RelatedSpan>,
+    /// The edit fixing the problem, if it can be fixed automatically. Boxed to keep
+    /// `Result<_, Issue>` small, as most issues have no fix.
+    pub fix: Option<Box<Fix>>,
+    /// How the source the problem applies to can be shown, besides the problem itself.
+    pub tag: Option<IssueTag>,
+}
+
+impl Issue {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            rule: None,
+            related: vec![],
+            fix: None,
+            tag: None,
+        }
+    }
+
+    /// Marks the issue as found by `rule`.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = Some(rule);
+        self
+    }
+
+    /// Points at another place of the source involved in the problem.
+    pub fn with_related(mut self, message: impl Into<String>, span: Span) -> Self {
+        self.related.push(RelatedSpan {
+            message: message.into(),
+            span,
+        });
+        self
+    }
+
+    /// Attaches the edit replacing `span` with `text` that fixes the problem.
+    pub fn with_fix(mut self, span: Span, text: impl Into<String>) -> Self {
+        self.fix = Some(Box::new(Fix {
+            span,
+            text: text.into(),
+        }));
+        self
+    }
+
+    /// Marks the source the problem applies to with `tag`.
+    pub fn with_tag(mut self, tag: IssueTag) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+}
+
+/// Describes the source an [`Issue`] applies to, so that editors can render it
+/// accordingly, e.g. greyed out or struck through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssueTag {
+    /// The source has no effect, e.g. a key shadowed by another one.
+    Unnecessary,
+    /// The source uses something that is deprecated.
+    Deprecated,
+}
+
+/// An edit fixing an [`Issue`]: the text to replace a span of source with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fix {
+    pub span: Span,
+    pub text: String,
+}
+
+/// A place of the source involved in an [`Issue`], e.g. the first definition of a
+/// duplicate key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelatedSpan {
+    pub message: String,
+    pub span: Span,
+}
+
+/// The rules checked by the [validation passes](crate::huml::validate).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// Exactly one space must follow the `:` or `::` indicator of an entry.
+    ColonSpacing,
+    /// A key must not appear more than once in the same dict.
+    DuplicateKey,
+    /// Comments with a `TODO` or `FIXME` marker are reported, to be tracked.
+    TodoComment,
+    /// Booleans must be spelled in lowercase, i.e. `true` or `false`.
+    BooleanSpelling,
+    /// Every nested block must be indented by the same number of spaces.
+    IndentStep,
+    /// Documents must match the [schema](crate::huml::schema::Schema) configured for them.
+    Schema,
+}
+
+impl Rule {
+    /// The identifier of the rule, e.g. `colon-spacing`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ColonSpacing => "colon-spacing",
+            Self::DuplicateKey => "duplicate-key",
+            Self::TodoComment => "todo-comment",
+            Self::BooleanSpelling => "boolean-spelling",
+            Self::IndentStep => "indent-step",
+            Self::Schema => "schema",
+        }
+    }
+}
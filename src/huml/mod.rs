@@ -0,0 +1,123 @@
+//! The `huml` module implements the [HUML](https://huml.io) language itself,
+//! independently of the Language Server Protocol.
+//!
+//! The parser turns source text into a [`Document`](ast::Document) syntax tree in which
+//! every node carries the [`Span`](ast::Span) of source it was parsed from, so that the
+//! `lsp` module can map any node back to a location in the editor.
+//!
+//! Parsing is error tolerant: syntax errors are collected as [`Issue`]s and the parser
+//! recovers at the next line, so that editor features keep working on partially
+//! written documents. After an edit, [`reparse`] only parses the edited part of a
+//! document again. Rules that don't prevent building a syntax tree, like the
+//! spacing after `:`, are checked afterwards by the [`validate`] module, and documents
+//! can be checked against the expected shape described by a [`schema`]. The [`emit`]
+//! module goes the other way, printing a syntax tree back to source text.
+//!
+//! Documents can also be read as plain [`Value`]s, which [`merge_documents`] layers on
+//! top of each other, e.g. to apply overrides to a default configuration.
+//!
+//! The module can be used as a library, without the language server: [`parse`] builds
+//! the syntax tree of a document, and [`validate()`] lints it, reporting every issue
+//! with the byte offsets of the source it applies to.
+
+/// Defines the syntax tree produced by the parser.
+pub mod ast;
+
+/// Prints syntax trees back to HUML source text.
+pub mod emit;
+
+/// Defines the problems reported while processing a document.
+mod error;
+
+/// Parses HUML source text into a syntax tree.
+mod parser;
+
+/// Checks documents against a schema describing their expected shape.
+pub mod schema;
+
+/// Checks the rules of HUML that aren't enforced while parsing.
+pub mod validate;
+
+/// Defines the values held by documents, independently of their source.
+mod value;
+
+pub use error::*;
+pub use parser::*;
+pub use value::*;
+
+/// Lints `source`: returns its syntax errors along with the issues found by every
+/// validation rule, in the order they appear in the source.
+///
+/// Issues point at the source with byte offsets, which the `lsp` module converts to
+/// editor positions. Syntax errors have no [`rule`](Issue::rule), and are the only
+/// issues preventing the document from being read as a [`Value`].
+///
+/// # Examples
+///
+/// ```
+/// use huml_lsp::huml::{self, Rule};
+///
+/// assert!(huml::validate("port: 8080\nhosts:: \"a\", \"b\"\n").is_empty());
+///
+/// let source = "port: 8080\nhost: oops\nport:  80\n";
+/// let issues = huml::validate(source);
+/// let messages: Vec<_> = issues.iter().map(|issue| issue.message.as_str()).collect();
+/// assert_eq!(
+///     messages,
+///     [
+///         "invalid value `oops`; strings must be quoted",
+///         "duplicate key `port`",
+///         "expected exactly one space after ':'",
+///     ]
+/// );
+/// assert_eq!(issues[0].rule, None);
+/// assert_eq!(issues[1].rule, Some(Rule::DuplicateKey));
+/// // Spans hold byte offsets into the source
+/// assert_eq!(&source[issues[0].span.start.offset..issues[0].span.end.offset], "oops");
+/// ```
+pub fn validate(source: &str) -> Vec<Issue> {
+    let parsed = parse(source);
+    let mut issues = parsed.issues;
+    issues.extend(validate::validate(source, &parsed.document));
+    issues.sort_by_key(|issue| issue.span.start.offset);
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_no_issues_in_valid_source() {
+        let source = "\
+%HUML v0.1.0
+server::
+  host: \"localhost\"
+  ports:: 80, 443
+  tls: true
+";
+
+        assert!(validate(source).is_empty());
+    }
+
+    #[test]
+    fn should_report_syntax_and_rule_issues_in_source_order() {
+        let source = "a:1\nb: yes\nc: True\na: 2\n";
+
+        let issues = validate(source);
+
+        let found: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.span.start.line, issue.rule))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (0, Some(Rule::ColonSpacing)),
+                (1, None),
+                (2, Some(Rule::BooleanSpelling)),
+                (3, Some(Rule::DuplicateKey)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1612 @@
+use std::cmp::Ordering;
+
+use crate::huml::{
+    Issue,
+    ast::{Comment, Document, Entry, Key, Layout, Location, Node, NodeKind, Scalar, Span, Version},
+};
+
+/// The directive declaring the HUML version, e.g. `%HUML v0.1.0`.
+const VERSION_DIRECTIVE: &str = "%HUML";
+
+/// The delimiter of multiline strings.
+pub(super) const MULTILINE_QUOTE: &str = "\"\"\"";
+
+/// The result of parsing a HUML document.
+#[derive(Debug)]
+pub struct Parsed {
+    /// The parsed document. If any issues were found, it only holds the values
+    /// that could be parsed.
+    pub document: Document,
+    /// The syntax errors found in the source.
+    pub issues: Vec<Issue>,
+}
+
+impl Parsed {
+    /// Returns `true` if the source was parsed without any issues.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Parses a HUML document.
+///
+/// Parsing never fails outright. Syntax errors are reported as [`Issue`]s and the
+/// offending line (along with any lines nested under it) is skipped.
+///
+/// The parser is lenient about whitespace: any consistent indentation is accepted for
+/// nested blocks, and the amount of spaces after `:` or at the end of a line is not
+/// checked. Enforcing HUML's whitespace rules is left to the validation passes.
+///
+/// # Examples
+///
+/// ```
+/// use huml_lsp::huml::{self, ast::NodeKind};
+///
+/// let parsed = huml::parse("name: \"huml\"\nport: 8080\n");
+/// assert!(parsed.is_valid());
+/// let Some(NodeKind::Dict(entries)) = parsed.document.root.map(|root| root.kind) else {
+///     panic!("Expected a dict");
+/// };
+/// assert_eq!(entries[1].key.name, "port");
+/// ```
+pub fn parse(source: &str) -> Parsed {
+    let mut parser = Parser::new(source);
+    let document = parser.parse_document();
+    Parsed {
+        document,
+        issues: parser.issues,
+    }
+}
+
+/// An edit turning a previous source text into a new one, as byte offsets.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SourceEdit {
+    /// The offset of the start of the replaced text, in both sources.
+    pub start: usize,
+    /// The offset of the end of the replaced text, in the previous source.
+    pub old_end: usize,
+    /// The offset of the end of the replacement, in the new source.
+    pub new_end: usize,
+}
+
+/// Parses `source`, the result of applying `edit` to the source `previous` was parsed
+/// from.
+///
+/// If the edit is contained in a single block of the root dict or list, i.e. one of its
+/// entries or items along with the lines nested under it, only that block is parsed
+/// again, and the rest of the syntax tree of `previous` is reused. Otherwise, e.g. if
+/// the edit spans multiple blocks or either source has syntax errors, the whole source
+/// is parsed. Either way, the result is the same as parsing `source` with [`parse`].
+pub fn reparse(previous: &Parsed, source: &str, edit: SourceEdit) -> Parsed {
+    reparse_block(previous, source, edit).unwrap_or_else(|| parse(source))
+}
+
+/// Reparses the block of the root of `previous` containing `edit`, returning `None` if
+/// the whole source must be parsed instead.
+fn reparse_block(previous: &Parsed, source: &str, edit: SourceEdit) -> Option<Parsed> {
+    let root = previous.document.root.as_ref()?;
+    if !previous.is_valid() || root.layout != Layout::Block {
+        return None;
+    }
+    let starts: Vec<Location> = match &root.kind {
+        NodeKind::Dict(entries) => entries.iter().map(|entry| entry.key.span.start).collect(),
+        NodeKind::List(items) => items.iter().map(|item| item.span.start).collect(),
+        NodeKind::Scalar(_) => return None,
+    };
+
+    // A block spans from the line of its entry or item to the line of the next one
+    let line_start = |location: Location| location.offset - location.column;
+    let index = starts
+        .partition_point(|&start| line_start(start) <= edit.start)
+        .checked_sub(1)?;
+    let block_start = line_start(starts[index]);
+    let next = starts.get(index + 1).copied();
+    let block_end = match next {
+        Some(next) => line_start(next),
+        None => (source.len() + edit.old_end).checked_sub(edit.new_end)?,
+    };
+    if edit.old_end > block_end {
+        return None;
+    }
+    let new_block_end = block_end - edit.old_end + edit.new_end;
+    let block_source = source.get(block_start..new_block_end)?;
+    // The line of the next block must be left whole
+    if next.is_some() && !block_source.ends_with('\n') {
+        return None;
+    }
+
+    let mut parser = Parser::starting_at(block_source, starts[index].line, block_start);
+    let block = parser.parse_block(None)?;
+    // The block must still be a single block of the root, parsing the same way on its own
+    if !parser.issues.is_empty()
+        || parser.peek_line().is_some()
+        || block.span.start.column != root.span.start.column
+    {
+        return None;
+    }
+
+    let bytes = edit.new_end as isize - edit.old_end as isize;
+    let lines = next.map_or(0, |next| {
+        let new_next_line = starts[index].line + block_source.matches('\n').count();
+        new_next_line as isize - next.line as isize
+    });
+
+    let mut document = previous.document.clone();
+    let root = document.root.as_mut()?;
+    match (&mut root.kind, block.kind) {
+        (NodeKind::Dict(entries), NodeKind::Dict(block_entries)) => {
+            for entry in &mut entries[index + 1..] {
+                shift_span(&mut entry.key.span, lines, bytes);
+                shift_node(&mut entry.value, lines, bytes);
+            }
+            entries.splice(index..=index, block_entries);
+        }
+        (NodeKind::List(items), NodeKind::List(block_items)) => {
+            for item in &mut items[index + 1..] {
+                shift_node(item, lines, bytes);
+            }
+            items.splice(index..=index, block_items);
+        }
+        _ => return None,
+    }
+    if index == 0 {
+        root.span.start = block.span.start;
+    }
+    if next.is_some() {
+        shift_location(&mut root.span.end, lines, bytes);
+    } else {
+        root.span.end = block.span.end;
+    }
+
+    let comments = &mut document.comments;
+    let first = comments.partition_point(|comment| comment.span.start.offset < block_start);
+    let last = comments.partition_point(|comment| comment.span.start.offset < block_end);
+    for comment in &mut comments[last..] {
+        shift_span(&mut comment.span, lines, bytes);
+    }
+    comments.splice(first..last, parser.comments);
+
+    Some(Parsed {
+        document,
+        issues: vec![],
+    })
+}
+
+/// Moves the spans of the tree rooted at `node` by `lines` and `bytes`.
+fn shift_node(node: &mut Node, lines: isize, bytes: isize) {
+    shift_span(&mut node.span, lines, bytes);
+    match &mut node.kind {
+        NodeKind::Dict(entries) => {
+            for entry in entries {
+                shift_span(&mut entry.key.span, lines, bytes);
+                shift_node(&mut entry.value, lines, bytes);
+            }
+        }
+        NodeKind::List(items) => {
+            for item in items {
+                shift_node(item, lines, bytes);
+            }
+        }
+        NodeKind::Scalar(_) => (),
+    }
+}
+
+fn shift_span(span: &mut Span, lines: isize, bytes: isize) {
+    shift_location(&mut span.start, lines, bytes);
+    shift_location(&mut span.end, lines, bytes);
+}
+
+/// Moves `location` by `lines` and `bytes`. Its column is kept, as only whole lines move.
+fn shift_location(location: &mut Location, lines: isize, bytes: isize) {
+    location.line = location.line.saturating_add_signed(lines);
+    location.offset = location.offset.saturating_add_signed(bytes);
+}
+
+/// Returns `true` if `text` is a single HUML key, either bare (e.g. `port`) or quoted
+/// (e.g. `"my key"`).
+pub fn is_key(text: &str) -> bool {
+    if text.contains(['\n', '\r']) {
+        return false;
+    }
+    let line = Line {
+        number: 0,
+        offset: 0,
+        text,
+    };
+    let mut cursor = Cursor { line, column: 0 };
+    cursor.parse_key().is_ok() && cursor.rest().is_empty()
+}
+
+/// A line of source text, without its line terminator.
+#[derive(Clone, Copy, Debug)]
+struct Line<'a> {
+    number: usize,
+    offset: usize,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// The number of spaces the line is indented with.
+    fn indent(&self) -> usize {
+        self.text.len() - self.text.trim_start_matches(' ').len()
+    }
+
+    /// The spaces and tabs the line starts with. Only spaces are valid indentation.
+    fn leading_whitespace(&self) -> &'a str {
+        let content = self.text.trim_start_matches([' ', '\t']);
+        &self.text[..self.text.len() - content.len()]
+    }
+
+    /// The line without its indentation.
+    fn content(&self) -> &'a str {
+        &self.text[self.indent()..]
+    }
+
+    /// Returns `true` if the line holds no value, i.e. it's blank or only holds a comment.
+    fn is_trivia(&self) -> bool {
+        let content = self.content().trim_start();
+        content.is_empty() || content.starts_with('#')
+    }
+
+    /// Returns `true` if the line starts with a list item marker (`- `).
+    fn is_list_item(&self) -> bool {
+        let content = self.content();
+        content == "-" || content.starts_with("- ")
+    }
+
+    /// Returns the comment starting at `column`, up to the end of the line.
+    fn comment_at(&self, column: usize) -> Comment {
+        let end = self.text.trim_end().len();
+        Comment {
+            text: self.text[column..end].to_string(),
+            span: Span::new(self.location(column), self.location(end)),
+            trailing: column > self.indent(),
+        }
+    }
+
+    fn location(&self, column: usize) -> Location {
+        Location {
+            line: self.number,
+            column,
+            offset: self.offset + column,
+        }
+    }
+
+    /// The span of the line, excluding indentation and trailing whitespace.
+    fn content_span(&self) -> Span {
+        Span::new(
+            self.location(self.indent()),
+            self.location(self.text.trim_end().len().max(self.indent())),
+        )
+    }
+}
+
+/// A position within a single line, used to parse the values on that line.
+#[derive(Clone, Copy, Debug)]
+struct Cursor<'a> {
+    line: Line<'a>,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Creates a cursor pointing at the first non-indentation character of the line.
+    fn new(line: Line<'a>) -> Self {
+        Self {
+            line,
+            column: line.indent(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.line.text[self.column..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn location(&self) -> Location {
+        self.line.location(self.column)
+    }
+
+    fn span_from(&self, start: Location) -> Span {
+        Span::new(start, self.location())
+    }
+
+    /// The span from the cursor to the end of the line, excluding trailing whitespace.
+    fn span_to_end(&self) -> Span {
+        let end = self.line.text.trim_end().len().max(self.column);
+        Span::new(self.location(), self.line.location(end))
+    }
+
+    /// Consumes `expected` if the rest of the line starts with it.
+    fn eat(&mut self, expected: &str) -> bool {
+        let matched = self.rest().starts_with(expected);
+        if matched {
+            self.column += expected.len();
+        }
+        matched
+    }
+
+    /// Consumes the spaces at the cursor and returns how many were consumed.
+    fn skip_spaces(&mut self) -> usize {
+        let rest = self.rest();
+        let spaces = rest.len() - rest.trim_start_matches(' ').len();
+        self.column += spaces;
+        spaces
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.column += len;
+        &rest[..len]
+    }
+
+    /// Returns `true` if nothing but whitespace or a comment is left on the line.
+    fn is_at_end(&self) -> bool {
+        let rest = self.rest().trim_start();
+        rest.is_empty() || rest.starts_with('#')
+    }
+
+    /// Ensures nothing but whitespace or a comment follows a value. Returns the
+    /// comment, if any.
+    fn expect_end(&mut self) -> Result<Option<Comment>, Issue> {
+        let spaces = self.skip_spaces();
+        let rest = self.rest().trim_end();
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        if spaces > 0 && rest.starts_with('#') {
+            return Ok(Some(self.line.comment_at(self.column)));
+        }
+
+        let message = if rest.starts_with(',') {
+            "unexpected `,`; use `::` to declare a list or dict".to_string()
+        } else {
+            format!("unexpected `{rest}` after value")
+        };
+        Err(Issue::new(message, self.span_to_end()))
+    }
+
+    /// Consumes a `,` separating two items of an inline vector, along with the spaces
+    /// around it. The cursor is left untouched if no separator is found.
+    fn eat_separator(&mut self) -> bool {
+        let mut lookahead = *self;
+        lookahead.skip_spaces();
+        if !lookahead.eat(",") {
+            return false;
+        }
+        lookahead.skip_spaces();
+        *self = lookahead;
+        true
+    }
+
+    /// Parses a bare (`[A-Za-z_][A-Za-z0-9_-]*`) or quoted key.
+    fn parse_key(&mut self) -> Result<Key, Issue> {
+        let start = self.location();
+        if self.peek() == Some('"') {
+            let name = self.parse_string_literal()?;
+            return Ok(Key {
+                name,
+                span: self.span_from(start),
+            });
+        }
+
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            self.column = start.column;
+            return Err(Issue::new("expected a key", self.span_to_end()));
+        }
+
+        Ok(Key {
+            name: name.to_string(),
+            span: self.span_from(start),
+        })
+    }
+
+    /// Returns `true` if the cursor is at a key followed by `:`.
+    fn is_at_key(&self) -> bool {
+        let mut lookahead = *self;
+        lookahead.parse_key().is_ok() && lookahead.peek() == Some(':')
+    }
+
+    /// Parses a double quoted string, resolving escape sequences.
+    fn parse_string_literal(&mut self) -> Result<String, Issue> {
+        let start = self.location();
+        self.eat("\"");
+
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.column += idx + 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .filter(|_| hex.len() == 4)
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| {
+                                    let escape_start = self.line.location(self.column + idx);
+                                    Issue::new(
+                                        format!("invalid unicode escape `\\u{hex}`"),
+                                        Span::new(escape_start, escape_start),
+                                    )
+                                })?
+                        }
+                        other => {
+                            let escape_start = self.line.location(self.column + idx);
+                            let escape = other.map(String::from).unwrap_or_default();
+                            return Err(Issue::new(
+                                format!("invalid escape sequence `\\{escape}`"),
+                                Span::new(escape_start, escape_start),
+                            ));
+                        }
+                    };
+                    value.push(escaped);
+                }
+                c => value.push(c),
+            }
+        }
+
+        self.column = self.line.text.len();
+        Err(Issue::new("unterminated string", self.span_from(start)))
+    }
+
+    /// Parses a single scalar value.
+    fn parse_scalar(&mut self) -> Result<Node, Issue> {
+        let start = self.location();
+        let scalar = if self.peek() == Some('"') {
+            Scalar::String(self.parse_string_literal()?)
+        } else {
+            let token = self.take_while(|c| !c.is_whitespace() && c != ',');
+            if token.is_empty() {
+                return Err(Issue::new("expected a value", self.span_to_end()));
+            }
+            match parse_keyword(token) {
+                Some(scalar) => scalar,
+                None if token.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+') => {
+                    parse_number(token).map_err(|e| {
+                        Issue::new(
+                            format!("invalid number `{token}`; {e}"),
+                            self.span_from(start),
+                        )
+                    })?
+                }
+                None => {
+                    let message = if is_boolean_like(token) {
+                        format!(
+                            "invalid value `{token}`; booleans are written `true` or `false`, \
+                             and strings must be quoted"
+                        )
+                    } else {
+                        format!("invalid value `{token}`; strings must be quoted")
+                    };
+                    return Err(Issue::new(message, self.span_from(start)));
+                }
+            }
+        };
+
+        Ok(Node {
+            kind: NodeKind::Scalar(scalar),
+            span: self.span_from(start),
+            layout: Layout::Inline,
+        })
+    }
+
+    /// Parses an inline vector, i.e. the values following `::` on the same line.
+    ///
+    /// This is either `[]` or `{}` for empty vectors, comma separated scalars for a
+    /// list, or comma separated `key: value` pairs for a dict.
+    fn parse_inline_vector(&mut self) -> Result<Node, Issue> {
+        let start = self.location();
+        if self.eat("[]") {
+            return Ok(Node {
+                kind: NodeKind::List(vec![]),
+                span: self.span_from(start),
+                layout: Layout::Inline,
+            });
+        }
+        if self.eat("{}") {
+            return Ok(Node {
+                kind: NodeKind::Dict(vec![]),
+                span: self.span_from(start),
+                layout: Layout::Inline,
+            });
+        }
+
+        let kind = if self.is_at_key() {
+            let mut entries = vec![];
+            loop {
+                let key = self.parse_key()?;
+                self.eat(":");
+                if self.peek() == Some(':') {
+                    return Err(Issue::new(
+                        "nested vectors are not allowed in an inline dict",
+                        self.span_to_end(),
+                    ));
+                }
+                self.skip_spaces();
+                let value = self.parse_scalar()?;
+                entries.push(Entry { key, value });
+                if !self.eat_separator() {
+                    break;
+                }
+            }
+            NodeKind::Dict(entries)
+        } else {
+            let mut items = vec![];
+            loop {
+                items.push(self.parse_scalar()?);
+                if !self.eat_separator() {
+                    break;
+                }
+            }
+            NodeKind::List(items)
+        };
+
+        Ok(Node {
+            kind,
+            span: self.span_from(start),
+            layout: Layout::Inline,
+        })
+    }
+}
+
+/// Parses a scalar that is a keyword, e.g. `true`, `null` or `inf`.
+fn parse_keyword(token: &str) -> Option<Scalar> {
+    let scalar = match token {
+        // Any case is accepted, the canonical spelling is checked by the validation passes
+        token if token.eq_ignore_ascii_case("true") => Scalar::Boolean(true),
+        token if token.eq_ignore_ascii_case("false") => Scalar::Boolean(false),
+        "null" => Scalar::Null,
+        "nan" => Scalar::Float(f64::NAN),
+        "inf" | "+inf" => Scalar::Float(f64::INFINITY),
+        "-inf" => Scalar::Float(f64::NEG_INFINITY),
+        _ => return None,
+    };
+    Some(scalar)
+}
+
+/// Returns `true` if `token` is a word commonly used as a boolean in other languages,
+/// e.g. `yes` or `off`.
+fn is_boolean_like(token: &str) -> bool {
+    ["yes", "no", "on", "off"]
+        .iter()
+        .any(|word| token.eq_ignore_ascii_case(word))
+}
+
+/// The reasons a token starting like a number isn't a valid one.
+#[derive(thiserror::Error, Clone, Copy, PartialEq, Eq, Debug)]
+enum NumberError {
+    /// A character isn't a digit where one is expected, e.g. `12ab`.
+    #[error("expected digits")]
+    NotADigit,
+    /// The digits following a `0x`, `0o` or `0b` prefix are missing or not in its base.
+    #[error("expected {0} digits")]
+    NotARadixDigit(&'static str),
+    /// A decimal integer starts with a `0`, e.g. `01`.
+    #[error("leading zeros are not allowed")]
+    LeadingZero,
+    /// A `.` isn't followed by digits, e.g. `1.`.
+    #[error("expected digits after `.`")]
+    MissingFraction,
+    /// An exponent has no digits, e.g. `1e`.
+    #[error("expected digits in the exponent")]
+    MissingExponent,
+    /// A `_` doesn't separate two digits, e.g. `1__0` or `1_`.
+    #[error("`_` must be placed between digits")]
+    MisplacedUnderscore,
+    /// The integer doesn't fit in 64 bits.
+    #[error("the integer is too large")]
+    OutOfRange,
+}
+
+/// Parses decimal integers and floats, and hexadecimal (`0x`), octal (`0o`) and
+/// binary (`0b`) integers, optionally signed. Digits may be separated with `_`, and
+/// decimal integers can't have leading zeros.
+fn parse_number(token: &str) -> Result<Scalar, NumberError> {
+    let (negative, unsigned) = match token.as_bytes().first() {
+        Some(b'-') => (true, &token[1..]),
+        Some(b'+') => (false, &token[1..]),
+        _ => (false, token),
+    };
+
+    for (prefix, radix, name) in [
+        ("0x", 16, "hexadecimal"),
+        ("0o", 8, "octal"),
+        ("0b", 2, "binary"),
+    ] {
+        if let Some(digits) = unsigned.strip_prefix(prefix) {
+            check_digit_group(digits, |c| c.is_digit(radix))
+                .map_err(|e| e.unwrap_or(NumberError::NotARadixDigit(name)))?;
+            let value = i64::from_str_radix(&digits.replace('_', ""), radix)
+                .map_err(|_| NumberError::OutOfRange)?;
+            return Ok(Scalar::Integer(if negative { -value } else { value }));
+        }
+    }
+
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+
+    let check_decimal = |digits: &str, missing: NumberError| {
+        check_digit_group(digits, |c| c.is_ascii_digit()).map_err(|e| e.unwrap_or(missing))
+    };
+    check_decimal(integer, NumberError::NotADigit)?;
+    if integer.len() > 1 && integer.starts_with('0') {
+        return Err(NumberError::LeadingZero);
+    }
+    if let Some(fraction) = fraction {
+        check_decimal(fraction, NumberError::MissingFraction)?;
+    }
+    if let Some(exponent) = exponent {
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        check_decimal(digits, NumberError::MissingExponent)?;
+    }
+
+    let literal = token.replace('_', "");
+    if fraction.is_some() || exponent.is_some() {
+        literal
+            .parse()
+            .map(Scalar::Float)
+            .map_err(|_| NumberError::NotADigit)
+    } else {
+        literal
+            .parse()
+            .map(Scalar::Integer)
+            .map_err(|_| NumberError::OutOfRange)
+    }
+}
+
+/// Checks that `digits` is a non-empty sequence of digits, optionally separated by
+/// single underscores. Fails with `None` if there are no digits or if a character isn't
+/// one, for the caller to tell what was expected.
+fn check_digit_group(
+    digits: &str,
+    is_digit: impl Fn(char) -> bool,
+) -> Result<(), Option<NumberError>> {
+    if digits.is_empty() || !digits.chars().all(|c| c == '_' || is_digit(c)) {
+        return Err(None);
+    }
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return Err(Some(NumberError::MisplacedUnderscore));
+    }
+    Ok(())
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    /// Index of the next line to be parsed
+    pos: usize,
+    issues: Vec<Issue>,
+    comments: Vec<Comment>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self::starting_at(source, 0, 0)
+    }
+
+    /// Creates a parser for `source`, a part of a larger source starting at the start of
+    /// line `first_line`, at byte `offset`.
+    fn starting_at(source: &'a str, first_line: usize, mut offset: usize) -> Self {
+        let mut lines = vec![];
+        for (number, raw_line) in (first_line..).zip(source.split_inclusive('\n')) {
+            let text = raw_line.strip_suffix('\n').unwrap_or(raw_line);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            lines.push(Line {
+                number,
+                offset,
+                text,
+            });
+            offset += raw_line.len();
+        }
+
+        Self {
+            lines,
+            pos: 0,
+            issues: vec![],
+            comments: vec![],
+        }
+    }
+
+    /// Returns the next line holding a value, skipping over blank and comment lines.
+    fn peek_line(&mut self) -> Option<Line<'a>> {
+        while let Some(line) = self.lines.get(self.pos) {
+            if !line.is_trivia() {
+                return Some(*line);
+            }
+            if let Some(column) = line.text.find('#') {
+                self.comments.push(line.comment_at(column));
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    /// Skips the lines nested under a line indented by `indent`.
+    fn skip_nested(&mut self, indent: usize) {
+        while self.peek_line().is_some_and(|line| line.indent() > indent) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_document(&mut self) -> Document {
+        let version = self.parse_version_directive();
+        let root = self.parse_root();
+
+        while let Some(line) = self.peek_line() {
+            self.issues.push(Issue::new(
+                "unexpected content after the root value",
+                line.content_span(),
+            ));
+            self.pos += 1;
+        }
+
+        Document {
+            version,
+            root,
+            comments: std::mem::take(&mut self.comments),
+        }
+    }
+
+    fn parse_version_directive(&mut self) -> Option<Version> {
+        let line = self.peek_line()?;
+        let arguments = line.content().strip_prefix(VERSION_DIRECTIVE)?;
+        self.pos += 1;
+
+        if let Some(column) = line.text.find('#') {
+            self.comments.push(line.comment_at(column));
+        }
+        let version = arguments
+            .split('#')
+            .next()
+            .and_then(|arguments| arguments.split_whitespace().next());
+        if !arguments.starts_with(' ') || version.is_none() {
+            self.issues.push(Issue::new(
+                "invalid version directive; expected `%HUML <version>`",
+                line.content_span(),
+            ));
+        }
+
+        version.map(|version| Version {
+            value: version.to_string(),
+            span: line.content_span(),
+        })
+    }
+
+    fn parse_root(&mut self) -> Option<Node> {
+        let line = self.peek_line()?;
+        if line.is_list_item() || Cursor::new(line).is_at_key() {
+            return self.parse_block(None);
+        }
+
+        self.pos += 1;
+        let cursor = Cursor::new(line);
+        let result = if cursor.rest().starts_with(MULTILINE_QUOTE) {
+            self.parse_multiline_string(cursor)
+        } else {
+            self.parse_inline_root(cursor)
+        };
+
+        result.map_err(|issue| self.issues.push(issue)).ok()
+    }
+
+    /// Parses an indented block holding either a dict or a list. Returns `None` if the
+    /// next line isn't indented further than `parent_indent`.
+    fn parse_block(&mut self, parent_indent: Option<usize>) -> Option<Node> {
+        let line = self.peek_line()?;
+        let indent = line.indent();
+        if parent_indent.is_some_and(|parent_indent| indent <= parent_indent) {
+            return None;
+        }
+
+        let block = if line.is_list_item() {
+            self.parse_list_block(indent)
+        } else {
+            self.parse_dict_block(indent)
+        };
+        Some(block)
+    }
+
+    /// Parses the lines of a block indented by `indent`. Each line is handled by
+    /// `parse_line`, which returns `None` if the line couldn't be parsed.
+    fn parse_block_lines<T>(
+        &mut self,
+        indent: usize,
+        is_item: impl Fn(&Line) -> bool,
+        unexpected_item: &str,
+        mut parse_line: impl FnMut(&mut Self, Line<'a>) -> Option<(T, Span)>,
+    ) -> (Vec<T>, Span) {
+        let first_line = self.peek_line().expect("Blocks contain at least one line");
+        let mut span = first_line.content_span();
+        let mut items = vec![];
+
+        while let Some(line) = self.peek_line() {
+            let whitespace = line.leading_whitespace();
+            if whitespace.contains('\t') {
+                let span = Span::new(line.location(0), line.location(whitespace.len()));
+                self.issues.push(Issue::new(
+                    format!("indentation must use spaces, not tabs; expected {indent} spaces"),
+                    span,
+                ));
+                self.pos += 1;
+                self.skip_nested(indent);
+                continue;
+            }
+
+            match line.indent().cmp(&indent) {
+                Ordering::Less => break,
+                Ordering::Greater => {
+                    self.issues.push(Issue::new(
+                        format!("unexpected indentation; expected {indent} spaces"),
+                        line.content_span(),
+                    ));
+                    self.pos += 1;
+                    self.skip_nested(indent);
+                    continue;
+                }
+                Ordering::Equal => (),
+            }
+
+            if !is_item(&line) {
+                self.issues
+                    .push(Issue::new(unexpected_item, line.content_span()));
+                self.pos += 1;
+                self.skip_nested(indent);
+                continue;
+            }
+
+            let item_start = line.location(indent);
+            if let Some((item, item_span)) = parse_line(self, line) {
+                if items.is_empty() {
+                    span = Span::new(item_start, item_span.end);
+                } else {
+                    span = span.to(item_span);
+                }
+                items.push(item);
+            }
+        }
+
+        (items, span)
+    }
+
+    fn parse_dict_block(&mut self, indent: usize) -> Node {
+        let (entries, span) = self.parse_block_lines(
+            indent,
+            |line| !line.is_list_item(),
+            "expected a key, found a list item",
+            |parser, line| {
+                let entry = parser.parse_entry(line)?;
+                let span = entry.span();
+                Some((entry, span))
+            },
+        );
+        Node {
+            kind: NodeKind::Dict(entries),
+            span,
+            layout: Layout::Block,
+        }
+    }
+
+    fn parse_list_block(&mut self, indent: usize) -> Node {
+        let (items, span) = self.parse_block_lines(
+            indent,
+            |line| line.is_list_item(),
+            "expected a list item (`- `), found a key",
+            |parser, line| {
+                let item = parser.parse_list_item(line)?;
+                let span = item.span;
+                Some((item, span))
+            },
+        );
+        Node {
+            kind: NodeKind::List(items),
+            span,
+            layout: Layout::Block,
+        }
+    }
+
+    /// Parses a `key: value` or `key:: vector` line, and any block nested under it.
+    fn parse_entry(&mut self, line: Line<'a>) -> Option<Entry> {
+        self.pos += 1;
+        let mut cursor = Cursor::new(line);
+        let entry = cursor.parse_key().and_then(|key| {
+            let value = if cursor.eat("::") {
+                self.parse_vector(cursor, line.indent())?
+            } else if cursor.eat(":") {
+                self.parse_scalar_value(cursor)?
+            } else {
+                return Err(Issue::new(
+                    format!("expected `:` after key `{}`", key.name),
+                    cursor.span_to_end(),
+                ));
+            };
+            Ok(Entry { key, value })
+        });
+        self.recover(entry, line.indent())
+    }
+
+    /// Parses a `- value` or `- :: vector` line, and any block nested under it.
+    fn parse_list_item(&mut self, line: Line<'a>) -> Option<Node> {
+        self.pos += 1;
+        let mut cursor = Cursor::new(line);
+        cursor.eat("-");
+        let item = if cursor.is_at_end() {
+            Err(Issue::new(
+                "expected a value after `-`",
+                line.content_span(),
+            ))
+        } else {
+            cursor.skip_spaces();
+            if cursor.eat("::") {
+                // Blocks start on the next line, so they are extended to start at the
+                // `-` marker introducing them.
+                self.parse_vector(cursor, line.indent()).map(|mut vector| {
+                    if vector.layout == Layout::Block {
+                        vector.span.start = line.location(line.indent());
+                    }
+                    vector
+                })
+            } else {
+                self.parse_scalar_value(cursor)
+            }
+        };
+        self.recover(item, line.indent())
+    }
+
+    /// Records the issue of a line that failed to parse and skips the lines nested
+    /// under it.
+    fn recover<T>(&mut self, result: Result<T, Issue>, indent: usize) -> Option<T> {
+        result
+            .map_err(|issue| {
+                self.issues.push(issue);
+                self.skip_nested(indent);
+            })
+            .ok()
+    }
+
+    /// Parses the scalar following a `:` indicator or a list item marker.
+    fn parse_scalar_value(&mut self, mut cursor: Cursor<'a>) -> Result<Node, Issue> {
+        cursor.skip_spaces();
+        if cursor.rest().starts_with(MULTILINE_QUOTE) {
+            return self.parse_multiline_string(cursor);
+        }
+        if cursor.is_at_end() {
+            return Err(Issue::new(
+                "expected a value after `:`",
+                cursor.span_to_end(),
+            ));
+        }
+
+        let value = cursor.parse_scalar()?;
+        self.expect_end(&mut cursor)?;
+        Ok(value)
+    }
+
+    /// Parses the vector following a `::` indicator. The vector is either inline, on the
+    /// same line, or a block nested under the line indented by `indent`.
+    fn parse_vector(&mut self, mut cursor: Cursor<'a>, indent: usize) -> Result<Node, Issue> {
+        let indicator_end = cursor.location();
+        if cursor.is_at_end() {
+            self.expect_end(&mut cursor)?;
+            let indicator_start = cursor.line.location(indicator_end.column - "::".len());
+            return self.parse_block(Some(indent)).ok_or_else(|| {
+                Issue::new(
+                    "expected an indented block after `::`",
+                    Span::new(indicator_start, indicator_end),
+                )
+            });
+        }
+
+        cursor.skip_spaces();
+        let vector = cursor.parse_inline_vector()?;
+        self.expect_end(&mut cursor)?;
+        Ok(vector)
+    }
+
+    /// Parses a `"""` delimited string spanning multiple lines. The indentation of the
+    /// least indented line is stripped from every line of the string.
+    fn parse_multiline_string(&mut self, mut cursor: Cursor<'a>) -> Result<Node, Issue> {
+        let start = cursor.location();
+        cursor.eat(MULTILINE_QUOTE);
+        self.expect_end(&mut cursor)?;
+
+        let mut content_lines = vec![];
+        let end = loop {
+            let Some(line) = self.lines.get(self.pos).copied() else {
+                return Err(Issue::new(
+                    "unterminated multiline string",
+                    Span::new(start, cursor.location()),
+                ));
+            };
+            self.pos += 1;
+
+            if line.content().trim_end() == MULTILINE_QUOTE {
+                break line.location(line.indent() + MULTILINE_QUOTE.len());
+            }
+            content_lines.push(line);
+        };
+
+        let strip = content_lines
+            .iter()
+            .filter(|line| !line.text.trim().is_empty())
+            .map(Line::indent)
+            .min()
+            .unwrap_or(0);
+        let value = content_lines
+            .iter()
+            .map(|line| line.text.get(strip..).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(Node {
+            kind: NodeKind::Scalar(Scalar::String(value)),
+            span: Span::new(start, end),
+            layout: Layout::Block,
+        })
+    }
+
+    /// Parses a root value held on a single line: a scalar, or an inline list or empty
+    /// vector.
+    fn parse_inline_root(&mut self, mut cursor: Cursor<'a>) -> Result<Node, Issue> {
+        let start = cursor.location();
+        let value = if cursor.rest().starts_with("[]") || cursor.rest().starts_with("{}") {
+            cursor.parse_inline_vector()?
+        } else {
+            let first = cursor.parse_scalar()?;
+            if cursor.eat_separator() {
+                let mut items = vec![first];
+                loop {
+                    items.push(cursor.parse_scalar()?);
+                    if !cursor.eat_separator() {
+                        break;
+                    }
+                }
+                Node {
+                    kind: NodeKind::List(items),
+                    span: cursor.span_from(start),
+                    layout: Layout::Inline,
+                }
+            } else {
+                first
+            }
+        };
+        self.expect_end(&mut cursor)?;
+        Ok(value)
+    }
+
+    /// Ensures nothing but whitespace or a comment is left on the line of `cursor`,
+    /// recording the comment if there is one.
+    fn expect_end(&mut self, cursor: &mut Cursor<'a>) -> Result<(), Issue> {
+        if let Some(comment) = cursor.expect_end()? {
+            self.comments.push(comment);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_valid(source: &str) -> Document {
+        let parsed = parse(source);
+        assert!(parsed.is_valid(), "Unexpected issues: {:?}", parsed.issues);
+        parsed.document
+    }
+
+    fn root(source: &str) -> NodeKind {
+        parse_valid(source)
+            .root
+            .expect("Expected a root value")
+            .kind
+    }
+
+    fn dict(kind: &NodeKind) -> &[Entry] {
+        match kind {
+            NodeKind::Dict(entries) => entries,
+            other => panic!("Expected a dict, found {other:?}"),
+        }
+    }
+
+    fn list(kind: &NodeKind) -> &[Node] {
+        match kind {
+            NodeKind::List(items) => items,
+            other => panic!("Expected a list, found {other:?}"),
+        }
+    }
+
+    fn scalar(node: &Node) -> &Scalar {
+        match &node.kind {
+            NodeKind::Scalar(scalar) => scalar,
+            other => panic!("Expected a scalar, found {other:?}"),
+        }
+    }
+
+    fn issue_messages(source: &str) -> Vec<String> {
+        parse(source)
+            .issues
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_empty_document() {
+        let document = parse_valid("# Only a comment\n\n");
+        assert_eq!(document.root, None);
+    }
+
+    #[test]
+    fn should_parse_version_directive() {
+        let document = parse_valid("%HUML v0.1.0\nkey: 1\n");
+        assert_eq!(
+            document.version.map(|version| version.value).as_deref(),
+            Some("v0.1.0")
+        );
+    }
+
+    #[test]
+    fn should_parse_scalars() {
+        let root = root(concat!(
+            "string: \"hello \\\"world\\\"\"\n",
+            "integer: -1_000\n",
+            "hex: 0xFF\n",
+            "float: 1.5e3\n",
+            "boolean: true\n",
+            "nothing: null\n",
+            "infinity: -inf\n",
+        ));
+        let values: Vec<_> = dict(&root)
+            .iter()
+            .map(|entry| scalar(&entry.value))
+            .collect();
+
+        assert_eq!(
+            values,
+            [
+                &Scalar::String("hello \"world\"".to_string()),
+                &Scalar::Integer(-1000),
+                &Scalar::Integer(255),
+                &Scalar::Float(1500.0),
+                &Scalar::Boolean(true),
+                &Scalar::Null,
+                &Scalar::Float(f64::NEG_INFINITY),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_parse_nested_blocks() {
+        let root = root(concat!(
+            "server::\n",
+            "  host: \"localhost\"\n",
+            "  ports::\n",
+            "    - 80\n",
+            "    - ::\n",
+            "      secure: true\n",
+            "name: \"test\"\n",
+        ));
+        let entries = dict(&root);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key.name, "server");
+        assert_eq!(entries[1].key.name, "name");
+
+        let server = dict(&entries[0].value.kind);
+        assert_eq!(server[0].key.name, "host");
+        let ports = list(&server[1].value.kind);
+        assert_eq!(scalar(&ports[0]), &Scalar::Integer(80));
+        let nested = dict(&ports[1].kind);
+        assert_eq!(nested[0].key.name, "secure");
+
+        // Blocks of list items start at their first `-` marker
+        assert_eq!(server[1].value.span.start.column, 4);
+        assert_eq!(ports[1].span.start.line, 4);
+        assert_eq!(ports[1].span.start.column, 4);
+    }
+
+    #[test]
+    fn should_parse_inline_vectors() {
+        let root = root(concat!(
+            "numbers:: 1, 2, 3\n",
+            "point:: x: 1, y: 2\n",
+            "empty_list:: []\n",
+            "empty_dict:: {}\n",
+        ));
+        let entries = dict(&root);
+        assert_eq!(list(&entries[0].value.kind).len(), 3);
+        let point = dict(&entries[1].value.kind);
+        assert_eq!(point[1].key.name, "y");
+        assert!(list(&entries[2].value.kind).is_empty());
+        assert!(dict(&entries[3].value.kind).is_empty());
+    }
+
+    #[test]
+    fn should_parse_root_list() {
+        let root = root("- 1\n- \"two\"\n");
+        assert_eq!(list(&root).len(), 2);
+    }
+
+    #[test]
+    fn should_parse_root_scalar() {
+        assert_eq!(
+            root("\"just a string\"  # trailing comment\n"),
+            NodeKind::Scalar(Scalar::String("just a string".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_parse_multiline_string() {
+        let root = root("text: \"\"\"\n  first\n    second\n\"\"\"\nafter: 1\n");
+        let entries = dict(&root);
+        assert_eq!(
+            scalar(&entries[0].value),
+            &Scalar::String("first\n  second".to_string())
+        );
+        assert_eq!(entries[0].value.span.end.line, 3);
+        assert_eq!(entries[1].key.name, "after");
+    }
+
+    #[test]
+    fn should_track_spans() {
+        let root = root("outer::\n  inner: \"value\"\n");
+        let outer = &dict(&root)[0];
+        let inner = &dict(&outer.value.kind)[0];
+
+        assert_eq!(
+            inner.key.span,
+            Span::new(
+                Location {
+                    line: 1,
+                    column: 2,
+                    offset: 10
+                },
+                Location {
+                    line: 1,
+                    column: 7,
+                    offset: 15
+                }
+            )
+        );
+        assert_eq!(inner.value.span.start.column, 9);
+        assert_eq!(inner.value.span.end.column, 16);
+        assert_eq!(outer.span().start.line, 0);
+        assert_eq!(outer.span().end.line, 1);
+    }
+
+    #[test]
+    fn should_collect_comments() {
+        let document = parse_valid(concat!(
+            "%HUML v0.1.0 # version\n",
+            "# leading\n",
+            "a: \"#not a comment\"  # trailing\n",
+            "b::\n",
+            "    # nested\n",
+            "  - 1\n",
+        ));
+        let comments: Vec<_> = document
+            .comments
+            .iter()
+            .map(|comment| {
+                (
+                    comment.text.as_str(),
+                    comment.span.start.line,
+                    comment.trailing,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            comments,
+            [
+                ("# version", 0, true),
+                ("# leading", 1, false),
+                ("# trailing", 2, true),
+                ("# nested", 4, false)
+            ]
+        );
+        assert_eq!(document.comments[2].span.start.column, 21);
+    }
+
+    #[test]
+    fn should_handle_crlf_line_endings() {
+        let root = root("a: 1\r\nb: 2\r\n");
+        let entries = dict(&root);
+        assert_eq!(entries[1].key.span.start.offset, 6);
+        assert_eq!(scalar(&entries[1].value), &Scalar::Integer(2));
+    }
+
+    #[test]
+    fn should_report_unquoted_string() {
+        assert_eq!(
+            issue_messages("key: value\n"),
+            ["invalid value `value`; strings must be quoted"]
+        );
+    }
+
+    #[test]
+    fn should_parse_booleans_in_any_case() {
+        let root = root("a: True\nb: FALSE\n");
+        let entries = dict(&root);
+
+        assert_eq!(scalar(&entries[0].value), &Scalar::Boolean(true));
+        assert_eq!(scalar(&entries[1].value), &Scalar::Boolean(false));
+    }
+
+    #[test]
+    fn should_suggest_booleans_for_boolean_like_words() {
+        assert_eq!(
+            issue_messages("enabled: yes\n"),
+            [
+                "invalid value `yes`; booleans are written `true` or `false`, and strings must be quoted"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_invalid_numbers() {
+        let cases = [
+            ("01", "leading zeros are not allowed"),
+            ("-00.5", "leading zeros are not allowed"),
+            ("1.", "expected digits after `.`"),
+            ("1.e5", "expected digits after `.`"),
+            ("1e", "expected digits in the exponent"),
+            ("2.5E+", "expected digits in the exponent"),
+            ("1__0", "`_` must be placed between digits"),
+            ("1_", "`_` must be placed between digits"),
+            ("12ab", "expected digits"),
+            ("-", "expected digits"),
+            ("0x", "expected hexadecimal digits"),
+            ("0b102", "expected binary digits"),
+            ("9223372036854775808", "the integer is too large"),
+        ];
+
+        for (number, reason) in cases {
+            assert_eq!(
+                issue_messages(&format!("a: {number}\n")),
+                [format!("invalid number `{number}`; {reason}")],
+                "for `{number}`"
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_invalid_numbers_precisely() {
+        let issues = parse("a:: 1, 01, 3\n").issues;
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].span.start.column, 7);
+        assert_eq!(issues[0].span.end.column, 9);
+    }
+
+    #[test]
+    fn should_parse_valid_numbers() {
+        let cases = [
+            ("0", Scalar::Integer(0)),
+            ("-0", Scalar::Integer(0)),
+            ("+5", Scalar::Integer(5)),
+            ("-42", Scalar::Integer(-42)),
+            ("1_000", Scalar::Integer(1000)),
+            ("0x1F", Scalar::Integer(31)),
+            ("0o17", Scalar::Integer(15)),
+            ("0b1010", Scalar::Integer(10)),
+            ("0.5", Scalar::Float(0.5)),
+            ("-3.25", Scalar::Float(-3.25)),
+            ("1e3", Scalar::Float(1000.0)),
+            ("6.02E+23", Scalar::Float(6.02e23)),
+            ("1e-05", Scalar::Float(1e-5)),
+        ];
+
+        for (number, expected) in cases {
+            let node = parse_valid(&format!("{number}\n")).root.unwrap();
+            assert_eq!(scalar(&node), &expected, "for `{number}`");
+        }
+    }
+
+    #[test]
+    fn should_report_missing_block() {
+        assert_eq!(
+            issue_messages("key::\nother: 1\n"),
+            ["expected an indented block after `::`"]
+        );
+    }
+
+    #[test]
+    fn should_report_scalar_list_without_vector_indicator() {
+        assert_eq!(
+            issue_messages("key: 1, 2\n"),
+            ["unexpected `,`; use `::` to declare a list or dict"]
+        );
+    }
+
+    #[test]
+    fn should_recover_after_invalid_line() {
+        let parsed = parse("a: oops\n  nested: 1\nb: 2\n");
+        assert_eq!(parsed.issues.len(), 1);
+
+        let root = parsed.document.root.unwrap().kind;
+        let entries = dict(&root);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key.name, "b");
+    }
+
+    #[test]
+    fn should_report_mixed_block_items() {
+        assert_eq!(
+            issue_messages("a: 1\n- 2\n"),
+            ["expected a key, found a list item"]
+        );
+    }
+
+    #[test]
+    fn should_report_unexpected_indentation() {
+        assert_eq!(
+            issue_messages("a: 1\n    b: 2\n"),
+            ["unexpected indentation; expected 0 spaces"]
+        );
+    }
+
+    #[test]
+    fn should_report_tab_indentation() {
+        let source = "a::\n  b: 1\n\tc: 2\n  \td: 3\n  e: 4\n";
+        let parsed = parse(source);
+
+        let messages: Vec<_> = parsed.issues.iter().map(|issue| &issue.message).collect();
+        assert_eq!(
+            messages,
+            [
+                "indentation must use spaces, not tabs; expected 2 spaces",
+                "indentation must use spaces, not tabs; expected 2 spaces",
+            ]
+        );
+        assert_eq!(parsed.issues[0].span.start.line, 2);
+        assert_eq!(parsed.issues[1].span.end.column, 3);
+        // The lines around are still parsed
+        let NodeKind::Dict(entries) = &parsed.document.root.unwrap().kind else {
+            panic!("Expected a dict");
+        };
+        assert_eq!(dict(&entries[0].value.kind).len(), 2);
+    }
+
+    #[test]
+    fn should_report_unterminated_string() {
+        assert_eq!(issue_messages("a: \"open\n"), ["unterminated string"]);
+        assert_eq!(
+            issue_messages("a: \"\"\"\n  text\n"),
+            ["unterminated multiline string"]
+        );
+    }
+
+    #[test]
+    fn should_recognize_keys() {
+        for key in ["port", "_private", "max-size2", "\"a key\"", "\"\""] {
+            assert!(is_key(key), "{key} should be a key");
+        }
+        for text in ["", "2fast", "a key", " port", "port:", "\"open", "\"a\nb\""] {
+            assert!(!is_key(text), "{text} shouldn't be a key");
+        }
+    }
+
+    /// Replaces the first `old` of `source` with `new`, returning the edited source along
+    /// with the edit
+    fn edit(source: &str, old: &str, new: &str) -> (String, SourceEdit) {
+        let start = source.find(old).expect("Expected the text to replace");
+        let edited = [&source[..start], new, &source[start + old.len()..]].concat();
+        let edit = SourceEdit {
+            start,
+            old_end: start + old.len(),
+            new_end: start + new.len(),
+        };
+        (edited, edit)
+    }
+
+    const SOURCE: &str = concat!(
+        "%HUML v0.1.0\n",
+        "# Server settings\n",
+        "name: \"huml\"\n",
+        "server::\n",
+        "  host: \"localhost\" # local only\n",
+        "  port: 80\n",
+        "\n",
+        "# Enabled features\n",
+        "features:: \"hover\", \"rename\"\n",
+        "notes: \"\"\"\n",
+        "  first\n",
+        "\"\"\"\n",
+    );
+
+    #[test]
+    fn should_reparse_edited_block_only() {
+        let previous = parse(SOURCE);
+        for (old, new) in [
+            ("80", "8080"),
+            ("  port: 80\n", ""),
+            ("  port: 80\n", "  port: 80\n  # TLS\n  tls: true\n"),
+            ("\"huml\"", "\"huml-lsp\" # renamed"),
+            ("name", "title"),
+            ("\n# Enabled", "\n\n\n# Enabled"),
+            ("\"rename\"", "\"rename\", \"format\""),
+            ("first\n", "first\n  second\n"),
+            ("features::", "extra: 1\nfeatures::"),
+        ] {
+            let (source, edit) = edit(SOURCE, old, new);
+
+            let reparsed = reparse_block(&previous, &source, edit)
+                .unwrap_or_else(|| panic!("Expected `{old}` to be reparsed in its block"));
+
+            assert_eq!(
+                reparsed.document,
+                parse(&source).document,
+                "Replacing `{old}`"
+            );
+            assert!(reparsed.is_valid());
+        }
+    }
+
+    #[test]
+    fn should_reparse_whole_source_unless_edit_is_in_a_block() {
+        let previous = parse(SOURCE);
+        for (old, new) in [
+            // Spanning multiple blocks
+            ("80\n\n# Enabled features\nfeatures", "80\nfeatures"),
+            ("\"huml\"\nserver", "\"huml\" server"),
+            ("\"rename\"\n", "\"rename\""),
+            // Before the root
+            ("# Server settings\n", ""),
+            // Nesting the block under the previous one
+            ("features::", "  features::"),
+            // Introducing syntax errors
+            ("80", "eighty"),
+            ("\"\"\"\n", "\n"),
+        ] {
+            let (source, edit) = edit(SOURCE, old, new);
+
+            assert!(
+                reparse_block(&previous, &source, edit).is_none(),
+                "Expected `{old}` to be reparsed in full"
+            );
+            let reparsed = reparse(&previous, &source, edit);
+            assert_eq!(reparsed.document, parse(&source).document);
+            assert_eq!(reparsed.issues, parse(&source).issues);
+        }
+    }
+
+    #[test]
+    fn should_reparse_in_full_after_syntax_errors() {
+        let (source, edit) = edit("a: oops\nb: 2\n", "2", "3");
+
+        assert!(reparse_block(&parse("a: oops\nb: 2\n"), &source, edit).is_none());
+    }
+
+    #[test]
+    fn should_reparse_root_list_items() {
+        let previous_source = "- 1\n- ::\n  - 2\n- 3\n";
+        let (source, edit) = edit(previous_source, "  - 2\n", "  - 2\n  - 2.5\n");
+
+        let reparsed = reparse_block(&parse(previous_source), &source, edit)
+            .expect("Expected the item to be reparsed in its block");
+
+        assert_eq!(reparsed.document, parse(&source).document);
+    }
+}
@@ -0,0 +1,356 @@
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+use crate::huml::{
+    Issue, IssueTag, Rule,
+    ast::{Document, Entry, Node, NodeKind, Scalar, Span},
+};
+
+/// The expected shape of a document, written in a subset of
+/// [JSON Schema](https://json-schema.org), e.g.
+///
+/// ```json
+/// {
+///   "type": "object",
+///   "properties": { "port": { "type": "integer" } },
+///   "required": ["port"],
+///   "additionalProperties": false
+/// }
+/// ```
+///
+/// Only `type`, `description`, `deprecated`, `properties`, `required`,
+/// `additionalProperties` and `items` are supported. As in JSON Schema, keys that aren't listed in `properties` are allowed
+/// unless `additionalProperties` is `false`.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    /// The type of the value, any type being accepted if `None`.
+    #[serde(default, rename = "type")]
+    kind: Option<SchemaType>,
+    /// What the value is for, shown to the user while writing it.
+    #[serde(default)]
+    description: Option<String>,
+    /// Whether the value shouldn't be used anymore. Deprecated entries of a dict are
+    /// reported on their key.
+    #[serde(default)]
+    deprecated: bool,
+    /// The schemas of the entries of a dict, by key.
+    #[serde(default)]
+    properties: IndexMap<String, Schema>,
+    /// The keys a dict must hold.
+    #[serde(default)]
+    required: Vec<String>,
+    /// Whether a dict can hold keys that aren't listed in `properties`.
+    #[serde(default = "additional_properties_default")]
+    additional_properties: bool,
+    /// The schema of every item of a list.
+    #[serde(default)]
+    items: Option<Box<Schema>>,
+}
+
+fn additional_properties_default() -> bool {
+    true
+}
+
+/// The types a [`Schema`] can expect a value to have, named as in JSON Schema.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaType {
+    /// A dict.
+    Object,
+    /// A list.
+    Array,
+    String,
+    Integer,
+    /// An integer or a float.
+    Number,
+    Boolean,
+    Null,
+}
+
+impl SchemaType {
+    /// The name of the type, as written in schemas.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Null => "null",
+        }
+    }
+
+    /// Returns `true` if `node` is a value of this type.
+    fn matches(self, node: &Node) -> bool {
+        matches!(
+            (self, &node.kind),
+            (Self::Object, NodeKind::Dict(_))
+                | (Self::Array, NodeKind::List(_))
+                | (Self::String, NodeKind::Scalar(Scalar::String(_)))
+                | (Self::Integer, NodeKind::Scalar(Scalar::Integer(_)))
+                | (
+                    Self::Number,
+                    NodeKind::Scalar(Scalar::Integer(_) | Scalar::Float(_))
+                )
+                | (Self::Boolean, NodeKind::Scalar(Scalar::Boolean(_)))
+                | (Self::Null, NodeKind::Scalar(Scalar::Null))
+        )
+    }
+}
+
+impl Schema {
+    pub fn kind(&self) -> Option<SchemaType> {
+        self.kind
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn deprecated(&self) -> bool {
+        self.deprecated
+    }
+
+    /// Returns the schema of the entry with `key` of a dict, if it's listed in
+    /// `properties`.
+    pub fn property(&self, key: &str) -> Option<&Schema> {
+        self.properties.get(key)
+    }
+
+    /// Returns the schema of the items of a list, if any.
+    pub fn items(&self) -> Option<&Schema> {
+        self.items.as_deref()
+    }
+
+    /// Describes the values accepted by the schema, e.g. `array of string`, or `any` if
+    /// it accepts any value.
+    pub fn type_label(&self) -> String {
+        match (self.kind, self.items()) {
+            (None, _) => "any".to_string(),
+            (Some(SchemaType::Array), Some(items)) if items.kind.is_some() => {
+                format!("array of {}", items.type_label())
+            }
+            (Some(kind), _) => kind.as_str().to_string(),
+        }
+    }
+
+    /// Checks `document` against the schema, returning an issue for every missing
+    /// required key, unknown key, deprecated key and value of the wrong type.
+    ///
+    /// Missing keys are reported on the key of the dict missing them, or at the start of
+    /// the document for the root dict.
+    pub fn validate(&self, document: &Document) -> Vec<Issue> {
+        let mut issues = vec![];
+        match &document.root {
+            Some(root) => self.check(
+                root,
+                Span::new(root.span.start, root.span.start),
+                &mut issues,
+            ),
+            // A document without value is read as an empty dict
+            None => self.check_entries(&[], Span::default(), &mut issues),
+        }
+        issues
+    }
+
+    /// Checks `node`, `anchor` being the span reporting the keys missing from it.
+    fn check(&self, node: &Node, anchor: Span, issues: &mut Vec<Issue>) {
+        if let Some(kind) = self.kind
+            && !kind.matches(node)
+        {
+            let message = format!(
+                "expected {}, found {}",
+                kind.as_str(),
+                value_type_name(node)
+            );
+            issues.push(Issue::new(message, node.span).with_rule(Rule::Schema));
+            return;
+        }
+
+        match &node.kind {
+            NodeKind::Dict(entries) => self.check_entries(entries, anchor, issues),
+            NodeKind::List(items) => {
+                if let Some(schema) = &self.items {
+                    for item in items {
+                        let anchor = Span::new(item.span.start, item.span.start);
+                        schema.check(item, anchor, issues);
+                    }
+                }
+            }
+            NodeKind::Scalar(_) => (),
+        }
+    }
+
+    fn check_entries(&self, entries: &[Entry], anchor: Span, issues: &mut Vec<Issue>) {
+        for key in &self.required {
+            if !entries.iter().any(|entry| &entry.key.name == key) {
+                let issue = Issue::new(format!("missing required key `{key}`"), anchor);
+                issues.push(issue.with_rule(Rule::Schema));
+            }
+        }
+        for entry in entries {
+            match self.properties.get(&entry.key.name) {
+                Some(schema) => {
+                    if schema.deprecated {
+                        let message = format!("key `{}` is deprecated", entry.key.name);
+                        let issue = Issue::new(message, entry.key.span).with_rule(Rule::Schema);
+                        issues.push(issue.with_tag(IssueTag::Deprecated));
+                    }
+                    schema.check(&entry.value, entry.key.span, issues)
+                }
+                None if !self.additional_properties => {
+                    let message = format!("unknown key `{}`", entry.key.name);
+                    issues.push(Issue::new(message, entry.key.span).with_rule(Rule::Schema));
+                }
+                None => (),
+            }
+        }
+    }
+}
+
+/// Returns the name of the type of `node`, as written in schemas.
+fn value_type_name(node: &Node) -> &'static str {
+    let kind = match &node.kind {
+        NodeKind::Dict(_) => SchemaType::Object,
+        NodeKind::List(_) => SchemaType::Array,
+        NodeKind::Scalar(Scalar::String(_)) => SchemaType::String,
+        NodeKind::Scalar(Scalar::Integer(_)) => SchemaType::Integer,
+        NodeKind::Scalar(Scalar::Float(_)) => SchemaType::Number,
+        NodeKind::Scalar(Scalar::Boolean(_)) => SchemaType::Boolean,
+        NodeKind::Scalar(Scalar::Null) => SchemaType::Null,
+    };
+    kind.as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+    use serde_json::json;
+
+    fn port_schema() -> Schema {
+        serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "port": { "type": "integer" },
+                "hosts": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["port"],
+            "additionalProperties": false
+        }))
+        .unwrap()
+    }
+
+    fn messages(schema: &Schema, source: &str) -> Vec<(usize, String)> {
+        let document = huml::parse(source).document;
+        schema
+            .validate(&document)
+            .into_iter()
+            .map(|issue| {
+                assert_eq!(issue.rule, Some(Rule::Schema));
+                (issue.span.start.line, issue.message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_accept_document_matching_schema() {
+        let source = "port: 8080\nhosts:: \"a\", \"b\"\n";
+
+        assert!(messages(&port_schema(), source).is_empty());
+    }
+
+    #[test]
+    fn should_report_type_mismatches() {
+        let source = "port: \"8080\"\nhosts:: \"a\", 2\n";
+
+        assert_eq!(
+            messages(&port_schema(), source),
+            [
+                (0, "expected integer, found string".to_string()),
+                (1, "expected string, found integer".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_missing_and_unknown_keys() {
+        assert_eq!(
+            messages(&port_schema(), "host: \"localhost\"\n"),
+            [
+                (0, "missing required key `port`".to_string()),
+                (0, "unknown key `host`".to_string()),
+            ]
+        );
+        assert_eq!(
+            messages(&port_schema(), ""),
+            [(0, "missing required key `port`".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_report_missing_keys_on_key_of_nested_dict() {
+        let schema: Schema = serde_json::from_value(json!({
+            "properties": {
+                "server": { "type": "object", "required": ["port"] }
+            }
+        }))
+        .unwrap();
+        let document = huml::parse("name: \"huml\"\nserver::\n  host: \"localhost\"\n").document;
+
+        let issues = schema.validate(&document);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].message, "missing required key `port`");
+        assert_eq!(issues[0].span.start.line, 1);
+        assert_eq!(issues[0].span.end.column, "server".len());
+    }
+
+    #[test]
+    fn should_report_deprecated_keys() {
+        let schema: Schema = serde_json::from_value(json!({
+            "properties": {
+                "port": { "type": "integer" },
+                "host": { "type": "string", "deprecated": true }
+            }
+        }))
+        .unwrap();
+        let document = huml::parse(
+            "port: 80
+host: \"localhost\"\n",
+        )
+        .document;
+
+        let issues = schema.validate(&document);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].message, "key `host` is deprecated");
+        assert_eq!(issues[0].span.start.line, 1);
+        assert_eq!(issues[0].tag, Some(IssueTag::Deprecated));
+    }
+
+    #[test]
+    fn should_allow_unlisted_keys_by_default() {
+        let schema: Schema =
+            serde_json::from_value(json!({ "properties": { "port": { "type": "number" } } }))
+                .unwrap();
+
+        assert!(messages(&schema, "port: 1.5\nhost: \"localhost\"\n").is_empty());
+    }
+
+    #[test]
+    fn should_describe_accepted_values() {
+        let schema = port_schema();
+
+        assert_eq!(schema.type_label(), "object");
+        assert_eq!(schema.property("port").unwrap().type_label(), "integer");
+        assert_eq!(
+            schema.property("hosts").unwrap().type_label(),
+            "array of string"
+        );
+        let any: Schema = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(any.type_label(), "any");
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+
+use crate::huml::{
+    Issue, IssueTag, Rule,
+    ast::{Comment, Document, Entry, Layout, Location, Node, NodeKind, Scalar, Span},
+};
+
+/// Options controlling how a document is validated.
+#[derive(Clone, Debug, Default)]
+pub struct ValidateOptions {
+    /// The number of spaces every block must be indented with, more than the line
+    /// introducing it. If `None`, the step of the first nested block is expected
+    /// throughout the document.
+    pub indent_width: Option<usize>,
+}
+
+/// Checks the rules of HUML that the parser is lenient about, returning the issues found.
+///
+/// The checks run on whatever could be parsed, so they report issues even in documents
+/// that have syntax errors elsewhere.
+pub fn validate(source: &str, document: &Document) -> Vec<Issue> {
+    validate_with(source, document, &ValidateOptions::default())
+}
+
+/// Checks the rules of HUML like [`validate`], as configured by `options`.
+pub fn validate_with(source: &str, document: &Document, options: &ValidateOptions) -> Vec<Issue> {
+    let mut issues = vec![];
+    if let Some(root) = &document.root {
+        for_each_dict(root, &mut |entries| {
+            check_colon_spacing(source, entries, &mut issues);
+            check_duplicate_keys(entries, &mut issues);
+        });
+        check_boolean_spelling(source, root, &mut issues);
+        check_indent_steps(source, root, options.indent_width, &mut issues);
+    }
+    check_todo_comments(&document.comments, &mut issues);
+    issues
+}
+
+/// A marker calling for attention in a comment, e.g. `# TODO: bump the version`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommentMarker {
+    pub kind: MarkerKind,
+    /// The text following the marker, e.g. `bump the version`.
+    pub note: String,
+}
+
+/// The kinds of markers recognized in comments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerKind {
+    Todo,
+    Fixme,
+    Note,
+}
+
+impl MarkerKind {
+    /// The marker as written in comments, e.g. `TODO`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Todo => "TODO",
+            Self::Fixme => "FIXME",
+            Self::Note => "NOTE",
+        }
+    }
+}
+
+impl CommentMarker {
+    /// Finds the first marker of `comment`. Markers are matched as whole, uppercase words,
+    /// optionally followed by a `:`.
+    pub fn find(comment: &Comment) -> Option<Self> {
+        let text = comment.text.trim_start_matches('#');
+        let mut words = text.split_inclusive(char::is_whitespace);
+        let mut marker_end = 0;
+        let kind = words.find_map(|word| {
+            marker_end += word.len();
+            match word.trim_end().trim_end_matches(':') {
+                "TODO" => Some(MarkerKind::Todo),
+                "FIXME" => Some(MarkerKind::Fixme),
+                "NOTE" => Some(MarkerKind::Note),
+                _ => None,
+            }
+        })?;
+
+        Some(Self {
+            kind,
+            note: text[marker_end..].trim().to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for CommentMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.note.as_str() {
+            "" => write!(f, "{}", self.kind.as_str()),
+            note => write!(f, "{}: {note}", self.kind.as_str()),
+        }
+    }
+}
+
+/// Calls `f` with the entries of every dict of the tree rooted at `node`.
+fn for_each_dict(node: &Node, f: &mut impl FnMut(&[Entry])) {
+    match &node.kind {
+        NodeKind::Dict(entries) => {
+            f(entries);
+            for entry in entries {
+                for_each_dict(&entry.value, f);
+            }
+        }
+        NodeKind::List(items) => {
+            for item in items {
+                for_each_dict(item, f);
+            }
+        }
+        NodeKind::Scalar(_) => (),
+    }
+}
+
+/// Checks that exactly one space separates the `:` or `::` indicator of every entry from
+/// a value written on the same line, e.g. `key: value` rather than `key:value` or
+/// `key:   value`. Every issue comes with the fix replacing the spacing with one space.
+fn check_colon_spacing(source: &str, entries: &[Entry], issues: &mut Vec<Issue>) {
+    issues.extend(
+        entries
+            .iter()
+            .filter_map(|entry| colon_spacing_issue(source, entry)),
+    );
+}
+
+fn colon_spacing_issue(source: &str, entry: &Entry) -> Option<Issue> {
+    let key_end = entry.key.span.end;
+    let value_start = entry.value.span.start;
+    // The value of a block vector starts on the next line
+    if value_start.line != key_end.line {
+        return None;
+    }
+
+    let indicator = &source[key_end.offset..value_start.offset];
+    let colons = indicator.len() - indicator.trim_start_matches(':').len();
+    let spaces = indicator.len() - colons;
+    if spaces == 1 {
+        return None;
+    }
+
+    let spacing_start = Location {
+        line: key_end.line,
+        column: key_end.column + colons,
+        offset: key_end.offset + colons,
+    };
+    let spacing = Span::new(spacing_start, value_start);
+    let issue = Issue::new("expected exactly one space after ':'", spacing);
+    Some(issue.with_rule(Rule::ColonSpacing).with_fix(spacing, " "))
+}
+
+/// Checks that no key appears more than once in the same dict. Every repetition is
+/// reported, pointing back at the first occurrence, and marked as unnecessary since the
+/// last occurrence is the one read.
+fn check_duplicate_keys(entries: &[Entry], issues: &mut Vec<Issue>) {
+    let mut first_occurrences = HashMap::new();
+    for entry in entries {
+        let key = &entry.key;
+        match first_occurrences.get(key.name.as_str()) {
+            Some(&first_span) => issues.push(
+                Issue::new(format!("duplicate key `{}`", key.name), key.span)
+                    .with_rule(Rule::DuplicateKey)
+                    .with_related(format!("`{}` is first defined here", key.name), first_span)
+                    .with_tag(IssueTag::Unnecessary),
+            ),
+            None => {
+                first_occurrences.insert(key.name.as_str(), key.span);
+            }
+        }
+    }
+}
+
+/// Checks that the booleans of the tree rooted at `node` are spelled `true` or `false`,
+/// which the parser doesn't enforce. Every misspelling comes with the fix to the
+/// lowercase spelling.
+fn check_boolean_spelling(source: &str, node: &Node, issues: &mut Vec<Issue>) {
+    match &node.kind {
+        NodeKind::Scalar(Scalar::Boolean(value)) => {
+            let written = &source[node.span.start.offset..node.span.end.offset];
+            let canonical = value.to_string();
+            if written != canonical {
+                let message = format!(
+                    "booleans are written `{canonical}`; quote `\"{written}\"` if it's a string"
+                );
+                issues.push(
+                    Issue::new(message, node.span)
+                        .with_rule(Rule::BooleanSpelling)
+                        .with_fix(node.span, canonical),
+                );
+            }
+        }
+        NodeKind::Scalar(_) => (),
+        NodeKind::Dict(entries) => {
+            for entry in entries {
+                check_boolean_spelling(source, &entry.value, issues);
+            }
+        }
+        NodeKind::List(items) => {
+            for item in items {
+                check_boolean_spelling(source, item, issues);
+            }
+        }
+    }
+}
+
+/// Checks that every block of the tree rooted at `node` is indented by the same number
+/// of spaces more than the line introducing it: `indent_width`, or the step of the first
+/// nested block if `None`, in which case every mismatch points back at that block.
+fn check_indent_steps(
+    source: &str,
+    node: &Node,
+    indent_width: Option<usize>,
+    issues: &mut Vec<Issue>,
+) {
+    let mut step = indent_width;
+    // The indentation of the block setting the step, if inferred
+    let mut reference = None;
+    for_each_nested_block(source, node, &mut |block_start, parent_indent| {
+        let indent = line_indent(source, block_start);
+        let found = indent.saturating_sub(parent_indent);
+        let line_start = Location {
+            column: 0,
+            offset: block_start.offset - block_start.column,
+            ..block_start
+        };
+        let whitespace = Span::new(line_start, block_start);
+        match step {
+            None => {
+                step = Some(found);
+                reference = Some(whitespace);
+            }
+            Some(step) if step != found => {
+                let expected = parent_indent + step;
+                let mut issue = Issue::new(
+                    format!("inconsistent indentation; expected {expected} spaces, found {indent}"),
+                    whitespace,
+                )
+                .with_rule(Rule::IndentStep);
+                if let Some(reference) = reference {
+                    let message = format!("the indentation step of {step} spaces is set here");
+                    issue = issue.with_related(message, reference);
+                }
+                issues.push(issue);
+            }
+            Some(_) => (),
+        }
+    });
+}
+
+/// Calls `f` with the start of the first line of every block nested in the tree rooted at
+/// `node`, and the indentation of the line introducing the block, in document order.
+fn for_each_nested_block(source: &str, node: &Node, f: &mut impl FnMut(Location, usize)) {
+    let children: Vec<(Location, &Node)> = match &node.kind {
+        NodeKind::Dict(entries) => entries
+            .iter()
+            .map(|entry| (entry.key.span.start, &entry.value))
+            .collect(),
+        // Blocks nested in list items start at the `-` marker introducing them
+        NodeKind::List(items) => items.iter().map(|item| (item.span.start, item)).collect(),
+        NodeKind::Scalar(_) => return,
+    };
+    for (parent_start, child) in children {
+        if child.layout == Layout::Block
+            && let Some(block_start) = first_line_start(child)
+        {
+            f(block_start, line_indent(source, parent_start));
+        }
+        for_each_nested_block(source, child, f);
+    }
+}
+
+/// Returns where the first line of the dict or list `node` starts, after its indentation.
+fn first_line_start(node: &Node) -> Option<Location> {
+    match &node.kind {
+        NodeKind::Dict(entries) => Some(entries.first()?.key.span.start),
+        NodeKind::List(items) => Some(items.first()?.span.start),
+        NodeKind::Scalar(_) => None,
+    }
+}
+
+/// Returns the number of spaces indenting the line of `location`.
+fn line_indent(source: &str, location: Location) -> usize {
+    let line_start = location.offset - location.column;
+    source[line_start..]
+        .bytes()
+        .take_while(|&byte| byte == b' ')
+        .count()
+}
+
+/// Reports the comments with a `TODO` or `FIXME` marker.
+fn check_todo_comments(comments: &[Comment], issues: &mut Vec<Issue>) {
+    for comment in comments {
+        let Some(marker) = CommentMarker::find(comment) else {
+            continue;
+        };
+        if marker.kind != MarkerKind::Note {
+            issues.push(Issue::new(marker.to_string(), comment.span).with_rule(Rule::TodoComment));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    fn validate_source(source: &str) -> Vec<Issue> {
+        let parsed = huml::parse(source);
+        assert!(parsed.is_valid(), "{:?}", parsed.issues);
+        validate(source, &parsed.document)
+    }
+
+    fn spans(issues: &[Issue]) -> Vec<(usize, usize, usize)> {
+        issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue.span.start.line,
+                    issue.span.start.column,
+                    issue.span.end.column,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_accept_single_space_after_colon() {
+        let source = "name: \"huml\"\nports:: 80, 443\nnested::\n  inline:: a: 1, b: 2\n";
+
+        assert!(validate_source(source).is_empty());
+    }
+
+    #[test]
+    fn should_report_missing_space_after_colon() {
+        let issues = validate_source("name:\"huml\"\nports::80, 443\n");
+
+        assert_eq!(spans(&issues), [(0, 5, 5), (1, 7, 7)]);
+        assert_eq!(issues[0].message, "expected exactly one space after ':'");
+    }
+
+    #[test]
+    fn should_report_extra_spaces_after_colon() {
+        let issues = validate_source("server::\n  port:   8080\n");
+
+        assert_eq!(spans(&issues), [(1, 7, 10)]);
+        let fix = issues[0].fix.as_ref().unwrap();
+        assert_eq!((fix.span, fix.text.as_str()), (issues[0].span, " "));
+    }
+
+    #[test]
+    fn should_report_spacing_in_inline_dicts() {
+        let issues = validate_source("point:: x:1, y:   2\n");
+
+        assert_eq!(spans(&issues), [(0, 10, 10), (0, 15, 18)]);
+    }
+
+    #[test]
+    fn should_report_duplicate_top_level_keys() {
+        let issues = validate_source("name: \"a\"\nport: 80\nname: \"b\"\n");
+
+        assert_eq!(spans(&issues), [(2, 0, 4)]);
+        assert_eq!(issues[0].message, "duplicate key `name`");
+        assert_eq!(issues[0].rule, Some(Rule::DuplicateKey));
+        assert_eq!(issues[0].related.len(), 1);
+        assert_eq!(issues[0].related[0].span.start.line, 0);
+        assert_eq!(issues[0].tag, Some(IssueTag::Unnecessary));
+    }
+
+    #[test]
+    fn should_report_duplicate_nested_keys() {
+        let source = "servers::\n  - ::\n    host: \"a\"\n    \"host\": \"b\"\n";
+
+        let issues = validate_source(source);
+
+        assert_eq!(spans(&issues), [(3, 4, 10)]);
+        assert_eq!(issues[0].related[0].span.start.line, 2);
+    }
+
+    #[test]
+    fn should_not_report_same_keys_in_sibling_dicts() {
+        let source = "a::\n  port: 1\nb::\n  port: 2\nlist::\n  - :: port: 3\n  - :: port: 4\n";
+
+        assert!(validate_source(source).is_empty());
+    }
+
+    #[test]
+    fn should_find_comment_markers() {
+        let document =
+            huml::parse("# TODO: fix this\na: 1 # FIXME\n# NOTE kept for v1\n# TODOS\n").document;
+        let markers: Vec<_> = document
+            .comments
+            .iter()
+            .map(|comment| CommentMarker::find(comment).map(|marker| marker.to_string()))
+            .collect();
+
+        assert_eq!(
+            markers,
+            [
+                Some("TODO: fix this".to_string()),
+                Some("FIXME".to_string()),
+                Some("NOTE: kept for v1".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_todo_comments() {
+        let issues = validate_source("# TODO: fix this\na: 1 # NOTE: fine\n");
+
+        assert_eq!(spans(&issues), [(0, 0, 16)]);
+        assert_eq!(issues[0].message, "TODO: fix this");
+        assert_eq!(issues[0].rule, Some(Rule::TodoComment));
+    }
+
+    #[test]
+    fn should_report_misspelled_booleans_with_fix() {
+        let issues = validate_source("enabled: True\nflags:: true, FALSE\n");
+
+        assert_eq!(spans(&issues), [(0, 9, 13), (1, 14, 19)]);
+        assert_eq!(issues[0].rule, Some(Rule::BooleanSpelling));
+        assert_eq!(
+            issues[0].message,
+            "booleans are written `true`; quote `\"True\"` if it's a string"
+        );
+        let fix = issues[0].fix.as_ref().unwrap();
+        assert_eq!((fix.span, fix.text.as_str()), (issues[0].span, "true"));
+        assert_eq!(issues[1].fix.as_ref().unwrap().text, "false");
+    }
+
+    #[test]
+    fn should_not_report_quoted_boolean_words() {
+        assert!(validate_source("answer: \"yes\"\ntitle: \"True\"\n").is_empty());
+    }
+
+    #[test]
+    fn should_accept_consistent_indent_steps() {
+        let source = "\
+server::
+    ports::
+        - 80
+        - ::
+            host: \"a\"
+    name: \"b\"
+";
+
+        assert!(validate_source(source).is_empty());
+    }
+
+    #[test]
+    fn should_report_inconsistent_indent_steps() {
+        let source = "\
+server::
+  tls::
+     enabled: true
+  ports::
+    - 80
+    - ::
+       host: \"a\"
+";
+
+        let issues = validate_source(source);
+
+        let messages: Vec<_> = issues.iter().map(|issue| issue.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "inconsistent indentation; expected 4 spaces, found 5",
+                "inconsistent indentation; expected 6 spaces, found 7",
+            ]
+        );
+        assert_eq!(spans(&issues), [(2, 0, 5), (6, 0, 7)]);
+        assert_eq!(issues[0].rule, Some(Rule::IndentStep));
+        assert_eq!(
+            issues[0].related[0].message,
+            "the indentation step of 2 spaces is set here"
+        );
+        assert_eq!(issues[0].related[0].span.start.line, 1);
+        assert_eq!(issues[1].related[0].span.end.column, 2);
+    }
+
+    #[test]
+    fn should_report_indent_steps_differing_from_configured_width() {
+        let source = "server::\n    port: 80\n    tls::\n      enabled: true\n";
+        let document = huml::parse(source).document;
+        let with_width = |indent_width| {
+            let options = ValidateOptions {
+                indent_width: Some(indent_width),
+            };
+            validate_with(source, &document, &options)
+        };
+
+        let issues = with_width(2);
+
+        let messages: Vec<_> = issues.iter().map(|issue| issue.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["inconsistent indentation; expected 2 spaces, found 4"]
+        );
+        assert_eq!(spans(&issues), [(1, 0, 4)]);
+        assert!(issues[0].related.is_empty());
+        assert_eq!(
+            with_width(4)
+                .iter()
+                .map(|issue| issue.message.as_str())
+                .collect::<Vec<_>>(),
+            ["inconsistent indentation; expected 8 spaces, found 6"]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::huml::{
+    self, Issue,
+    ast::{Entry, Key, Layout, Node, NodeKind, Scalar, Span},
+};
+
+/// A HUML value stripped of its source information, e.g. to work with the data a
+/// document holds rather than with its syntax.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Scalar(Scalar),
+    /// The entries of a dict, in the order they first appear. Keys are unique.
+    Dict(Vec<(String, Value)>),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Returns the value of `key` if `self` is a dict holding it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Dict(entries) => entries
+                .iter()
+                .find_map(|(name, value)| (name == key).then_some(value)),
+            Self::Scalar(_) | Self::List(_) => None,
+        }
+    }
+
+    /// Merges `other` into `self`, with the values of `other` taking precedence.
+    ///
+    /// Dicts are merged recursively, keys of `other` missing from `self` being added
+    /// after the existing ones. Any other value, lists included, is replaced as a whole.
+    pub fn merge(&mut self, other: &Value) {
+        match (self, other) {
+            (Self::Dict(entries), Self::Dict(other_entries)) => {
+                for (key, other_value) in other_entries {
+                    match entries.iter_mut().find(|(name, _)| name == key) {
+                        Some((_, value)) => value.merge(other_value),
+                        None => entries.push((key.clone(), other_value.clone())),
+                    }
+                }
+            }
+            (value, other) => *value = other.clone(),
+        }
+    }
+}
+
+impl From<&Node> for Value {
+    /// Converts a syntax tree node. If a dict has duplicate keys, the last value wins.
+    fn from(node: &Node) -> Self {
+        match &node.kind {
+            NodeKind::Scalar(scalar) => Self::Scalar(scalar.clone()),
+            NodeKind::List(items) => Self::List(items.iter().map(Value::from).collect()),
+            NodeKind::Dict(entries) => {
+                let mut dict: Vec<(String, Value)> = vec![];
+                for entry in entries {
+                    let value = Value::from(&entry.value);
+                    match dict.iter_mut().find(|(name, _)| *name == entry.key.name) {
+                        Some((_, existing)) => *existing = value,
+                        None => dict.push((entry.key.name.clone(), value)),
+                    }
+                }
+                Self::Dict(dict)
+            }
+        }
+    }
+}
+
+impl From<&Value> for Node {
+    /// Converts a value to a syntax tree node without source information, e.g. to print
+    /// it with [`emit::to_string`](huml::emit::to_string). Every span is empty, and dicts
+    /// and lists are laid out as blocks.
+    fn from(value: &Value) -> Self {
+        let (kind, layout) = match value {
+            Value::Scalar(scalar) => (NodeKind::Scalar(scalar.clone()), Layout::Inline),
+            Value::Dict(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(name, value)| Entry {
+                        key: Key {
+                            name: name.clone(),
+                            span: Span::default(),
+                        },
+                        value: Node::from(value),
+                    })
+                    .collect();
+                (NodeKind::Dict(entries), Layout::Block)
+            }
+            Value::List(items) => (
+                NodeKind::List(items.iter().map(Node::from).collect()),
+                Layout::Block,
+            ),
+        };
+        Self {
+            kind,
+            span: Span::default(),
+            layout,
+        }
+    }
+}
+
+/// Parses the HUML documents of `sources` and merges their values in order, so that
+/// each source overrides the ones before it, e.g. defaults followed by user settings.
+///
+/// Documents holding no value are skipped. If none holds a value, the result is an
+/// empty dict. Fails with the issues of the first source that has syntax errors.
+pub fn merge_documents(sources: &[&str]) -> Result<Value, Vec<Issue>> {
+    let mut merged: Option<Value> = None;
+    for source in sources {
+        let parsed = huml::parse(source);
+        if !parsed.is_valid() {
+            return Err(parsed.issues);
+        }
+        let Some(root) = &parsed.document.root else {
+            continue;
+        };
+        let value = Value::from(root);
+        match &mut merged {
+            Some(merged) => merged.merge(&value),
+            None => merged = Some(value),
+        }
+    }
+    Ok(merged.unwrap_or(Value::Dict(vec![])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml::ast::Document;
+
+    fn merge(sources: &[&str]) -> Value {
+        merge_documents(sources).expect("Sources should be valid")
+    }
+
+    fn string(value: &str) -> Value {
+        Value::Scalar(Scalar::String(value.to_string()))
+    }
+
+    #[test]
+    fn should_merge_dicts_recursively() {
+        let merged = merge(&[
+            "server::\n  host: \"localhost\"\n  tls::\n    enabled: false\n    port: 443\n",
+            "server::\n  tls::\n    enabled: true\n  timeout: 30\nname: \"app\"\n",
+        ]);
+
+        let server = merged.get("server").unwrap();
+        let tls = server.get("tls").unwrap();
+        assert_eq!(server.get("host"), Some(&string("localhost")));
+        assert_eq!(
+            server.get("timeout"),
+            Some(&Value::Scalar(Scalar::Integer(30)))
+        );
+        assert_eq!(
+            tls.get("enabled"),
+            Some(&Value::Scalar(Scalar::Boolean(true)))
+        );
+        assert_eq!(tls.get("port"), Some(&Value::Scalar(Scalar::Integer(443))));
+        assert_eq!(merged.get("name"), Some(&string("app")));
+
+        let Value::Dict(entries) = merged else {
+            panic!("Expected a dict");
+        };
+        let keys: Vec<_> = entries.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["server", "name"]);
+    }
+
+    #[test]
+    fn should_replace_lists() {
+        let merged = merge(&["hosts:: \"a\", \"b\", \"c\"\n", "hosts:: \"d\"\n"]);
+
+        assert_eq!(merged.get("hosts"), Some(&Value::List(vec![string("d")])));
+    }
+
+    #[test]
+    fn should_replace_scalars_and_mismatched_kinds() {
+        let merged = merge(&[
+            "port: 80\nlog::\n  level: \"debug\"\n",
+            "port: 8080\nlog: null\n",
+        ]);
+
+        assert_eq!(
+            merged.get("port"),
+            Some(&Value::Scalar(Scalar::Integer(8080)))
+        );
+        assert_eq!(merged.get("log"), Some(&Value::Scalar(Scalar::Null)));
+    }
+
+    #[test]
+    fn should_skip_empty_documents() {
+        assert_eq!(merge(&[]), Value::Dict(vec![]));
+        assert_eq!(merge(&["# only a comment\n", "a: 1\n"]), merge(&["a: 1\n"]));
+    }
+
+    #[test]
+    fn should_report_issues_of_invalid_source() {
+        let issues = merge_documents(&["a: 1\n", "b: oops\n"]).unwrap_err();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].span.start.line, 0);
+    }
+
+    #[test]
+    fn should_print_values_converted_to_nodes() {
+        let value = merge(&["name: \"app\"\nports:: 80, 443\nlog::\n  level: \"debug\"\n"]);
+        let document = Document {
+            root: Some(Node::from(&value)),
+            ..Document::default()
+        };
+
+        let printed = huml::emit::to_string(&document, &huml::emit::EmitOptions::default());
+
+        assert_eq!(
+            printed,
+            "name: \"app\"\nports::\n  - 80\n  - 443\nlog::\n  level: \"debug\"\n"
+        );
+        assert_eq!(merge(&[&printed]), value);
+    }
+}
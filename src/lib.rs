@@ -12,9 +12,12 @@
 //!
 //! ## Modules
 //!
+//! - **`huml`**: This module implements the HUML language itself. It parses documents into a syntax tree annotated with source spans, independently of the language server.
+//!
 //! - **`rpc`**: This module handles the JSON-RPC communication between the language server and the client (the editor or IDE). It is responsible for serializing and deserializing the LSP messages that are exchanged.
 //!
 //! - **`lsp`**: This is the core module that implements the `LanguageServer` trait. It connects the `huml` parser with the `rpc` communication layer. It receives notifications and requests from the client, such as `textDocument/didOpen`, `textDocument/hover`, or `textDocument/completion`, and uses the `huml` module to provide the appropriate responses.
 
+pub mod huml;
 pub mod lsp;
 pub mod rpc;
@@ -0,0 +1,159 @@
+//! Logs the activity of the server to a file, as stdout is reserved to the messages sent
+//! to the client.
+
+use std::{
+    env,
+    fmt::{self, Arguments},
+    fs::OpenOptions,
+    io::{self, Write},
+};
+
+/// The file logged to unless `HUML_LOG_PATH` is set.
+const DEFAULT_LOG_PATH: &str = "/tmp/huml.log";
+
+/// How important a logged message is, from the most to the least important.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    /// Parses the name of a level, e.g. `warn`, ignoring case.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "error" => Some(Self::Error),
+            "warn" | "warning" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Writes the messages at or above a level to an output, one line each, prefixed by
+/// their level.
+pub struct Logger {
+    level: LogLevel,
+    output: Box<dyn Write>,
+}
+
+impl Logger {
+    pub fn new(level: LogLevel, output: impl Write + 'static) -> Self {
+        Self {
+            level,
+            output: Box::new(output),
+        }
+    }
+
+    /// Builds the logger configured by the environment.
+    ///
+    /// Messages are appended to the file at `HUML_LOG_PATH`, `/tmp/huml.log` by default,
+    /// or written to stderr if it can't be opened. Only the messages at or above
+    /// `HUML_LOG_LEVEL` are logged, `info` by default.
+    pub fn from_env() -> Self {
+        let level = env::var("HUML_LOG_LEVEL")
+            .ok()
+            .and_then(|level| LogLevel::parse(&level))
+            .unwrap_or(LogLevel::Info);
+        let path = env::var("HUML_LOG_PATH").unwrap_or_else(|_| DEFAULT_LOG_PATH.to_string());
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Self::new(level, file),
+            Err(e) => {
+                let mut logger = Self::new(level, io::stderr());
+                logger.warn(format_args!("Failed to open log file {path}: {e}"));
+                logger
+            }
+        }
+    }
+
+    /// Returns `true` if the messages of `level` are logged.
+    pub fn enabled(&self, level: LogLevel) -> bool {
+        level <= self.level
+    }
+
+    /// Logs `message` if `level` is enabled. The message is only formatted if it is.
+    pub fn log(&mut self, level: LogLevel, message: Arguments) {
+        if self.enabled(level) {
+            // Failing to log must never stop the server
+            let _ = writeln!(self.output, "[{level}] {message}");
+        }
+    }
+
+    pub fn error(&mut self, message: Arguments) {
+        self.log(LogLevel::Error, message);
+    }
+
+    pub fn warn(&mut self, message: Arguments) {
+        self.log(LogLevel::Warn, message);
+    }
+
+    pub fn info(&mut self, message: Arguments) {
+        self.log(LogLevel::Info, message);
+    }
+
+    pub fn debug(&mut self, message: Arguments) {
+        self.log(LogLevel::Debug, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// An output whose content stays readable by the test once written to
+    #[derive(Clone, Default)]
+    struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn should_drop_messages_below_level() {
+        let output = SharedOutput::default();
+        let mut logger = Logger::new(LogLevel::Warn, output.clone());
+
+        logger.error(format_args!("Failed to read message"));
+        logger.warn(format_args!("Failed to handle request"));
+        logger.info(format_args!("Started Server"));
+        logger.debug(format_args!("Request: {}", 1));
+
+        assert_eq!(
+            String::from_utf8(output.0.take()).unwrap(),
+            "[ERROR] Failed to read message\n[WARN] Failed to handle request\n"
+        );
+    }
+
+    #[test]
+    fn should_parse_level_names() {
+        assert_eq!(LogLevel::parse("DEBUG"), Some(LogLevel::Debug));
+        assert_eq!(LogLevel::parse("warning"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::parse("verbose"), None);
+    }
+}
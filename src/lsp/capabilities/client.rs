@@ -1,16 +1,101 @@
 use serde::{Deserialize, Serialize};
 
+use crate::lsp::common::{markup::MarkupKind, text_document::PositionEncodingKind};
+
 #[derive(Deserialize, Serialize, Clone, Default, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ClientCapabilities {
     #[serde(default)]
     text_document: Option<TextDocumentClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    workspace: Option<WorkspaceClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    general: Option<GeneralClientCapabilities>,
 }
 
 impl ClientCapabilities {
     pub fn text_document(&self) -> Option<&TextDocumentClientCapabilities> {
         self.text_document.as_ref()
     }
+
+    pub fn workspace(&self) -> Option<&WorkspaceClientCapabilities> {
+        self.workspace.as_ref()
+    }
+
+    pub fn general(&self) -> Option<&GeneralClientCapabilities> {
+        self.general.as_ref()
+    }
+
+    /// Picks the encoding of positions among the ones the client supports.
+    ///
+    /// Documents are stored as UTF-8, so UTF-8 is preferred when available. Clients that
+    /// don't list their encodings only support UTF-16.
+    pub fn position_encoding(&self) -> PositionEncodingKind {
+        let supported = self
+            .general()
+            .map(|general| general.position_encodings.as_slice())
+            .unwrap_or_default();
+        [
+            PositionEncodingKind::Utf8,
+            PositionEncodingKind::Utf16,
+            PositionEncodingKind::Utf32,
+        ]
+        .into_iter()
+        .find(|encoding| supported.contains(encoding))
+        .unwrap_or_default()
+    }
+}
+
+/// Workspace specific client capabilities.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#clientCapabilities) for more info.
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceClientCapabilities {
+    /// Whether the client supports applying edits to the workspace, with
+    /// `workspace/applyEdit` requests.
+    #[serde(default)]
+    apply_edit: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    did_change_watched_files: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    symbol: Option<FeatureClientCapabilities>,
+}
+
+impl WorkspaceClientCapabilities {
+    pub fn apply_edit(&self) -> bool {
+        self.apply_edit
+    }
+
+    pub fn did_change_watched_files(&self) -> Option<&FeatureClientCapabilities> {
+        self.did_change_watched_files.as_ref()
+    }
+
+    pub fn symbol(&self) -> Option<&FeatureClientCapabilities> {
+        self.symbol.as_ref()
+    }
+}
+
+/// General client capabilities.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#clientCapabilities) for more info.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneralClientCapabilities {
+    /// The position encodings supported by the client, in decreasing order of
+    /// preference.
+    #[serde(default)]
+    position_encodings: Vec<PositionEncodingKind>,
+}
+
+impl GeneralClientCapabilities {
+    pub fn position_encodings(&self) -> &[PositionEncodingKind] {
+        &self.position_encodings
+    }
 }
 
 /// Text document specific client capabilities.
@@ -18,8 +103,306 @@ impl ClientCapabilities {
 /// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocumentClientCapabilities) for more info.
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct TextDocumentClientCapabilities {
+    #[serde(default)]
     synchronization: Option<TextDocumentSyncClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completion: Option<CompletionClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hover: Option<HoverClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    document_symbol: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    formatting: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folding_range: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    semantic_tokens: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    publish_diagnostics: Option<PublishDiagnosticsClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rename: Option<RenameClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code_action: Option<CodeActionClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inlay_hint: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    definition: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    references: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature_help: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color_provider: Option<FeatureClientCapabilities>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code_lens: Option<FeatureClientCapabilities>,
+}
+
+impl TextDocumentClientCapabilities {
+    pub fn synchronization(&self) -> Option<&TextDocumentSyncClientCapabilities> {
+        self.synchronization.as_ref()
+    }
+
+    pub fn completion(&self) -> Option<&CompletionClientCapabilities> {
+        self.completion.as_ref()
+    }
+
+    pub fn hover(&self) -> Option<&HoverClientCapabilities> {
+        self.hover.as_ref()
+    }
+
+    pub fn document_symbol(&self) -> Option<&FeatureClientCapabilities> {
+        self.document_symbol.as_ref()
+    }
+
+    pub fn formatting(&self) -> Option<&FeatureClientCapabilities> {
+        self.formatting.as_ref()
+    }
+
+    pub fn folding_range(&self) -> Option<&FeatureClientCapabilities> {
+        self.folding_range.as_ref()
+    }
+
+    pub fn semantic_tokens(&self) -> Option<&FeatureClientCapabilities> {
+        self.semantic_tokens.as_ref()
+    }
+
+    pub fn publish_diagnostics(&self) -> Option<&PublishDiagnosticsClientCapabilities> {
+        self.publish_diagnostics.as_ref()
+    }
+
+    pub fn rename(&self) -> Option<&RenameClientCapabilities> {
+        self.rename.as_ref()
+    }
+
+    pub fn code_action(&self) -> Option<&CodeActionClientCapabilities> {
+        self.code_action.as_ref()
+    }
+
+    pub fn inlay_hint(&self) -> Option<&FeatureClientCapabilities> {
+        self.inlay_hint.as_ref()
+    }
+
+    pub fn definition(&self) -> Option<&FeatureClientCapabilities> {
+        self.definition.as_ref()
+    }
+
+    pub fn references(&self) -> Option<&FeatureClientCapabilities> {
+        self.references.as_ref()
+    }
+
+    pub fn signature_help(&self) -> Option<&FeatureClientCapabilities> {
+        self.signature_help.as_ref()
+    }
+
+    pub fn color_provider(&self) -> Option<&FeatureClientCapabilities> {
+        self.color_provider.as_ref()
+    }
+
+    pub fn code_lens(&self) -> Option<&FeatureClientCapabilities> {
+        self.code_lens.as_ref()
+    }
+}
+
+/// The capabilities of the client for a feature whose details the server doesn't
+/// depend on, e.g. hover. The client supports the feature if it sends them at all.
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureClientCapabilities {
+    /// Whether the feature supports dynamic registration.
+    #[serde(default)]
+    dynamic_registration: bool,
+}
+
+impl FeatureClientCapabilities {
+    pub fn dynamic_registration(&self) -> bool {
+        self.dynamic_registration
+    }
+}
+
+/// Represents the hover capabilities supported by the client
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#hoverClientCapabilities) for more info
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HoverClientCapabilities {
+    /// Whether hover supports dynamic registration.
+    #[serde(default)]
+    dynamic_registration: bool,
+
+    /// The content types supported by the client for the content of a hover, in
+    /// decreasing order of preference.
+    #[serde(default)]
+    content_format: Vec<MarkupKind>,
+}
+
+impl HoverClientCapabilities {
+    pub fn dynamic_registration(&self) -> bool {
+        self.dynamic_registration
+    }
+
+    pub fn content_format(&self) -> &[MarkupKind] {
+        &self.content_format
+    }
+}
+
+/// Represents the rename capabilities supported by the client
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#renameClientCapabilities) for more info
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameClientCapabilities {
+    /// Whether rename supports dynamic registration.
+    #[serde(default)]
+    dynamic_registration: bool,
+
+    /// Whether the client sends `textDocument/prepareRename` requests before renaming.
+    #[serde(default)]
+    prepare_support: bool,
+}
+
+impl RenameClientCapabilities {
+    pub fn dynamic_registration(&self) -> bool {
+        self.dynamic_registration
+    }
+
+    pub fn prepare_support(&self) -> bool {
+        self.prepare_support
+    }
+}
+
+/// Represents the code action capabilities supported by the client
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeActionClientCapabilities) for more info
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionClientCapabilities {
+    /// Whether code action supports dynamic registration.
+    #[serde(default)]
+    dynamic_registration: bool,
+
+    /// Whether the client supports code action literals as the result of code action
+    /// requests, rather than commands only. Its content isn't modeled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code_action_literal_support: Option<serde_json::Value>,
+}
+
+impl CodeActionClientCapabilities {
+    pub fn dynamic_registration(&self) -> bool {
+        self.dynamic_registration
+    }
+
+    pub fn code_action_literal_support(&self) -> bool {
+        self.code_action_literal_support.is_some()
+    }
+}
+
+/// Represents the completion capabilities supported by the client
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionClientCapabilities) for more info
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionClientCapabilities {
+    /// Whether completion supports dynamic registration.
+    #[serde(default)]
+    dynamic_registration: bool,
+
+    /// The capabilities of the client for completion items.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completion_item: Option<CompletionItemClientCapabilities>,
+}
+
+impl CompletionClientCapabilities {
+    pub fn dynamic_registration(&self) -> bool {
+        self.dynamic_registration
+    }
+
+    pub fn completion_item(&self) -> Option<&CompletionItemClientCapabilities> {
+        self.completion_item.as_ref()
+    }
+}
+
+/// The capabilities of the client for the items of a completion list.
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItemClientCapabilities {
+    /// Whether the client supports snippets as insert text.
+    #[serde(default)]
+    snippet_support: bool,
+
+    /// The content types supported by the client for the documentation of an item, in
+    /// decreasing order of preference.
+    #[serde(default)]
+    documentation_format: Vec<MarkupKind>,
+}
+
+impl CompletionItemClientCapabilities {
+    pub fn snippet_support(&self) -> bool {
+        self.snippet_support
+    }
+
+    pub fn documentation_format(&self) -> &[MarkupKind] {
+        &self.documentation_format
+    }
+}
+
+/// Represents the capabilities of the client for published diagnostics
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#publishDiagnosticsClientCapabilities) for more info
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishDiagnosticsClientCapabilities {
+    /// Whether the client accepts diagnostics with related information.
+    #[serde(default)]
+    related_information: bool,
+
+    /// Whether the client interprets the version of a document sent along with its
+    /// diagnostics.
+    #[serde(default)]
+    version_support: bool,
+
+    /// Whether the client supports a description of the code of diagnostics.
+    #[serde(default)]
+    code_description_support: bool,
+
+    /// Whether the client preserves the data of diagnostics between a publish
+    /// notification and a code action request.
+    #[serde(default)]
+    data_support: bool,
+}
+
+impl PublishDiagnosticsClientCapabilities {
+    pub fn related_information(&self) -> bool {
+        self.related_information
+    }
+
+    pub fn version_support(&self) -> bool {
+        self.version_support
+    }
+
+    pub fn code_description_support(&self) -> bool {
+        self.code_description_support
+    }
+
+    pub fn data_support(&self) -> bool {
+        self.data_support
+    }
 }
 
 /// Represents the synchronization capabilities supported by the client
@@ -46,3 +429,192 @@ pub struct TextDocumentSyncClientCapabilities {
     #[serde(default)]
     did_save: bool,
 }
+
+impl TextDocumentSyncClientCapabilities {
+    pub fn dynamic_registration(&self) -> bool {
+        self.dynamic_registration
+    }
+
+    pub fn will_save(&self) -> bool {
+        self.will_save
+    }
+
+    pub fn will_save_wait_until(&self) -> bool {
+        self.will_save_wait_until
+    }
+
+    pub fn did_save(&self) -> bool {
+        self.did_save
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn capabilities(value: serde_json::Value) -> ClientCapabilities {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn should_default_to_utf16_position_encoding() {
+        assert_eq!(
+            capabilities(json!({})).position_encoding(),
+            PositionEncodingKind::Utf16
+        );
+    }
+
+    #[test]
+    fn should_prefer_utf8_position_encoding() {
+        let capabilities = capabilities(json!({
+            "general": { "positionEncodings": ["utf-16", "utf-8"] }
+        }));
+
+        assert_eq!(capabilities.position_encoding(), PositionEncodingKind::Utf8);
+    }
+
+    #[test]
+    fn should_ignore_unknown_position_encodings() {
+        let capabilities = capabilities(json!({
+            "general": { "positionEncodings": ["utf-7", "utf-32"] }
+        }));
+
+        assert_eq!(
+            capabilities.position_encoding(),
+            PositionEncodingKind::Utf32
+        );
+    }
+
+    #[test]
+    fn should_deserialize_vscode_capabilities() {
+        let value = json!({
+            "workspace": {
+                "applyEdit": true,
+                "workspaceEdit": { "documentChanges": true },
+                "didChangeConfiguration": { "dynamicRegistration": true },
+                "didChangeWatchedFiles": { "dynamicRegistration": true },
+                "configuration": true,
+                "workspaceFolders": true
+            },
+            "textDocument": {
+                "publishDiagnostics": {
+                    "relatedInformation": true,
+                    "versionSupport": false,
+                    "tagSupport": { "valueSet": [1, 2] },
+                    "codeDescriptionSupport": true,
+                    "dataSupport": true
+                },
+                "synchronization": {
+                    "dynamicRegistration": true,
+                    "willSave": true,
+                    "willSaveWaitUntil": true,
+                    "didSave": true
+                },
+                "completion": {
+                    "dynamicRegistration": true,
+                    "contextSupport": true,
+                    "completionItem": {
+                        "snippetSupport": true,
+                        "commitCharactersSupport": true,
+                        "documentationFormat": ["markdown", "plaintext"],
+                        "deprecatedSupport": true,
+                        "preselectSupport": true,
+                        "tagSupport": { "valueSet": [1] },
+                        "insertReplaceSupport": true,
+                        "resolveSupport": {
+                            "properties": ["documentation", "detail", "additionalTextEdits"]
+                        },
+                        "insertTextModeSupport": { "valueSet": [1, 2] },
+                        "labelDetailsSupport": true
+                    },
+                    "insertTextMode": 2,
+                    "completionItemKind": { "valueSet": [1, 2, 3, 4, 5] },
+                    "completionList": {
+                        "itemDefaults": ["commitCharacters", "editRange", "insertTextFormat"]
+                    }
+                },
+                "hover": {
+                    "dynamicRegistration": true,
+                    "contentFormat": ["markdown", "plaintext"]
+                },
+                "documentSymbol": {
+                    "dynamicRegistration": true,
+                    "hierarchicalDocumentSymbolSupport": true,
+                    "labelSupport": true
+                },
+                "codeAction": {
+                    "dynamicRegistration": true,
+                    "isPreferredSupport": true,
+                    "codeActionLiteralSupport": {
+                        "codeActionKind": { "valueSet": ["", "quickfix", "refactor"] }
+                    }
+                },
+                "formatting": { "dynamicRegistration": true },
+                "foldingRange": {
+                    "dynamicRegistration": true,
+                    "rangeLimit": 5000,
+                    "lineFoldingOnly": true
+                },
+                "semanticTokens": {
+                    "dynamicRegistration": true,
+                    "tokenTypes": ["property", "string", "number"],
+                    "tokenModifiers": ["declaration"],
+                    "formats": ["relative"],
+                    "requests": { "range": true, "full": { "delta": true } }
+                }
+            },
+            "window": {
+                "showMessage": { "messageActionItem": { "additionalPropertiesSupport": true } },
+                "workDoneProgress": true
+            },
+            "general": {
+                "staleRequestSupport": { "cancel": true, "retryOnContentModified": [] },
+                "regularExpressions": { "engine": "ECMAScript", "version": "ES2020" },
+                "markdown": { "parser": "marked", "version": "1.1.0" },
+                "positionEncodings": ["utf-16"]
+            }
+        });
+
+        let deserialized = capabilities(value);
+        let round_tripped = capabilities(serde_json::to_value(&deserialized).unwrap());
+
+        let text_document = round_tripped.text_document().unwrap();
+        assert!(text_document.synchronization().unwrap().did_save());
+        let workspace = round_tripped.workspace().unwrap();
+        assert!(workspace.apply_edit());
+        assert!(
+            workspace
+                .did_change_watched_files()
+                .unwrap()
+                .dynamic_registration()
+        );
+        assert_eq!(
+            text_document.hover().unwrap().content_format(),
+            [MarkupKind::Markdown, MarkupKind::PlainText]
+        );
+        let completion_item = text_document
+            .completion()
+            .unwrap()
+            .completion_item()
+            .unwrap();
+        assert!(completion_item.snippet_support());
+        assert_eq!(
+            completion_item.documentation_format(),
+            [MarkupKind::Markdown, MarkupKind::PlainText]
+        );
+        let publish_diagnostics = text_document.publish_diagnostics().unwrap();
+        assert!(publish_diagnostics.related_information());
+        assert!(!publish_diagnostics.version_support());
+        assert!(
+            text_document
+                .folding_range()
+                .unwrap()
+                .dynamic_registration()
+        );
+        assert_eq!(
+            round_tripped.position_encoding(),
+            PositionEncodingKind::Utf16
+        );
+    }
+}
@@ -1,34 +1,575 @@
 use serde::Serialize;
 use serde_repr::Serialize_repr;
 
-#[derive(Serialize, Debug)]
+use crate::lsp::{
+    capabilities::client::{ClientCapabilities, TextDocumentClientCapabilities},
+    command::Command,
+    common::text_document::PositionEncodingKind,
+    response::{
+        code_action::{CONVERT_INDENTATION, FORMAT_DOCUMENT, QUICK_FIX},
+        semantic_tokens::SemanticTokenType,
+    },
+    settings::ServerSettings,
+};
+
+/// The features provided by the server, advertised in the response to `initialize`.
+///
+/// The default holds every feature the server implements.
+#[derive(Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerCapabilities {
+    position_encoding: PositionEncodingKind,
     text_document_sync: TextDocumentSyncOptions,
+    document_symbol_provider: bool,
+    document_formatting_provider: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completion_provider: Option<CompletionOptions>,
+    hover_provider: bool,
+    folding_range_provider: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    semantic_tokens_provider: Option<SemanticTokensOptions>,
+    rename_provider: RenameProvider,
+    execute_command_provider: ExecuteCommandOptions,
+    code_action_provider: CodeActionProvider,
+    inlay_hint_provider: bool,
+    definition_provider: bool,
+    references_provider: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature_help_provider: Option<SignatureHelpOptions>,
+    workspace_symbol_provider: bool,
+    color_provider: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code_lens_provider: Option<CodeLensOptions>,
+}
+
+/// Picks the features advertised to a client, given its capabilities and the settings of
+/// the server.
+///
+/// Features are only advertised if the client declares the capabilities to consume them,
+/// and completion only if it's enabled in the settings as well. Documents are synced
+/// incrementally with clients declaring synchronization capabilities, and in full
+/// otherwise.
+pub fn negotiate_capabilities(
+    client: &ClientCapabilities,
+    settings: &ServerSettings,
+) -> ServerCapabilities {
+    let supports = |feature: fn(&TextDocumentClientCapabilities) -> bool| {
+        client.text_document().is_some_and(feature)
+    };
+
+    let mut capabilities =
+        ServerCapabilities::default().with_position_encoding(client.position_encoding());
+    if !supports(|text_document| text_document.synchronization().is_some()) {
+        capabilities.text_document_sync.change = TextDocumentSyncKind::Full;
+    }
+    if !supports(|text_document| {
+        text_document
+            .synchronization()
+            .is_some_and(|synchronization| synchronization.did_save())
+    }) {
+        capabilities.text_document_sync.save = None;
+    }
+    capabilities.text_document_sync.will_save_wait_until &= supports(|text_document| {
+        text_document
+            .synchronization()
+            .is_some_and(|synchronization| synchronization.will_save_wait_until())
+    });
+    if !(settings.enable_completion()
+        && supports(|text_document| text_document.completion().is_some()))
+    {
+        capabilities.completion_provider = None;
+    }
+    capabilities.hover_provider &= supports(|text_document| text_document.hover().is_some());
+    capabilities.document_symbol_provider &=
+        supports(|text_document| text_document.document_symbol().is_some());
+    capabilities.document_formatting_provider &=
+        supports(|text_document| text_document.formatting().is_some());
+    capabilities.folding_range_provider &=
+        supports(|text_document| text_document.folding_range().is_some());
+    capabilities.inlay_hint_provider &=
+        supports(|text_document| text_document.inlay_hint().is_some());
+    capabilities.definition_provider &=
+        supports(|text_document| text_document.definition().is_some());
+    capabilities.references_provider &=
+        supports(|text_document| text_document.references().is_some());
+    if !supports(|text_document| text_document.signature_help().is_some()) {
+        capabilities.signature_help_provider = None;
+    }
+    capabilities.workspace_symbol_provider &= client
+        .workspace()
+        .is_some_and(|workspace| workspace.symbol().is_some());
+    capabilities.color_provider &=
+        supports(|text_document| text_document.color_provider().is_some());
+    if !supports(|text_document| text_document.code_lens().is_some()) {
+        capabilities.code_lens_provider = None;
+    }
+    if !supports(|text_document| text_document.semantic_tokens().is_some()) {
+        capabilities.semantic_tokens_provider = None;
+    }
+    capabilities.rename_provider = match client.text_document().and_then(|t| t.rename()) {
+        None => RenameProvider::Enabled(false),
+        // Rename options can only be sent to clients supporting `prepareRename`
+        Some(rename) if !rename.prepare_support() => RenameProvider::Enabled(true),
+        Some(_) => capabilities.rename_provider,
+    };
+    capabilities.code_action_provider = match client.text_document().and_then(|t| t.code_action()) {
+        None => CodeActionProvider::Enabled(false),
+        // Code action options can only be sent to clients supporting code action literals
+        Some(code_action) if !code_action.code_action_literal_support() => {
+            CodeActionProvider::Enabled(true)
+        }
+        Some(_) => capabilities.code_action_provider,
+    };
+    capabilities
 }
 
 impl Default for ServerCapabilities {
     fn default() -> Self {
         Self {
+            position_encoding: PositionEncodingKind::default(),
             text_document_sync: TextDocumentSyncOptions {
                 open_close: true,
                 change: TextDocumentSyncKind::Incremental,
+                will_save_wait_until: true,
+                save: Some(SaveOptions { include_text: true }),
             },
+            document_symbol_provider: true,
+            document_formatting_provider: true,
+            completion_provider: Some(CompletionOptions {
+                resolve_provider: true,
+            }),
+            hover_provider: true,
+            folding_range_provider: true,
+            semantic_tokens_provider: Some(SemanticTokensOptions {
+                legend: SemanticTokensLegend {
+                    token_types: SemanticTokenType::ALL.map(|kind| kind.as_str()).to_vec(),
+                    token_modifiers: vec![],
+                },
+                full: true,
+            }),
+            rename_provider: RenameProvider::Options(RenameOptions {
+                prepare_provider: true,
+            }),
+            execute_command_provider: ExecuteCommandOptions {
+                commands: Command::ALL.map(|command| command.name()).to_vec(),
+            },
+            code_action_provider: CodeActionProvider::Options(CodeActionOptions {
+                code_action_kinds: vec![QUICK_FIX, FORMAT_DOCUMENT, CONVERT_INDENTATION],
+            }),
+            inlay_hint_provider: true,
+            definition_provider: true,
+            references_provider: true,
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: vec![":", " "],
+            }),
+            workspace_symbol_provider: true,
+            color_provider: true,
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: false,
+            }),
         }
     }
 }
 
-#[derive(Serialize, Debug)]
+impl ServerCapabilities {
+    /// Sets the encoding in which the server counts the `character` offsets of positions.
+    pub fn with_position_encoding(mut self, position_encoding: PositionEncodingKind) -> Self {
+        self.position_encoding = position_encoding;
+        self
+    }
+
+    pub fn position_encoding(&self) -> PositionEncodingKind {
+        self.position_encoding
+    }
+
+    /// Returns how documents are synced by the client.
+    pub fn text_document_sync(&self) -> TextDocumentSyncKind {
+        self.text_document_sync.change
+    }
+
+    /// Returns `true` if the client is told to send `textDocument/willSaveWaitUntil`
+    /// requests.
+    pub fn will_save_wait_until(&self) -> bool {
+        self.text_document_sync.will_save_wait_until
+    }
+
+    /// Returns the options of the `textDocument/didSave` notifications sent by the client,
+    /// if it's told to send them.
+    pub fn save(&self) -> Option<&SaveOptions> {
+        self.text_document_sync.save.as_ref()
+    }
+
+    pub fn completion_provider(&self) -> Option<&CompletionOptions> {
+        self.completion_provider.as_ref()
+    }
+
+    pub fn hover_provider(&self) -> bool {
+        self.hover_provider
+    }
+
+    pub fn document_symbol_provider(&self) -> bool {
+        self.document_symbol_provider
+    }
+
+    pub fn document_formatting_provider(&self) -> bool {
+        self.document_formatting_provider
+    }
+
+    pub fn folding_range_provider(&self) -> bool {
+        self.folding_range_provider
+    }
+
+    pub fn inlay_hint_provider(&self) -> bool {
+        self.inlay_hint_provider
+    }
+
+    pub fn definition_provider(&self) -> bool {
+        self.definition_provider
+    }
+
+    pub fn references_provider(&self) -> bool {
+        self.references_provider
+    }
+
+    pub fn signature_help_provider(&self) -> Option<&SignatureHelpOptions> {
+        self.signature_help_provider.as_ref()
+    }
+
+    pub fn workspace_symbol_provider(&self) -> bool {
+        self.workspace_symbol_provider
+    }
+
+    pub fn color_provider(&self) -> bool {
+        self.color_provider
+    }
+
+    pub fn code_lens_provider(&self) -> Option<&CodeLensOptions> {
+        self.code_lens_provider.as_ref()
+    }
+
+    pub fn semantic_tokens_provider(&self) -> Option<&SemanticTokensOptions> {
+        self.semantic_tokens_provider.as_ref()
+    }
+
+    pub fn rename_provider(&self) -> bool {
+        !matches!(self.rename_provider, RenameProvider::Enabled(false))
+    }
+
+    /// Returns the names of the commands the client can run with
+    /// `workspace/executeCommand`.
+    pub fn commands(&self) -> &[&'static str] {
+        &self.execute_command_provider.commands
+    }
+
+    pub fn code_action_provider(&self) -> bool {
+        !matches!(
+            self.code_action_provider,
+            CodeActionProvider::Enabled(false)
+        )
+    }
+
+    /// Returns `true` if the client is told to send `textDocument/prepareRename` requests.
+    pub fn prepare_rename_provider(&self) -> bool {
+        matches!(
+            self.rename_provider,
+            RenameProvider::Options(RenameOptions {
+                prepare_provider: true
+            })
+        )
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TextDocumentSyncOptions {
     open_close: bool,
     change: TextDocumentSyncKind,
+    will_save_wait_until: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    save: Option<SaveOptions>,
 }
 
-#[derive(Serialize_repr, Debug)]
+/// Save options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#saveOptions)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveOptions {
+    /// The client is supposed to include the content on save.
+    include_text: bool,
+}
+
+impl SaveOptions {
+    pub fn include_text(&self) -> bool {
+        self.include_text
+    }
+}
+
+/// Completion options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionOptions)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionOptions {
+    /// The server provides support to resolve additional information for a
+    /// completion item.
+    resolve_provider: bool,
+}
+
+/// Code lens options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeLensOptions)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLensOptions {
+    /// Code lens has a resolve provider as well.
+    resolve_provider: bool,
+}
+
+/// Signature help options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#signatureHelpOptions)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureHelpOptions {
+    /// The characters that trigger signature help automatically.
+    trigger_characters: Vec<&'static str>,
+}
+
+impl SignatureHelpOptions {
+    pub fn trigger_characters(&self) -> &[&'static str] {
+        &self.trigger_characters
+    }
+}
+
+/// Whether the server supports renaming, with the options of rename if the client can
+/// consume them.
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(untagged)]
+pub enum RenameProvider {
+    Enabled(bool),
+    Options(RenameOptions),
+}
+
+/// Rename options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#renameOptions)
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameOptions {
+    /// Renames should be checked and tested before being executed.
+    prepare_provider: bool,
+}
+
+/// Whether the server provides code actions, with the options of code actions if the
+/// client can consume them.
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum CodeActionProvider {
+    Enabled(bool),
+    Options(CodeActionOptions),
+}
+
+/// Code action options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeActionOptions)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionOptions {
+    /// The kinds of the code actions the server may return.
+    code_action_kinds: Vec<&'static str>,
+}
+
+/// Execute command options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#executeCommandOptions)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteCommandOptions {
+    /// The commands to be executed on the server.
+    commands: Vec<&'static str>,
+}
+
+/// Semantic tokens options.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokensOptions)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensOptions {
+    /// The legend used by the server.
+    legend: SemanticTokensLegend,
+    /// The server provides support for the tokens of whole documents.
+    full: bool,
+}
+
+/// The names of the token types and modifiers, which tokens refer to by index.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokensLegend)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensLegend {
+    token_types: Vec<&'static str>,
+    token_modifiers: Vec<&'static str>,
+}
+
+#[derive(Serialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u8)]
 pub enum TextDocumentSyncKind {
     None = 0,
     Full = 1,
     Incremental = 2,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::settings::PartialServerSettings;
+    use serde_json::json;
+
+    fn client(value: serde_json::Value) -> ClientCapabilities {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn settings(value: serde_json::Value) -> ServerSettings {
+        ServerSettings::resolve([&PartialServerSettings::from_json(&value).unwrap()])
+    }
+
+    #[test]
+    fn should_advertise_completion_to_supporting_clients() {
+        let client = client(json!({ "textDocument": { "completion": {} } }));
+
+        let capabilities = negotiate_capabilities(&client, &ServerSettings::default());
+
+        assert!(capabilities.completion_provider().is_some());
+    }
+
+    #[test]
+    fn should_not_advertise_completion_when_disabled() {
+        let client = client(json!({ "textDocument": { "completion": {} } }));
+
+        let capabilities =
+            negotiate_capabilities(&client, &settings(json!({ "enableCompletion": false })));
+
+        assert!(capabilities.completion_provider().is_none());
+    }
+
+    #[test]
+    fn should_advertise_features_consumed_by_client() {
+        let client = client(json!({
+            "textDocument": {
+                "synchronization": { "didSave": true },
+                "hover": {},
+                "foldingRange": { "dynamicRegistration": true },
+                "inlayHint": {}
+            }
+        }));
+
+        let capabilities = negotiate_capabilities(&client, &ServerSettings::default());
+
+        assert_eq!(
+            capabilities.text_document_sync(),
+            TextDocumentSyncKind::Incremental
+        );
+        assert!(capabilities.save().is_some_and(SaveOptions::include_text));
+        assert!(capabilities.hover_provider());
+        assert!(capabilities.folding_range_provider());
+        assert!(capabilities.inlay_hint_provider());
+        assert!(!capabilities.definition_provider());
+        assert!(!capabilities.references_provider());
+        assert!(capabilities.signature_help_provider().is_none());
+        assert!(!capabilities.workspace_symbol_provider());
+        assert!(!capabilities.document_symbol_provider());
+        assert!(!capabilities.document_formatting_provider());
+        assert!(capabilities.semantic_tokens_provider().is_none());
+        assert!(capabilities.completion_provider().is_none());
+        assert!(!capabilities.rename_provider());
+        assert!(!capabilities.code_action_provider());
+        assert!(!capabilities.will_save_wait_until());
+    }
+
+    #[test]
+    fn should_advertise_prepare_rename_to_supporting_clients() {
+        let rename_capabilities = |rename| {
+            let client = client(json!({ "textDocument": { "rename": rename } }));
+            let capabilities = negotiate_capabilities(&client, &ServerSettings::default());
+            let json = serde_json::to_value(&capabilities).unwrap();
+            (
+                capabilities.prepare_rename_provider(),
+                json["renameProvider"].clone(),
+            )
+        };
+
+        assert_eq!(
+            rename_capabilities(json!({ "prepareSupport": true })),
+            (true, json!({ "prepareProvider": true }))
+        );
+        assert_eq!(rename_capabilities(json!({})), (false, json!(true)));
+    }
+
+    #[test]
+    fn should_advertise_code_action_kinds_to_supporting_clients() {
+        let code_action_provider = |code_action| {
+            let client = client(json!({ "textDocument": { "codeAction": code_action } }));
+            let capabilities = negotiate_capabilities(&client, &ServerSettings::default());
+            assert!(capabilities.code_action_provider());
+            serde_json::to_value(&capabilities).unwrap()["codeActionProvider"].clone()
+        };
+
+        assert_eq!(
+            code_action_provider(json!({
+                "codeActionLiteralSupport": { "codeActionKind": { "valueSet": ["quickfix"] } }
+            })),
+            json!({
+                "codeActionKinds": [
+                    "quickfix",
+                    "source.formatDocument",
+                    "source.convertIndentation"
+                ]
+            })
+        );
+        assert_eq!(code_action_provider(json!({})), json!(true));
+    }
+
+    #[test]
+    fn should_advertise_will_save_wait_until_to_supporting_clients() {
+        let client = client(json!({
+            "textDocument": { "synchronization": { "willSaveWaitUntil": true } }
+        }));
+
+        let capabilities = negotiate_capabilities(&client, &ServerSettings::default());
+
+        assert!(capabilities.will_save_wait_until());
+        let json = serde_json::to_value(&capabilities).unwrap();
+        assert_eq!(json["textDocumentSync"]["willSaveWaitUntil"], true);
+    }
+
+    #[test]
+    fn should_fall_back_to_full_sync() {
+        let client = client(json!({ "textDocument": { "hover": {} } }));
+
+        let capabilities = negotiate_capabilities(&client, &ServerSettings::default());
+
+        assert_eq!(
+            capabilities.text_document_sync(),
+            TextDocumentSyncKind::Full
+        );
+        let json = serde_json::to_value(&capabilities).unwrap();
+        assert_eq!(json["textDocumentSync"]["change"], 1);
+        assert!(json["textDocumentSync"].get("save").is_none());
+        assert_eq!(json["hoverProvider"], true);
+        assert_eq!(json["documentSymbolProvider"], false);
+    }
+
+    #[test]
+    fn should_not_advertise_completion_to_unsupporting_clients() {
+        let capabilities = negotiate_capabilities(&client(json!({})), &ServerSettings::default());
+
+        assert!(capabilities.completion_provider().is_none());
+        assert!(
+            serde_json::to_value(&capabilities)
+                .unwrap()
+                .get("completionProvider")
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{MapAccess, SeqAccess, Visitor},
+};
+
+use crate::{
+    huml::{
+        Parsed, Value,
+        ast::{Document, Node, Scalar},
+        emit::{self, EmitOptions},
+    },
+    lsp::error::CommandError,
+};
+
+/// The commands run by the server on `workspace/executeCommand` requests.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// Converts the JSON text passed as the only argument to HUML text.
+    ConvertFromJson,
+    /// Converts the open document whose URI is passed as the only argument to JSON text.
+    ConvertToJson,
+}
+
+impl Command {
+    /// Every command, in the order they are advertised to the client.
+    pub const ALL: [Command; 2] = [Command::ConvertFromJson, Command::ConvertToJson];
+
+    /// The identifier of the command, e.g. `huml.convertFromJson`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ConvertFromJson => "huml.convertFromJson",
+            Self::ConvertToJson => "huml.convertToJson",
+        }
+    }
+
+    /// Returns the command identified by `name`, failing if there is none.
+    pub fn from_name(name: &str) -> Result<Self, CommandError> {
+        Self::ALL
+            .into_iter()
+            .find(|command| command.name() == name)
+            .ok_or_else(|| CommandError::UnknownCommand(name.to_string()))
+    }
+
+    /// Returns the only argument of the command, which is a string for every command.
+    pub fn string_argument(self, arguments: &[serde_json::Value]) -> Result<&str, CommandError> {
+        match arguments {
+            [serde_json::Value::String(argument)] => Ok(argument),
+            _ => Err(CommandError::InvalidArguments {
+                command: self.name(),
+                expected: match self {
+                    Self::ConvertFromJson => "a JSON string",
+                    Self::ConvertToJson => "the URI of an open document",
+                },
+            }),
+        }
+    }
+}
+
+/// Converts the JSON text `json` to HUML text, printed with `options`. The keys of
+/// objects keep their order, and if a key is repeated the last value wins.
+pub fn convert_from_json(json: &str, options: &EmitOptions) -> Result<String, CommandError> {
+    let JsonValue(value) = serde_json::from_str(json)?;
+    let document = Document {
+        root: Some(Node::from(&value)),
+        ..Document::default()
+    };
+    Ok(emit::to_string(&document, options))
+}
+
+/// Converts the `parsed` HUML document to pretty printed JSON text, keeping the order of
+/// the keys. A document holding no value is converted to an empty object.
+///
+/// Fails with the first syntax error of the document, if any.
+pub fn convert_to_json(parsed: &Parsed) -> Result<String, CommandError> {
+    if let Some(issue) = parsed.issues.first() {
+        return Err(CommandError::InvalidHuml {
+            line: issue.span.start.line + 1,
+            message: issue.message.clone(),
+        });
+    }
+    let value = parsed
+        .document
+        .root
+        .as_ref()
+        .map_or(Value::Dict(vec![]), Value::from);
+    Ok(serde_json::to_string_pretty(&AsJson(&value))?)
+}
+
+/// A HUML value read from JSON.
+///
+/// Unlike going through a [`serde_json::Value`], the keys of objects keep their order.
+struct JsonValue(Value);
+
+/// A HUML value written as JSON, keeping the order of the keys of dicts. Floats that are
+/// not finite, which JSON can't represent, are written as `null`.
+struct AsJson<'a>(&'a Value);
+
+impl Serialize for AsJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Scalar(Scalar::Null) => serializer.serialize_unit(),
+            Value::Scalar(Scalar::Boolean(value)) => serializer.serialize_bool(*value),
+            Value::Scalar(Scalar::Integer(value)) => serializer.serialize_i64(*value),
+            Value::Scalar(Scalar::Float(value)) => serializer.serialize_f64(*value),
+            Value::Scalar(Scalar::String(value)) => serializer.serialize_str(value),
+            Value::List(items) => serializer.collect_seq(items.iter().map(AsJson)),
+            Value::Dict(entries) => {
+                serializer.collect_map(entries.iter().map(|(key, value)| (key, AsJson(value))))
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonValueVisitor)
+    }
+}
+
+struct JsonValueVisitor;
+
+impl JsonValueVisitor {
+    fn scalar(scalar: Scalar) -> JsonValue {
+        JsonValue(Value::Scalar(scalar))
+    }
+}
+
+impl<'de> Visitor<'de> for JsonValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<JsonValue, E> {
+        Ok(Self::scalar(Scalar::Null))
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<JsonValue, E> {
+        Ok(Self::scalar(Scalar::Boolean(value)))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<JsonValue, E> {
+        Ok(Self::scalar(Scalar::Integer(value)))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<JsonValue, E> {
+        // HUML integers are 64-bit signed, larger numbers are kept approximately
+        Ok(Self::scalar(match i64::try_from(value) {
+            Ok(value) => Scalar::Integer(value),
+            Err(_) => Scalar::Float(value as f64),
+        }))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<JsonValue, E> {
+        Ok(Self::scalar(Scalar::Float(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<JsonValue, E> {
+        Ok(Self::scalar(Scalar::String(value.to_string())))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut items = vec![];
+        while let Some(JsonValue(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(JsonValue(Value::List(items)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut entries: Vec<(String, Value)> = vec![];
+        while let Some((key, JsonValue(value))) = map.next_entry::<String, _>()? {
+            match entries.iter_mut().find(|(name, _)| *name == key) {
+                Some((_, existing)) => *existing = value,
+                None => entries.push((key, value)),
+            }
+        }
+        Ok(JsonValue(Value::Dict(entries)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    const JSON: &str = r#"{
+        "name": "huml-lsp",
+        "version": 1.5,
+        "private": true,
+        "license": null,
+        "keywords": ["lsp", "huml"],
+        "server": {
+            "port": 8080,
+            "hosts": [{ "name": "a b", "weight": -1 }, []],
+            "tls": {}
+        }
+    }"#;
+
+    #[test]
+    fn should_convert_json_to_equivalent_huml() {
+        let huml = convert_from_json(JSON, &EmitOptions::default()).unwrap();
+
+        let parsed = huml::parse(&huml);
+        assert!(parsed.is_valid(), "Invalid HUML:\n{huml}");
+        let JsonValue(expected) = serde_json::from_str(JSON).unwrap();
+        assert_eq!(
+            Value::from(parsed.document.root.as_ref().unwrap()),
+            expected
+        );
+        assert!(huml.starts_with("name: \"huml-lsp\"\nversion: 1.5\nprivate: true\n"));
+    }
+
+    #[test]
+    fn should_convert_json_scalars() {
+        let convert = |json| convert_from_json(json, &EmitOptions::default()).unwrap();
+
+        assert_eq!(convert("\"a\\nb\""), "\"a\\nb\"\n");
+        assert_eq!(convert("[1, 2]"), "- 1\n- 2\n");
+        assert_eq!(convert(r#"{ "a": 1, "a": 2 }"#), "a: 2\n");
+    }
+
+    #[test]
+    fn should_find_commands_by_name() {
+        for command in Command::ALL {
+            assert_eq!(Command::from_name(command.name()).unwrap(), command);
+        }
+        assert!(matches!(
+            Command::from_name("huml.unknown"),
+            Err(CommandError::UnknownCommand(_))
+        ));
+    }
+
+    #[test]
+    fn should_take_a_single_string_argument() {
+        let command = Command::ConvertFromJson;
+
+        assert_eq!(command.string_argument(&["{}".into()]).unwrap(), "{}");
+        for arguments in [vec![], vec![1.into()], vec!["{}".into(), "{}".into()]] {
+            assert!(matches!(
+                command.string_argument(&arguments),
+                Err(CommandError::InvalidArguments { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn should_round_trip_json_through_huml() {
+        let huml = convert_from_json(JSON, &EmitOptions::default()).unwrap();
+        let json = convert_to_json(&huml::parse(&huml)).unwrap();
+
+        let original: serde_json::Value = serde_json::from_str(JSON).unwrap();
+        let round_tripped: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped, original);
+        // Keys keep the order of the document
+        assert!(json.starts_with("{\n  \"name\": \"huml-lsp\",\n  \"version\": 1.5,"));
+    }
+
+    #[test]
+    fn should_convert_huml_scalars_to_json() {
+        let convert = |huml| convert_to_json(&huml::parse(huml)).unwrap();
+
+        assert_eq!(convert(""), "{}");
+        assert_eq!(convert("0x10"), "16");
+        assert_eq!(
+            convert("- true\n- null\n- nan\n"),
+            "[\n  true,\n  null,\n  null\n]"
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_huml() {
+        let error = convert_to_json(&huml::parse("a: 1\nb: oops\n")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid HUML at line 2: invalid value `oops`; strings must be quoted"
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_json() {
+        assert!(matches!(
+            convert_from_json("{ \"a\": ", &EmitOptions::default()),
+            Err(CommandError::InvalidJson(_))
+        ));
+    }
+}
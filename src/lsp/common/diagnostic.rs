@@ -0,0 +1,647 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+use crate::{
+    huml::{self, Issue, IssueTag, Parsed, validate},
+    lsp::{
+        common::text_document::{Location, PositionEncodingKind, Range, TextEdit},
+        settings::ServerSettings,
+    },
+    rpc::{Integer, LSPAny},
+};
+
+/// The `source` of every diagnostic produced by the server.
+pub const DIAGNOSTIC_SOURCE: &str = "huml";
+
+/// Represents a diagnostic, such as a compiler error or warning.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#diagnostic)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// The range at which the message applies.
+    range: Range,
+
+    /// The diagnostic's severity. To avoid interpretation mismatches when a
+    /// server is used with different clients it is highly recommended that
+    /// servers always provide a severity value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<DiagnosticSeverity>,
+
+    /// The diagnostic's code, which might appear in the user interface.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<DiagnosticCode>,
+
+    /// A human-readable string describing the source of this
+    /// diagnostic, e.g. 'typescript' or 'super lint'.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+
+    /// The diagnostic's message.
+    message: String,
+
+    /// An array of related diagnostic information, e.g. when symbol-names within
+    /// a scope collide all definitions can be marked via this property.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    related_information: Vec<DiagnosticRelatedInformation>,
+
+    /// Additional metadata about the diagnostic.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    tags: Vec<DiagnosticTag>,
+
+    /// A data entry field that is preserved between a
+    /// `textDocument/publishDiagnostics` notification and
+    /// `textDocument/codeAction` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<LSPAny>,
+}
+
+impl Diagnostic {
+    pub fn new(range: Range, severity: DiagnosticSeverity, message: String) -> Self {
+        Self::builder(range, message).severity(severity).build()
+    }
+
+    /// Starts building a diagnostic reporting `message` at `range`, with the server as its
+    /// source and no severity until one is set.
+    pub fn builder(range: Range, message: impl Into<String>) -> DiagnosticBuilder {
+        DiagnosticBuilder {
+            diagnostic: Self {
+                range,
+                severity: None,
+                code: None,
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message: message.into(),
+                related_information: vec![],
+                tags: vec![],
+                data: None,
+            },
+        }
+    }
+
+    /// Lints `source`, the text of the document identified by `uri`: reports its syntax
+    /// errors, along with the issues found by the validation rules enabled in `settings`,
+    /// with the severity configured for their rule. The `character` offsets of their
+    /// ranges are counted in `encoding`.
+    pub fn lint(
+        source: &str,
+        uri: &str,
+        settings: &ServerSettings,
+        encoding: PositionEncodingKind,
+    ) -> Vec<Self> {
+        Self::lint_parsed(source, &huml::parse(source), uri, settings, encoding)
+    }
+
+    /// Lints `source` like [`Diagnostic::lint`], reusing its already `parsed` form.
+    pub fn lint_parsed(
+        source: &str,
+        parsed: &Parsed,
+        uri: &str,
+        settings: &ServerSettings,
+        encoding: PositionEncodingKind,
+    ) -> Vec<Self> {
+        let options = validate::ValidateOptions {
+            indent_width: Some(settings.indent_width()),
+        };
+        let mut validation_issues = validate::validate_with(source, &parsed.document, &options);
+        if let Some(schema) = settings.schema() {
+            validation_issues.extend(schema.validate(&parsed.document));
+        }
+        parsed
+            .issues
+            .iter()
+            .chain(&validation_issues)
+            .filter_map(|issue| {
+                // Syntax errors are always reported
+                let severity = match issue.rule {
+                    Some(rule) => settings.severity(rule)?,
+                    None => DiagnosticSeverity::Error,
+                };
+                Some(Self::from_issue(issue, source, uri, severity, encoding))
+            })
+            .collect()
+    }
+
+    /// Reports an issue found in `source`, the text of the document identified by `uri`,
+    /// with `character` offsets counted in `encoding`. Issues found by a validation rule
+    /// carry the [`DiagnosticData`] of the rule, along with the issue's fix.
+    ///
+    /// The code of these diagnostics is the identifier of their rule.
+    pub fn from_issue(
+        issue: &Issue,
+        source: &str,
+        uri: &str,
+        severity: DiagnosticSeverity,
+        encoding: PositionEncodingKind,
+    ) -> Self {
+        let related_information = issue
+            .related
+            .iter()
+            .map(|related| DiagnosticRelatedInformation {
+                location: Location::new(uri.to_string(), encoding.range(source, related.span)),
+                message: related.message.clone(),
+            })
+            .collect();
+        let builder = Self::builder(encoding.range(source, issue.span), issue.message.clone())
+            .severity(severity)
+            .related_information(related_information)
+            .tags(issue.tag.into_iter().map(DiagnosticTag::from).collect());
+        let Some(rule) = issue.rule else {
+            return builder.build();
+        };
+        let fix = issue
+            .fix
+            .as_ref()
+            .map(|fix| TextEdit::new(encoding.range(source, fix.span), fix.text.clone()));
+        builder
+            .code(DiagnosticCode::String(rule.name().to_string()))
+            .data(&DiagnosticData::new(rule.name().to_string(), fix))
+            .build()
+    }
+
+    /// Attaches `data` to the diagnostic, to be read back with [`Diagnostic::data_as`]
+    /// when the client sends the diagnostic back, e.g. in a code action request.
+    ///
+    /// # Panics
+    /// Panics if `data` doesn't serialize to a JSON value representable as [`LSPAny`],
+    /// e.g. if it contains a `null`.
+    pub fn with_data(mut self, data: &impl Serialize) -> Self {
+        let value = serde_json::to_value(data).expect("Diagnostic data should be serializable");
+        let data = LSPAny::deserialize(value).expect("Diagnostic data should be an LSPAny");
+        self.data = Some(data);
+        self
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn severity(&self) -> Option<DiagnosticSeverity> {
+        self.severity
+    }
+
+    pub fn code(&self) -> Option<&DiagnosticCode> {
+        self.code.as_ref()
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn related_information(&self) -> &[DiagnosticRelatedInformation] {
+        &self.related_information
+    }
+
+    pub fn tags(&self) -> &[DiagnosticTag] {
+        &self.tags
+    }
+
+    pub fn data(&self) -> Option<&LSPAny> {
+        self.data.as_ref()
+    }
+
+    /// Interprets the data attached to the diagnostic as a `T`.
+    ///
+    /// Returns `None` if the diagnostic has no data, or if the data isn't a `T`.
+    pub fn data_as<T: DeserializeOwned>(&self) -> Option<T> {
+        let value = serde_json::to_value(self.data.as_ref()?).ok()?;
+        serde_json::from_value(value).ok()
+    }
+}
+
+/// Builds a [`Diagnostic`], started with [`Diagnostic::builder`].
+#[derive(Clone, Debug)]
+pub struct DiagnosticBuilder {
+    diagnostic: Diagnostic,
+}
+
+impl DiagnosticBuilder {
+    pub fn severity(mut self, severity: DiagnosticSeverity) -> Self {
+        self.diagnostic.severity = Some(severity);
+        self
+    }
+
+    pub fn code(mut self, code: DiagnosticCode) -> Self {
+        self.diagnostic.code = Some(code);
+        self
+    }
+
+    pub fn related_information(mut self, related: Vec<DiagnosticRelatedInformation>) -> Self {
+        self.diagnostic.related_information = related;
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<DiagnosticTag>) -> Self {
+        self.diagnostic.tags = tags;
+        self
+    }
+
+    /// Attaches `data` to the diagnostic, as [`Diagnostic::with_data`] does.
+    ///
+    /// # Panics
+    /// Panics if `data` doesn't serialize to a JSON value representable as [`LSPAny`].
+    pub fn data(mut self, data: &impl Serialize) -> Self {
+        self.diagnostic = self.diagnostic.with_data(data);
+        self
+    }
+
+    pub fn build(self) -> Diagnostic {
+        self.diagnostic
+    }
+}
+
+/// The code of a diagnostic, either a number or a string.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(untagged)]
+pub enum DiagnosticCode {
+    Integer(Integer),
+    String(String),
+}
+
+/// The severity of a diagnostic.
+#[derive(Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum DiagnosticSeverity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+    Hint = 4,
+}
+
+/// The diagnostic tags, letting clients render the code a diagnostic applies to
+/// differently.
+#[derive(Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum DiagnosticTag {
+    /// Unused or unnecessary code, which clients may render faded out.
+    Unnecessary = 1,
+    /// Deprecated or obsolete code, which clients may render struck through.
+    Deprecated = 2,
+}
+
+impl From<IssueTag> for DiagnosticTag {
+    fn from(value: IssueTag) -> Self {
+        match value {
+            IssueTag::Unnecessary => Self::Unnecessary,
+            IssueTag::Deprecated => Self::Deprecated,
+        }
+    }
+}
+
+/// Represents a related message and source code location for a diagnostic.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#diagnosticRelatedInformation)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DiagnosticRelatedInformation {
+    /// The location of this related diagnostic information.
+    location: Location,
+
+    /// The message of this related diagnostic information.
+    message: String,
+}
+
+impl DiagnosticRelatedInformation {
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// The data attached to the diagnostics published by the server.
+///
+/// It identifies the rule that produced a diagnostic and, when known, the edit that
+/// fixes it, so that code actions can fix the diagnostic without analyzing the
+/// document again.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DiagnosticData {
+    /// The identifier of the rule that produced the diagnostic.
+    rule: String,
+
+    /// The edit fixing the diagnostic, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fix: Option<TextEdit>,
+}
+
+impl DiagnosticData {
+    pub fn new(rule: String, fix: Option<TextEdit>) -> Self {
+        Self { rule, fix }
+    }
+
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub fn fix(&self) -> Option<&TextEdit> {
+        self.fix.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{common::text_document::Position, settings::PartialServerSettings};
+    use serde_json::json;
+
+    fn range() -> Range {
+        Range::new(Position::new(0, 4), Position::new(0, 6))
+    }
+
+    #[test]
+    fn should_serialize_diagnostic_without_data() {
+        let diagnostic = Diagnostic::new(
+            range(),
+            DiagnosticSeverity::Warning,
+            "Something is off".to_string(),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&diagnostic).unwrap(),
+            json!({
+                "range": {
+                    "start": { "line": 0, "character": 4 },
+                    "end": { "line": 0, "character": 6 }
+                },
+                "severity": 2,
+                "source": "huml",
+                "message": "Something is off"
+            })
+        );
+    }
+
+    #[test]
+    fn should_serialize_built_diagnostic_with_optional_fields() {
+        let related = DiagnosticRelatedInformation {
+            location: Location::new("file:///a.huml".to_string(), range()),
+            message: "First defined here".to_string(),
+        };
+        let diagnostic = Diagnostic::builder(range(), "Duplicate key")
+            .severity(DiagnosticSeverity::Error)
+            .code(DiagnosticCode::String("duplicate-key".to_string()))
+            .related_information(vec![related])
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&diagnostic).unwrap(),
+            json!({
+                "range": {
+                    "start": { "line": 0, "character": 4 },
+                    "end": { "line": 0, "character": 6 }
+                },
+                "severity": 1,
+                "code": "duplicate-key",
+                "source": "huml",
+                "message": "Duplicate key",
+                "relatedInformation": [{
+                    "location": {
+                        "uri": "file:///a.huml",
+                        "range": {
+                            "start": { "line": 0, "character": 4 },
+                            "end": { "line": 0, "character": 6 }
+                        }
+                    },
+                    "message": "First defined here"
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn should_serialize_severities_as_numbers() {
+        let severities = [
+            DiagnosticSeverity::Error,
+            DiagnosticSeverity::Warning,
+            DiagnosticSeverity::Information,
+            DiagnosticSeverity::Hint,
+        ];
+
+        let encoded: Vec<_> = severities
+            .into_iter()
+            .map(|severity| {
+                let diagnostic = Diagnostic::builder(range(), "").severity(severity).build();
+                serde_json::to_value(&diagnostic).unwrap()["severity"].clone()
+            })
+            .collect();
+
+        assert_eq!(encoded, [json!(1), json!(2), json!(3), json!(4)]);
+        let numeric = Diagnostic::builder(range(), "")
+            .code(DiagnosticCode::Integer(42))
+            .build();
+        let json = serde_json::to_value(&numeric).unwrap();
+        assert_eq!(json["code"], json!(42));
+        assert!(json.get("severity").is_none());
+    }
+
+    #[test]
+    fn should_round_trip_diagnostic_data() {
+        let fix = TextEdit::new(range(), " ".to_string());
+        let data = DiagnosticData::new("colon-spacing".to_string(), Some(fix));
+        let diagnostic = Diagnostic::new(
+            range(),
+            DiagnosticSeverity::Error,
+            "expected exactly one space after ':'".to_string(),
+        )
+        .with_data(&data);
+
+        // Published to the client and sent back in a code action request
+        let json = serde_json::to_string(&diagnostic).unwrap();
+        let received: Diagnostic = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(received, diagnostic);
+        assert_eq!(received.data_as::<DiagnosticData>(), Some(data));
+    }
+
+    #[test]
+    fn should_ignore_foreign_data() {
+        let diagnostic: Diagnostic = serde_json::from_value(json!({
+            "range": {
+                "start": { "line": 0, "character": 4 },
+                "end": { "line": 0, "character": 6 }
+            },
+            "message": "From another server",
+            "data": 42
+        }))
+        .unwrap();
+
+        assert_eq!(diagnostic.data_as::<DiagnosticData>(), None);
+    }
+
+    #[test]
+    fn should_point_issue_at_related_locations() {
+        let source = "name: 1\nname: 2\n";
+        let parsed = crate::huml::parse(source);
+        let issues = crate::huml::validate::validate(source, &parsed.document);
+
+        let diagnostic = Diagnostic::from_issue(
+            &issues[0],
+            source,
+            "file:///a.huml",
+            DiagnosticSeverity::Warning,
+            PositionEncodingKind::Utf16,
+        );
+
+        assert_eq!(diagnostic.severity(), Some(DiagnosticSeverity::Warning));
+        assert_eq!(
+            diagnostic.related_information()[0].location(),
+            &Location::new(
+                "file:///a.huml".to_string(),
+                Range::new(Position::new(0, 0), Position::new(0, 4))
+            )
+        );
+        assert_eq!(
+            diagnostic
+                .data_as::<DiagnosticData>()
+                .map(|data| data.rule().to_string()),
+            Some("duplicate-key".to_string())
+        );
+    }
+
+    #[test]
+    fn should_tag_duplicate_key_as_unnecessary() {
+        let settings = ServerSettings::default();
+
+        let diagnostics = Diagnostic::lint(
+            "name: 1\nname: 2\n",
+            "file:///a.huml",
+            &settings,
+            PositionEncodingKind::Utf16,
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].tags(), [DiagnosticTag::Unnecessary]);
+        let json = serde_json::to_value(&diagnostics[0]).unwrap();
+        assert_eq!(json["tags"], json!([1]));
+    }
+
+    #[test]
+    fn should_carry_fix_of_issue() {
+        let source = "enabled: True\n";
+        let parsed = crate::huml::parse(source);
+        let issues = crate::huml::validate::validate(source, &parsed.document);
+
+        let diagnostic = Diagnostic::from_issue(
+            &issues[0],
+            source,
+            "file:///a.huml",
+            DiagnosticSeverity::Warning,
+            PositionEncodingKind::Utf16,
+        );
+
+        assert_eq!(
+            diagnostic.code(),
+            Some(&DiagnosticCode::String("boolean-spelling".to_string()))
+        );
+        let data = diagnostic.data_as::<DiagnosticData>().unwrap();
+        assert_eq!(data.rule(), "boolean-spelling");
+        assert_eq!(
+            data.fix(),
+            Some(&TextEdit::new(
+                Range::new(Position::new(0, 9), Position::new(0, 13)),
+                "true".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_lint_with_configured_severities() {
+        let source = "name:\"huml\"\nname: \"lsp\"\n";
+        let lint = |settings: serde_json::Value| {
+            let overrides = PartialServerSettings::from_json(&settings).unwrap();
+            let settings = ServerSettings::resolve([&overrides]);
+            Diagnostic::lint(
+                source,
+                "file:///a.huml",
+                &settings,
+                PositionEncodingKind::Utf16,
+            )
+            .iter()
+            .map(|diagnostic| (diagnostic.message().to_string(), diagnostic.severity()))
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            lint(json!({})),
+            [
+                (
+                    "expected exactly one space after ':'".to_string(),
+                    Some(DiagnosticSeverity::Error)
+                ),
+                (
+                    "duplicate key `name`".to_string(),
+                    Some(DiagnosticSeverity::Error)
+                ),
+            ]
+        );
+        assert_eq!(
+            lint(json!({ "colonSpacingSeverity": "off", "duplicateKeySeverity": "hint" })),
+            [(
+                "duplicate key `name`".to_string(),
+                Some(DiagnosticSeverity::Hint)
+            )]
+        );
+    }
+
+    #[test]
+    fn should_lint_against_configured_schema_only() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "port": { "type": "integer" } },
+            "required": ["port"]
+        });
+        let overrides = PartialServerSettings::from_json(
+            &json!({ "schema": schema, "schemaSeverity": "warning" }),
+        )
+        .unwrap();
+        let settings = ServerSettings::resolve([&overrides]);
+        let lint = |source: &str, settings: &ServerSettings| {
+            Diagnostic::lint(
+                source,
+                "file:///a.huml",
+                settings,
+                PositionEncodingKind::Utf16,
+            )
+            .iter()
+            .map(|diagnostic| (diagnostic.message().to_string(), diagnostic.severity()))
+            .collect::<Vec<_>>()
+        };
+
+        assert!(lint("port: 8080\n", &settings).is_empty());
+        assert_eq!(
+            lint("port: \"8080\"\n", &settings),
+            [(
+                "expected integer, found string".to_string(),
+                Some(DiagnosticSeverity::Warning)
+            )]
+        );
+        assert!(lint("port: \"8080\"\n", &ServerSettings::default()).is_empty());
+    }
+
+    #[test]
+    fn should_lint_against_configured_indent_width() {
+        let overrides = PartialServerSettings::from_json(&json!({ "indentWidth": 4 })).unwrap();
+        let settings = ServerSettings::resolve([&overrides]);
+        let lint = |source: &str, settings: &ServerSettings| {
+            Diagnostic::lint(
+                source,
+                "file:///a.huml",
+                settings,
+                PositionEncodingKind::Utf16,
+            )
+            .iter()
+            .map(|diagnostic| diagnostic.message().to_string())
+            .collect::<Vec<_>>()
+        };
+
+        assert!(lint("a::\n    b: 1\n", &settings).is_empty());
+        assert_eq!(
+            lint("a::\n  b: 1\n", &settings),
+            ["inconsistent indentation; expected 4 spaces, found 2"]
+        );
+        assert!(lint("a::\n  b: 1\n", &ServerSettings::default()).is_empty());
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// A string value displayed by the client, e.g. in a hover or the documentation of a
+/// completion item.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#markupContent)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct MarkupContent {
+    /// The type of the markup.
+    kind: MarkupKind,
+
+    /// The content itself.
+    value: String,
+}
+
+impl MarkupContent {
+    /// Creates a markdown content.
+    pub fn markdown(value: String) -> Self {
+        Self {
+            kind: MarkupKind::Markdown,
+            value,
+        }
+    }
+
+    pub fn kind(&self) -> MarkupKind {
+        self.kind
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Describes the content type that a client supports in [`MarkupContent`].
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkupKind {
+    PlainText,
+    Markdown,
+    /// A content type the server doesn't support.
+    #[serde(other, skip_serializing)]
+    Unknown,
+}
@@ -1 +1,5 @@
+pub mod diagnostic;
+pub mod markup;
 pub mod text_document;
+pub mod uri;
+pub mod workspace_edit;
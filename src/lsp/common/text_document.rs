@@ -1,6 +1,9 @@
 use std::borrow::Cow;
 
-use crate::rpc::{Integer, UInteger};
+use crate::{
+    huml::ast::{self, Span},
+    rpc::{Integer, UInteger},
+};
 use serde::{Deserialize, Serialize};
 
 /// An item to transfer a text document from the client to the server.
@@ -26,11 +29,11 @@ pub struct TextDocumentItem<'a> {
 
 impl<'a> TextDocumentItem<'a> {
     pub fn uri(&self) -> &str {
-        &self.uri
+        self.uri
     }
 
     pub fn language_id(&self) -> &str {
-        &self.language_id
+        self.language_id
     }
 
     pub fn version(&self) -> i32 {
@@ -119,7 +122,7 @@ pub struct TextDocumentIdentifier<'a> {
 
 impl<'a> TextDocumentIdentifier<'a> {
     pub fn uri(&self) -> &str {
-        &self.uri
+        self.uri
     }
 }
 
@@ -146,7 +149,9 @@ impl<'a> VersionedTextDocumentIdentifier<'a> {
 }
 
 /// Indicates a position in the document
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+///
+/// Positions are ordered as they appear in the document, by line then by character.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct Position {
     line: UInteger,
     character: UInteger,
@@ -166,8 +171,83 @@ impl Position {
     }
 }
 
+/// The encoding in which the `character` offsets of [`Position`]s are counted.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#positionEncodingKind)
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum PositionEncodingKind {
+    /// Offsets count UTF-8 code units, i.e. bytes.
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// Offsets count UTF-16 code units. This is the default of the protocol.
+    #[default]
+    #[serde(rename = "utf-16")]
+    Utf16,
+    /// Offsets count unicode code points.
+    #[serde(rename = "utf-32")]
+    Utf32,
+    /// An encoding the server doesn't support.
+    #[serde(other, skip_serializing)]
+    Unknown,
+}
+
+impl PositionEncodingKind {
+    /// Returns the byte offset, in `line`, of the `character` offset counted in this
+    /// encoding.
+    ///
+    /// Offsets past the end of the line are clamped to its end, and offsets pointing
+    /// inside a character are moved to the end of that character.
+    pub fn byte_offset(self, line: &str, character: UInteger) -> usize {
+        let mut units = 0;
+        for (offset, c) in line.char_indices() {
+            if units >= character {
+                return offset;
+            }
+            units += match self {
+                Self::Utf8 => c.len_utf8(),
+                Self::Utf16 => c.len_utf16(),
+                Self::Utf32 | Self::Unknown => 1,
+            };
+        }
+        line.len()
+    }
+
+    /// Returns the `character` offset, counted in this encoding, of the `byte_offset` in
+    /// `line`. The inverse of [`byte_offset`](Self::byte_offset).
+    ///
+    /// Offsets past the end of the line are clamped to its end, and offsets pointing
+    /// inside a character are moved to the end of that character.
+    pub fn character_offset(self, line: &str, byte_offset: usize) -> UInteger {
+        line.char_indices()
+            .take_while(|&(offset, _)| offset < byte_offset)
+            .map(|(_, c)| match self {
+                Self::Utf8 => c.len_utf8(),
+                Self::Utf16 => c.len_utf16(),
+                Self::Utf32 | Self::Unknown => 1,
+            })
+            .sum()
+    }
+
+    /// Returns the position of `location` in `source`, the text it was found in, with its
+    /// `character` offset counted in this encoding.
+    pub fn position(self, source: &str, location: ast::Location) -> Position {
+        let line_start = location.offset - location.column;
+        let line = source[line_start..].split('\n').next().unwrap_or_default();
+        Position::new(location.line, self.character_offset(line, location.column))
+    }
+
+    /// Returns the range of `span` in `source`, the text it was found in, with the
+    /// `character` offsets of its ends counted in this encoding.
+    pub fn range(self, source: &str, span: Span) -> Range {
+        Range::new(
+            self.position(source, span.start),
+            self.position(source, span.end),
+        )
+    }
+}
+
 /// Indicates a range of text in the document
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Range {
     start: Position,
     end: Position,
@@ -185,6 +265,75 @@ impl Range {
     pub fn end(&self) -> Position {
         self.end
     }
+
+    /// Returns `true` if the range covers no character, its end not being after its start.
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// Returns `true` if `position` is within the range, its start included and its end
+    /// excluded.
+    pub fn contains(&self, position: Position) -> bool {
+        (self.start..self.end).contains(&position)
+    }
+
+    /// Returns `true` if the ranges share at least one character. Empty ranges share none,
+    /// and ranges that only touch, one ending where the other starts, don't overlap.
+    pub fn overlaps(&self, other: Range) -> bool {
+        !self.is_empty() && !other.is_empty() && self.start < other.end && other.start < self.end
+    }
+}
+
+/// Represents a location inside a resource, such as a line inside a text file.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#location)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Location {
+    uri: String,
+    range: Range,
+}
+
+impl Location {
+    pub fn new(uri: String, range: Range) -> Self {
+        Self { uri, range }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+}
+
+/// A textual edit applicable to a text document.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textEdit)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    /// The range of the text document to be manipulated. To insert
+    /// text into a document create a range where start === end.
+    range: Range,
+
+    /// The string to be inserted. For delete operations use an
+    /// empty string.
+    new_text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range, new_text: String) -> Self {
+        Self { range, new_text }
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn new_text(&self) -> &str {
+        &self.new_text
+    }
 }
 
 #[cfg(test)]
@@ -283,4 +432,86 @@ mod tests {
         assert_eq!(deserialized.end().line(), 5);
         assert_eq!(deserialized.end().character(), 20);
     }
+
+    #[test]
+    fn should_convert_character_offsets_to_bytes() {
+        let line = "café: \"🦀\"";
+
+        assert_eq!(PositionEncodingKind::Utf8.byte_offset(line, 5), 5);
+        assert_eq!(PositionEncodingKind::Utf16.byte_offset(line, 4), 5);
+        assert_eq!(PositionEncodingKind::Utf32.byte_offset(line, 4), 5);
+
+        // After the crab, which is two UTF-16 code units long
+        assert_eq!(PositionEncodingKind::Utf16.byte_offset(line, 9), 12);
+        assert_eq!(PositionEncodingKind::Utf32.byte_offset(line, 8), 12);
+    }
+
+    #[test]
+    fn should_clamp_character_offsets() {
+        let line = "🦀🦀";
+
+        assert_eq!(PositionEncodingKind::Utf16.byte_offset(line, 1), 4);
+        assert_eq!(
+            PositionEncodingKind::Utf16.byte_offset(line, 42),
+            line.len()
+        );
+    }
+
+    #[test]
+    fn should_convert_byte_offsets_to_characters() {
+        let line = "café: \"🦀\"";
+
+        assert_eq!(PositionEncodingKind::Utf8.character_offset(line, 5), 5);
+        assert_eq!(PositionEncodingKind::Utf16.character_offset(line, 12), 9);
+        assert_eq!(PositionEncodingKind::Utf32.character_offset(line, 12), 8);
+        // Inside the crab, and past the end of the line
+        assert_eq!(PositionEncodingKind::Utf16.character_offset(line, 9), 9);
+        assert_eq!(PositionEncodingKind::Utf32.character_offset(line, 42), 9);
+    }
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn should_order_positions_by_line_then_character() {
+        assert!(Position::new(0, 10) < Position::new(1, 0));
+        assert!(Position::new(1, 2) < Position::new(1, 3));
+        assert_eq!(
+            Position::new(2, 0).max(Position::new(1, 9)),
+            Position::new(2, 0)
+        );
+    }
+
+    #[test]
+    fn should_contain_start_but_not_end() {
+        let range = range((1, 4), (3, 2));
+
+        assert!(range.contains(Position::new(1, 4)));
+        assert!(range.contains(Position::new(2, 0)));
+        assert!(range.contains(Position::new(2, 100)));
+        assert!(range.contains(Position::new(3, 1)));
+        assert!(!range.contains(Position::new(3, 2)));
+        assert!(!range.contains(Position::new(1, 3)));
+        assert!(!range.contains(Position::new(0, 8)));
+    }
+
+    #[test]
+    fn should_detect_overlapping_ranges() {
+        let multiline = range((1, 4), (3, 2));
+
+        assert!(multiline.overlaps(range((0, 0), (1, 5))));
+        assert!(multiline.overlaps(range((2, 0), (2, 1))));
+        assert!(multiline.overlaps(range((3, 1), (4, 0))));
+        assert!(!multiline.overlaps(range((3, 2), (4, 0))));
+        assert!(!multiline.overlaps(range((0, 0), (1, 4))));
+        assert!(!multiline.overlaps(range((2, 0), (2, 0))));
+    }
+
+    #[test]
+    fn should_detect_empty_ranges() {
+        assert!(range((2, 3), (2, 3)).is_empty());
+        assert!(range((2, 3), (1, 0)).is_empty());
+        assert!(!range((2, 3), (3, 0)).is_empty());
+    }
 }
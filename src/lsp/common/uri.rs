@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+const FILE_SCHEME: &str = "file://";
+
+/// Converts a `file://` URI into a filesystem path.
+///
+/// Percent-encoded octets in the path are decoded. Returns `None` for URIs with a
+/// scheme other than `file`, or when the decoded path isn't valid UTF-8.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded_path = uri.strip_prefix(FILE_SCHEME)?;
+    // Skip the (usually empty) authority component
+    let encoded_path = &encoded_path[encoded_path.find('/')?..];
+
+    let mut decoded = Vec::with_capacity(encoded_path.len());
+    let mut bytes = encoded_path.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'%' {
+            decoded.push(byte);
+            continue;
+        }
+        let hex = [bytes.next()?, bytes.next()?];
+        let hex = str::from_utf8(&hex).ok()?;
+        decoded.push(u8::from_str_radix(hex, 16).ok()?);
+    }
+
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// Normalizes `uri`, so that the different spellings clients may use for the same
+/// document compare equal.
+///
+/// The scheme is lowercased, percent-encoded unreserved characters are decoded, and the
+/// hex digits of the remaining percent-encoded octets are uppercased, as described by
+/// [RFC 3986](https://datatracker.ietf.org/doc/html/rfc3986#section-6.2.2).
+pub fn normalize_uri(uri: &str) -> String {
+    let (scheme, rest) = match uri.split_once(':') {
+        Some((scheme, rest)) => (scheme.to_ascii_lowercase(), rest),
+        None => (String::new(), uri),
+    };
+
+    let mut normalized = scheme;
+    if !normalized.is_empty() {
+        normalized.push(':');
+    }
+    let mut chars = rest.char_indices();
+    while let Some((index, char)) = chars.next() {
+        let octet = rest
+            .get(index + 1..index + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (char, octet) {
+            ('%', Some(octet)) => {
+                chars.nth(1);
+                if octet.is_ascii_alphanumeric() || b"-._~".contains(&octet) {
+                    normalized.push(char::from(octet));
+                } else {
+                    normalized.push_str(&format!("%{octet:02X}"));
+                }
+            }
+            _ => normalized.push(char),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_convert_file_uri() {
+        assert_eq!(
+            uri_to_path("file:///home/user/config.huml"),
+            Some(PathBuf::from("/home/user/config.huml"))
+        );
+    }
+
+    #[test]
+    fn should_decode_percent_encoded_path() {
+        assert_eq!(
+            uri_to_path("file:///home/user/my%20project/a%2Bb.huml"),
+            Some(PathBuf::from("/home/user/my project/a+b.huml"))
+        );
+    }
+
+    #[test]
+    fn should_reject_other_schemes() {
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+        assert_eq!(uri_to_path("https://huml.io/config.huml"), None);
+    }
+
+    #[test]
+    fn should_normalize_uri_spellings() {
+        assert_eq!(
+            normalize_uri("FILE:///home/%7euser/my%2fdir/a%2Db.huml"),
+            "file:///home/~user/my%2Fdir/a-b.huml"
+        );
+        assert_eq!(
+            normalize_uri("file:///home/user/100%.huml"),
+            "file:///home/user/100%.huml"
+        );
+    }
+}
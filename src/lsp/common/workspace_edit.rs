@@ -0,0 +1,25 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::lsp::common::text_document::TextEdit;
+
+/// Changes to many documents of the workspace.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspaceEdit)
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+pub struct WorkspaceEdit {
+    /// The edits to apply to each document, keyed by the URI of the document.
+    changes: IndexMap<String, Vec<TextEdit>>,
+}
+
+impl WorkspaceEdit {
+    /// Adds `edits` to the edits of the document identified by `uri`.
+    pub fn with_edits(mut self, uri: String, edits: impl IntoIterator<Item = TextEdit>) -> Self {
+        self.changes.entry(uri).or_default().extend(edits);
+        self
+    }
+
+    pub fn changes(&self) -> &IndexMap<String, Vec<TextEdit>> {
+        &self.changes
+    }
+}
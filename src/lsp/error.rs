@@ -1,13 +1,190 @@
+use std::{io, path::PathBuf};
+
 use thiserror::Error;
 
+use crate::{lsp::response::ErrorCode, rpc::Integer};
+
 #[derive(Error, Debug)]
 pub enum ServerError {
-    #[error("Failed to initialize server")]
+    #[error(transparent)]
     Initialize(#[from] InitializeError),
+    #[error(transparent)]
+    Lifecycle(#[from] LifecycleError),
+    /// The id of a request is already used by another request in flight.
+    #[error("Request id {0} is already in use by a request in flight")]
+    DuplicateRequestId(Integer),
+    /// The client answered a request the server isn't awaiting a response to.
+    #[error("Unexpected response to request {0:?}")]
+    UnexpectedResponse(Option<Integer>),
+}
+
+/// An error answering a request: a JSON-RPC error code along with a message describing
+/// the problem.
+#[derive(Error, Clone, PartialEq, Eq, Debug)]
+#[error("{message}")]
+pub struct JsonRpcError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl JsonRpcError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<ServerError> for JsonRpcError {
+    fn from(e: ServerError) -> Self {
+        let code = match &e {
+            ServerError::Initialize(_) => ErrorCode::InvalidRequest,
+            ServerError::Lifecycle(LifecycleError::NotInitialized) => {
+                ErrorCode::ServerNotInitialized
+            }
+            ServerError::Lifecycle(LifecycleError::ShutDown) => ErrorCode::InvalidRequest,
+            ServerError::DuplicateRequestId(_) | ServerError::UnexpectedResponse(_) => {
+                ErrorCode::InvalidRequest
+            }
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<InitializeError> for JsonRpcError {
+    fn from(e: InitializeError) -> Self {
+        ServerError::from(e).into()
+    }
+}
+
+impl From<LifecycleError> for JsonRpcError {
+    fn from(e: LifecycleError) -> Self {
+        ServerError::from(e).into()
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum InitializeError {
     #[error("Server already initialized")]
     AlreadyInitialized,
+    #[error("Server is shut down")]
+    ShutDown,
+}
+
+/// The reasons a request can't be handled in the current lifecycle state of the server.
+#[derive(Error, Debug)]
+pub enum LifecycleError {
+    #[error("Server not initialized")]
+    NotInitialized,
+    #[error("Server is shut down")]
+    ShutDown,
+}
+
+/// The reasons an edit sent by the client can't be applied to a document.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DocumentSyncError {
+    #[error("Document out of sync. Edit at line {line}, but the document has {line_count} lines")]
+    LineOutOfRange { line: usize, line_count: usize },
+    #[error("Edit range ends before it starts")]
+    InvertedRange,
+    #[error("Edit ranges overlap")]
+    OverlappingRanges,
+}
+
+/// The reasons a `workspace/executeCommand` request can't be run.
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("Unknown command `{0}`")]
+    UnknownCommand(String),
+    #[error("`{command}` expects {expected}")]
+    InvalidArguments {
+        command: &'static str,
+        expected: &'static str,
+    },
+    #[error("Document `{0}` isn't open")]
+    UnknownDocument(String),
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("Invalid HUML at line {line}: {message}")]
+    InvalidHuml { line: usize, message: String },
+}
+
+impl From<CommandError> for JsonRpcError {
+    fn from(e: CommandError) -> Self {
+        Self::new(ErrorCode::InvalidParams, e.to_string())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Failed to read settings file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid settings file: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Invalid settings: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to load schema from {}: {reason}", path.display())]
+    Schema { path: PathBuf, reason: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_map_server_errors_to_json_rpc_errors() {
+        let errors = [
+            (
+                ServerError::from(InitializeError::AlreadyInitialized),
+                ErrorCode::InvalidRequest,
+                "Server already initialized",
+            ),
+            (
+                InitializeError::ShutDown.into(),
+                ErrorCode::InvalidRequest,
+                "Server is shut down",
+            ),
+            (
+                LifecycleError::NotInitialized.into(),
+                ErrorCode::ServerNotInitialized,
+                "Server not initialized",
+            ),
+            (
+                LifecycleError::ShutDown.into(),
+                ErrorCode::InvalidRequest,
+                "Server is shut down",
+            ),
+        ];
+
+        for (error, code, message) in errors {
+            let error = JsonRpcError::from(error);
+            assert_eq!((error.code(), error.message()), (code, message));
+        }
+    }
+
+    #[test]
+    fn should_use_standard_error_codes() {
+        let codes = [
+            (ErrorCode::ParseError, -32700),
+            (ErrorCode::InvalidRequest, -32600),
+            (ErrorCode::MethodNotFound, -32601),
+            (ErrorCode::InvalidParams, -32602),
+            (ErrorCode::InternalError, -32603),
+            (ErrorCode::ServerNotInitialized, -32002),
+            (ErrorCode::RequestCancelled, -32800),
+        ];
+
+        for (code, value) in codes {
+            assert_eq!(code as i32, value, "{code:?}");
+        }
+    }
 }
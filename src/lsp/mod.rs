@@ -29,8 +29,14 @@ pub mod request;
 /// Contains the definitions for all LSP response messages.
 pub mod response;
 
+/// Defines the commands the server runs on `workspace/executeCommand` requests
+pub mod command;
+
 /// Contains the definitions of  common JSON structures used in the LSP specification
 pub mod common;
 
 /// Contains the server state and request handlers
 pub mod server;
+
+/// Defines the user configurable settings of the server and how they are resolved
+pub mod settings;
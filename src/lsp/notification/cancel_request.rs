@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+use crate::rpc::Integer;
+
+/// Params for the [`$/cancelRequest`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#cancelRequest)
+///
+/// [`$/cancelRequest`]: crate::lsp::notification::ClientServerNotificationVariant::CancelRequest
+#[derive(Deserialize, Debug)]
+pub struct CancelParams {
+    /// The request id to cancel.
+    id: Integer,
+}
+
+impl CancelParams {
+    pub fn id(&self) -> Integer {
+        self.id
+    }
+}
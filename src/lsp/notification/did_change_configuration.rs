@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+/// Params for the [`workspace/didChangeConfiguration`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#didChangeConfigurationParams)
+///
+/// [`workspace/didChangeConfiguration`]: crate::lsp::notification::ClientServerNotificationVariant::DidChangeConfiguration
+#[derive(Deserialize, Debug)]
+pub struct DidChangeConfigurationParams {
+    /// The actual changed settings, holding the settings of the server in its
+    /// [`section`](DidChangeConfigurationParams::SECTION).
+    #[serde(default)]
+    settings: serde_json::Value,
+}
+
+impl DidChangeConfigurationParams {
+    /// The section of the client settings holding the settings of the server.
+    pub const SECTION: &str = "huml";
+
+    pub fn settings(&self) -> &serde_json::Value {
+        &self.settings
+    }
+
+    /// Returns the settings of the server, if the client sent any.
+    pub fn server_settings(&self) -> Option<&serde_json::Value> {
+        self.settings
+            .get(Self::SECTION)
+            .filter(|section| !section.is_null())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_extract_server_settings_section() {
+        let params: DidChangeConfigurationParams = serde_json::from_value(json!({
+            "settings": { "huml": { "indentWidth": 4 }, "editor": { "tabSize": 8 } }
+        }))
+        .unwrap();
+
+        assert_eq!(params.server_settings(), Some(&json!({ "indentWidth": 4 })));
+    }
+
+    #[test]
+    fn should_tolerate_missing_settings() {
+        let params: DidChangeConfigurationParams =
+            serde_json::from_value(json!({ "settings": null })).unwrap();
+
+        assert_eq!(params.server_settings(), None);
+    }
+}
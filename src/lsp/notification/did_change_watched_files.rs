@@ -0,0 +1,75 @@
+use serde::Deserialize;
+use serde_repr::Deserialize_repr;
+
+/// Params for the [`workspace/didChangeWatchedFiles`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#didChangeWatchedFilesParams)
+///
+/// [`workspace/didChangeWatchedFiles`]: crate::lsp::notification::ClientServerNotificationVariant::DidChangeWatchedFiles
+#[derive(Deserialize, Debug)]
+pub struct DidChangeWatchedFilesParams {
+    /// The actual file events.
+    changes: Vec<FileEvent>,
+}
+
+impl DidChangeWatchedFilesParams {
+    pub fn changes(&self) -> &[FileEvent] {
+        &self.changes
+    }
+}
+
+/// An event describing a file change.
+#[derive(Deserialize, Debug)]
+pub struct FileEvent {
+    /// The file's URI.
+    uri: String,
+
+    /// The change type.
+    #[serde(rename = "type")]
+    change_type: FileChangeType,
+}
+
+impl FileEvent {
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn change_type(&self) -> FileChangeType {
+        self.change_type
+    }
+}
+
+/// The file event type.
+#[derive(Deserialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum FileChangeType {
+    Created = 1,
+    Changed = 2,
+    Deleted = 3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_did_change_watched_files_params() {
+        let json_input = r#"{
+            "changes": [
+                { "uri": "file:///tmp/project/.huml-lsp.toml", "type": 2 },
+                { "uri": "file:///tmp/project/old.huml", "type": 3 }
+            ]
+        }"#;
+
+        let params: DidChangeWatchedFilesParams =
+            serde_json::from_str(json_input).expect("Deserialization failed");
+
+        assert_eq!(params.changes().len(), 2);
+        assert_eq!(
+            params.changes()[0].uri(),
+            "file:///tmp/project/.huml-lsp.toml"
+        );
+        assert_eq!(params.changes()[0].change_type(), FileChangeType::Changed);
+        assert_eq!(params.changes()[1].change_type(), FileChangeType::Deleted);
+    }
+}
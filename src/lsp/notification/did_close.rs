@@ -0,0 +1,21 @@
+use crate::lsp::common::text_document::TextDocumentIdentifier;
+use serde::Deserialize;
+
+/// Params for the [`textDocument/didClose`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#didCloseTextDocumentParams)
+///
+/// [`textDocument/didClose`]: crate::lsp::notification::ClientServerNotificationVariant::DidClose
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DidCloseTextDocumentParams<'a> {
+    /// The document that was closed.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+}
+
+impl<'a> DidCloseTextDocumentParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+}
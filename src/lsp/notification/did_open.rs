@@ -19,7 +19,7 @@ impl<'a> DidOpenTextDocumentParams<'a> {
     }
 
     pub fn into_text_document(self) -> TextDocumentItemOwned {
-        return self.text_document.into();
+        self.text_document.into()
     }
 }
 
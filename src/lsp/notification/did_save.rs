@@ -0,0 +1,54 @@
+use std::borrow::Cow;
+
+use crate::lsp::common::text_document::TextDocumentIdentifier;
+use serde::Deserialize;
+
+/// Params for the [`textDocument/didSave`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#didSaveTextDocumentParams)
+///
+/// [`textDocument/didSave`]: crate::lsp::notification::ClientServerNotificationVariant::DidSave
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DidSaveTextDocumentParams<'a> {
+    /// The document that was saved.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The content when saved, if the server asked for it with `includeText`.
+    #[serde(borrow, default)]
+    text: Option<Cow<'a, str>>,
+}
+
+impl<'a> DidSaveTextDocumentParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_did_save_params() {
+        let with_text = r#"{
+            "textDocument": { "uri": "file:///config.huml" },
+            "text": "name: \"huml\"\n"
+        }"#;
+        let without_text = r#"{ "textDocument": { "uri": "file:///config.huml" } }"#;
+
+        let params: DidSaveTextDocumentParams =
+            serde_json::from_str(with_text).expect("Deserialization failed");
+        assert_eq!(params.text_document().uri(), "file:///config.huml");
+        assert_eq!(params.text(), Some("name: \"huml\"\n"));
+
+        let params: DidSaveTextDocumentParams =
+            serde_json::from_str(without_text).expect("Deserialization failed");
+        assert_eq!(params.text(), None);
+    }
+}
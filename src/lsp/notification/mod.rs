@@ -5,16 +5,35 @@
 //! - [`ClientServerNotification`]: Notifications sent from the client to the server.
 //! - [`ServerClientNotification`]: Notifications sent from the server to the client.
 
+pub mod cancel_request;
 pub mod did_change;
+pub mod did_change_configuration;
+pub mod did_change_watched_files;
+pub mod did_close;
 pub mod did_open;
+pub mod did_save;
+pub mod publish_diagnostics;
+pub mod telemetry;
 pub mod trace;
+pub mod window;
 
 use crate::lsp::notification::{
+    cancel_request::CancelParams,
     did_change::DidChangeTextDocumentParams,
+    did_change_configuration::DidChangeConfigurationParams,
+    did_change_watched_files::DidChangeWatchedFilesParams,
+    did_close::DidCloseTextDocumentParams,
     did_open::DidOpenTextDocumentParams,
+    did_save::DidSaveTextDocumentParams,
+    publish_diagnostics::PublishDiagnosticsParams,
     trace::{LogTraceParams, SetTraceParams},
+    window::{LogMessageParams, ShowMessageParams},
+};
+use crate::rpc::LSPAny;
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{Error, IgnoredAny},
 };
-use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
@@ -50,6 +69,11 @@ pub enum ClientServerNotificationVariant<'a> {
     #[serde(rename = "$/setTrace")]
     SetTrace(SetTraceParams),
 
+    /// The `$/cancelRequest` notification is sent from the client to the server to cancel
+    /// a request it's no longer interested in.
+    #[serde(rename = "$/cancelRequest")]
+    CancelRequest(CancelParams),
+
     /// The document open notification is sent from the client to the server to signal
     /// newly opened text documents.
     #[serde(borrow)]
@@ -62,6 +86,28 @@ pub enum ClientServerNotificationVariant<'a> {
     #[serde(rename = "textDocument/didChange")]
     DidChange(DidChangeTextDocumentParams<'a>),
 
+    /// The document close notification is sent from the client to the server when a
+    /// document is closed, after which the client owns its content again.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/didClose")]
+    DidClose(DidCloseTextDocumentParams<'a>),
+
+    /// The document save notification is sent from the client to the server when a
+    /// document was saved, with its content if the server asked for it.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/didSave")]
+    DidSave(DidSaveTextDocumentParams<'a>),
+
+    /// The watched files notification is sent from the client to the server when the client
+    /// detects changes to files and folders watched by the language client.
+    #[serde(rename = "workspace/didChangeWatchedFiles")]
+    DidChangeWatchedFiles(DidChangeWatchedFilesParams),
+
+    /// The configuration change notification is sent from the client to the server when
+    /// the settings of the client changed, e.g. the `huml` section of its user settings.
+    #[serde(rename = "workspace/didChangeConfiguration")]
+    DidChangeConfiguration(DidChangeConfigurationParams),
+
     /// The `exit` notification is sent from the client to the server to ask it to exit.
     /// This notification must only be sent after a `shutdown` request has been successfully
     /// handled, transitioning the [Server] into the [Server::Shutdown] state.
@@ -70,6 +116,60 @@ pub enum ClientServerNotificationVariant<'a> {
     /// [Server::Shutdown]: crate::lsp::server::Server::Shutdown
     #[serde(rename = "exit")]
     Exit,
+
+    /// A notification whose method isn't supported by the server, which is ignored. Only
+    /// the notifications without params decode to it, the others are read as an
+    /// [`UnknownNotification`].
+    #[serde(other)]
+    Unknown,
+}
+
+impl ClientServerNotificationVariant<'_> {
+    /// Returns `true` if `method` names a notification supported by the server.
+    pub fn is_known(method: &str) -> bool {
+        // Without params, only the unknown methods are sure to decode
+        let probe = serde_json::json!({ "method": method }).to_string();
+        !matches!(
+            serde_json::from_str(&probe),
+            Ok(ClientServerNotificationVariant::Unknown)
+        )
+    }
+}
+
+/// A notification from the client whose method isn't supported by the server. As
+/// notifications can't be answered, it's only kept to be logged before being ignored.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UnknownNotification<'a> {
+    #[serde(borrow, deserialize_with = "deserialize_unknown_method")]
+    method: &'a str,
+
+    #[serde(default, rename = "params")]
+    _params: Option<IgnoredAny>,
+
+    #[serde(rename = "jsonrpc")]
+    _jsonrpc: &'a str,
+}
+
+impl UnknownNotification<'_> {
+    pub fn method(&self) -> &str {
+        self.method
+    }
+}
+
+/// Deserializes the method of an [`UnknownNotification`], failing for the methods
+/// supported by the server so that their notifications are decoded as such.
+fn deserialize_unknown_method<'de, D>(deserializer: D) -> Result<&'de str, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let method = <&str>::deserialize(deserializer)?;
+    if ClientServerNotificationVariant::is_known(method) {
+        return Err(D::Error::custom(format!(
+            "`{method}` is a supported notification"
+        )));
+    }
+    Ok(method)
 }
 
 /// The parameters for the `initialized` notification.
@@ -85,6 +185,28 @@ pub enum ServerClientNotification {
     /// diagnostic information. Its verbosity is controlled by the `$/setTrace` notification.
     #[serde(rename = "$/logTrace")]
     LogTrace(LogTraceParams),
+
+    /// The `textDocument/publishDiagnostics` notification is sent from the server to the
+    /// client to report the problems found in a document. Every notification replaces the
+    /// diagnostics previously published for the document.
+    #[serde(rename = "textDocument/publishDiagnostics")]
+    PublishDiagnostics(PublishDiagnosticsParams),
+
+    /// The `window/showMessage` notification is sent from the server to the client to ask
+    /// it to display a message in the user interface.
+    #[serde(rename = "window/showMessage")]
+    ShowMessage(ShowMessageParams),
+
+    /// The `window/logMessage` notification is sent from the server to the client to ask
+    /// it to log a message, e.g. in its output panel.
+    #[serde(rename = "window/logMessage")]
+    LogMessage(LogMessageParams),
+
+    /// The `telemetry/event` notification is sent from the server to the client to ask
+    /// it to log a telemetry event, e.g. the [lint metrics](telemetry::lint_event) of a
+    /// document. Only sent if the user enabled telemetry.
+    #[serde(rename = "telemetry/event")]
+    Telemetry(LSPAny),
 }
 
 /// A convenience implementation to easily convert `LogTraceParams` into a `ServerClientNotification`.
@@ -95,9 +217,34 @@ impl From<LogTraceParams> for ServerClientNotification {
     }
 }
 
+/// A convenience implementation to easily convert `PublishDiagnosticsParams` into a
+/// `ServerClientNotification`.
+impl From<PublishDiagnosticsParams> for ServerClientNotification {
+    fn from(v: PublishDiagnosticsParams) -> Self {
+        Self::PublishDiagnostics(v)
+    }
+}
+
+/// A convenience implementation to easily convert `ShowMessageParams` into a
+/// `ServerClientNotification`.
+impl From<ShowMessageParams> for ServerClientNotification {
+    fn from(v: ShowMessageParams) -> Self {
+        Self::ShowMessage(v)
+    }
+}
+
+/// A convenience implementation to easily convert `LogMessageParams` into a
+/// `ServerClientNotification`.
+impl From<LogMessageParams> for ServerClientNotification {
+    fn from(v: LogMessageParams) -> Self {
+        Self::LogMessage(v)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::lsp::notification::window::MessageType;
 
     #[test]
     fn should_deserialize_initialized_notification() {
@@ -137,6 +284,23 @@ mod test {
         ))
     }
 
+    #[test]
+    fn should_deserialize_cancel_request() {
+        let json_input = r#"{
+          "jsonrpc": "2.0",
+          "method": "$/cancelRequest",
+          "params": { "id": 12 }
+        }"#;
+
+        let notification: ClientServerNotification = serde_json::from_str(json_input).unwrap();
+
+        let ClientServerNotificationVariant::CancelRequest(params) = notification.into_variant()
+        else {
+            panic!("Expected a cancel request notification");
+        };
+        assert_eq!(params.id(), 12);
+    }
+
     #[test]
     fn should_deserialize_did_open() {
         let json_input = r#"{
@@ -196,6 +360,106 @@ mod test {
         ));
     }
 
+    #[test]
+    fn should_deserialize_did_close() {
+        let json_input = r#"{
+          "jsonrpc": "2.0",
+          "method": "textDocument/didClose",
+          "params": {
+            "textDocument": { "uri": "file:///tmp/test.huml" }
+          }
+        }"#;
+
+        let notification: ClientServerNotification = serde_json::from_str(json_input).unwrap();
+
+        let ClientServerNotificationVariant::DidClose(params) = notification.into_variant() else {
+            panic!("Expected a didClose notification");
+        };
+        assert_eq!(params.text_document().uri(), "file:///tmp/test.huml");
+    }
+
+    #[test]
+    fn should_deserialize_did_change_watched_files() {
+        let json_input = r#"{
+          "jsonrpc": "2.0",
+          "method": "workspace/didChangeWatchedFiles",
+          "params": {
+            "changes": [
+              { "uri": "file:///tmp/.huml-lsp.toml", "type": 1 }
+            ]
+          }
+        }"#;
+
+        let notification: ClientServerNotification = serde_json::from_str(json_input).unwrap();
+
+        assert!(matches!(
+            notification,
+            ClientServerNotification {
+                variant: ClientServerNotificationVariant::DidChangeWatchedFiles(..),
+                _jsonrpc: "2.0"
+            }
+        ));
+    }
+
+    #[test]
+    fn should_serialize_show_message() {
+        let notification: ServerClientNotification =
+            ShowMessageParams::new(MessageType::Error, "Failed".to_string()).into();
+
+        assert_eq!(
+            serde_json::to_value(notification).unwrap(),
+            serde_json::json!({
+                "method": "window/showMessage",
+                "params": { "type": 1, "message": "Failed" }
+            })
+        );
+    }
+
+    #[test]
+    fn should_serialize_log_message() {
+        let types = [
+            MessageType::Error,
+            MessageType::Warning,
+            MessageType::Info,
+            MessageType::Log,
+        ];
+        let notifications: Vec<_> = types
+            .into_iter()
+            .map(|kind| {
+                let notification: ServerClientNotification =
+                    LogMessageParams::new(kind, "Logged".to_string()).into();
+                serde_json::to_value(notification).unwrap()
+            })
+            .collect();
+
+        assert_eq!(notifications[0]["method"], "window/logMessage");
+        assert_eq!(notifications[0]["params"]["message"], "Logged");
+        let types: Vec<_> = notifications
+            .iter()
+            .map(|notification| notification["params"]["type"].clone())
+            .collect();
+        assert_eq!(types, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn should_serialize_telemetry_event() {
+        let event = telemetry::lint_event(std::time::Duration::from_millis(2), &[]);
+        let notification = ServerClientNotification::Telemetry(event);
+
+        assert_eq!(
+            serde_json::to_value(notification).unwrap(),
+            serde_json::json!({
+                "method": "telemetry/event",
+                "params": {
+                    "event": "lint",
+                    "durationMs": 2.0,
+                    "diagnosticCount": 0,
+                    "errorCount": 0
+                }
+            })
+        );
+    }
+
     #[test]
     fn should_deserialize_exit_notification() {
         let json_input = r#"{
@@ -0,0 +1,43 @@
+use serde::Serialize;
+
+use crate::{lsp::common::diagnostic::Diagnostic, rpc::Integer};
+
+/// Params for the [`textDocument/publishDiagnostics`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#publishDiagnosticsParams)
+///
+/// [`textDocument/publishDiagnostics`]: crate::lsp::notification::ServerClientNotification::PublishDiagnostics
+#[derive(Serialize, Clone, Debug)]
+pub struct PublishDiagnosticsParams {
+    /// The URI for which diagnostic information is reported.
+    uri: String,
+
+    /// The version number of the document the diagnostics are published for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<Integer>,
+
+    /// An array of diagnostic information items.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl PublishDiagnosticsParams {
+    pub fn new(uri: String, version: Option<Integer>, diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            uri,
+            version,
+            diagnostics,
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn version(&self) -> Option<Integer> {
+        self.version
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use crate::{
+    lsp::common::diagnostic::{Diagnostic, DiagnosticSeverity},
+    rpc::{LSPAny, LSPObject},
+};
+
+/// Builds the `telemetry/event` reporting that a document was linted: how long it took,
+/// and how many diagnostics and errors were found. Nothing identifying the document or
+/// its content is included.
+///
+/// [`telemetry/event`]: crate::lsp::notification::ServerClientNotification::Telemetry
+pub fn lint_event(duration: Duration, diagnostics: &[Diagnostic]) -> LSPAny {
+    let error_count = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity() == Some(DiagnosticSeverity::Error))
+        .count();
+    LSPAny::LSPObject(LSPObject::from([
+        ("event".to_string(), LSPAny::String("lint".to_string())),
+        (
+            "durationMs".to_string(),
+            LSPAny::Decimal(duration.as_secs_f64() * 1000.0),
+        ),
+        (
+            "diagnosticCount".to_string(),
+            LSPAny::UInteger(diagnostics.len()),
+        ),
+        ("errorCount".to_string(), LSPAny::UInteger(error_count)),
+    ]))
+}
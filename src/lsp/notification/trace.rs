@@ -51,6 +51,7 @@ pub struct LogTraceParams {
     message: String,
     /// Additional information that can be computed if the `trace` configuration
     /// is set to `'verbose'`
+    #[serde(skip_serializing_if = "Option::is_none")]
     verbose: Option<String>,
 }
 
@@ -59,6 +60,8 @@ impl LogTraceParams {
         Self { message, verbose }
     }
 
+    /// Adapts the params to the `trace` level of the client: drops them when tracing is
+    /// off, and drops the verbose information unless tracing is verbose.
     pub fn with_trace_level(self, trace: TraceValue) -> Option<Self> {
         match trace {
             TraceValue::Off => None,
@@ -66,4 +69,12 @@ impl LogTraceParams {
             TraceValue::Verbose => Some(Self::new(self.message, self.verbose)),
         }
     }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn verbose(&self) -> Option<&str> {
+        self.verbose.as_deref()
+    }
 }
@@ -0,0 +1,70 @@
+use serde::Serialize;
+use serde_repr::Serialize_repr;
+
+/// The type of a message shown or logged by the client.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#messageType)
+#[derive(Serialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum MessageType {
+    Error = 1,
+    Warning = 2,
+    Info = 3,
+    Log = 4,
+}
+
+/// Params for the [`window/showMessage`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#showMessageParams)
+///
+/// [`window/showMessage`]: crate::lsp::notification::ServerClientNotification::ShowMessage
+#[derive(Serialize, Clone, Debug)]
+pub struct ShowMessageParams {
+    /// The message type.
+    #[serde(rename = "type")]
+    kind: MessageType,
+    /// The actual message.
+    message: String,
+}
+
+impl ShowMessageParams {
+    pub fn new(kind: MessageType, message: String) -> Self {
+        Self { kind, message }
+    }
+
+    pub fn kind(&self) -> MessageType {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Params for the [`window/logMessage`] notification
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#logMessageParams)
+///
+/// [`window/logMessage`]: crate::lsp::notification::ServerClientNotification::LogMessage
+#[derive(Serialize, Clone, Debug)]
+pub struct LogMessageParams {
+    /// The message type.
+    #[serde(rename = "type")]
+    kind: MessageType,
+    /// The actual message.
+    message: String,
+}
+
+impl LogMessageParams {
+    pub fn new(kind: MessageType, message: String) -> Self {
+        Self { kind, message }
+    }
+
+    pub fn kind(&self) -> MessageType {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
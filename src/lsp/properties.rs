@@ -14,3 +14,13 @@ impl Default for ServerInfo {
         }
     }
 }
+
+impl ServerInfo {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn version(&self) -> &'static str {
+        self.version
+    }
+}
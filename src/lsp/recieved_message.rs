@@ -1,14 +1,276 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, de::Error};
+use serde_json::{Value, value::RawValue};
 
-use crate::lsp::{notification::ClientServerNotification, request::Request};
+use crate::{
+    lsp::{
+        notification::{ClientServerNotification, UnknownNotification},
+        request::{Request, RequestMethod},
+        response::{ErrorCode, ResponseMessage},
+    },
+    rpc::Integer,
+};
 
 /// Any message recieved by the server:
-/// Either a request or a notification
+/// Either a request, a notification, or the response to a request of the server.
+/// Notifications of methods the server doesn't support are kept apart, to be ignored.
+// Messages are handled one at a time, so boxing the requests wouldn't save memory
+#[allow(clippy::large_enum_variant)]
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
 pub enum RecievedMessage<'a> {
     #[serde(borrow)]
     Request(Request<'a>),
     #[serde(borrow)]
+    UnknownNotification(UnknownNotification<'a>),
+    #[serde(borrow)]
     Notification(ClientServerNotification<'a>),
+    #[serde(borrow)]
+    Response(ClientResponse<'a>),
+}
+
+/// The response of the client to a request sent by the server, e.g. to
+/// `client/registerCapability`. Its result is kept as JSON, to be read according to the
+/// method of the request answered.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ClientResponse<'a> {
+    /// The id of the request answered, `null` if the client couldn't read it.
+    id: Option<Integer>,
+
+    #[serde(default)]
+    result: Option<Value>,
+
+    #[serde(default)]
+    error: Option<Value>,
+
+    #[serde(rename = "jsonrpc")]
+    _jsonrpc: &'a str,
+}
+
+impl ClientResponse<'_> {
+    pub fn id(&self) -> Option<Integer> {
+        self.id
+    }
+
+    /// Returns the result the client answered with, `None` if it's `null` or if the
+    /// client answered with an error.
+    pub fn result(&self) -> Option<&Value> {
+        self.result.as_ref()
+    }
+
+    /// Returns `true` if the client answered with an error.
+    pub fn is_error(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// Returns the message of the error the client answered with, if any.
+    pub fn error_message(&self) -> Option<&str> {
+        let error = self.error.as_ref()?;
+        Some(
+            error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+        )
+    }
+}
+
+impl RecievedMessage<'_> {
+    /// Builds the error response to the message `body`, which failed to decode into a
+    /// [`RecievedMessage`] with `error`.
+    ///
+    /// Messages that aren't valid JSON are answered with a [`ErrorCode::ParseError`],
+    /// requests for methods the server doesn't support with an
+    /// [`ErrorCode::MethodNotFound`], and other requests that aren't understood with an
+    /// [`ErrorCode::InvalidRequest`]. Returns `None` for notifications, which can't be
+    /// answered.
+    pub fn decode_error_response(body: &str, error: &impl ToString) -> Option<ResponseMessage> {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+            return Some(ResponseMessage::error_for_undecoded(
+                None,
+                ErrorCode::ParseError,
+                error.to_string(),
+            ));
+        };
+
+        let id = serde_json::from_value(value.get("id")?.clone()).ok();
+        let method = value.get("method").and_then(serde_json::Value::as_str);
+        if let Some(method) = method.filter(|method| !RequestMethod::is_known(method)) {
+            return Some(ResponseMessage::error_for_undecoded(
+                id,
+                ErrorCode::MethodNotFound,
+                format!("Unknown method `{method}`"),
+            ));
+        }
+
+        Some(ResponseMessage::error_for_undecoded(
+            id,
+            ErrorCode::InvalidRequest,
+            error.to_string(),
+        ))
+    }
+}
+
+/// The content of a message frame: either a single message, or a batch of messages sent
+/// as a JSON array.
+///
+/// The messages are kept as raw JSON, to be decoded one by one into [`RecievedMessage`]s,
+/// so that an invalid message doesn't prevent handling the rest of its batch.
+#[derive(Debug)]
+pub enum RecievedMessages<'a> {
+    Single(&'a RawValue),
+    Batch(Vec<&'a RawValue>),
+}
+
+impl<'a> RecievedMessages<'a> {
+    /// Returns the JSON bodies of the messages, in the order they were sent.
+    pub fn bodies(&self) -> Vec<&'a str> {
+        match self {
+            Self::Single(message) => vec![message.get()],
+            Self::Batch(messages) => messages.iter().map(|message| message.get()).collect(),
+        }
+    }
+
+    /// Returns `true` if the messages were sent as a batch, to be answered with a batch.
+    pub fn is_batch(&self) -> bool {
+        matches!(self, Self::Batch(_))
+    }
+}
+
+impl<'de> Deserialize<'de> for RecievedMessages<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = <&RawValue>::deserialize(deserializer)?;
+        if raw.get().starts_with('[') {
+            let messages = serde_json::from_str(raw.get()).map_err(D::Error::custom)?;
+            Ok(Self::Batch(messages))
+        } else {
+            Ok(Self::Single(raw))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_error_response(body: &str) -> Option<serde_json::Value> {
+        let error = serde_json::from_str::<RecievedMessage>(body).unwrap_err();
+        RecievedMessage::decode_error_response(body, &error)
+            .map(|response| serde_json::to_value(response).unwrap())
+    }
+
+    #[test]
+    fn should_answer_invalid_json_with_parse_error() {
+        let response = decode_error_response("{\"jsonrpc\": ").unwrap();
+
+        assert_eq!(response["id"], serde_json::Value::Null);
+        assert_eq!(response["error"]["code"], ErrorCode::ParseError as i32);
+    }
+
+    #[test]
+    fn should_answer_unknown_method_with_method_not_found() {
+        let body = r#"{"jsonrpc":"2.0","id":7,"method":"textDocument/foo","params":{}}"#;
+
+        let response = decode_error_response(body).unwrap();
+
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], ErrorCode::MethodNotFound as i32);
+        assert_eq!(
+            response["error"]["message"],
+            "Unknown method `textDocument/foo`"
+        );
+    }
+
+    #[test]
+    fn should_answer_invalid_params_with_invalid_request() {
+        let body = r#"{"jsonrpc":"2.0","id":7,"method":"textDocument/hover","params":[]}"#;
+
+        let response = decode_error_response(body).unwrap();
+
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], ErrorCode::InvalidRequest as i32);
+    }
+
+    #[test]
+    fn should_not_answer_invalid_notification() {
+        let body = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":[]}"#;
+
+        assert_eq!(decode_error_response(body), None);
+    }
+
+    #[test]
+    fn should_recognize_unknown_notifications() {
+        let messages = [
+            r#"{"jsonrpc":"2.0","method":"$/unknown"}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/unknownNotify","params":{"a":[1]}}"#,
+        ];
+
+        let methods: Vec<_> = messages
+            .into_iter()
+            .map(|body| match serde_json::from_str(body) {
+                Ok(RecievedMessage::UnknownNotification(notification)) => {
+                    notification.method().to_string()
+                }
+                other => panic!("Expected an unknown notification, got {other:?}"),
+            })
+            .collect();
+
+        assert_eq!(methods, ["$/unknown", "textDocument/unknownNotify"]);
+        let exit = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+        assert!(matches!(
+            serde_json::from_str(exit),
+            Ok(RecievedMessage::Notification(_))
+        ));
+    }
+
+    #[test]
+    fn should_recognize_responses_of_client() {
+        let success = r#"{"jsonrpc":"2.0","id":1,"result":null}"#;
+        let failure = r#"{"jsonrpc":"2.0","id":2,"error":{"code":-32601,"message":"no"}}"#;
+
+        let Ok(RecievedMessage::Response(success)) = serde_json::from_str(success) else {
+            panic!("Expected a response");
+        };
+        let Ok(RecievedMessage::Response(failure)) = serde_json::from_str(failure) else {
+            panic!("Expected a response");
+        };
+
+        assert_eq!(success.id(), Some(1));
+        assert!(!success.is_error());
+        assert_eq!(failure.id(), Some(2));
+        assert!(failure.is_error());
+        assert_eq!(failure.error_message(), Some("no"));
+    }
+
+    #[test]
+    fn should_split_batch_into_messages() {
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#;
+        let notification = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+        let body = format!("[{request}, {notification}]");
+
+        let messages: RecievedMessages = serde_json::from_str(&body).unwrap();
+
+        assert!(messages.is_batch());
+        assert_eq!(messages.bodies(), [request, notification]);
+        let decoded: Vec<_> = messages
+            .bodies()
+            .into_iter()
+            .map(serde_json::from_str::<RecievedMessage>)
+            .collect();
+        assert!(matches!(decoded[0], Ok(RecievedMessage::Request(_))));
+        assert!(matches!(decoded[1], Ok(RecievedMessage::Notification(_))));
+    }
+
+    #[test]
+    fn should_keep_single_message() {
+        let body = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+
+        let messages: RecievedMessages = serde_json::from_str(body).unwrap();
+
+        assert!(!messages.is_batch());
+        assert_eq!(messages.bodies(), [body]);
+    }
 }
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{lsp::common::workspace_edit::WorkspaceEdit, rpc::UInteger};
+
+/// Params for a [super::ServerClientRequestMethod::ApplyEdit]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#applyWorkspaceEditParams)
+#[derive(Serialize, Clone, Debug)]
+pub struct ApplyWorkspaceEditParams {
+    /// An optional label of the workspace edit. This label is presented in the user
+    /// interface for example on an undo stack to undo the workspace edit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+
+    /// The edits to apply.
+    edit: WorkspaceEdit,
+}
+
+impl ApplyWorkspaceEditParams {
+    pub fn new(label: Option<String>, edit: WorkspaceEdit) -> Self {
+        Self { label, edit }
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn edit(&self) -> &WorkspaceEdit {
+        &self.edit
+    }
+}
+
+/// The response of the client to a [super::ServerClientRequestMethod::ApplyEdit].
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#applyWorkspaceEditResult)
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyWorkspaceEditResult {
+    /// Indicates whether the edit was applied or not.
+    applied: bool,
+
+    /// An optional textual description for why the edit was not applied.
+    #[serde(default)]
+    failure_reason: Option<String>,
+
+    /// Depending on the client's failure handling strategy, the index of the change that
+    /// failed, if the edit wasn't applied.
+    #[serde(default)]
+    failed_change: Option<UInteger>,
+}
+
+impl ApplyWorkspaceEditResult {
+    pub fn applied(&self) -> bool {
+        self.applied
+    }
+
+    pub fn failure_reason(&self) -> Option<&str> {
+        self.failure_reason.as_deref()
+    }
+
+    pub fn failed_change(&self) -> Option<UInteger> {
+        self.failed_change
+    }
+}
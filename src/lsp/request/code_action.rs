@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+use crate::lsp::common::{
+    diagnostic::Diagnostic,
+    text_document::{Range, TextDocumentIdentifier},
+};
+
+/// Params for a [super::RequestMethod::CodeAction]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeActionParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionParams<'a> {
+    /// The document in which the command was invoked.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The range for which the command was invoked.
+    range: Range,
+
+    /// Context carrying additional information.
+    context: CodeActionContext,
+}
+
+impl<'a> CodeActionParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn context(&self) -> &CodeActionContext {
+        &self.context
+    }
+}
+
+/// Contains additional diagnostic information about the context in which a code action
+/// is run.
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeActionContext)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionContext {
+    /// The diagnostics known on the client side overlapping the range of the request.
+    diagnostics: Vec<Diagnostic>,
+
+    /// The kinds of code actions requested, or `None` for every kind. Actions of
+    /// sub-kinds are requested as well, e.g. `source` includes `source.formatDocument`.
+    #[serde(default)]
+    only: Option<Vec<String>>,
+}
+
+impl CodeActionContext {
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns `true` if code actions of `kind` are requested.
+    pub fn is_requested(&self, kind: &str) -> bool {
+        self.only.as_ref().is_none_or(|only| {
+            only.iter().any(|requested| {
+                kind.strip_prefix(requested.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(only: &str) -> CodeActionContext {
+        serde_json::from_str(&format!(r#"{{ "diagnostics": [], "only": {only} }}"#)).unwrap()
+    }
+
+    #[test]
+    fn should_request_kinds_and_their_sub_kinds() {
+        assert!(context("null").is_requested("quickfix"));
+        assert!(context(r#"["source"]"#).is_requested("source.formatDocument"));
+        assert!(context(r#"["source.formatDocument"]"#).is_requested("source.formatDocument"));
+        assert!(!context(r#"["source"]"#).is_requested("quickfix"));
+        assert!(!context(r#"["source.format"]"#).is_requested("source.formatDocument"));
+    }
+}
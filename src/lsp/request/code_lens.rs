@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+use crate::lsp::common::text_document::TextDocumentIdentifier;
+
+/// Params for a [super::RequestMethod::CodeLens]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeLensParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLensParams<'a> {
+    /// The document to request code lens for.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+}
+
+impl<'a> CodeLensParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+}
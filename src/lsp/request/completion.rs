@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+use crate::lsp::common::text_document::{Position, TextDocumentIdentifier};
+
+/// Params for a [super::RequestMethod::Completion]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionParams<'a> {
+    /// The text document.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The position inside the text document.
+    position: Position,
+}
+
+impl<'a> CompletionParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Deserialize;
+
+use crate::lsp::{
+    common::text_document::{Range, TextDocumentIdentifier},
+    response::document_color::Color,
+};
+
+/// Params for a [super::RequestMethod::DocumentColor]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#documentColorParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentColorParams<'a> {
+    /// The text document.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+}
+
+impl<'a> DocumentColorParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+}
+
+/// Params for a [super::RequestMethod::ColorPresentation]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#colorPresentationParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorPresentationParams<'a> {
+    /// The text document.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The color to request presentations for.
+    color: Color,
+
+    /// The range where the color would be inserted.
+    range: Range,
+}
+
+impl<'a> ColorPresentationParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+}
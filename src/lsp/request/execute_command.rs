@@ -0,0 +1,43 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Params for a [super::RequestMethod::ExecuteCommand]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#executeCommandParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteCommandParams {
+    /// The identifier of the actual command handler.
+    command: String,
+
+    /// Arguments that the command should be invoked with.
+    #[serde(default)]
+    arguments: Vec<Value>,
+}
+
+impl ExecuteCommandParams {
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn arguments(&self) -> &[Value] {
+        &self.arguments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_execute_command_params() {
+        let json = r#"{ "command": "huml.convertFromJson", "arguments": ["{}"] }"#;
+
+        let params: ExecuteCommandParams = serde_json::from_str(json).unwrap();
+
+        assert_eq!(params.command(), "huml.convertFromJson");
+        assert_eq!(params.arguments(), ["{}"]);
+
+        let params: ExecuteCommandParams = serde_json::from_str(r#"{ "command": "a" }"#).unwrap();
+        assert!(params.arguments().is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+use serde::Deserialize;
+
+use crate::{lsp::common::text_document::TextDocumentIdentifier, rpc::UInteger};
+
+/// Params for a [super::RequestMethod::Formatting]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#documentFormattingParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentFormattingParams<'a> {
+    /// The document to format.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The format options.
+    options: FormattingOptions,
+}
+
+impl<'a> DocumentFormattingParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn options(&self) -> &FormattingOptions {
+        &self.options
+    }
+}
+
+/// Value-object describing what options formatting should use.
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#formattingOptions)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattingOptions {
+    /// Size of a tab in spaces.
+    tab_size: UInteger,
+
+    /// Prefer spaces over tabs. HUML only allows spaces for indentation, so this is
+    /// ignored when formatting.
+    insert_spaces: bool,
+}
+
+impl FormattingOptions {
+    pub fn tab_size(&self) -> UInteger {
+        self.tab_size
+    }
+
+    pub fn insert_spaces(&self) -> bool {
+        self.insert_spaces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_document_formatting_params() {
+        let json = r#"{
+            "textDocument": { "uri": "file:///config.huml" },
+            "options": { "tabSize": 4, "insertSpaces": true, "trimTrailingWhitespace": true }
+        }"#;
+
+        let params: DocumentFormattingParams = serde_json::from_str(json).unwrap();
+
+        assert_eq!(params.text_document().uri(), "file:///config.huml");
+        assert_eq!(params.options().tab_size(), 4);
+        assert!(params.options().insert_spaces());
+    }
+}
@@ -16,15 +16,23 @@ pub struct InitializeParams<'a> {
     #[serde(borrow)]
     client_info: Option<ClientInfo<'a>>,
 
+    /// User provided initialization options.
+    initialization_options: Option<serde_json::Value>,
+
     /// The capabilities provided by the client (editor or tool)
     capabilities: ClientCapabilities,
 
+    /// The rootUri of the workspace. Is null if no folder is open. If both `rootUri` and
+    /// `workspaceFolders` are set, `workspaceFolders` wins.
+    #[serde(default, borrow)]
+    root_uri: Option<&'a str>,
+
     /// The workspace folders configured in the client when the server starts.
-    ///	This property is only available if the client supports workspace folders.
-    ///	It can be `null` if the client supports workspace folders but none are
-    ///	configured.
+    ///  This property is only available if the client supports workspace folders.
+    ///  It can be `null` if the client supports workspace folders but none are
+    ///  configured.
     #[serde(borrow)]
-    workspace_folders: Option<WorkspaceFolder<'a>>,
+    workspace_folders: Option<Vec<WorkspaceFolder<'a>>>,
 }
 
 impl<'a> InitializeParams<'a> {
@@ -36,12 +44,29 @@ impl<'a> InitializeParams<'a> {
         self.client_info.as_ref()
     }
 
+    pub fn initialization_options(&self) -> Option<&serde_json::Value> {
+        self.initialization_options.as_ref()
+    }
+
     pub fn capabilities(&self) -> &ClientCapabilities {
         &self.capabilities
     }
 
-    pub fn workspace_folders(&self) -> Option<&WorkspaceFolder<'_>> {
-        self.workspace_folders.as_ref()
+    pub fn root_uri(&self) -> Option<&str> {
+        self.root_uri
+    }
+
+    pub fn workspace_folders(&self) -> &[WorkspaceFolder<'_>] {
+        self.workspace_folders.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the URIs of the folders open in the client: its workspace folders, or its
+    /// `rootUri` for clients without any, e.g. the ones not supporting workspace folders.
+    pub fn workspace_uris(&self) -> Vec<&str> {
+        match self.workspace_folders() {
+            [] => self.root_uri.into_iter().collect(),
+            folders => folders.iter().map(WorkspaceFolder::uri).collect(),
+        }
     }
 }
 
@@ -56,11 +81,11 @@ pub struct ClientInfo<'a> {
 
 impl<'a> ClientInfo<'a> {
     pub fn name(&self) -> &str {
-        &self.name
+        self.name
     }
 
     pub fn version(&self) -> &str {
-        &self.version
+        self.version
     }
 }
 
@@ -76,10 +101,60 @@ pub struct WorkspaceFolder<'a> {
 
 impl<'a> WorkspaceFolder<'a> {
     pub fn uri(&self) -> &str {
-        &self.uri
+        self.uri
     }
 
     pub fn name(&self) -> &str {
-        &self.name
+        self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_workspace_folders() {
+        let json = r#"{
+            "processId": null,
+            "capabilities": {},
+            "rootUri": "file:///projects/a",
+            "workspaceFolders": [
+                { "uri": "file:///projects/a", "name": "a" },
+                { "uri": "file:///projects/b", "name": "b" }
+            ]
+        }"#;
+
+        let params: InitializeParams = serde_json::from_str(json).unwrap();
+
+        let names: Vec<_> = params
+            .workspace_folders()
+            .iter()
+            .map(|f| f.name())
+            .collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(
+            params.workspace_uris(),
+            ["file:///projects/a", "file:///projects/b"]
+        );
+    }
+
+    #[test]
+    fn should_fall_back_to_root_uri_without_workspace_folders() {
+        let json = r#"{
+            "processId": 1,
+            "capabilities": {},
+            "rootUri": "file:///projects/a",
+            "workspaceFolders": null
+        }"#;
+
+        let params: InitializeParams = serde_json::from_str(json).unwrap();
+
+        assert!(params.workspace_folders().is_empty());
+        assert_eq!(params.workspace_uris(), ["file:///projects/a"]);
+
+        let params: InitializeParams =
+            serde_json::from_str(r#"{ "processId": 1, "capabilities": {} }"#).unwrap();
+        assert!(params.workspace_uris().is_empty());
     }
 }
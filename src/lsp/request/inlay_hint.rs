@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+use crate::lsp::common::text_document::{Range, TextDocumentIdentifier};
+
+/// Params for a [super::RequestMethod::InlayHint]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#inlayHintParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintParams<'a> {
+    /// The text document.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The visible document range for which inlay hints should be computed.
+    range: Range,
+}
+
+impl<'a> InlayHintParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+}
@@ -5,12 +5,72 @@
 //! This module defines the top-level `Request` container and an enumeration of all
 //! supported request types (`RequestMethods`) along with their specific parameters.
 
+/// structures and functionality related to apply workspace edit request
+mod apply_edit;
+/// structures and functionality related to code action request
+mod code_action;
+/// structures and functionality related to code lens request
+mod code_lens;
+/// structures and functionality related to completion request
+mod completion;
+/// structures and functionality related to definition request
+mod definition;
+/// structures and functionality related to document color and color presentation requests
+mod document_color;
+/// structures and functionality related to document symbol request
+mod document_symbol;
+/// structures and functionality related to execute command request
+mod execute_command;
+/// structures and functionality related to folding range request
+mod folding_range;
+/// structures and functionality related to formatting request
+mod formatting;
+/// structures and functionality related to hover request
+mod hover;
 /// structures and functionality related to initialize request
 mod initialize;
+/// structures and functionality related to inlay hint request
+mod inlay_hint;
+/// structures and functionality related to prepare rename request
+mod prepare_rename;
+/// structures and functionality related to references request
+mod references;
+/// structures and functionality related to register capability request
+mod register_capability;
+/// structures and functionality related to rename request
+mod rename;
+/// structures and functionality related to semantic tokens request
+mod semantic_tokens;
+/// structures and functionality related to signature help request
+mod signature_help;
+/// structures and functionality related to will save wait until request
+mod will_save;
+/// structures and functionality related to workspace symbol request
+mod workspace_symbol;
 
-use crate::rpc::Integer;
+use crate::{lsp::response::completion::CompletionItem, rpc::Integer};
+pub use apply_edit::*;
+pub use code_action::*;
+pub use code_lens::*;
+pub use completion::*;
+pub use definition::*;
+pub use document_color::*;
+pub use document_symbol::*;
+pub use execute_command::*;
+pub use folding_range::*;
+pub use formatting::*;
+pub use hover::*;
 pub use initialize::*;
-use serde::Deserialize;
+pub use inlay_hint::*;
+pub use prepare_rename::*;
+pub use references::*;
+pub use register_capability::*;
+pub use rename::*;
+pub use semantic_tokens::*;
+use serde::{Deserialize, Serialize};
+pub use signature_help::*;
+pub use will_save::*;
+pub use workspace_symbol::*;
 
 /// Describes a request message sent from the client to the server.
 ///
@@ -48,6 +108,71 @@ impl<'a> Request<'a> {
     }
 }
 
+/// Describes a request message sent from the server to the client.
+///
+/// See the [LSP specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#requestMessage)
+/// for more details.
+#[derive(Serialize, Debug)]
+pub struct ServerClientRequest {
+    /// The identifier of the request, used by the client to answer it.
+    id: Integer,
+
+    /// The method and parameters of the request.
+    #[serde(flatten)]
+    method: ServerClientRequestMethod,
+
+    jsonrpc: &'static str,
+}
+
+impl ServerClientRequest {
+    pub fn new(id: Integer, method: ServerClientRequestMethod) -> Self {
+        Self {
+            id,
+            method,
+            jsonrpc: "2.0",
+        }
+    }
+
+    pub fn id(&self) -> Integer {
+        self.id
+    }
+
+    pub fn method(&self) -> &ServerClientRequestMethod {
+        &self.method
+    }
+}
+
+/// The requests sent from the server to the client.
+#[derive(Serialize, Debug)]
+#[serde(tag = "method", content = "params")]
+pub enum ServerClientRequestMethod {
+    /// The `client/registerCapability` request asks the client to register a capability
+    /// dynamically, e.g. to watch files.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#client_registerCapability)
+    /// for more details.
+    #[serde(rename = "client/registerCapability")]
+    RegisterCapability(RegistrationParams),
+
+    /// The `workspace/applyEdit` request asks the client to modify resources, e.g. to
+    /// replace the text of a document with the result of a command.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_applyEdit)
+    /// for more details.
+    #[serde(rename = "workspace/applyEdit")]
+    ApplyEdit(ApplyWorkspaceEditParams),
+}
+
+impl ServerClientRequestMethod {
+    /// The method of the request, as sent to the client.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RegisterCapability(_) => "client/registerCapability",
+            Self::ApplyEdit(_) => "workspace/applyEdit",
+        }
+    }
+}
+
 /// An enumeration of all supported LSP request methods and their corresponding parameters.
 ///
 /// This enum uses `serde` attributes to deserialize incoming JSON-RPC requests based on
@@ -71,4 +196,197 @@ pub enum RequestMethod<'a> {
     /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#shutdown)
     /// for more details.
     Shutdown,
+
+    /// The `textDocument/documentSymbol` request asks for the hierarchy of symbols found in
+    /// a document, which editors display as an outline.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentSymbol)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/documentSymbol")]
+    DocumentSymbol(DocumentSymbolParams<'a>),
+
+    /// The `textDocument/formatting` request asks the server to format a whole document.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_formatting)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/formatting")]
+    Formatting(DocumentFormattingParams<'a>),
+
+    /// The `textDocument/completion` request asks for the completion items at a given
+    /// position in a document.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_completion)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/completion")]
+    Completion(CompletionParams<'a>),
+
+    /// The `completionItem/resolve` request asks for the details of a completion item
+    /// the user selected, e.g. its documentation.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionItem_resolve)
+    /// for more details.
+    #[serde(rename = "completionItem/resolve")]
+    CompletionResolve(CompletionItem),
+
+    /// The `textDocument/hover` request asks for the information to show when hovering a
+    /// position in a document.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_hover)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/hover")]
+    Hover(HoverParams<'a>),
+
+    /// The `textDocument/foldingRange` request asks for the ranges of lines the client
+    /// can fold in a document.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_foldingRange)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/foldingRange")]
+    FoldingRange(FoldingRangeParams<'a>),
+
+    /// The `textDocument/semanticTokens/full` request asks for the semantic tokens of a
+    /// whole document, which clients use for highlighting.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens_fullRequest)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/semanticTokens/full")]
+    SemanticTokensFull(SemanticTokensParams<'a>),
+
+    /// The `textDocument/rename` request asks for the edits renaming the symbol at a given
+    /// position in a document.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_rename)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/rename")]
+    Rename(RenameParams<'a>),
+
+    /// The `textDocument/prepareRename` request asks whether the symbol at a given position
+    /// can be renamed, before the client asks the user for a new name.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_prepareRename)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/prepareRename")]
+    PrepareRename(PrepareRenameParams<'a>),
+
+    /// The `workspace/executeCommand` request asks the server to run one of the commands
+    /// it advertised.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_executeCommand)
+    /// for more details.
+    #[serde(rename = "workspace/executeCommand")]
+    ExecuteCommand(ExecuteCommandParams),
+
+    /// The `textDocument/codeAction` request asks for the changes the client can apply to
+    /// a range of a document, e.g. to fix its diagnostics.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_codeAction)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/codeAction")]
+    CodeAction(CodeActionParams<'a>),
+
+    /// The `textDocument/willSaveWaitUntil` request is sent before a document is saved,
+    /// asking for the edits to apply to it first, e.g. to format it.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_willSaveWaitUntil)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/willSaveWaitUntil")]
+    WillSaveWaitUntil(WillSaveTextDocumentParams<'a>),
+
+    /// The `textDocument/inlayHint` request asks for the hints to show inline in a range of
+    /// a document, here the types of its values.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_inlayHint)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/inlayHint")]
+    InlayHint(InlayHintParams<'a>),
+
+    /// The `textDocument/definition` request asks for the location where the symbol at a
+    /// given position is defined.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_definition)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/definition")]
+    Definition(DefinitionParams<'a>),
+
+    /// The `textDocument/references` request asks for the locations of the symbol at a
+    /// given position, here every key declared with the same path as the key at it.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_references)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/references")]
+    References(ReferenceParams<'a>),
+
+    /// The `textDocument/signatureHelp` request asks for the signature of what is being
+    /// written at a given position, here the value a schema expects for a key.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_signatureHelp)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/signatureHelp")]
+    SignatureHelp(SignatureHelpParams<'a>),
+
+    /// The `workspace/symbol` request asks for the symbols matching a query across the
+    /// workspace, here the keys of the open documents.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_symbol)
+    /// for more details.
+    #[serde(rename = "workspace/symbol")]
+    WorkspaceSymbol(WorkspaceSymbolParams),
+
+    /// The `textDocument/documentColor` request asks for the colors written in a document,
+    /// for the client to show them, e.g. as swatches.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentColor)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/documentColor")]
+    DocumentColor(DocumentColorParams<'a>),
+
+    /// The `textDocument/colorPresentation` request asks for the ways to write a color,
+    /// e.g. once the user picked it.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_colorPresentation)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/colorPresentation")]
+    ColorPresentation(ColorPresentationParams<'a>),
+
+    /// The `textDocument/codeLens` request asks for the commands to show along with the
+    /// source of a document, here the summaries of its top-level collections.
+    ///
+    /// See the [specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_codeLens)
+    /// for more details.
+    #[serde(borrow)]
+    #[serde(rename = "textDocument/codeLens")]
+    CodeLens(CodeLensParams<'a>),
+
+    /// A request whose method isn't supported by the server, to be answered with a
+    /// [`MethodNotFound`] error. Only the requests without params decode to it, the others
+    /// fail to decode and are recognized with [`RequestMethod::is_known`].
+    ///
+    /// [`MethodNotFound`]: crate::lsp::response::ErrorCode::MethodNotFound
+    #[serde(other)]
+    Unknown,
+}
+
+impl RequestMethod<'_> {
+    /// Returns `true` if `method` names a request supported by the server.
+    pub fn is_known(method: &str) -> bool {
+        // Without params, only the unknown methods are sure to decode
+        let probe = serde_json::json!({ "method": method }).to_string();
+        !matches!(serde_json::from_str(&probe), Ok(RequestMethod::Unknown))
+    }
 }
@@ -0,0 +1,66 @@
+use serde::Deserialize;
+
+use crate::lsp::common::text_document::{Position, TextDocumentIdentifier};
+
+/// Params for a [super::RequestMethod::References]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#referenceParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceParams<'a> {
+    /// The text document.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The position inside the text document.
+    position: Position,
+
+    context: ReferenceContext,
+}
+
+impl<'a> ReferenceParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn context(&self) -> &ReferenceContext {
+        &self.context
+    }
+}
+
+/// The context of a [`ReferenceParams`].
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceContext {
+    /// Include the declaration of the current symbol.
+    include_declaration: bool,
+}
+
+impl ReferenceContext {
+    pub fn include_declaration(&self) -> bool {
+        self.include_declaration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_reference_params() {
+        let json = r#"{
+            "textDocument": { "uri": "file:///config.huml" },
+            "position": { "line": 1, "character": 2 },
+            "context": { "includeDeclaration": true }
+        }"#;
+
+        let params: ReferenceParams = serde_json::from_str(json).unwrap();
+
+        assert_eq!(params.text_document().uri(), "file:///config.huml");
+        assert_eq!(params.position(), Position::new(1, 2));
+        assert!(params.context().include_declaration());
+    }
+}
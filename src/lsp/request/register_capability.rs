@@ -0,0 +1,109 @@
+use serde::Serialize;
+
+/// Params for a [super::ServerClientRequestMethod::RegisterCapability]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#registrationParams)
+#[derive(Serialize, Clone, Debug)]
+pub struct RegistrationParams {
+    registrations: Vec<Registration>,
+}
+
+impl RegistrationParams {
+    pub fn new(registrations: Vec<Registration>) -> Self {
+        Self { registrations }
+    }
+
+    pub fn registrations(&self) -> &[Registration] {
+        &self.registrations
+    }
+}
+
+/// General parameters to register for a capability.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#registration)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Registration {
+    /// The id used to register the request. The id can be used to deregister the request
+    /// again.
+    id: String,
+
+    /// The method / capability to register for.
+    method: String,
+
+    /// Options necessary for the registration.
+    register_options: DidChangeWatchedFilesRegistrationOptions,
+}
+
+impl Registration {
+    /// Registers for `workspace/didChangeWatchedFiles` notifications about the files
+    /// matching `glob_patterns`.
+    pub fn did_change_watched_files(id: String, glob_patterns: Vec<String>) -> Self {
+        Self {
+            id,
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: DidChangeWatchedFilesRegistrationOptions {
+                watchers: glob_patterns
+                    .into_iter()
+                    .map(|glob_pattern| FileSystemWatcher { glob_pattern })
+                    .collect(),
+            },
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the glob patterns of the files to watch.
+    pub fn glob_patterns(&self) -> impl Iterator<Item = &str> {
+        self.register_options
+            .watchers
+            .iter()
+            .map(|watcher| watcher.glob_pattern.as_str())
+    }
+}
+
+/// Describe options to be used when registering for file system change events.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#didChangeWatchedFilesRegistrationOptions)
+#[derive(Serialize, Clone, Debug)]
+struct DidChangeWatchedFilesRegistrationOptions {
+    /// The watchers to register.
+    watchers: Vec<FileSystemWatcher>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FileSystemWatcher {
+    /// The glob pattern to watch, relative to the workspace folders or absolute.
+    glob_pattern: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_serialize_watched_files_registration() {
+        let params = RegistrationParams::new(vec![Registration::did_change_watched_files(
+            "huml-watched-files".to_string(),
+            vec!["**/.huml-lsp.toml".to_string()],
+        )]);
+
+        assert_eq!(
+            serde_json::to_value(params).unwrap(),
+            json!({
+                "registrations": [{
+                    "id": "huml-watched-files",
+                    "method": "workspace/didChangeWatchedFiles",
+                    "registerOptions": { "watchers": [{ "globPattern": "**/.huml-lsp.toml" }] }
+                }]
+            })
+        );
+    }
+}
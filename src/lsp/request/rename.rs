@@ -0,0 +1,53 @@
+use serde::Deserialize;
+
+use crate::lsp::common::text_document::{Position, TextDocumentIdentifier};
+
+/// Params for a [super::RequestMethod::Rename]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#renameParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameParams<'a> {
+    /// The document to rename.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The position at which this request was sent.
+    position: Position,
+
+    /// The new name of the symbol. Owned, as it may hold escaped characters.
+    new_name: String,
+}
+
+impl<'a> RenameParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn new_name(&self) -> &str {
+        &self.new_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_rename_params() {
+        let json = r#"{
+            "textDocument": { "uri": "file:///config.huml" },
+            "position": { "line": 1, "character": 2 },
+            "newName": "\"quoted\tkey\""
+        }"#;
+
+        let params: RenameParams = serde_json::from_str(json).unwrap();
+
+        assert_eq!(params.text_document().uri(), "file:///config.huml");
+        assert_eq!(params.position(), Position::new(1, 2));
+        assert_eq!(params.new_name(), "\"quoted\tkey\"");
+    }
+}
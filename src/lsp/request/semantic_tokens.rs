@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+use crate::lsp::common::text_document::TextDocumentIdentifier;
+
+/// Params for a [super::RequestMethod::SemanticTokensFull]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokensParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensParams<'a> {
+    /// The text document.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+}
+
+impl<'a> SemanticTokensParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+}
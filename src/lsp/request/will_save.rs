@@ -0,0 +1,56 @@
+use serde::Deserialize;
+use serde_repr::Deserialize_repr;
+
+use crate::lsp::common::text_document::TextDocumentIdentifier;
+
+/// Params for a [super::RequestMethod::WillSaveWaitUntil]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#willSaveTextDocumentParams)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WillSaveTextDocumentParams<'a> {
+    /// The document that will be saved.
+    #[serde(borrow)]
+    text_document: TextDocumentIdentifier<'a>,
+
+    /// The reason why the document is saved.
+    reason: TextDocumentSaveReason,
+}
+
+impl<'a> WillSaveTextDocumentParams<'a> {
+    pub fn text_document(&self) -> &TextDocumentIdentifier<'_> {
+        &self.text_document
+    }
+
+    pub fn reason(&self) -> TextDocumentSaveReason {
+        self.reason
+    }
+}
+
+/// Represents reasons why a text document is saved.
+#[derive(Deserialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum TextDocumentSaveReason {
+    /// Manually triggered, e.g. by the user pressing save, by starting debugging, or by
+    /// an API call.
+    Manual = 1,
+    /// Automatic after a delay.
+    AfterDelay = 2,
+    /// When the editor lost focus.
+    FocusOut = 3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_will_save_text_document_params() {
+        let json = r#"{ "textDocument": { "uri": "file:///config.huml" }, "reason": 2 }"#;
+
+        let params: WillSaveTextDocumentParams =
+            serde_json::from_str(json).expect("Deserialization failed");
+
+        assert_eq!(params.text_document().uri(), "file:///config.huml");
+        assert_eq!(params.reason(), TextDocumentSaveReason::AfterDelay);
+    }
+}
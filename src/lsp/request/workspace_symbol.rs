@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+/// Params for a [super::RequestMethod::WorkspaceSymbol]
+/// [Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspaceSymbolParams)
+#[derive(Deserialize, Debug)]
+pub struct WorkspaceSymbolParams {
+    /// A query string to filter symbols by. Clients may send an empty string here to
+    /// request all symbols.
+    query: String,
+}
+
+impl WorkspaceSymbolParams {
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+}
@@ -0,0 +1,148 @@
+use serde::Serialize;
+
+use crate::lsp::common::{
+    diagnostic::{Diagnostic, DiagnosticData},
+    text_document::{Range, TextEdit},
+    workspace_edit::WorkspaceEdit,
+};
+
+/// The kind of the code actions fixing a diagnostic.
+pub const QUICK_FIX: &str = "quickfix";
+
+/// The kind of the code action formatting a whole document.
+pub const FORMAT_DOCUMENT: &str = "source.formatDocument";
+
+/// The kind of the code action converting the tabs indenting a document to spaces.
+pub const CONVERT_INDENTATION: &str = "source.convertIndentation";
+
+/// A change the client can apply to the workspace, e.g. to fix a diagnostic.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeAction)
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeAction {
+    /// A short, human-readable, title for this code action.
+    title: String,
+
+    /// The kind of the code action, e.g. [`QUICK_FIX`].
+    kind: &'static str,
+
+    /// The diagnostics that this code action resolves.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    diagnostics: Vec<Diagnostic>,
+
+    /// The workspace edit this code action performs.
+    edit: WorkspaceEdit,
+}
+
+impl CodeAction {
+    /// The action applying `edits` to format the document identified by `uri`.
+    pub fn format_document(uri: &str, edits: Vec<TextEdit>) -> Self {
+        Self {
+            title: "Format document".to_string(),
+            kind: FORMAT_DOCUMENT,
+            diagnostics: vec![],
+            edit: WorkspaceEdit::default().with_edits(uri.to_string(), edits),
+        }
+    }
+
+    /// The action applying `edits` to indent the document identified by `uri` with spaces
+    /// instead of tabs.
+    pub fn convert_indentation(uri: &str, edits: Vec<TextEdit>) -> Self {
+        Self {
+            title: "Convert indentation to spaces".to_string(),
+            kind: CONVERT_INDENTATION,
+            diagnostics: vec![],
+            edit: WorkspaceEdit::default().with_edits(uri.to_string(), edits),
+        }
+    }
+
+    /// The quick fixes of the `diagnostics` of the document identified by `uri` that
+    /// overlap `range`, one per diagnostic carrying a fix in its [`DiagnosticData`].
+    pub fn quick_fixes(uri: &str, range: Range, diagnostics: &[Diagnostic]) -> Vec<Self> {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| overlap(diagnostic.range(), range))
+            .filter_map(|diagnostic| {
+                let data = diagnostic.data_as::<DiagnosticData>()?;
+                let fix = data.fix()?.clone();
+                Some(Self {
+                    title: format!("Fix this {} problem", data.rule()),
+                    kind: QUICK_FIX,
+                    diagnostics: vec![diagnostic.clone()],
+                    edit: WorkspaceEdit::default().with_edits(uri.to_string(), [fix]),
+                })
+            })
+            .collect()
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn kind(&self) -> &str {
+        self.kind
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn edit(&self) -> &WorkspaceEdit {
+        &self.edit
+    }
+}
+
+/// Returns `true` if the ranges share a position, their ends included.
+fn overlap(a: Range, b: Range) -> bool {
+    a.start() <= b.end() && b.start() <= a.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{
+        common::text_document::{Position, PositionEncodingKind},
+        settings::ServerSettings,
+    };
+
+    const URI: &str = "file:///config.huml";
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn should_fix_diagnostics_overlapping_range() {
+        let diagnostics = Diagnostic::lint(
+            "a:   1\nb: 2\nc:3\n",
+            URI,
+            &ServerSettings::default(),
+            PositionEncodingKind::Utf16,
+        );
+
+        let fixes = CodeAction::quick_fixes(URI, range((0, 0), (1, 0)), &diagnostics);
+
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].kind(), QUICK_FIX);
+        assert_eq!(fixes[0].title(), "Fix this colon-spacing problem");
+        assert_eq!(fixes[0].diagnostics(), &diagnostics[..1]);
+        assert_eq!(
+            fixes[0].edit().changes()[URI],
+            [TextEdit::new(range((0, 2), (0, 5)), " ".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_not_fix_diagnostics_without_fix() {
+        let diagnostics = Diagnostic::lint(
+            "a: 1\na: 2\n",
+            URI,
+            &ServerSettings::default(),
+            PositionEncodingKind::Utf16,
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert!(CodeAction::quick_fixes(URI, range((1, 0), (1, 0)), &diagnostics).is_empty());
+    }
+}
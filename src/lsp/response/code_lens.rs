@@ -0,0 +1,149 @@
+use serde::Serialize;
+
+use crate::{
+    huml::ast::{Document, Node, NodeKind, Span},
+    lsp::common::text_document::{PositionEncodingKind, Range},
+};
+
+/// A command shown in the editor along with source code, here a summary of a collection.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeLens)
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct CodeLens {
+    /// The range in which this code lens is valid, the lens being shown above its first
+    /// line.
+    range: Range,
+
+    /// The command this code lens represents.
+    command: CodeLensCommand,
+}
+
+/// The command of a [`CodeLens`]. Summaries don't run anything when clicked, so their
+/// command identifier is empty.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#command)
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct CodeLensCommand {
+    /// Title of the command, like `save`.
+    title: String,
+
+    /// The identifier of the actual command handler.
+    command: String,
+}
+
+impl CodeLens {
+    /// Summarizes each top-level dict or list of a document, e.g. `dict: 4 keys`, with a
+    /// lens on the line of its key, or of its list marker within a root list.
+    ///
+    /// `source` is the text of the document, and `encoding` the encoding the `character`
+    /// offsets of the ranges are counted in.
+    pub fn from_document(
+        document: &Document,
+        source: &str,
+        encoding: PositionEncodingKind,
+    ) -> Vec<Self> {
+        let collections: Vec<(Span, &Node)> = match document.root.as_ref().map(|root| &root.kind) {
+            Some(NodeKind::Dict(entries)) => entries
+                .iter()
+                .map(|entry| (entry.key.span, &entry.value))
+                .collect(),
+            Some(NodeKind::List(items)) => items.iter().map(|item| (item.span, item)).collect(),
+            Some(NodeKind::Scalar(_)) | None => vec![],
+        };
+        collections
+            .into_iter()
+            .filter_map(|(span, node)| {
+                let title = summary(node)?;
+                Some(Self {
+                    range: encoding.range(source, span),
+                    command: CodeLensCommand {
+                        title,
+                        command: String::new(),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn title(&self) -> &str {
+        &self.command.title
+    }
+}
+
+/// Returns the summary of `node`, e.g. `list: 3 items`, or `None` if it's a scalar.
+fn summary(node: &Node) -> Option<String> {
+    let (kind, len, unit) = match &node.kind {
+        NodeKind::Dict(entries) => ("dict", entries.len(), "key"),
+        NodeKind::List(items) => ("list", items.len(), "item"),
+        NodeKind::Scalar(_) => return None,
+    };
+    let plural = if len == 1 { "" } else { "s" };
+    Some(format!("{kind}: {len} {unit}{plural}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{huml, lsp::common::text_document::Position};
+
+    fn lenses(source: &str) -> Vec<(usize, String)> {
+        let document = huml::parse(source).document;
+        CodeLens::from_document(&document, source, PositionEncodingKind::Utf16)
+            .iter()
+            .map(|lens| (lens.range().start().line(), lens.title().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn should_summarize_top_level_collections() {
+        let source = "\
+name: \"huml\"
+server::
+  host: \"localhost\"
+  tls::
+    enabled: true
+ports:: 80, 443
+tags:: []
+single::
+  - \"a\"
+";
+
+        assert_eq!(
+            lenses(source),
+            [
+                (1, "dict: 2 keys".to_string()),
+                (5, "list: 2 items".to_string()),
+                (6, "list: 0 items".to_string()),
+                (7, "list: 1 item".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_summarize_collections_of_root_list() {
+        let source = "\
+- ::
+  a: 1
+- 2
+";
+
+        assert_eq!(lenses(source), [(0, "dict: 1 key".to_string())]);
+    }
+
+    #[test]
+    fn should_place_lens_on_key() {
+        let source = "a: 1\nserver::\n  host: \"localhost\"\n";
+        let document = huml::parse(source).document;
+
+        let lenses = CodeLens::from_document(&document, source, PositionEncodingKind::Utf16);
+
+        assert_eq!(
+            lenses[0].range(),
+            Range::new(Position::new(1, 0), Position::new(1, 6))
+        );
+    }
+}
@@ -0,0 +1,518 @@
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+use crate::{
+    huml::ast::{Document, Entry, Node, NodeKind},
+    lsp::common::markup::MarkupContent,
+    rpc::Integer,
+};
+
+/// The keywords that can be written in place of a value, along with their documentation.
+const KEYWORDS: [(&str, &str); 5] = [
+    ("true", "The boolean `true`."),
+    ("false", "The boolean `false`."),
+    ("null", "The absence of a value."),
+    ("nan", "The float that is not a number."),
+    (
+        "inf",
+        "The positive infinite float. Write `-inf` for the negative one.",
+    ),
+];
+
+/// The maximum number of distinct values listed in the documentation of a key.
+const MAX_DOCUMENTED_VALUES: usize = 5;
+
+/// A completion item suggested to the user.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionItem)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItem {
+    /// The label of this completion item. Also the text inserted when selecting it.
+    label: String,
+
+    /// The kind of this completion item.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<CompletionItemKind>,
+
+    /// A human-readable string with additional information about this item.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+
+    /// A human-readable string that represents a doc-comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    documentation: Option<MarkupContent>,
+
+    /// A data entry field that is preserved on a completion item between a completion
+    /// and a completion resolve request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<CompletionItemData>,
+}
+
+impl CompletionItem {
+    /// Builds the completion items at the byte `column` of `line` in `source`, whose
+    /// syntax tree is `document`.
+    ///
+    /// Keywords are suggested in place of values, and the keys of the dicts found at the
+    /// same path are suggested in place of keys, except for the keys already present in
+    /// the dict being written, to avoid duplicates. The documentation of keys is costly to
+    /// build, so it's left to the `completionItem/resolve` request, with `data` attached
+    /// to identify the document.
+    pub fn at_position(
+        document: &Document,
+        source: &str,
+        line: usize,
+        column: usize,
+        data: &CompletionItemData,
+    ) -> Vec<Self> {
+        let lines: Vec<_> = source.lines().collect();
+        let text = lines.get(line).copied().unwrap_or_default();
+        let prefix = text.get(..column).unwrap_or(text);
+
+        match CompletionContext::of(prefix) {
+            CompletionContext::Key { indent } => {
+                let previous_lines = &lines[..line.min(lines.len())];
+                let path = parent_path(previous_lines, indent);
+                let dicts = dicts_at(document, &path);
+                let present = present_keys(&dicts, &lines, line, indent);
+                sibling_keys(&dicts)
+                    .into_iter()
+                    .filter(|key| !present.contains(key))
+                    .map(|key| Self::key(key, data.clone()))
+                    .collect()
+            }
+            CompletionContext::Value => Self::keywords(),
+            CompletionContext::None => vec![],
+        }
+    }
+
+    fn key(name: &str, data: CompletionItemData) -> Self {
+        Self {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::Property),
+            detail: None,
+            documentation: None,
+            data: Some(data),
+        }
+    }
+
+    fn keywords() -> Vec<Self> {
+        KEYWORDS
+            .iter()
+            .map(|(keyword, documentation)| Self {
+                label: keyword.to_string(),
+                kind: Some(CompletionItemKind::Keyword),
+                detail: None,
+                documentation: Some(MarkupContent::markdown(documentation.to_string())),
+                data: None,
+            })
+            .collect()
+    }
+
+    /// Builds the documentation of the key `name`: how many times it is used in the
+    /// document, and with which values.
+    pub fn key_documentation(document: &Document, source: &str, name: &str) -> MarkupContent {
+        let mut count = 0;
+        let mut values: Vec<String> = vec![];
+        if let Some(root) = &document.root {
+            for_each_entry(root, &mut |entry| {
+                if entry.key.name != name {
+                    return;
+                }
+                count += 1;
+                let value = value_summary(source, &entry.value);
+                if values.len() < MAX_DOCUMENTED_VALUES && !values.contains(&value) {
+                    values.push(value);
+                }
+            });
+        }
+
+        let times = match count {
+            1 => "once".to_string(),
+            count => format!("{count} times"),
+        };
+        let mut documentation = format!("`{name}` is used {times} in this document.");
+        if !values.is_empty() {
+            documentation.push_str("\n\nValues: ");
+            documentation.push_str(&values.join(", "));
+        }
+        MarkupContent::markdown(documentation)
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn kind(&self) -> Option<CompletionItemKind> {
+        self.kind
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    pub fn documentation(&self) -> Option<&MarkupContent> {
+        self.documentation.as_ref()
+    }
+
+    pub fn set_documentation(&mut self, documentation: MarkupContent) {
+        self.documentation = Some(documentation);
+    }
+
+    pub fn data(&self) -> Option<&CompletionItemData> {
+        self.data.as_ref()
+    }
+}
+
+/// The data attached to completion items, identifying the version of the document
+/// they were built for.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct CompletionItemData {
+    uri: String,
+    version: Integer,
+}
+
+impl CompletionItemData {
+    pub fn new(uri: String, version: Integer) -> Self {
+        Self { uri, version }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn version(&self) -> Integer {
+        self.version
+    }
+}
+
+/// The kind of a completion entry.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionItemKind)
+#[derive(Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum CompletionItemKind {
+    Text = 1,
+    Method = 2,
+    Function = 3,
+    Constructor = 4,
+    Field = 5,
+    Variable = 6,
+    Class = 7,
+    Interface = 8,
+    Module = 9,
+    Property = 10,
+    Unit = 11,
+    Value = 12,
+    Enum = 13,
+    Keyword = 14,
+    Snippet = 15,
+    Color = 16,
+    File = 17,
+    Reference = 18,
+    Folder = 19,
+    EnumMember = 20,
+    Constant = 21,
+    Struct = 22,
+    Event = 23,
+    Operator = 24,
+    TypeParameter = 25,
+}
+
+/// What is being written at the position of a completion request.
+#[derive(PartialEq, Eq, Debug)]
+enum CompletionContext {
+    /// A key, on a line indented by `indent` spaces.
+    Key { indent: usize },
+    /// A scalar value.
+    Value,
+    /// Something that can't be completed, e.g. a string or a comment.
+    None,
+}
+
+impl CompletionContext {
+    /// Finds out what is being written from the text of the line before the cursor.
+    fn of(prefix: &str) -> Self {
+        let content = prefix.trim_start();
+        let indent = prefix.len() - content.len();
+
+        let value = if let Some(item) = content.strip_prefix('-') {
+            item
+        } else if let Some((_, value)) = content.split_once(':') {
+            value
+        } else if content
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Self::Key { indent };
+        } else {
+            return Self::None;
+        };
+
+        // Only the last value of an inline vector is being written
+        let value = value.strip_prefix(':').unwrap_or(value);
+        let last_value = value.rsplit(',').next().unwrap_or(value);
+        let last_value = last_value.rsplit(':').next().unwrap_or(last_value);
+        if last_value
+            .trim_start()
+            .chars()
+            .all(|c| c.is_ascii_alphabetic())
+        {
+            Self::Value
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// A step of the path from the root to a value.
+#[derive(PartialEq, Eq, Debug)]
+pub(super) enum PathSegment {
+    /// The value of the entry with this key.
+    Key(String),
+    /// Any item of a list.
+    Item,
+}
+
+/// Finds the path to the block of a line indented by `indent` spaces, from the headers
+/// of its parent blocks among the `previous_lines`.
+pub(super) fn parent_path(previous_lines: &[&str], mut indent: usize) -> Vec<PathSegment> {
+    let mut path = vec![];
+    for line in previous_lines.iter().rev() {
+        if indent == 0 {
+            break;
+        }
+
+        let content = line.trim_start();
+        let line_indent = line.len() - content.len();
+        if content.is_empty() || content.starts_with('#') || line_indent >= indent {
+            continue;
+        }
+
+        indent = line_indent;
+        if content.starts_with('-') {
+            path.push(PathSegment::Item);
+        } else if let Some((key, _)) = content.split_once("::") {
+            path.push(PathSegment::Key(key.trim().trim_matches('"').to_string()));
+        } else {
+            // A scalar entry has no block, the line isn't nested in a valid one
+            return vec![];
+        }
+    }
+    path.reverse();
+    path
+}
+
+/// Returns the dicts found at `path`.
+fn dicts_at<'a>(document: &'a Document, path: &[PathSegment]) -> Vec<&'a [Entry]> {
+    let mut nodes: Vec<&Node> = document.root.iter().collect();
+    for segment in path {
+        nodes = nodes
+            .into_iter()
+            .flat_map(|node| match (&node.kind, segment) {
+                (NodeKind::Dict(entries), PathSegment::Key(key)) => entries
+                    .iter()
+                    .filter(|entry| &entry.key.name == key)
+                    .map(|entry| &entry.value)
+                    .collect(),
+                (NodeKind::List(items), PathSegment::Item) => items.iter().collect(),
+                _ => vec![],
+            })
+            .collect();
+    }
+
+    nodes
+        .into_iter()
+        .filter_map(|node| match &node.kind {
+            NodeKind::Dict(entries) => Some(entries.as_slice()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the keys of all the `dicts`, without duplicates.
+fn sibling_keys<'a>(dicts: &[&'a [Entry]]) -> Vec<&'a str> {
+    let mut keys = vec![];
+    for entry in dicts.iter().copied().flatten() {
+        if !keys.contains(&entry.key.name.as_str()) {
+            keys.push(entry.key.name.as_str());
+        }
+    }
+    keys
+}
+
+/// Returns the keys present in the dict being written on `line`, indented by `indent`
+/// spaces, among the `dicts` found at its path. The key being written on `line` itself
+/// doesn't count.
+fn present_keys<'a>(
+    dicts: &[&'a [Entry]],
+    lines: &[&str],
+    line: usize,
+    indent: usize,
+) -> Vec<&'a str> {
+    // The dict spans the lines around `line` that are indented at least as much
+    let is_outside = |text: &&str| {
+        let content = text.trim_start();
+        !content.is_empty() && !content.starts_with('#') && text.len() - content.len() < indent
+    };
+    let start = lines[..line.min(lines.len())]
+        .iter()
+        .rposition(is_outside)
+        .map_or(0, |index| index + 1);
+    let end = lines
+        .iter()
+        .skip(line + 1)
+        .position(is_outside)
+        .map_or(lines.len(), |index| line + 1 + index);
+
+    dicts
+        .iter()
+        .copied()
+        .flatten()
+        .filter(|entry| {
+            let key_line = entry.key.span.start.line;
+            key_line != line && (start..end).contains(&key_line)
+        })
+        .map(|entry| entry.key.name.as_str())
+        .collect()
+}
+
+/// Calls `f` on every entry of the tree rooted at `node`.
+fn for_each_entry(node: &Node, f: &mut impl FnMut(&Entry)) {
+    match &node.kind {
+        NodeKind::Dict(entries) => {
+            for entry in entries {
+                f(entry);
+                for_each_entry(&entry.value, f);
+            }
+        }
+        NodeKind::List(items) => {
+            for item in items {
+                for_each_entry(item, f);
+            }
+        }
+        NodeKind::Scalar(_) => (),
+    }
+}
+
+/// Summarizes a value for the documentation of its key.
+fn value_summary(source: &str, value: &Node) -> String {
+    match &value.kind {
+        NodeKind::Scalar(_) if !value.span.is_multiline() => {
+            format!(
+                "`{}`",
+                &source[value.span.start.offset..value.span.end.offset]
+            )
+        }
+        NodeKind::Scalar(_) => "a multiline string".to_string(),
+        NodeKind::Dict(_) => "a dict".to_string(),
+        NodeKind::List(_) => "a list".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{huml, lsp::common::text_document::Position};
+
+    const SOURCE: &str = "\
+servers::
+  - ::
+    host: \"alpha\"
+    port: 8080
+  - ::
+    host: \"beta\"
+    enabled: true
+";
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(CompletionItem::label).collect()
+    }
+
+    fn complete(source: &str, position: Position) -> Vec<CompletionItem> {
+        let parsed = huml::parse(source);
+        let data = CompletionItemData::new("file:///test.huml".to_string(), 1);
+        CompletionItem::at_position(
+            &parsed.document,
+            source,
+            position.line(),
+            position.character(),
+            &data,
+        )
+    }
+
+    #[test]
+    fn should_find_completion_context() {
+        assert_eq!(
+            CompletionContext::of("    po"),
+            CompletionContext::Key { indent: 4 }
+        );
+        assert_eq!(CompletionContext::of("port: tr"), CompletionContext::Value);
+        assert_eq!(
+            CompletionContext::of("ports:: 80, n"),
+            CompletionContext::Value
+        );
+        assert_eq!(CompletionContext::of("  - "), CompletionContext::Value);
+        assert_eq!(CompletionContext::of("host: \"al"), CompletionContext::None);
+        assert_eq!(CompletionContext::of("# a comm"), CompletionContext::None);
+    }
+
+    #[test]
+    fn should_suggest_keys_of_sibling_dicts() {
+        // The cursor is on a new line of the second item, which already has a host
+        let source = format!("{SOURCE}    ");
+        let items = complete(&source, Position::new(7, 4));
+
+        assert_eq!(labels(&items), ["port"]);
+        assert!(items.iter().all(|item| item.documentation().is_none()));
+    }
+
+    #[test]
+    fn should_not_suggest_keys_present_in_dict() {
+        let source = "\
+- ::
+  name: \"a\"
+  port: 80
+  debug: true
+- ::
+  name: \"b\"
+  \n\
+  enabled: false
+";
+
+        // Keys written after the cursor are present as well
+        let items = complete(source, Position::new(6, 2));
+
+        assert_eq!(labels(&items), ["port", "debug"]);
+    }
+
+    #[test]
+    fn should_suggest_key_being_edited() {
+        let source = "name: \"a\"\nport: 80\n";
+
+        let items = complete(source, Position::new(0, 2));
+
+        assert_eq!(labels(&items), ["name"]);
+    }
+
+    #[test]
+    fn should_suggest_keywords_for_values() {
+        let source = "port: 8080\nenabled: t\n";
+
+        let items = complete(source, Position::new(1, 10));
+
+        assert_eq!(labels(&items), ["true", "false", "null", "nan", "inf"]);
+    }
+
+    #[test]
+    fn should_document_key_usage() {
+        let parsed = huml::parse(SOURCE);
+
+        let documentation = CompletionItem::key_documentation(&parsed.document, SOURCE, "host");
+
+        assert_eq!(
+            documentation.value(),
+            "`host` is used 2 times in this document.\n\nValues: `\"alpha\"`, `\"beta\"`"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    huml::ast::{self, Document, Layout, Node, Scalar, Span, VisitContext, Visitor},
+    lsp::common::text_document::{PositionEncodingKind, Range, TextEdit},
+};
+
+/// A color in RGBA space, every component ranging from `0` to `1`.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#color)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Color {
+    red: f64,
+    green: f64,
+    blue: f64,
+    alpha: f64,
+}
+
+impl Color {
+    pub fn new(red: f64, green: f64, blue: f64, alpha: f64) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Parses a hex color, e.g. `#ff8800`: `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`,
+    /// in any case.
+    pub fn from_hex(text: &str) -> Option<Self> {
+        let digits = text.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let components: Vec<u8> = match digits.len() {
+            // Each digit is repeated, e.g. `#f80` is `#ff8800`
+            3 | 4 => digits
+                .chars()
+                .map(|c| c.to_digit(16).map(|digit| digit as u8 * 0x11))
+                .collect::<Option<_>>()?,
+            6 | 8 => (0..digits.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+                .collect::<Option<_>>()?,
+            _ => return None,
+        };
+        let component = |i: usize| components.get(i).map_or(1.0, |&c| f64::from(c) / 255.0);
+        Some(Self::new(
+            component(0),
+            component(1),
+            component(2),
+            component(3),
+        ))
+    }
+
+    /// Returns the color as `#rrggbb`, or `#rrggbbaa` if it's translucent.
+    pub fn to_hex(&self) -> String {
+        let byte = |component: f64| (component.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let [red, green, blue, alpha] = [self.red, self.green, self.blue, self.alpha].map(byte);
+        if alpha == u8::MAX {
+            format!("#{red:02x}{green:02x}{blue:02x}")
+        } else {
+            format!("#{red:02x}{green:02x}{blue:02x}{alpha:02x}")
+        }
+    }
+
+    pub fn red(&self) -> f64 {
+        self.red
+    }
+
+    pub fn green(&self) -> f64 {
+        self.green
+    }
+
+    pub fn blue(&self) -> f64 {
+        self.blue
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+}
+
+/// A color found in a document, along with the range it's written at.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#colorInformation)
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ColorInformation {
+    /// The range in the document where this color appears.
+    range: Range,
+
+    /// The actual color value for this color range.
+    color: Color,
+}
+
+impl ColorInformation {
+    /// Finds the strings of a document holding a hex color, e.g. `"#ff8800"`. The range
+    /// of each color covers its whole string, quotes included, with `character` offsets
+    /// counted in `encoding` in `source`, the text of the document.
+    pub fn from_document(
+        document: &Document,
+        source: &str,
+        encoding: PositionEncodingKind,
+    ) -> Vec<Self> {
+        let mut colors = ColorCollector::default();
+        ast::walk(document, &mut colors);
+        colors
+            .0
+            .into_iter()
+            .map(|(span, color)| Self {
+                range: encoding.range(source, span),
+                color,
+            })
+            .collect()
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+}
+
+/// A way to write a color, offered by the client to the user picking it.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#colorPresentation)
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorPresentation {
+    /// The label of this color presentation, shown in the color picker.
+    label: String,
+
+    /// The edit writing the color in the document when the presentation is picked.
+    text_edit: TextEdit,
+}
+
+impl ColorPresentation {
+    /// Returns the ways to write `color` as a string replacing `range`: hex in lowercase,
+    /// then in uppercase.
+    pub fn for_color(color: Color, range: Range) -> Vec<Self> {
+        let hex = color.to_hex();
+        [hex.clone(), hex.to_uppercase()]
+            .into_iter()
+            .map(|label| Self {
+                text_edit: TextEdit::new(range, format!("\"{label}\"")),
+                label,
+            })
+            .collect()
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn text_edit(&self) -> &TextEdit {
+        &self.text_edit
+    }
+}
+
+/// Collects the hex colors written as inline strings, along with their span.
+#[derive(Default)]
+struct ColorCollector(Vec<(Span, Color)>);
+
+impl<'a> Visitor<'a> for ColorCollector {
+    fn visit_scalar(&mut self, node: &'a Node, scalar: &'a Scalar, _: VisitContext) {
+        if let Scalar::String(text) = scalar
+            && node.layout == Layout::Inline
+            && let Some(color) = Color::from_hex(text)
+        {
+            self.0.push((node.span, color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{huml, lsp::common::text_document::Position};
+
+    #[test]
+    fn should_parse_hex_colors() {
+        assert_eq!(
+            Color::from_hex("#ff8800"),
+            Some(Color::new(1.0, 136.0 / 255.0, 0.0, 1.0))
+        );
+        assert_eq!(
+            Color::from_hex("#F80"),
+            Some(Color::new(1.0, 136.0 / 255.0, 0.0, 1.0))
+        );
+        assert_eq!(
+            Color::from_hex("#00000080"),
+            Some(Color::new(0.0, 0.0, 0.0, 128.0 / 255.0))
+        );
+        assert_eq!(Color::from_hex("ff8800"), None);
+        assert_eq!(Color::from_hex("#ff880"), None);
+        assert_eq!(Color::from_hex("#gg8800"), None);
+    }
+
+    #[test]
+    fn should_find_colors_in_strings() {
+        let source = "\
+theme::
+  accent: \"#ff8800\"
+  name: \"#hashtag\"
+  palette:: \"#000\", \"#ffffff80\"
+";
+        let document = huml::parse(source).document;
+
+        let colors =
+            ColorInformation::from_document(&document, source, PositionEncodingKind::Utf16);
+
+        let hex: Vec<_> = colors.iter().map(|info| info.color().to_hex()).collect();
+        assert_eq!(hex, ["#ff8800", "#000000", "#ffffff80"]);
+        assert_eq!(
+            colors[0].range(),
+            Range::new(Position::new(1, 10), Position::new(1, 19))
+        );
+    }
+
+    #[test]
+    fn should_present_colors_as_hex_strings() {
+        let range = Range::new(Position::new(1, 10), Position::new(1, 19));
+
+        let presentations = ColorPresentation::for_color(Color::new(1.0, 0.5, 0.0, 1.0), range);
+
+        let labels: Vec<_> = presentations.iter().map(ColorPresentation::label).collect();
+        assert_eq!(labels, ["#ff8000", "#FF8000"]);
+        assert_eq!(
+            presentations[0].text_edit(),
+            &TextEdit::new(range, "\"#ff8000\"".to_string())
+        );
+    }
+}
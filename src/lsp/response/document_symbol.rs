@@ -0,0 +1,239 @@
+use serde::Serialize;
+use serde_repr::Serialize_repr;
+
+use crate::{
+    huml::ast::{Document, Entry, Node, NodeKind, Scalar, Span},
+    lsp::common::text_document::{PositionEncodingKind, Range},
+};
+
+/// Represents programming constructs like variables, classes, interfaces etc. that
+/// appear in a document. Document symbols can be hierarchical.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#documentSymbol)
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    /// The name of this symbol.
+    name: String,
+
+    /// The kind of this symbol.
+    kind: SymbolKind,
+
+    /// The range enclosing this symbol not including leading/trailing whitespace
+    /// but everything else like comments.
+    range: Range,
+
+    /// The range that should be selected and revealed when this symbol is being
+    /// picked, e.g. the name of a function. Must be contained by the `range`.
+    selection_range: Range,
+
+    /// Children of this symbol, e.g. properties of a class.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<DocumentSymbol>,
+}
+
+impl DocumentSymbol {
+    /// Builds the outline of a document: one symbol for each entry of the root dict, or
+    /// for each item of the root list.
+    ///
+    /// The `character` offsets of the ranges are counted in `encoding` in `source`, the
+    /// text of the document.
+    pub fn from_document(
+        document: &Document,
+        source: &str,
+        encoding: PositionEncodingKind,
+    ) -> Vec<Self> {
+        let range = |span| encoding.range(source, span);
+        document
+            .root
+            .as_ref()
+            .map(|root| Self::children_of(root, &range))
+            .unwrap_or_default()
+    }
+
+    /// Builds the symbol of a dict entry. The selection range is the key.
+    fn from_entry(entry: &Entry, range: &impl Fn(Span) -> Range) -> Self {
+        Self {
+            name: entry.key.name.clone(),
+            kind: SymbolKind::from(&entry.value),
+            range: range(entry.span()),
+            selection_range: range(entry.key.span),
+            children: Self::children_of(&entry.value, range),
+        }
+    }
+
+    /// Builds the symbol of a list item, named after its index in the list.
+    fn from_item(index: usize, item: &Node, range: &impl Fn(Span) -> Range) -> Self {
+        Self {
+            name: index.to_string(),
+            kind: SymbolKind::from(item),
+            range: range(item.span),
+            selection_range: range(item.span),
+            children: Self::children_of(item, range),
+        }
+    }
+
+    fn children_of(node: &Node, range: &impl Fn(Span) -> Range) -> Vec<Self> {
+        match &node.kind {
+            NodeKind::Dict(entries) => entries
+                .iter()
+                .map(|entry| Self::from_entry(entry, range))
+                .collect(),
+            NodeKind::List(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| Self::from_item(index, item, range))
+                .collect(),
+            NodeKind::Scalar(_) => vec![],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn selection_range(&self) -> Range {
+        self.selection_range
+    }
+
+    pub fn children(&self) -> &[DocumentSymbol] {
+        &self.children
+    }
+}
+
+/// A symbol kind.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#symbolKind)
+#[derive(Serialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum SymbolKind {
+    File = 1,
+    Module = 2,
+    Namespace = 3,
+    Package = 4,
+    Class = 5,
+    Method = 6,
+    Property = 7,
+    Field = 8,
+    Constructor = 9,
+    Enum = 10,
+    Interface = 11,
+    Function = 12,
+    Variable = 13,
+    Constant = 14,
+    String = 15,
+    Number = 16,
+    Boolean = 17,
+    Array = 18,
+    Object = 19,
+    Key = 20,
+    Null = 21,
+    EnumMember = 22,
+    Struct = 23,
+    Event = 24,
+    Operator = 25,
+    TypeParameter = 26,
+}
+
+impl From<&Node> for SymbolKind {
+    /// Maps the kind of a HUML value to the closest symbol kind.
+    fn from(node: &Node) -> Self {
+        match &node.kind {
+            NodeKind::Dict(_) => Self::Object,
+            NodeKind::List(_) => Self::Array,
+            NodeKind::Scalar(Scalar::String(_)) => Self::String,
+            NodeKind::Scalar(Scalar::Integer(_) | Scalar::Float(_)) => Self::Number,
+            NodeKind::Scalar(Scalar::Boolean(_)) => Self::Boolean,
+            NodeKind::Scalar(Scalar::Null) => Self::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    const NESTED_DOCUMENT: &str = r#"name: "huml-lsp"
+server::
+  port: 8080
+  tags:: "a", "b"
+enabled: true
+"#;
+
+    #[test]
+    fn should_build_nested_outline() {
+        let parsed = huml::parse(NESTED_DOCUMENT);
+        let symbols = DocumentSymbol::from_document(
+            &parsed.document,
+            NESTED_DOCUMENT,
+            PositionEncodingKind::Utf16,
+        );
+
+        let outline: Vec<_> = symbols
+            .iter()
+            .map(|symbol| (symbol.name(), symbol.kind()))
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                ("name", SymbolKind::String),
+                ("server", SymbolKind::Object),
+                ("enabled", SymbolKind::Boolean)
+            ]
+        );
+
+        let server = &symbols[1];
+        assert_eq!(server.range().start().line(), 1);
+        assert_eq!(server.range().end().line(), 3);
+        assert_eq!(server.selection_range().start().character(), 0);
+        assert_eq!(server.selection_range().end().character(), "server".len());
+
+        let children: Vec<_> = server
+            .children()
+            .iter()
+            .map(|symbol| (symbol.name(), symbol.kind()))
+            .collect();
+        assert_eq!(
+            children,
+            [("port", SymbolKind::Number), ("tags", SymbolKind::Array)]
+        );
+
+        let tags = &server.children()[1];
+        assert_eq!(tags.children().len(), 2);
+        assert_eq!(tags.children()[0].name(), "0");
+    }
+
+    #[test]
+    fn should_serialize_document_symbol() {
+        let source = "key: null\n";
+        let parsed = huml::parse(source);
+        let symbols =
+            DocumentSymbol::from_document(&parsed.document, source, PositionEncodingKind::Utf16);
+
+        let json = serde_json::to_value(&symbols).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "name": "key",
+                "kind": 21,
+                "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 9 }
+                },
+                "selectionRange": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 3 }
+                }
+            }])
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Serialize;
+
+use crate::{
+    huml::ast::{Comment, Document, Node, NodeKind, Span},
+    rpc::UInteger,
+};
+
+/// A range of lines the client can fold, hiding every line but the first.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#foldingRange)
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldingRange {
+    /// The zero-based line the folded range starts at, which stays visible.
+    start_line: UInteger,
+
+    /// The zero-based line the folded range ends at.
+    end_line: UInteger,
+
+    /// The kind of the range, used by clients to fold e.g. all comments at once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<FoldingRangeKind>,
+}
+
+impl FoldingRange {
+    /// Finds the ranges of a document that can be folded: every dict or list written
+    /// over several lines, from the line introducing it to its last line, and every run
+    /// of consecutive comment lines.
+    pub fn from_document(document: &Document) -> Vec<Self> {
+        let mut ranges = vec![];
+        if let Some(root) = &document.root {
+            // Folding the root would hide the whole document
+            collect_children(root, &mut ranges);
+        }
+        collect_comments(&document.comments, &mut ranges);
+        ranges.sort_by_key(|range| range.start_line);
+        ranges
+    }
+
+    pub fn start_line(&self) -> UInteger {
+        self.start_line
+    }
+
+    pub fn end_line(&self) -> UInteger {
+        self.end_line
+    }
+
+    pub fn kind(&self) -> Option<FoldingRangeKind> {
+        self.kind
+    }
+}
+
+/// The kinds of folding ranges.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#foldingRangeKind)
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum FoldingRangeKind {
+    Comment,
+    Imports,
+    Region,
+}
+
+/// Adds the ranges of the collections nested in `node`. Dicts and lists are folded from
+/// the line of their key or list marker, so that it stays visible.
+fn collect_children(node: &Node, ranges: &mut Vec<FoldingRange>) {
+    match &node.kind {
+        NodeKind::Dict(entries) => {
+            for entry in entries {
+                collect_collection(entry.span(), &entry.value, ranges);
+            }
+        }
+        NodeKind::List(items) => {
+            for item in items {
+                collect_collection(item.span, item, ranges);
+            }
+        }
+        NodeKind::Scalar(_) => (),
+    }
+}
+
+/// Adds the range of the collection `node`, introduced on the first line of `span`, if
+/// it spans several lines, along with the ranges nested in it.
+fn collect_collection(span: Span, node: &Node, ranges: &mut Vec<FoldingRange>) {
+    if matches!(node.kind, NodeKind::Scalar(_)) {
+        return;
+    }
+    if span.is_multiline() {
+        ranges.push(FoldingRange {
+            start_line: span.start.line,
+            end_line: span.end.line,
+            kind: None,
+        });
+    }
+    collect_children(node, ranges);
+}
+
+/// Adds a range for each run of comments on consecutive lines of their own.
+fn collect_comments(comments: &[Comment], ranges: &mut Vec<FoldingRange>) {
+    let mut run: Option<(UInteger, UInteger)> = None;
+    let own_line_comments = comments.iter().filter(|comment| !comment.trailing);
+    for line in own_line_comments.map(|comment| comment.span.start.line) {
+        run = match run {
+            Some((start, end)) if end + 1 == line => Some((start, line)),
+            _ => {
+                push_comment_run(run, ranges);
+                Some((line, line))
+            }
+        };
+    }
+    push_comment_run(run, ranges);
+}
+
+fn push_comment_run(run: Option<(UInteger, UInteger)>, ranges: &mut Vec<FoldingRange>) {
+    if let Some((start_line, end_line)) = run.filter(|(start, end)| start < end) {
+        ranges.push(FoldingRange {
+            start_line,
+            end_line,
+            kind: Some(FoldingRangeKind::Comment),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    fn lines(source: &str) -> Vec<(UInteger, UInteger, Option<FoldingRangeKind>)> {
+        let document = huml::parse(source).document;
+        FoldingRange::from_document(&document)
+            .iter()
+            .map(|range| (range.start_line(), range.end_line(), range.kind()))
+            .collect()
+    }
+
+    #[test]
+    fn should_fold_nested_collections_and_comments() {
+        let source = "\
+# The servers to
+# connect to
+servers::
+  - ::
+    host: \"a\" # trailing
+    port: 1
+  - :: host: \"b\", port: 2
+# Single comment
+ports::
+  - 80
+  - 443
+point:: x: 1, y: 2
+";
+
+        assert_eq!(
+            lines(source),
+            [
+                (0, 1, Some(FoldingRangeKind::Comment)),
+                (2, 6, None),
+                (3, 5, None),
+                (8, 10, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_serialize_folding_range() {
+        let range = FoldingRange {
+            start_line: 1,
+            end_line: 3,
+            kind: Some(FoldingRangeKind::Comment),
+        };
+
+        assert_eq!(
+            serde_json::to_value(range).unwrap(),
+            serde_json::json!({ "startLine": 1, "endLine": 3, "kind": "comment" })
+        );
+    }
+}
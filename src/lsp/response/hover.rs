@@ -0,0 +1,163 @@
+use serde::Serialize;
+
+use crate::{
+    huml::{
+        ast::{Comment, Document, Key},
+        validate::CommentMarker,
+    },
+    lsp::common::{
+        markup::MarkupContent,
+        text_document::{PositionEncodingKind, Range},
+    },
+};
+
+/// The result of a hover request.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#hover)
+#[derive(Serialize, Debug)]
+pub struct Hover {
+    /// The hover's content.
+    contents: MarkupContent,
+
+    /// The range of the hovered element, used to highlight it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<Range>,
+}
+
+impl Hover {
+    /// Builds the hover of the element found at the byte `column` of `line`.
+    ///
+    /// Hovering a comment shows its `TODO`, `FIXME` or `NOTE` marker, if any. Hovering a
+    /// key shows the comments documenting it, with the markers they hold listed first.
+    /// The `character` offsets of the range of the hover are counted in `encoding` in
+    /// `source`, the text of the document.
+    pub fn at(
+        source: &str,
+        document: &Document,
+        line: usize,
+        column: usize,
+        encoding: PositionEncodingKind,
+    ) -> Option<Self> {
+        let comment = document
+            .comments
+            .iter()
+            .find(|comment| comment.span.contains(line, column));
+        if let Some(comment) = comment {
+            let marker = CommentMarker::find(comment)?;
+            return Some(Self {
+                contents: MarkupContent::markdown(marker_markdown(&marker)),
+                range: Some(encoding.range(source, comment.span)),
+            });
+        }
+
+        let key = document.key_at(line, column)?;
+        let comments = key_comments(document, key);
+        if comments.is_empty() {
+            return None;
+        }
+
+        let markers: Vec<_> = comments
+            .iter()
+            .filter_map(|comment| CommentMarker::find(comment))
+            .map(|marker| marker_markdown(&marker))
+            .collect();
+        let text: Vec<_> = comments
+            .iter()
+            .map(|comment| comment.text.trim_start_matches('#').trim())
+            .collect();
+        let mut contents = markers.join("\n\n");
+        if !contents.is_empty() {
+            contents.push_str("\n\n---\n\n");
+        }
+        contents.push_str(&text.join("\n"));
+
+        Some(Self {
+            contents: MarkupContent::markdown(contents),
+            range: Some(encoding.range(source, key.span)),
+        })
+    }
+
+    pub fn contents(&self) -> &MarkupContent {
+        &self.contents
+    }
+
+    pub fn range(&self) -> Option<Range> {
+        self.range
+    }
+}
+
+fn marker_markdown(marker: &CommentMarker) -> String {
+    match marker.note.as_str() {
+        "" => format!("**{}**", marker.kind.as_str()),
+        note => format!("**{}**: {note}", marker.kind.as_str()),
+    }
+}
+
+/// Returns the comments documenting `key`: the comments on the lines right above it, and
+/// the comment trailing its line.
+fn key_comments<'a>(document: &'a Document, key: &Key) -> Vec<&'a Comment> {
+    let line = key.span.start.line;
+    let mut comments = vec![];
+    let mut next_line = line;
+    let preceding = document
+        .comments
+        .iter()
+        .rev()
+        .skip_while(|c| c.span.start.line >= line);
+    for comment in preceding {
+        if comment.trailing || comment.span.start.line + 1 != next_line {
+            break;
+        }
+        next_line = comment.span.start.line;
+        comments.push(comment);
+    }
+    comments.reverse();
+
+    let trailing = document
+        .comments
+        .iter()
+        .find(|comment| comment.trailing && comment.span.start.line == line);
+    comments.extend(trailing);
+    comments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    const SOURCE: &str = "\
+# The port to listen on
+# TODO: make it configurable
+port: 8080 # FIXME
+host: \"localhost\"
+";
+
+    fn hover(line: usize, column: usize) -> Option<String> {
+        let document = huml::parse(SOURCE).document;
+        Hover::at(SOURCE, &document, line, column, PositionEncodingKind::Utf16)
+            .map(|hover| hover.contents().value().to_string())
+    }
+
+    #[test]
+    fn should_show_marker_of_hovered_comment() {
+        assert_eq!(
+            hover(1, 4),
+            Some("**TODO**: make it configurable".to_string())
+        );
+        assert_eq!(hover(0, 4), None);
+    }
+
+    #[test]
+    fn should_show_comments_of_hovered_key() {
+        assert_eq!(
+            hover(2, 1),
+            Some(
+                "**TODO**: make it configurable\n\n**FIXME**\n\n---\n\n\
+                 The port to listen on\nTODO: make it configurable\nFIXME"
+                    .to_string()
+            )
+        );
+        assert_eq!(hover(3, 1), None);
+    }
+}
@@ -7,3 +7,20 @@ pub struct InitializeResult {
     #[serde(rename = "serverInfo")]
     server_info: ServerInfo,
 }
+
+impl InitializeResult {
+    pub fn new(capabilities: ServerCapabilities) -> Self {
+        Self {
+            capabilities,
+            server_info: ServerInfo::default(),
+        }
+    }
+
+    pub fn capabilities(&self) -> &ServerCapabilities {
+        &self.capabilities
+    }
+
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+}
@@ -0,0 +1,191 @@
+use serde::Serialize;
+use serde_repr::Serialize_repr;
+
+use crate::{
+    huml::ast::{Document, Location, Node, NodeKind},
+    lsp::common::text_document::{Position, PositionEncodingKind, Range},
+};
+
+/// A hint shown inline by the client, here the type inferred for a value.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#inlayHint)
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHint {
+    /// The position the hint is shown at.
+    position: Position,
+
+    /// The text of the hint.
+    label: String,
+
+    /// The kind of the hint, used by clients to style it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<InlayHintKind>,
+}
+
+impl InlayHint {
+    /// Builds a hint at the end of each scalar value of a document found within `range`,
+    /// labeled with its type, e.g. `: int`.
+    ///
+    /// The `character` offsets of `range`, like the ones of the hints, are counted in
+    /// `encoding` in `source`, the text of the document. A range reaching past the end of
+    /// the document covers it up to its end.
+    pub fn in_range(
+        source: &str,
+        document: &Document,
+        range: Range,
+        encoding: PositionEncodingKind,
+    ) -> Vec<Self> {
+        let mut hints = vec![];
+        if let Some(root) = &document.root {
+            let position = |location| encoding.position(source, location);
+            collect_hints(root, range, &position, &mut hints);
+        }
+        hints
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn kind(&self) -> Option<InlayHintKind> {
+        self.kind
+    }
+}
+
+/// The kinds of inlay hints.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#inlayHintKind)
+#[derive(Serialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum InlayHintKind {
+    /// A hint annotating a type.
+    Type = 1,
+    /// A hint annotating a parameter.
+    Parameter = 2,
+}
+
+/// Adds the hints of the scalars of the tree rooted at `node` that end within `range`,
+/// at the `position` of their end.
+fn collect_hints(
+    node: &Node,
+    range: Range,
+    position: &impl Fn(Location) -> Position,
+    hints: &mut Vec<InlayHint>,
+) {
+    match &node.kind {
+        NodeKind::Scalar(scalar) => {
+            let position = position(node.span.end);
+            if contains(range, position) {
+                hints.push(InlayHint {
+                    position,
+                    label: format!(": {}", scalar.type_name()),
+                    kind: Some(InlayHintKind::Type),
+                });
+            }
+        }
+        NodeKind::Dict(entries) => {
+            for entry in entries {
+                collect_hints(&entry.value, range, position, hints);
+            }
+        }
+        NodeKind::List(items) => {
+            for item in items {
+                collect_hints(item, range, position, hints);
+            }
+        }
+    }
+}
+
+/// Returns `true` if `position` is within `range`, its end included.
+fn contains(range: Range, position: Position) -> bool {
+    (range.start()..=range.end()).contains(&position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    const SOURCE: &str = "\
+name: \"huml\"
+port: 8080
+ratio: 0.5
+enabled: true
+tags:: \"a\", 2
+missing: null
+";
+
+    fn hints(source: &str, range: Range) -> Vec<(usize, usize, String)> {
+        let document = huml::parse(source).document;
+        InlayHint::in_range(source, &document, range, PositionEncodingKind::Utf16)
+            .iter()
+            .map(|hint| {
+                let position = hint.position();
+                (
+                    position.line(),
+                    position.character(),
+                    hint.label().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn lines(start: usize, end: usize) -> Range {
+        Range::new(Position::new(start, 0), Position::new(end, 0))
+    }
+
+    #[test]
+    fn should_hint_type_at_end_of_each_value() {
+        let expected = [
+            (0, 12, ": string"),
+            (1, 10, ": int"),
+            (2, 10, ": float"),
+            (3, 13, ": bool"),
+            (4, 10, ": string"),
+            (4, 13, ": int"),
+            (5, 13, ": null"),
+        ]
+        .map(|(line, character, label)| (line, character, label.to_string()));
+
+        assert_eq!(hints(SOURCE, lines(0, 6)), expected);
+    }
+
+    #[test]
+    fn should_hint_values_within_range_only() {
+        assert_eq!(
+            hints(SOURCE, lines(1, 3)),
+            [(1, 10, ": int".to_string()), (2, 10, ": float".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_count_hint_characters_in_encoding() {
+        assert_eq!(
+            hints("name: \"café ☕\"\n", lines(0, 1)),
+            [(0, 14, ": string".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_serialize_inlay_hint() {
+        let hint = InlayHint {
+            position: Position::new(1, 10),
+            label: ": int".to_string(),
+            kind: Some(InlayHintKind::Type),
+        };
+
+        assert_eq!(
+            serde_json::to_value(hint).unwrap(),
+            serde_json::json!({
+                "position": { "line": 1, "character": 10 },
+                "label": ": int",
+                "kind": 1
+            })
+        );
+    }
+}
@@ -5,10 +5,44 @@
 //! This module provides the necessary structures to build both successful responses,
 //! which contain a `result`, and error responses, which contain an `error` object.
 
+pub mod code_action;
+pub mod code_lens;
+pub mod completion;
+pub mod document_color;
+pub mod document_symbol;
+pub mod folding_range;
+pub mod hover;
 pub mod initialize;
+pub mod inlay_hint;
+pub mod prepare_rename;
+pub mod semantic_tokens;
+pub mod signature_help;
+pub mod workspace_symbol;
 
 use crate::{
-    lsp::{request::Request, response::initialize::InitializeResult},
+    lsp::{
+        common::{
+            text_document::{Location, TextEdit},
+            workspace_edit::WorkspaceEdit,
+        },
+        error::{JsonRpcError, ServerError},
+        request::Request,
+        response::{
+            code_action::CodeAction,
+            code_lens::CodeLens,
+            completion::CompletionItem,
+            document_color::{ColorInformation, ColorPresentation},
+            document_symbol::DocumentSymbol,
+            folding_range::FoldingRange,
+            hover::Hover,
+            initialize::InitializeResult,
+            inlay_hint::InlayHint,
+            prepare_rename::PrepareRenameResult,
+            semantic_tokens::SemanticTokens,
+            signature_help::SignatureHelp,
+            workspace_symbol::SymbolInformation,
+        },
+    },
     rpc::{Integer, LSPAny},
 };
 use serde::Serialize;
@@ -22,8 +56,9 @@ use serde::Serialize;
 /// for more details.
 #[derive(Serialize, Debug)]
 pub struct ResponseMessage {
-    /// The ID of the request that this response is for.
-    id: Integer,
+    /// The ID of the request that this response is for, or `null` if the ID of the
+    /// request couldn't be read.
+    id: Option<Integer>,
 
     /// The payload of the response, containing either a `Result` or an `Error`.
     #[serde(flatten)]
@@ -36,14 +71,14 @@ pub struct ResponseMessage {
 impl ResponseMessage {
     /// Creates a new `ResponseMessage` with a specified request ID and payload.
     ///
-    /// # Unsafe
+    /// # Safety
     /// This function is marked `unsafe` because it allows the creation of a response
     /// with an arbitrary ID, which could potentially violate the LSP specification if
     /// the ID does not correspond to a pending request from the client. It should be
     /// used with caution.
     pub unsafe fn new(request_id: Integer, payload: ResponsePayload) -> Self {
         Self {
-            id: request_id,
+            id: Some(request_id),
             payload,
             jsonrpc: "2.0".to_string(),
         }
@@ -55,14 +90,25 @@ impl ResponseMessage {
     /// that the response ID correctly matches the request ID.
     pub fn new_for(request: &Request, payload: ResponsePayload) -> Self {
         Self {
-            id: request.id(),
+            id: Some(request.id()),
             payload,
             jsonrpc: "2.0".to_string(),
         }
     }
 
-    /// Returns the ID of the request this message is responding to.
-    pub fn id(&self) -> i32 {
+    /// Creates the error response to a request that couldn't be decoded into a
+    /// [`Request`], identified by `id`. The `id` is `None` if it couldn't be read either,
+    /// e.g. because the message isn't valid JSON.
+    pub fn error_for_undecoded(id: Option<Integer>, code: ErrorCode, message: String) -> Self {
+        Self {
+            id,
+            payload: ResponsePayload::error(code, message),
+            jsonrpc: "2.0".to_string(),
+        }
+    }
+
+    /// Returns the ID of the request this message is responding to, if known.
+    pub fn id(&self) -> Option<i32> {
         self.id
     }
 
@@ -91,6 +137,54 @@ pub enum ResponsePayload {
     },
 }
 
+impl ResponsePayload {
+    /// Creates an error payload without additional data.
+    pub fn error(code: ErrorCode, message: String) -> Self {
+        Self::Error {
+            code: code as Integer,
+            message,
+            data: None,
+        }
+    }
+}
+
+impl From<JsonRpcError> for ResponsePayload {
+    fn from(e: JsonRpcError) -> Self {
+        Self::error(e.code(), e.message().to_string())
+    }
+}
+
+impl From<ServerError> for ResponsePayload {
+    fn from(e: ServerError) -> Self {
+        JsonRpcError::from(e).into()
+    }
+}
+
+/// Error codes of failed requests, as defined by JSON-RPC and LSP.
+///
+/// See the [LSP specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#errorCodes)
+/// for more details.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(i32)]
+pub enum ErrorCode {
+    ParseError = -32700,
+    InvalidRequest = -32600,
+    MethodNotFound = -32601,
+    InvalidParams = -32602,
+    InternalError = -32603,
+    /// The server received a request before the `initialize` request.
+    ServerNotInitialized = -32002,
+    UnknownErrorCode = -32001,
+    /// The request was valid, but the server failed to process it.
+    RequestFailed = -32803,
+    /// The server cancelled the request.
+    ServerCancelled = -32802,
+    /// The document changed while the request was being processed.
+    ContentModified = -32801,
+    /// The client cancelled the request.
+    RequestCancelled = -32800,
+}
+
 /// A convenience implementation to easily wrap a `ResponseResult` in a `ResponsePayload`.
 impl From<ResponseResult> for ResponsePayload {
     fn from(v: ResponseResult) -> Self {
@@ -117,4 +211,86 @@ pub enum ResponseResult {
     Initialize(InitializeResult),
     /// The result of a successful `shutdown` request, which is `null` in JSON.
     Shutdown,
+    /// The result of a successful `textDocument/documentSymbol` request.
+    DocumentSymbol(Vec<DocumentSymbol>),
+    /// The result of a successful `textDocument/formatting` request.
+    Formatting(Vec<TextEdit>),
+    /// The result of a successful `textDocument/completion` request.
+    Completion(Vec<CompletionItem>),
+    /// The result of a successful `completionItem/resolve` request.
+    CompletionResolve(CompletionItem),
+    /// The result of a successful `textDocument/hover` request, `null` if there is
+    /// nothing to show.
+    Hover(Option<Hover>),
+    /// The result of a successful `textDocument/foldingRange` request.
+    FoldingRange(Vec<FoldingRange>),
+    /// The result of a successful `textDocument/semanticTokens/full` request.
+    SemanticTokens(SemanticTokens),
+    /// The result of a successful `textDocument/rename` request, `null` if there is
+    /// nothing to rename.
+    Rename(Option<WorkspaceEdit>),
+    /// The result of a successful `textDocument/prepareRename` request, `null` if there
+    /// is nothing to rename at the position.
+    PrepareRename(Option<PrepareRenameResult>),
+    /// The result of a successful `workspace/executeCommand` request, `null` if the
+    /// command has no result.
+    ExecuteCommand(Option<LSPAny>),
+    /// The result of a successful `textDocument/codeAction` request.
+    CodeAction(Vec<CodeAction>),
+    /// The result of a successful `textDocument/willSaveWaitUntil` request.
+    WillSaveWaitUntil(Vec<TextEdit>),
+    /// The result of a successful `textDocument/inlayHint` request.
+    InlayHint(Vec<InlayHint>),
+    /// The result of a successful `textDocument/definition` request, `null` if there is
+    /// no definition to go to.
+    Definition(Option<Location>),
+    /// The result of a successful `textDocument/references` request.
+    References(Vec<Location>),
+    /// The result of a successful `textDocument/signatureHelp` request, `null` if there
+    /// is nothing to show.
+    SignatureHelp(Option<SignatureHelp>),
+    /// The result of a successful `workspace/symbol` request.
+    WorkspaceSymbol(Vec<SymbolInformation>),
+    /// The result of a successful `textDocument/documentColor` request.
+    DocumentColor(Vec<ColorInformation>),
+    /// The result of a successful `textDocument/colorPresentation` request.
+    ColorPresentation(Vec<ColorPresentation>),
+    /// The result of a successful `textDocument/codeLens` request.
+    CodeLens(Vec<CodeLens>),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn response_json(result: ResponseResult) -> serde_json::Value {
+        let request: Request =
+            serde_json::from_str(r#"{ "id": 1, "method": "shutdown", "jsonrpc": "2.0" }"#).unwrap();
+        serde_json::to_value(ResponseMessage::new_for(&request, result.into())).unwrap()
+    }
+
+    #[test]
+    fn should_serialize_null_hover_as_null_result() {
+        let json = response_json(ResponseResult::Hover(None));
+
+        assert_eq!(json, json!({ "id": 1, "result": null, "jsonrpc": "2.0" }));
+    }
+
+    #[test]
+    fn should_serialize_empty_results_as_null() {
+        for result in [
+            ResponseResult::Shutdown,
+            ResponseResult::Definition(None),
+            ResponseResult::Rename(None),
+            ResponseResult::ExecuteCommand(None),
+        ] {
+            let json = response_json(result);
+
+            let object = json.as_object().unwrap();
+            assert_eq!(object.get("result"), Some(&serde_json::Value::Null));
+            assert!(!object.contains_key("error"));
+        }
+    }
 }
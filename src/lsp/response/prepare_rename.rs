@@ -0,0 +1,83 @@
+use serde::Serialize;
+
+use crate::{
+    huml::ast::Document,
+    lsp::common::text_document::{PositionEncodingKind, Range},
+};
+
+/// The result of a prepare rename request: the range of the symbol to rename, along
+/// with the text the client shows to be edited.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_prepareRename)
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrepareRenameResult {
+    /// The range of the symbol to rename.
+    range: Range,
+
+    /// The current text of the symbol.
+    placeholder: String,
+}
+
+impl PrepareRenameResult {
+    /// Returns the key found at the byte `column` of `line`, which is the only symbol that
+    /// can be renamed, or `None` if there is no key there. The placeholder is the key as
+    /// written in `source`, quotes included, and the `character` offsets of its range are
+    /// counted in `encoding`.
+    pub fn at(
+        source: &str,
+        document: &Document,
+        line: usize,
+        column: usize,
+        encoding: PositionEncodingKind,
+    ) -> Option<Self> {
+        let key = document.key_at(line, column)?;
+        Some(Self {
+            range: encoding.range(source, key.span),
+            placeholder: source[key.span.start.offset..key.span.end.offset].to_string(),
+        })
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn placeholder(&self) -> &str {
+        &self.placeholder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{huml, lsp::common::text_document::Position};
+
+    const SOURCE: &str = "# The server\n\"server name\": \"huml\"\n";
+
+    fn prepare_rename(line: usize, column: usize) -> Option<PrepareRenameResult> {
+        PrepareRenameResult::at(
+            SOURCE,
+            &huml::parse(SOURCE).document,
+            line,
+            column,
+            PositionEncodingKind::Utf16,
+        )
+    }
+
+    #[test]
+    fn should_prepare_renaming_key() {
+        let result = prepare_rename(1, 3).expect("Key should be renameable");
+
+        assert_eq!(
+            result.range(),
+            Range::new(Position::new(1, 0), Position::new(1, 13))
+        );
+        assert_eq!(result.placeholder(), "\"server name\"");
+    }
+
+    #[test]
+    fn should_not_prepare_renaming_values_or_comments() {
+        assert_eq!(prepare_rename(1, 17), None);
+        assert_eq!(prepare_rename(0, 4), None);
+    }
+}
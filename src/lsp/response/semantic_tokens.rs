@@ -0,0 +1,268 @@
+use serde::Serialize;
+
+use crate::{
+    huml::ast::{Document, Entry, Location, Node, NodeKind, Scalar, Span},
+    lsp::common::text_document::PositionEncodingKind,
+    rpc::UInteger,
+};
+
+/// The semantic tokens of a document, telling the client how to highlight it.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens)
+#[derive(Serialize, Clone, Default, PartialEq, Eq, Debug)]
+pub struct SemanticTokens {
+    /// The tokens, as groups of 5 integers: the line of the token relative to the
+    /// previous one, its start character, relative to the previous token if on the same
+    /// line, its length, its type and its modifiers.
+    data: Vec<UInteger>,
+}
+
+impl SemanticTokens {
+    /// Finds the tokens of `document`, parsed from `source`: keys, scalars, the `:` and
+    /// `::` indicators, and comments.
+    ///
+    /// Tokens can't span several lines, so multiline strings are split into a token per
+    /// line. Their start and length are counted in `encoding`.
+    pub fn from_document(
+        source: &str,
+        document: &Document,
+        encoding: PositionEncodingKind,
+    ) -> Self {
+        let mut tokens = TokenCollector {
+            lines: source.lines().collect(),
+            source,
+            encoding,
+            tokens: vec![],
+        };
+        if let Some(root) = &document.root {
+            tokens.collect_node(root);
+        }
+        for comment in &document.comments {
+            tokens.push(comment.span, SemanticTokenType::Comment);
+        }
+        tokens.tokens.sort_by_key(|token| (token.line, token.start));
+
+        let mut data = Vec::with_capacity(tokens.tokens.len() * 5);
+        let mut previous = Token::default();
+        for token in tokens.tokens {
+            let delta_start = if token.line == previous.line {
+                token.start - previous.start
+            } else {
+                token.start
+            };
+            data.extend([
+                token.line - previous.line,
+                delta_start,
+                token.length,
+                token.kind as UInteger,
+                0,
+            ]);
+            previous = token;
+        }
+        Self { data }
+    }
+
+    pub fn data(&self) -> &[UInteger] {
+        &self.data
+    }
+}
+
+/// The types of the tokens, in the order of the legend advertised to the client, which
+/// tokens refer to by index.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum SemanticTokenType {
+    /// A key.
+    #[default]
+    Property,
+    String,
+    Number,
+    /// `true`, `false` or `null`.
+    Keyword,
+    Comment,
+    /// The `:` and `::` indicators.
+    Operator,
+}
+
+impl SemanticTokenType {
+    /// Every token type, in the order of the legend.
+    pub const ALL: [Self; 6] = [
+        Self::Property,
+        Self::String,
+        Self::Number,
+        Self::Keyword,
+        Self::Comment,
+        Self::Operator,
+    ];
+
+    /// The name of the type in the legend, as defined by the specification.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Property => "property",
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Keyword => "keyword",
+            Self::Comment => "comment",
+            Self::Operator => "operator",
+        }
+    }
+}
+
+/// A token at an absolute position.
+#[derive(Clone, Copy, Default, Debug)]
+struct Token {
+    line: UInteger,
+    start: UInteger,
+    length: UInteger,
+    kind: SemanticTokenType,
+}
+
+struct TokenCollector<'a> {
+    source: &'a str,
+    lines: Vec<&'a str>,
+    encoding: PositionEncodingKind,
+    tokens: Vec<Token>,
+}
+
+impl TokenCollector<'_> {
+    fn collect_node(&mut self, node: &Node) {
+        match &node.kind {
+            NodeKind::Dict(entries) => {
+                for entry in entries {
+                    self.collect_entry(entry);
+                }
+            }
+            NodeKind::List(items) => {
+                for item in items {
+                    self.collect_node(item);
+                }
+            }
+            NodeKind::Scalar(scalar) => {
+                let kind = match scalar {
+                    Scalar::String(_) => SemanticTokenType::String,
+                    Scalar::Integer(_) | Scalar::Float(_) => SemanticTokenType::Number,
+                    Scalar::Boolean(_) | Scalar::Null => SemanticTokenType::Keyword,
+                };
+                self.push(node.span, kind);
+            }
+        }
+    }
+
+    fn collect_entry(&mut self, entry: &Entry) {
+        self.push(entry.key.span, SemanticTokenType::Property);
+
+        let key_end = entry.key.span.end;
+        let indicator = &self.source[key_end.offset..];
+        let colons = indicator.len() - indicator.trim_start_matches(':').len();
+        let indicator_end = Location {
+            line: key_end.line,
+            column: key_end.column + colons,
+            offset: key_end.offset + colons,
+        };
+        self.push(
+            Span::new(key_end, indicator_end),
+            SemanticTokenType::Operator,
+        );
+
+        self.collect_node(&entry.value);
+    }
+
+    /// Adds a token of `kind` covering `span`, split at line ends.
+    fn push(&mut self, span: Span, kind: SemanticTokenType) {
+        for line in span.start.line..=span.end.line {
+            let start = if line == span.start.line {
+                span.start.column
+            } else {
+                0
+            };
+            let end = if line == span.end.line {
+                span.end.column
+            } else {
+                self.lines.get(line).map_or(0, |text| text.len())
+            };
+            if end > start {
+                let text = self.lines.get(line).copied().unwrap_or_default();
+                let start = self.encoding.character_offset(text, start);
+                let end = self.encoding.character_offset(text, end);
+                self.tokens.push(Token {
+                    line,
+                    start,
+                    length: end - start,
+                    kind,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    fn tokens(source: &str) -> Vec<(UInteger, UInteger, UInteger, &'static str)> {
+        let document = huml::parse(source).document;
+        let data =
+            SemanticTokens::from_document(source, &document, PositionEncodingKind::Utf16).data;
+
+        // Decode the relative positions back into absolute ones
+        let (mut line, mut start) = (0, 0);
+        data.chunks(5)
+            .map(|token| {
+                if token[0] > 0 {
+                    start = 0;
+                }
+                line += token[0];
+                start += token[1];
+                let kind = SemanticTokenType::ALL[token[3]].as_str();
+                (line, start, token[2], kind)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_encode_tokens_of_document() {
+        let source = "\
+# Server
+server::
+  port: 8080 # main
+  tags:: \"a\", null
+text: \"\"\"
+  x
+\"\"\"
+";
+
+        assert_eq!(
+            tokens(source),
+            [
+                (0, 0, 8, "comment"),
+                (1, 0, 6, "property"),
+                (1, 6, 2, "operator"),
+                (2, 2, 4, "property"),
+                (2, 6, 1, "operator"),
+                (2, 8, 4, "number"),
+                (2, 13, 6, "comment"),
+                (3, 2, 4, "property"),
+                (3, 6, 2, "operator"),
+                (3, 9, 3, "string"),
+                (3, 14, 4, "keyword"),
+                (4, 0, 4, "property"),
+                (4, 4, 1, "operator"),
+                (4, 6, 3, "string"),
+                (5, 0, 3, "string"),
+                (6, 0, 3, "string"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_count_token_characters_in_encoding() {
+        assert_eq!(
+            tokens("\"é\": \"ü\"\n"),
+            [
+                (0, 0, 3, "property"),
+                (0, 3, 1, "operator"),
+                (0, 5, 3, "string")
+            ]
+        );
+    }
+}
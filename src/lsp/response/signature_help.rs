@@ -0,0 +1,153 @@
+use serde::Serialize;
+
+use crate::{
+    huml::schema::Schema,
+    lsp::{
+        common::markup::MarkupContent,
+        response::completion::{PathSegment, parent_path},
+    },
+    rpc::UInteger,
+};
+
+/// Signature help, here the value the schema expects for the key being written.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#signatureHelp)
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureHelp {
+    /// One or more signatures, here a single one describing the expected value.
+    signatures: Vec<SignatureInformation>,
+
+    /// The active signature.
+    active_signature: UInteger,
+}
+
+/// A signature shown to the user, here a key along with the type of its value, e.g.
+/// `port: integer`.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#signatureInformation)
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct SignatureInformation {
+    /// The label of this signature. Will be shown in the UI.
+    label: String,
+
+    /// The human-readable doc-comment of this signature, the description of the value
+    /// in the schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    documentation: Option<MarkupContent>,
+}
+
+impl SignatureHelp {
+    /// Builds the hint for the value being written before the byte `column` of `line` in
+    /// `source`, after the colon of its key.
+    ///
+    /// The path of the key is found from the headers of its parent blocks, as the
+    /// document being written may not parse. Returns `None` if the cursor isn't after a
+    /// key, or if the schema doesn't list the key.
+    pub fn at(schema: &Schema, source: &str, line: usize, column: usize) -> Option<Self> {
+        let lines: Vec<_> = source.lines().collect();
+        let text = lines.get(line).copied().unwrap_or_default();
+        let prefix = text.get(..column).unwrap_or(text);
+        let content = prefix.trim_start();
+        let indent = prefix.len() - content.len();
+        if content.starts_with(['#', '-']) {
+            return None;
+        }
+
+        let (key, _) = content.split_once(':')?;
+        let key = key.trim().trim_matches('"');
+        let mut path = parent_path(&lines[..line.min(lines.len())], indent);
+        if key.is_empty() || (indent > 0 && path.is_empty()) {
+            return None;
+        }
+        path.push(PathSegment::Key(key.to_string()));
+
+        let schema = path
+            .iter()
+            .try_fold(schema, |schema, segment| match segment {
+                PathSegment::Key(key) => schema.property(key),
+                PathSegment::Item => schema.items(),
+            })?;
+        let signature = SignatureInformation {
+            label: format!("{key}: {}", schema.type_label()),
+            documentation: schema
+                .description()
+                .map(|description| MarkupContent::markdown(description.to_string())),
+        };
+        Some(Self {
+            signatures: vec![signature],
+            active_signature: 0,
+        })
+    }
+
+    pub fn signatures(&self) -> &[SignatureInformation] {
+        &self.signatures
+    }
+}
+
+impl SignatureInformation {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn documentation(&self) -> Option<&MarkupContent> {
+        self.documentation.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        serde_json::from_value(json!({
+            "properties": {
+                "server": {
+                    "type": "object",
+                    "properties": {
+                        "port": { "type": "integer", "description": "The port to listen on." }
+                    }
+                },
+                "hosts": { "type": "array", "items": { "type": "string" } }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn label(source: &str, line: usize, column: usize) -> Option<String> {
+        let help = SignatureHelp::at(&schema(), source, line, column)?;
+        Some(help.signatures()[0].label().to_string())
+    }
+
+    #[test]
+    fn should_describe_value_of_nested_key() {
+        let source = "server::\n  port: \n";
+
+        let help = SignatureHelp::at(&schema(), source, 1, 8).unwrap();
+
+        assert_eq!(help.signatures().len(), 1);
+        assert_eq!(help.signatures()[0].label(), "port: integer");
+        assert_eq!(
+            help.signatures()[0]
+                .documentation()
+                .map(MarkupContent::value),
+            Some("The port to listen on.")
+        );
+    }
+
+    #[test]
+    fn should_describe_value_of_root_key() {
+        assert_eq!(
+            label("hosts:: \"a\", ", 0, 12).as_deref(),
+            Some("hosts: array of string")
+        );
+    }
+
+    #[test]
+    fn should_not_describe_unknown_keys_or_keys_being_written() {
+        assert_eq!(label("port: ", 0, 6), None);
+        assert_eq!(label("server::\n  po", 1, 4), None);
+        assert_eq!(label("# server: ", 0, 10), None);
+    }
+}
@@ -0,0 +1,159 @@
+use serde::Serialize;
+
+use crate::{
+    huml::ast::{Document, Node, NodeKind, Span},
+    lsp::{
+        common::text_document::{Location, PositionEncodingKind, Range},
+        response::document_symbol::SymbolKind,
+    },
+};
+
+/// Represents information about programming constructs like variables, classes,
+/// interfaces etc., here the keys of the open documents.
+///
+/// See [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#symbolInformation)
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolInformation {
+    /// The name of this symbol.
+    name: String,
+
+    /// The kind of this symbol.
+    kind: SymbolKind,
+
+    /// The location of this symbol, the whole entry of the key.
+    location: Location,
+
+    /// The name of the symbol containing this symbol, the path of the dict holding the
+    /// key, e.g. `server.tls`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container_name: Option<String>,
+}
+
+impl SymbolInformation {
+    /// Returns the keys of `document`, opened at `uri`, whose name contains `query`
+    /// ignoring case, in the order they're declared. An empty query matches every key.
+    ///
+    /// The `character` offsets of the locations are counted in `encoding` in `source`,
+    /// the text of the document.
+    pub fn matching(
+        document: &Document,
+        source: &str,
+        uri: &str,
+        query: &str,
+        encoding: PositionEncodingKind,
+    ) -> Vec<Self> {
+        let mut symbols = vec![];
+        if let Some(root) = &document.root {
+            let query = query.to_lowercase();
+            let range = |span| encoding.range(source, span);
+            collect_matching(root, uri, &query, &range, &mut vec![], &mut symbols);
+        }
+        symbols
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn container_name(&self) -> Option<&str> {
+        self.container_name.as_deref()
+    }
+}
+
+/// Adds the keys of the tree rooted at `node` matching the lowercase `query` to
+/// `symbols`, `path` being the keys of the dicts holding `node`. The locations cover the
+/// `range` of the entries.
+fn collect_matching<'a>(
+    node: &'a Node,
+    uri: &str,
+    query: &str,
+    range: &impl Fn(Span) -> Range,
+    path: &mut Vec<&'a str>,
+    symbols: &mut Vec<SymbolInformation>,
+) {
+    match &node.kind {
+        NodeKind::Dict(entries) => {
+            for entry in entries {
+                if entry.key.name.to_lowercase().contains(query) {
+                    symbols.push(SymbolInformation {
+                        name: entry.key.name.clone(),
+                        kind: SymbolKind::from(&entry.value),
+                        location: Location::new(uri.to_string(), range(entry.span())),
+                        container_name: (!path.is_empty()).then(|| path.join(".")),
+                    });
+                }
+                path.push(&entry.key.name);
+                collect_matching(&entry.value, uri, query, range, path, symbols);
+                path.pop();
+            }
+        }
+        NodeKind::List(items) => {
+            for item in items {
+                collect_matching(item, uri, query, range, path, symbols);
+            }
+        }
+        NodeKind::Scalar(_) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huml;
+
+    const DOCUMENT: &str = "\
+name: \"huml-lsp\"
+server::
+  Port: 8080
+  tls::
+    port: 8443
+";
+
+    fn symbols(query: &str) -> Vec<(String, Option<String>, usize)> {
+        let parsed = huml::parse(DOCUMENT);
+        SymbolInformation::matching(
+            &parsed.document,
+            DOCUMENT,
+            "file:///config.huml",
+            query,
+            PositionEncodingKind::Utf16,
+        )
+        .iter()
+        .map(|symbol| {
+            assert_eq!(symbol.location().uri(), "file:///config.huml");
+            (
+                symbol.name().to_string(),
+                symbol.container_name().map(str::to_string),
+                symbol.location().range().start().line(),
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn should_match_keys_ignoring_case() {
+        assert_eq!(
+            symbols("PORT"),
+            [
+                ("Port".to_string(), Some("server".to_string()), 2),
+                ("port".to_string(), Some("server.tls".to_string()), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_match_every_key_with_empty_query() {
+        let names: Vec<_> = symbols("").into_iter().map(|(name, ..)| name).collect();
+
+        assert_eq!(names, ["name", "server", "Port", "tls", "port"]);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+
+use crate::lsp::{
+    common::{
+        diagnostic::Diagnostic,
+        text_document::{PositionEncodingKind, TextDocumentItemOwned},
+        uri::normalize_uri,
+    },
+    notification::{
+        ServerClientNotification, publish_diagnostics::PublishDiagnosticsParams, telemetry,
+    },
+    settings::ServerSettings,
+};
+
+/// Publishes the diagnostics of edited documents from a thread of its own, once they
+/// haven't been edited for a while, so that typing doesn't lint a document on every
+/// keystroke.
+///
+/// Only the last version of a document scheduled before the delay elapses is linted.
+pub struct DiagnosticsDebouncer {
+    sender: mpsc::Sender<Job>,
+    thread: JoinHandle<()>,
+}
+
+enum Job {
+    /// Replaces the lint scheduled for the document, if any.
+    Schedule(Box<ScheduledLint>),
+    /// Drops the lint scheduled for the document identified by the normalized URI.
+    Cancel(String),
+    /// Publishes the diagnostics scheduled right away, then acknowledges it.
+    #[cfg(test)]
+    Flush(mpsc::Sender<()>),
+}
+
+/// The lint of `document` with `settings`, once `deadline` is reached, with positions
+/// counted in `encoding`.
+struct ScheduledLint {
+    deadline: Instant,
+    document: TextDocumentItemOwned,
+    settings: ServerSettings,
+    encoding: PositionEncodingKind,
+}
+
+impl DiagnosticsDebouncer {
+    /// Spawns the thread publishing diagnostics with `notifications`.
+    pub fn spawn(notifications: mpsc::Sender<ServerClientNotification>) -> Self {
+        let (sender, jobs) = mpsc::channel();
+        let thread = thread::spawn(move || run(jobs, notifications));
+        Self { sender, thread }
+    }
+
+    /// Publishes the diagnostics of `document`, linted with `settings`, unless another
+    /// version of it is scheduled within `delay`. The `character` offsets of their ranges
+    /// are counted in `encoding`.
+    pub fn schedule(
+        &self,
+        document: TextDocumentItemOwned,
+        settings: ServerSettings,
+        encoding: PositionEncodingKind,
+        delay: Duration,
+    ) {
+        // The thread only stops once the debouncer is closed
+        let _ = self.sender.send(Job::Schedule(Box::new(ScheduledLint {
+            deadline: Instant::now() + delay,
+            document,
+            settings,
+            encoding,
+        })));
+    }
+
+    /// Drops the diagnostics scheduled for the document identified by `uri`, e.g. because
+    /// fresher ones are published right away.
+    pub fn cancel(&self, uri: &str) {
+        let _ = self.sender.send(Job::Cancel(normalize_uri(uri)));
+    }
+
+    /// Publishes the diagnostics scheduled so far without waiting for their delay, and
+    /// returns once they're sent, so that tests don't depend on timing.
+    #[cfg(test)]
+    pub fn flush(&self) {
+        let (done, flushed) = mpsc::channel();
+        if self.sender.send(Job::Flush(done)).is_ok() {
+            let _ = flushed.recv();
+        }
+    }
+
+    /// Stops the thread, dropping the diagnostics still scheduled.
+    pub fn close(self) {
+        drop(self.sender);
+        let _ = self.thread.join();
+    }
+}
+
+fn run(jobs: mpsc::Receiver<Job>, notifications: mpsc::Sender<ServerClientNotification>) {
+    let mut scheduled: IndexMap<String, ScheduledLint> = IndexMap::new();
+    loop {
+        let next_deadline = scheduled.values().map(|lint| lint.deadline).min();
+        let job = match next_deadline {
+            Some(deadline) => {
+                match jobs.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(job) => Some(job),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            None => match jobs.recv() {
+                Ok(job) => Some(job),
+                Err(_) => return,
+            },
+        };
+
+        match job {
+            Some(Job::Schedule(lint)) => {
+                scheduled.insert(normalize_uri(lint.document.uri()), *lint);
+            }
+            Some(Job::Cancel(uri)) => {
+                scheduled.shift_remove(&uri);
+            }
+            #[cfg(test)]
+            Some(Job::Flush(done)) => {
+                for (_, lint) in scheduled.drain(..) {
+                    publish(lint, &notifications);
+                }
+                let _ = done.send(());
+            }
+            None => {
+                let now = Instant::now();
+                let due: Vec<_> = scheduled
+                    .extract_if(.., |_, lint| lint.deadline <= now)
+                    .map(|(_, lint)| lint)
+                    .collect();
+                for lint in due {
+                    publish(lint, &notifications);
+                }
+            }
+        }
+    }
+}
+
+/// Lints the document of `lint`, and publishes its diagnostics with `notifications`.
+fn publish(lint: ScheduledLint, notifications: &mpsc::Sender<ServerClientNotification>) {
+    let document = lint.document;
+    let start = Instant::now();
+    let diagnostics = Diagnostic::lint(
+        document.text(),
+        document.uri(),
+        &lint.settings,
+        lint.encoding,
+    );
+    let event = telemetry::lint_event(start.elapsed(), &diagnostics);
+    let params = PublishDiagnosticsParams::new(
+        document.uri().to_string(),
+        Some(document.version()),
+        diagnostics,
+    );
+    // Nobody is left to report to once the client stopped listening
+    let _ = notifications.send(params.into());
+    if lint.settings.enable_telemetry() {
+        let _ = notifications.send(ServerClientNotification::Telemetry(event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(version: i32, text: &str) -> TextDocumentItemOwned {
+        TextDocumentItemOwned::new(
+            "file:///a.huml".to_string(),
+            "huml".to_string(),
+            version,
+            text.to_string(),
+        )
+    }
+
+    #[test]
+    fn should_drop_cancelled_diagnostics() {
+        let (sender, notifications) = mpsc::channel();
+        let debouncer = DiagnosticsDebouncer::spawn(sender);
+
+        debouncer.schedule(
+            document(2, "a:1\n"),
+            ServerSettings::default(),
+            PositionEncodingKind::Utf16,
+            Duration::from_secs(60),
+        );
+        debouncer.cancel("file:///%61.huml");
+        debouncer.flush();
+        debouncer.close();
+
+        assert!(
+            notifications.recv().is_err(),
+            "Expected no diagnostics to be published"
+        );
+    }
+
+    #[test]
+    fn should_publish_last_scheduled_version() {
+        let (sender, notifications) = mpsc::channel();
+        let debouncer = DiagnosticsDebouncer::spawn(sender);
+
+        for (version, text) in [(2, "a:1\n"), (3, "a: 1\n")] {
+            debouncer.schedule(
+                document(version, text),
+                ServerSettings::default(),
+                PositionEncodingKind::Utf16,
+                Duration::from_secs(60),
+            );
+        }
+        debouncer.flush();
+        debouncer.close();
+
+        let published: Vec<_> = notifications
+            .iter()
+            .map(|notification| match notification {
+                ServerClientNotification::PublishDiagnostics(params) => {
+                    (params.version(), params.diagnostics().len())
+                }
+                other => panic!("Unexpected notification {other:?}"),
+            })
+            .collect();
+        assert_eq!(published, [(Some(3), 0)]);
+    }
+}
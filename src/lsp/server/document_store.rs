@@ -0,0 +1,150 @@
+use indexmap::IndexMap;
+
+use crate::lsp::{
+    common::{text_document::TextDocumentItemOwned, uri::normalize_uri},
+    server::state::LineSeperatedDocument,
+};
+
+/// The documents opened by the client, keyed by their normalized URI.
+///
+/// Looking a document up doesn't depend on the number of open documents, which are kept
+/// in the order they were opened.
+#[derive(Default)]
+pub struct DocumentStore {
+    documents: IndexMap<String, LineSeperatedDocument>,
+}
+
+impl DocumentStore {
+    /// Stores the opened `document`, replacing the one with the same URI, if any, in
+    /// place.
+    pub fn open(&mut self, document: TextDocumentItemOwned) {
+        let uri = normalize_uri(document.uri());
+        self.documents
+            .insert(uri, LineSeperatedDocument::from(document));
+    }
+
+    /// Replaces the open document identified by `uri` with its `updated` version. Returns
+    /// `false`, leaving the store as is, if the document isn't open.
+    pub fn update(&mut self, uri: &str, updated: LineSeperatedDocument) -> bool {
+        match self.documents.get_mut(&normalize_uri(uri)) {
+            Some(document) => {
+                *document = updated;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the open document identified by `uri`, if any.
+    pub fn get(&self, uri: &str) -> Option<&LineSeperatedDocument> {
+        self.documents.get(&normalize_uri(uri))
+    }
+
+    /// Returns the open document identified by `uri` to be modified in place, if any.
+    pub fn get_mut(&mut self, uri: &str) -> Option<&mut LineSeperatedDocument> {
+        self.documents.get_mut(&normalize_uri(uri))
+    }
+
+    /// Forgets the open document identified by `uri`, returning it if it was open. The
+    /// other documents keep their order.
+    pub fn remove(&mut self, uri: &str) -> Option<LineSeperatedDocument> {
+        self.documents.shift_remove(&normalize_uri(uri))
+    }
+
+    /// Returns the open documents, in the order they were opened.
+    pub fn iter(&self) -> impl Iterator<Item = &LineSeperatedDocument> {
+        self.documents.values()
+    }
+
+    /// Returns the URIs of the open documents, as sent by the client, in the order they
+    /// were opened.
+    pub fn uris(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|document| document.full_document().uri())
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn document(uri: &str, version: i32, text: &str) -> TextDocumentItemOwned {
+        TextDocumentItemOwned::new(
+            uri.to_string(),
+            "huml".to_string(),
+            version,
+            text.to_string(),
+        )
+    }
+
+    fn text<'a>(store: &'a DocumentStore, uri: &str) -> Option<&'a str> {
+        store
+            .get(uri)
+            .map(|document| document.full_document().text())
+    }
+
+    #[test]
+    fn should_open_and_replace_documents() {
+        let mut store = DocumentStore::default();
+        store.open(document("file:///a.huml", 1, "a: 1\n"));
+        store.open(document("file:///b.huml", 1, "b: 1\n"));
+
+        store.open(document("file:///%61.huml", 2, "a: 2\n"));
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(text(&store, "file:///a.huml"), Some("a: 2\n"));
+        assert_eq!(
+            store.uris().collect::<Vec<_>>(),
+            ["file:///%61.huml", "file:///b.huml"]
+        );
+    }
+
+    #[test]
+    fn should_update_open_documents_only() {
+        let mut store = DocumentStore::default();
+        store.open(document("file:///a.huml", 1, "a: 1\n"));
+
+        let updated = document("file:///a.huml", 2, "a: 2\n").into();
+        assert!(store.update("file:///a.huml", updated));
+        let unknown = document("file:///b.huml", 2, "b: 2\n").into();
+        assert!(!store.update("file:///b.huml", unknown));
+
+        assert_eq!(text(&store, "file:///a.huml"), Some("a: 2\n"));
+        assert_eq!(text(&store, "file:///b.huml"), None);
+    }
+
+    #[test]
+    fn should_remove_documents() {
+        let mut store = DocumentStore::default();
+        store.open(document("file:///a.huml", 1, "a: 1\n"));
+
+        assert!(store.remove("FILE:///a.huml").is_some());
+        assert!(store.remove("file:///a.huml").is_none());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn should_look_up_among_many_documents_quickly() {
+        let mut store = DocumentStore::default();
+        let start = Instant::now();
+
+        for i in 0..10_000 {
+            store.open(document(&format!("file:///{i}.huml"), 1, "a: 1\n"));
+        }
+        for i in 0..10_000 {
+            assert!(store.get(&format!("file:///{i}.huml")).is_some());
+        }
+
+        // Generous enough for slow machines, but far below quadratic lookups
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
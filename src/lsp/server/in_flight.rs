@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use crate::rpc::Integer;
+
+/// Tracks the requests being handled, and which of them the client cancelled.
+///
+/// Long running handlers check [`InFlightRequests::is_cancelled`] to stop early, and a
+/// request cancelled before being answered is answered with a `RequestCancelled` error.
+#[derive(Default, Debug)]
+pub struct InFlightRequests {
+    in_flight: HashSet<Integer>,
+    cancelled: HashSet<Integer>,
+}
+
+impl InFlightRequests {
+    /// Starts tracking the request `id`. Returns `false`, leaving the request in flight as
+    /// is, if the id is already used by a request in flight.
+    #[must_use]
+    pub fn register(&mut self, id: Integer) -> bool {
+        self.in_flight.insert(id)
+    }
+
+    /// Marks the request `id` as cancelled. Requests that aren't in flight, e.g. because
+    /// they were already answered, are ignored.
+    pub fn cancel(&mut self, id: Integer) {
+        if self.in_flight.contains(&id) {
+            self.cancelled.insert(id);
+        }
+    }
+
+    /// Returns `true` if the request `id` was cancelled by the client.
+    pub fn is_cancelled(&self, id: Integer) -> bool {
+        self.cancelled.contains(&id)
+    }
+
+    /// Returns the ids of the cancelled requests still in flight.
+    pub fn cancelled(&self) -> &HashSet<Integer> {
+        &self.cancelled
+    }
+
+    /// Stops tracking the request `id` once answered, returning `true` if it was
+    /// cancelled meanwhile.
+    pub fn finish(&mut self, id: Integer) -> bool {
+        self.in_flight.remove(&id);
+        self.cancelled.remove(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_cancel_registered_request() {
+        let mut requests = InFlightRequests::default();
+        assert!(requests.register(4));
+
+        requests.cancel(4);
+
+        assert!(requests.cancelled().contains(&4));
+        assert!(requests.is_cancelled(4));
+        assert!(requests.finish(4));
+        assert!(requests.cancelled().is_empty());
+    }
+
+    #[test]
+    fn should_ignore_cancellation_of_unknown_request() {
+        let mut requests = InFlightRequests::default();
+        assert!(requests.register(1));
+
+        requests.cancel(2);
+
+        assert!(!requests.is_cancelled(2));
+        assert!(!requests.finish(1));
+    }
+
+    #[test]
+    fn should_reject_id_in_flight_until_finished() {
+        let mut requests = InFlightRequests::default();
+        assert!(requests.register(3));
+
+        assert!(!requests.register(3));
+        requests.cancel(3);
+        assert!(requests.finish(3));
+        assert!(requests.register(3));
+    }
+}
@@ -5,38 +5,92 @@
 //! responsible for receiving requests and notifications, dispatching them to the
 //! appropriate handlers, and managing the server's state accordingly.
 
+mod debounce;
+mod document_store;
+mod in_flight;
+mod pending;
+mod resolve_cache;
 mod state;
 mod writer;
 
-use crate::lsp::{
-    common::text_document::TextDocumentItemOwned,
-    error::ServerError,
-    notification::{
-        ClientServerNotification, ClientServerNotificationVariant,
-        did_change::DidChangeTextDocumentParams,
-        did_open::DidOpenTextDocumentParams,
-        trace::{LogTraceParams, SetTraceParams, TraceValue},
+use crate::{
+    huml::{self, SourceEdit, emit::EmitOptions},
+    lsp::{
+        capabilities::{
+            client::ClientCapabilities,
+            server::{ServerCapabilities, negotiate_capabilities},
+        },
+        command::{self, Command},
+        common::{
+            diagnostic::Diagnostic,
+            text_document::{Location, TextDocumentItemOwned, TextEdit},
+            uri::{normalize_uri, uri_to_path},
+            workspace_edit::WorkspaceEdit,
+        },
+        error::{CommandError, InitializeError, JsonRpcError, LifecycleError, ServerError},
+        notification::{
+            ClientServerNotification, ClientServerNotificationVariant, ServerClientNotification,
+            did_change::DidChangeTextDocumentParams,
+            did_change_configuration::DidChangeConfigurationParams,
+            did_change_watched_files::DidChangeWatchedFilesParams,
+            did_close::DidCloseTextDocumentParams,
+            did_open::DidOpenTextDocumentParams,
+            did_save::DidSaveTextDocumentParams,
+            publish_diagnostics::PublishDiagnosticsParams,
+            telemetry,
+            trace::{LogTraceParams, SetTraceParams},
+            window::{MessageType, ShowMessageParams},
+        },
+        recieved_message::ClientResponse,
+        request::{
+            ApplyWorkspaceEditParams, ApplyWorkspaceEditResult, CodeActionParams, CodeLensParams,
+            ColorPresentationParams, CompletionParams, DefinitionParams, DocumentColorParams,
+            DocumentFormattingParams, DocumentSymbolParams, ExecuteCommandParams,
+            FoldingRangeParams, HoverParams, InitializeParams, InlayHintParams,
+            PrepareRenameParams, ReferenceParams, Registration, RegistrationParams, RenameParams,
+            Request, RequestMethod, SemanticTokensParams, ServerClientRequestMethod,
+            SignatureHelpParams, WillSaveTextDocumentParams, WorkspaceSymbolParams,
+        },
+        response::{
+            ErrorCode, ResponseMessage, ResponsePayload, ResponseResult,
+            code_action::{CONVERT_INDENTATION, CodeAction, FORMAT_DOCUMENT, QUICK_FIX},
+            code_lens::CodeLens,
+            completion::{CompletionItem, CompletionItemData},
+            document_color::{ColorInformation, ColorPresentation},
+            document_symbol::DocumentSymbol,
+            folding_range::FoldingRange,
+            hover::Hover,
+            initialize::InitializeResult,
+            inlay_hint::InlayHint,
+            prepare_rename::PrepareRenameResult,
+            semantic_tokens::SemanticTokens,
+            signature_help::SignatureHelp,
+            workspace_symbol::SymbolInformation,
+        },
+        server::{
+            resolve_cache::ResolveKey,
+            state::{InitializedServerState, LineSeperatedDocument},
+            writer::{NOTIFICATION_FLUSH_TIMEOUT, initialize_notification_loop},
+        },
+        settings::{PartialServerSettings, ServerSettings, WORKSPACE_SETTINGS_FILE},
     },
-    request::{InitializeParams, Request, RequestMethod},
-    response::{ResponseMessage, ResponsePayload, ResponseResult, initialize::InitializeResult},
-    server::{
-        state::{InitializedServerState, LineSeperatedDocument},
-        writer::initialize_notification_loop,
-    },
-};
-use std::{
-    io::{self, Write},
-    process,
+    rpc::{LSPAny, LspWriter},
 };
+use std::{io, mem, ops::ControlFlow, sync::mpsc, time::Instant};
 
 /// Represents the state of the language server throughout its lifecycle.
 ///
 /// The server transitions through these states based on the LSP lifecycle messages
 /// it receives from the client (e.g., `initialize`, `initialized`, `shutdown`, `exit`).
+// A single server lives for the whole process, so boxing the state wouldn't save memory
+#[allow(clippy::large_enum_variant)]
 pub enum Server {
     /// The initial state of the server before the `initialize` request is received.
     /// In this state, the server can only respond to the `initialize` request.
-    Uninitialized,
+    ///
+    /// It holds the writer reaching the client, which notifications are sent with once
+    /// the server is initialized, and the channel failures to write them are sent to.
+    Uninitialized(LspWriter, mpsc::Sender<io::Error>),
     /// The state after the server has successfully responded to an `initialize` request.
     /// It holds the server's state, including client capabilities and trace settings.
     Initialized(InitializedServerState),
@@ -48,9 +102,14 @@ pub enum Server {
 
 // Generic functions related to server
 impl Server {
-    /// Creates a new server in the `Uninitialized` state.
-    pub fn new() -> Self {
-        Self::Uninitialized
+    /// Creates a new server in the `Uninitialized` state, reaching the client with
+    /// `writer`. The responses to requests must be sent with the same writer, or a clone
+    /// of it, so that they don't interleave with notifications.
+    ///
+    /// Notifications are written from a thread of their own. If writing them fails, the
+    /// client can't be reached anymore, and the error is sent to `write_failures`.
+    pub fn new(writer: LspWriter, write_failures: mpsc::Sender<io::Error>) -> Self {
+        Self::Uninitialized(writer, write_failures)
     }
 
     /// Returns an immutable reference to the initialized server state, if available.
@@ -77,6 +136,52 @@ impl Server {
         }
     }
 
+    /// Returns the initialized server state, failing if the server isn't initialized, as
+    /// requests other than `initialize` require it.
+    fn require_initialized(&self) -> Result<&InitializedServerState, LifecycleError> {
+        match self {
+            Self::Initialized(state) => Ok(state),
+            Self::Uninitialized(..) => Err(LifecycleError::NotInitialized),
+            Self::Shutdown => Err(LifecycleError::ShutDown),
+        }
+    }
+
+    /// Returns the initialized server state for update, failing if the server isn't
+    /// initialized.
+    fn require_initialized_mut(&mut self) -> Result<&mut InitializedServerState, LifecycleError> {
+        match self {
+            Self::Initialized(state) => Ok(state),
+            Self::Uninitialized(..) => Err(LifecycleError::NotInitialized),
+            Self::Shutdown => Err(LifecycleError::ShutDown),
+        }
+    }
+
+    /// Returns the capabilities advertised to the client in the response to `initialize`.
+    ///
+    /// # Panics
+    /// Panics if the server is not [`Initialized`].
+    ///
+    /// [`Initialized`]: Server::Initialized
+    pub fn capabilities(&self) -> &ServerCapabilities {
+        &self
+            .as_initialized()
+            .expect("Capabilities are only negotiated once the server is initialized")
+            .capabilities
+    }
+
+    /// Returns the capabilities the client declared in the `initialize` request.
+    ///
+    /// # Panics
+    /// Panics if the server is not [`Initialized`].
+    ///
+    /// [`Initialized`]: Server::Initialized
+    pub fn client_capabilities(&self) -> &ClientCapabilities {
+        &self
+            .as_initialized()
+            .expect("Client capabilities are only known once the server is initialized")
+            .client_capabilities
+    }
+
     /// Returns `true` if the server is [`Initialized`].
     ///
     /// [`Initialized`]: Server::Initialized
@@ -93,63 +198,743 @@ impl Server {
     /// This method transitions the server from the `Uninitialized` state to the `Initialized`
     /// state. It sets up the notification writer, stores client capabilities, and prepares
     /// the server for further communication. It returns an error if called more than once.
-    fn handle_initialize_req(&mut self, params: &InitializeParams) -> ResponsePayload {
-        use ResponsePayload::*;
-        if matches!(self, Server::Initialized { .. }) {
-            return Error {
-                // TODO: Standardize errors
-                // As per LSP spec: "Using the initialize request a second time..."
-                // "...is diagnosed as an error with a predefined code."
-                code: -32002, // ServerErrorCodes::ServerNotInitialized
-                message: "Server is already initialized".to_string(),
-                data: None,
-            };
-        }
+    fn handle_initialize_req(
+        &mut self,
+        params: &InitializeParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        // A repeated initialize is an invalid request. The existing state is kept as is.
+        let (writer, write_failures) = match self {
+            Self::Uninitialized(writer, write_failures) => (writer.clone(), write_failures.clone()),
+            Self::Initialized(_) => return Err(InitializeError::AlreadyInitialized.into()),
+            Self::Shutdown => return Err(InitializeError::ShutDown.into()),
+        };
 
-        // Initialize notification writer
-        let notification_sender = initialize_notification_loop(|msg| {
-            let mut writer = io::stdout().lock();
-            write!(writer, "{msg}")?;
-            writer.flush()?;
-            Ok(())
-        });
+        // Initialize notification writer. Without an output to reach the client there is
+        // nothing left to serve, which the owner of the server is told about.
+        let (notification_sender, notification_thread) =
+            initialize_notification_loop(writer.clone(), move |e| {
+                // The owner may have stopped serving already
+                let _ = write_failures.send(e);
+            });
 
-        *self = Server::Initialized(InitializedServerState {
-            _client_capabilities: params.capabilities().clone(),
-            is_client_initialized: false,
-            trace: TraceValue::Off,
-            notification_sender,
-            documents: vec![],
-        });
+        let mut state =
+            InitializedServerState::new(params.capabilities().clone(), notification_sender);
+        state.notification_thread = Some(notification_thread);
+        state.writer = Some(writer);
+        state.workspace_folders = params
+            .workspace_uris()
+            .into_iter()
+            .filter_map(uri_to_path)
+            .collect();
+        let initialization_options = params
+            .initialization_options()
+            .map(PartialServerSettings::from_json)
+            .transpose();
+        *self = Server::Initialized(state);
+
+        match initialization_options {
+            Ok(options) => {
+                let state = self
+                    .as_mut_initialized()
+                    .expect("Server was just initialized");
+                state.initialization_options = options.unwrap_or_default();
+            }
+            Err(e) => self.log_message(format!("Ignoring initializationOptions: {e}"), None),
+        }
+        self.reload_settings();
+
+        let state = self
+            .as_mut_initialized()
+            .expect("Server was just initialized");
+        state.capabilities = negotiate_capabilities(&state.client_capabilities, &state.settings);
+        let capabilities = state.capabilities.clone();
 
         self.log_message(
             "Server initialized. Waiting for client initialized ack".to_string(),
             None,
         );
 
-        InitializeResult::default().into()
+        Ok(InitializeResult::new(capabilities).into())
     }
 
     /// Handles the `shutdown` request from the client.
     ///
     /// This method transitions the server to the `Shutdown` state, preparing it
     /// to terminate upon receiving an `exit` notification.
-    fn handle_shutdown_req(&mut self) -> ResponsePayload {
-        *self = Server::Shutdown;
-        ResponsePayload::Result(ResponseResult::Shutdown)
+    fn handle_shutdown_req(&mut self) -> Result<ResponsePayload, JsonRpcError> {
+        self.shut_down();
+        Ok(ResponsePayload::Result(ResponseResult::Shutdown))
+    }
+
+    /// Transitions the server to the `Shutdown` state, once the notifications sent so far
+    /// are written or [`NOTIFICATION_FLUSH_TIMEOUT`] is elapsed, e.g. once the client
+    /// disconnected without sending `exit`.
+    pub fn shut_down(&mut self) {
+        if let Self::Initialized(state) = mem::replace(self, Self::Shutdown) {
+            state.close(NOTIFICATION_FLUSH_TIMEOUT);
+        }
+    }
+
+    /// Handles the `exit` notification, returning the [exit code] of the process once
+    /// the notifications sent so far are written.
+    ///
+    /// [exit code]: Server::exit_code
+    fn handle_exit(&mut self) -> u8 {
+        let code = self.exit_code();
+        self.shut_down();
+        code
+    }
+
+    /// Returns the code the process exits with on `exit`: `0` if the client asked for a
+    /// shutdown first, `1` otherwise, as required by the specification.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Shutdown => 0,
+            Self::Uninitialized(..) | Self::Initialized(_) => 1,
+        }
+    }
+
+    /// Handles the `textDocument/documentSymbol` request.
+    ///
+    /// Returns the outline of the document, built from its syntax tree. Documents that
+    /// aren't open have an empty outline.
+    fn handle_document_symbol_req(
+        &self,
+        params: &DocumentSymbolParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let symbols = state
+            .documents
+            .get(params.text_document().uri())
+            .map(|document| {
+                DocumentSymbol::from_document(
+                    &document.parsed().document,
+                    document.full_document().text(),
+                    state.capabilities.position_encoding(),
+                )
+            })
+            .unwrap_or_default();
+
+        Ok(ResponseResult::DocumentSymbol(symbols).into())
+    }
+
+    /// Handles the `textDocument/formatting` request.
+    ///
+    /// Replaces the whole document with its canonical form, indented by the requested
+    /// tab size. No edits are returned if the document is already formatted, or if it
+    /// can't be formatted, e.g. because it has syntax errors.
+    fn handle_formatting_req(
+        &self,
+        params: &DocumentFormattingParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let options = EmitOptions {
+            indent_width: params.options().tab_size(),
+            ..EmitOptions::default()
+        };
+        let edits = state
+            .documents
+            .get(params.text_document().uri())
+            .and_then(|document| {
+                document.formatting_edit(&options, state.capabilities.position_encoding())
+            })
+            .into_iter()
+            .collect();
+
+        Ok(ResponseResult::Formatting(edits).into())
+    }
+
+    /// Handles the `textDocument/willSaveWaitUntil` request.
+    ///
+    /// Formats the document before it's saved, like `textDocument/formatting` with the
+    /// indent width of the settings. No edits are returned if the client wasn't told to
+    /// send the request, or if the document has syntax errors.
+    fn handle_will_save_wait_until_req(
+        &self,
+        params: &WillSaveTextDocumentParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+        if !state.capabilities.will_save_wait_until() {
+            return Ok(ResponseResult::WillSaveWaitUntil(vec![]).into());
+        }
+
+        let options = EmitOptions {
+            indent_width: state.settings.indent_width(),
+            ..EmitOptions::default()
+        };
+        let edits = state
+            .documents
+            .get(params.text_document().uri())
+            .and_then(|document| {
+                document.formatting_edit(&options, state.capabilities.position_encoding())
+            })
+            .into_iter()
+            .collect();
+
+        Ok(ResponseResult::WillSaveWaitUntil(edits).into())
+    }
+
+    /// Handles the `textDocument/completion` request.
+    ///
+    /// Documents that aren't open have no completion items.
+    fn handle_completion_req(
+        &self,
+        params: &CompletionParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let items = state
+            .documents
+            .get(params.text_document().uri())
+            .and_then(|document| {
+                let position = params.position();
+                let column =
+                    document.byte_column(position, state.capabilities.position_encoding())?;
+                let parsed = document.parsed();
+                let document = document.full_document();
+                let data = CompletionItemData::new(document.uri().to_string(), document.version());
+                Some(CompletionItem::at_position(
+                    &parsed.document,
+                    document.text(),
+                    position.line(),
+                    column,
+                    &data,
+                ))
+            })
+            .unwrap_or_default();
+
+        Ok(ResponseResult::Completion(items).into())
+    }
+
+    /// Handles the `completionItem/resolve` request.
+    ///
+    /// Fills in the documentation of key items, which is cached until the document
+    /// changes. Items built for an outdated version of the document are returned as is.
+    fn handle_completion_resolve_req(
+        &mut self,
+        item: &CompletionItem,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let InitializedServerState {
+            documents,
+            resolve_cache,
+            ..
+        } = self.require_initialized_mut()?;
+
+        let mut item = item.clone();
+        let document = item.data().and_then(|data| {
+            documents
+                .get(data.uri())
+                .filter(|document| document.full_document().version() == data.version())
+        });
+        if let Some(document) = document {
+            let text_document = document.full_document();
+            let key = ResolveKey {
+                uri: text_document.uri().to_string(),
+                version: text_document.version(),
+                label: item.label().to_string(),
+            };
+            let documentation = resolve_cache.get_or_insert_with(key, || {
+                CompletionItem::key_documentation(
+                    &document.parsed().document,
+                    text_document.text(),
+                    item.label(),
+                )
+            });
+            item.set_documentation(documentation.clone());
+        }
+
+        Ok(ResponseResult::CompletionResolve(item).into())
+    }
+
+    /// Handles the `textDocument/hover` request.
+    fn handle_hover_req(&self, params: &HoverParams) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let hover = state
+            .documents
+            .get(params.text_document().uri())
+            .and_then(|document| {
+                let position = params.position();
+                let column =
+                    document.byte_column(position, state.capabilities.position_encoding())?;
+                Hover::at(
+                    document.full_document().text(),
+                    &document.parsed().document,
+                    position.line(),
+                    column,
+                    state.capabilities.position_encoding(),
+                )
+            });
+
+        Ok(ResponseResult::Hover(hover).into())
+    }
+
+    /// Handles the `textDocument/foldingRange` request.
+    fn handle_folding_range_req(
+        &self,
+        params: &FoldingRangeParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let ranges = state
+            .documents
+            .get(params.text_document().uri())
+            .map(|document| FoldingRange::from_document(&document.parsed().document))
+            .unwrap_or_default();
+
+        Ok(ResponseResult::FoldingRange(ranges).into())
+    }
+
+    /// Handles the `textDocument/inlayHint` request.
+    fn handle_inlay_hint_req(
+        &self,
+        params: &InlayHintParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let hints = state
+            .documents
+            .get(params.text_document().uri())
+            .map(|document| {
+                InlayHint::in_range(
+                    document.full_document().text(),
+                    &document.parsed().document,
+                    params.range(),
+                    state.capabilities.position_encoding(),
+                )
+            })
+            .unwrap_or_default();
+
+        Ok(ResponseResult::InlayHint(hints).into())
+    }
+
+    /// Handles the `textDocument/definition` request. The definition of a key is the first
+    /// key declared with the same path, so that repeated keys lead to their first
+    /// occurrence.
+    fn handle_definition_req(
+        &self,
+        params: &DefinitionParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let uri = params.text_document().uri();
+        let location = state.documents.get(uri).and_then(|document| {
+            let position = params.position();
+            let column = document.byte_column(position, state.capabilities.position_encoding())?;
+            let key = document
+                .parsed()
+                .document
+                .first_definition_at(position.line(), column)?;
+            let range = document.span_to_range(key.span, state.capabilities.position_encoding());
+            Some(Location::new(uri.to_string(), range))
+        });
+
+        Ok(ResponseResult::Definition(location).into())
+    }
+
+    /// Handles the `textDocument/references` request.
+    ///
+    /// Returns every key declared with the same path as the key at the position, the
+    /// first of them being its declaration.
+    fn handle_references_req(
+        &self,
+        params: &ReferenceParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let uri = params.text_document().uri();
+        let locations = state
+            .documents
+            .get(uri)
+            .and_then(|document| {
+                let encoding = state.capabilities.position_encoding();
+                let position = params.position();
+                let column = document.byte_column(position, encoding)?;
+                let occurrences = document
+                    .parsed()
+                    .document
+                    .occurrences_at(position.line(), column);
+                let skipped = usize::from(!params.context().include_declaration());
+                let locations = occurrences
+                    .into_iter()
+                    .skip(skipped)
+                    .map(|key| {
+                        Location::new(uri.to_string(), document.span_to_range(key.span, encoding))
+                    })
+                    .collect();
+                Some(locations)
+            })
+            .unwrap_or_default();
+
+        Ok(ResponseResult::References(locations).into())
+    }
+
+    /// Handles the `textDocument/signatureHelp` request.
+    ///
+    /// Describes the value expected by the schema for the key being written, if a schema
+    /// is configured.
+    fn handle_signature_help_req(
+        &self,
+        params: &SignatureHelpParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let help = state.settings.schema().and_then(|schema| {
+            let document = state.documents.get(params.text_document().uri())?;
+            let position = params.position();
+            let column = document.byte_column(position, state.capabilities.position_encoding())?;
+            let text = document.full_document().text();
+            SignatureHelp::at(schema, text, position.line(), column)
+        });
+
+        Ok(ResponseResult::SignatureHelp(help).into())
+    }
+
+    /// Handles the `workspace/symbol` request.
+    ///
+    /// Returns the keys of the open documents matching the query, in the order the
+    /// documents were opened.
+    fn handle_workspace_symbol_req(
+        &self,
+        params: &WorkspaceSymbolParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let symbols = state
+            .documents
+            .iter()
+            .flat_map(|document| {
+                let full_document = document.full_document();
+                SymbolInformation::matching(
+                    &document.parsed().document,
+                    full_document.text(),
+                    full_document.uri(),
+                    params.query(),
+                    state.capabilities.position_encoding(),
+                )
+            })
+            .collect();
+
+        Ok(ResponseResult::WorkspaceSymbol(symbols).into())
+    }
+
+    /// Handles the `textDocument/documentColor` request.
+    fn handle_document_color_req(
+        &self,
+        params: &DocumentColorParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let colors = state
+            .documents
+            .get(params.text_document().uri())
+            .map(|document| {
+                ColorInformation::from_document(
+                    &document.parsed().document,
+                    document.full_document().text(),
+                    state.capabilities.position_encoding(),
+                )
+            })
+            .unwrap_or_default();
+
+        Ok(ResponseResult::DocumentColor(colors).into())
+    }
+
+    /// Handles the `textDocument/colorPresentation` request.
+    fn handle_color_presentation_req(
+        &self,
+        params: &ColorPresentationParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        self.require_initialized()?;
+
+        let presentations = ColorPresentation::for_color(params.color(), params.range());
+        Ok(ResponseResult::ColorPresentation(presentations).into())
+    }
+
+    /// Handles the `textDocument/codeLens` request.
+    fn handle_code_lens_req(
+        &self,
+        params: &CodeLensParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let lenses = state
+            .documents
+            .get(params.text_document().uri())
+            .map(|document| {
+                CodeLens::from_document(
+                    &document.parsed().document,
+                    document.full_document().text(),
+                    state.capabilities.position_encoding(),
+                )
+            })
+            .unwrap_or_default();
+
+        Ok(ResponseResult::CodeLens(lenses).into())
+    }
+
+    /// Handles the `textDocument/semanticTokens/full` request.
+    fn handle_semantic_tokens_req(
+        &self,
+        params: &SemanticTokensParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let tokens = state
+            .documents
+            .get(params.text_document().uri())
+            .map(|document| {
+                let source = document.full_document().text();
+                SemanticTokens::from_document(
+                    source,
+                    &document.parsed().document,
+                    state.capabilities.position_encoding(),
+                )
+            })
+            .unwrap_or_default();
+
+        Ok(ResponseResult::SemanticTokens(tokens).into())
+    }
+
+    /// Handles the `textDocument/codeAction` request.
+    ///
+    /// The document can always be formatted, with no edit if it's already formatted or
+    /// has syntax errors. The diagnostics of the range sent by the client are fixed with
+    /// the fix they carry, if any.
+    fn handle_code_action_req(
+        &self,
+        params: &CodeActionParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let uri = params.text_document().uri();
+        let context = params.context();
+        let mut actions = vec![];
+        if context.is_requested(QUICK_FIX) {
+            actions.extend(CodeAction::quick_fixes(
+                uri,
+                params.range(),
+                context.diagnostics(),
+            ));
+        }
+        if let Some(document) = state.documents.get(uri)
+            && context.is_requested(FORMAT_DOCUMENT)
+        {
+            let options = EmitOptions {
+                indent_width: state.settings.indent_width(),
+                ..EmitOptions::default()
+            };
+            let edits = document
+                .formatting_edit(&options, state.capabilities.position_encoding())
+                .into_iter()
+                .collect();
+            actions.push(CodeAction::format_document(uri, edits));
+        }
+        if let Some(document) = state.documents.get(uri)
+            && context.is_requested(CONVERT_INDENTATION)
+        {
+            let edits = document.indentation_edits(
+                state.settings.indent_width(),
+                state.capabilities.position_encoding(),
+            );
+            if !edits.is_empty() {
+                actions.push(CodeAction::convert_indentation(uri, edits));
+            }
+        }
+
+        Ok(ResponseResult::CodeAction(actions).into())
+    }
+
+    /// Handles the `workspace/executeCommand` request, running one of the [`Command`]s.
+    ///
+    /// The converted text is returned. When converting an open document, the client is
+    /// also asked to replace the text of the document with it, if it supports
+    /// `workspace/applyEdit`.
+    ///
+    /// Fails with [`ErrorCode::InvalidParams`] if the command is unknown or can't run with
+    /// the arguments it's given.
+    fn handle_execute_command_req(
+        &mut self,
+        params: &ExecuteCommandParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+        let mut replaced_document = None;
+
+        let command = Command::from_name(params.command())?;
+        let argument = command.string_argument(params.arguments())?;
+        let result = match command {
+            Command::ConvertFromJson => {
+                let options = EmitOptions {
+                    indent_width: state.settings.indent_width(),
+                    ..EmitOptions::default()
+                };
+                command::convert_from_json(argument, &options)?
+            }
+            Command::ConvertToJson => {
+                let document = state
+                    .documents
+                    .get(argument)
+                    .ok_or_else(|| CommandError::UnknownDocument(argument.to_string()))?;
+                let json = command::convert_to_json(document.parsed())?;
+                replaced_document = Some((
+                    document.full_document().uri().to_string(),
+                    document.full_range(state.capabilities.position_encoding()),
+                ));
+                json
+            }
+        };
+
+        let supports_apply_edit = state
+            .client_capabilities
+            .workspace()
+            .is_some_and(|workspace| workspace.apply_edit());
+        if let Some((uri, range)) = replaced_document.filter(|_| supports_apply_edit) {
+            let edit =
+                WorkspaceEdit::default().with_edits(uri, [TextEdit::new(range, result.clone())]);
+            let params = ApplyWorkspaceEditParams::new(Some("Convert to JSON".to_string()), edit);
+            self.require_initialized_mut()?
+                .send_request(ServerClientRequestMethod::ApplyEdit(params));
+        }
+
+        Ok(ResponseResult::ExecuteCommand(Some(LSPAny::String(result))).into())
+    }
+
+    /// Handles the `textDocument/prepareRename` request. Only keys can be renamed.
+    fn handle_prepare_rename_req(
+        &self,
+        params: &PrepareRenameParams,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        let result = state
+            .documents
+            .get(params.text_document().uri())
+            .and_then(|document| {
+                let position = params.position();
+                let column =
+                    document.byte_column(position, state.capabilities.position_encoding())?;
+                let source = document.full_document().text();
+                PrepareRenameResult::at(
+                    source,
+                    &document.parsed().document,
+                    position.line(),
+                    column,
+                    state.capabilities.position_encoding(),
+                )
+            });
+
+        Ok(ResponseResult::PrepareRename(result).into())
+    }
+
+    /// Handles the `textDocument/rename` request, renaming the key at the position.
+    ///
+    /// Only the key at the position is renamed, not the other places referring to it.
+    /// Fails with [`ErrorCode::InvalidParams`] if the new name isn't a valid key.
+    fn handle_rename_req(&self, params: &RenameParams) -> Result<ResponsePayload, JsonRpcError> {
+        let state = self.require_initialized()?;
+
+        if !huml::is_key(params.new_name()) {
+            return Err(JsonRpcError::new(
+                ErrorCode::InvalidParams,
+                format!("`{}` isn't a valid key", params.new_name()),
+            ));
+        }
+
+        let uri = params.text_document().uri();
+        let edit = state.documents.get(uri).and_then(|document| {
+            let position = params.position();
+            let encoding = state.capabilities.position_encoding();
+            let column = document.byte_column(position, encoding)?;
+            let key = document.parsed().document.key_at(position.line(), column)?;
+            let range = document.span_to_range(key.span, encoding);
+            let edit = TextEdit::new(range, params.new_name().to_string());
+            Some(WorkspaceEdit::default().with_edits(uri.to_string(), [edit]))
+        });
+
+        Ok(ResponseResult::Rename(edit).into())
     }
 
     /// The main entry point for dispatching all incoming requests from the client.
     ///
     /// It takes a `Request` and routes it to the appropriate handler based on its method.
     /// It returns a `ResponseMessage` to be sent back to the client.
-    pub fn handle_request<'a>(&mut self, req: &'a Request) -> Result<ResponseMessage, ServerError> {
-        let response_payload = match req.method() {
-            RequestMethod::Initialize(params) => self.handle_initialize_req(params),
-            RequestMethod::Shutdown => self.handle_shutdown_req(),
+    ///
+    /// A request reusing the id of a request still in flight fails with
+    /// [`ServerError::DuplicateRequestId`], leaving the request in flight as is.
+    pub fn handle_request(&mut self, req: &Request) -> Result<ResponseMessage, ServerError> {
+        if let Some(state) = self.as_mut_initialized()
+            && !state.requests.register(req.id())
+        {
+            return Err(ServerError::DuplicateRequestId(req.id()));
+        }
+
+        let response_payload = self
+            .dispatch_request(req.method())
+            .unwrap_or_else(ResponsePayload::from);
+
+        let is_cancelled = self
+            .as_mut_initialized()
+            .is_some_and(|state| state.requests.finish(req.id()));
+        let response_payload = if is_cancelled {
+            JsonRpcError::new(
+                ErrorCode::RequestCancelled,
+                "Request cancelled by the client",
+            )
+            .into()
+        } else {
+            response_payload
         };
         Ok(ResponseMessage::new_for(req, response_payload))
     }
+
+    /// Routes the request for `method` to its handler.
+    ///
+    /// Only `initialize` is handled before the server is initialized, and nothing is
+    /// handled once it's shut down. Unknown methods are never handled.
+    fn dispatch_request(
+        &mut self,
+        method: &RequestMethod,
+    ) -> Result<ResponsePayload, JsonRpcError> {
+        if !matches!(
+            method,
+            RequestMethod::Initialize(_) | RequestMethod::Unknown
+        ) {
+            self.require_initialized()?;
+        }
+
+        match method {
+            RequestMethod::Initialize(params) => self.handle_initialize_req(params),
+            RequestMethod::Shutdown => self.handle_shutdown_req(),
+            RequestMethod::DocumentSymbol(params) => self.handle_document_symbol_req(params),
+            RequestMethod::Formatting(params) => self.handle_formatting_req(params),
+            RequestMethod::Completion(params) => self.handle_completion_req(params),
+            RequestMethod::CompletionResolve(item) => self.handle_completion_resolve_req(item),
+            RequestMethod::Hover(params) => self.handle_hover_req(params),
+            RequestMethod::FoldingRange(params) => self.handle_folding_range_req(params),
+            RequestMethod::InlayHint(params) => self.handle_inlay_hint_req(params),
+            RequestMethod::Definition(params) => self.handle_definition_req(params),
+            RequestMethod::References(params) => self.handle_references_req(params),
+            RequestMethod::SignatureHelp(params) => self.handle_signature_help_req(params),
+            RequestMethod::WorkspaceSymbol(params) => self.handle_workspace_symbol_req(params),
+            RequestMethod::CodeLens(params) => self.handle_code_lens_req(params),
+            RequestMethod::DocumentColor(params) => self.handle_document_color_req(params),
+            RequestMethod::ColorPresentation(params) => self.handle_color_presentation_req(params),
+            RequestMethod::SemanticTokensFull(params) => self.handle_semantic_tokens_req(params),
+            RequestMethod::Rename(params) => self.handle_rename_req(params),
+            RequestMethod::PrepareRename(params) => self.handle_prepare_rename_req(params),
+            RequestMethod::ExecuteCommand(params) => self.handle_execute_command_req(params),
+            RequestMethod::CodeAction(params) => self.handle_code_action_req(params),
+            RequestMethod::WillSaveWaitUntil(params) => {
+                self.handle_will_save_wait_until_req(params)
+            }
+            RequestMethod::Unknown => Err(JsonRpcError::new(
+                ErrorCode::MethodNotFound,
+                "Unknown method",
+            )),
+        }
+    }
 }
 
 // Notification related methods
@@ -157,35 +942,34 @@ impl Server {
     /// Handles the `initialized` notification from the client.
     ///
     /// This notification confirms that the client has successfully processed the
-    /// `initialize` response.
+    /// `initialize` response. The notifications held back until then are sent, and the
+    /// files the settings depend on are registered to be watched.
     fn handle_initialized_notification(&mut self) {
-        match self {
-            Server::Uninitialized => panic!(
-                "Received initialized notification before the initialize request. Server not yet initialized"
-            ),
-            Server::Initialized(InitializedServerState {
-                is_client_initialized,
-                ..
-            }) => *is_client_initialized = false,
-            _ => (),
+        if let Server::Initialized(state) = self {
+            state.mark_client_initialized();
+            register_watched_files(state);
         }
     }
 
     /// Handles the [`$/setTrace`] notification to adjust the server's logging verbosity.
     ///
+    /// The level is read by [`Server::log_message`] whenever a trace is logged, so it
+    /// applies to every trace logged from then on.
+    ///
     /// [`$/setTrace`]: crate::lsp::notification::ClientServerNotification::SetTrace
     fn handle_set_trace(&mut self, params: SetTraceParams) {
-        match self {
-            Self::Initialized(InitializedServerState { trace, .. }) => {
-                *trace = params.value();
-            }
-            _ => panic!("Cannot set trace level when server not initialized"),
+        if let Self::Initialized(InitializedServerState { trace, .. }) = self {
+            *trace = params.value();
         }
     }
 
     /// Handles the `textDocument/didOpen` notification
     pub fn handle_did_open(&mut self, params: DidOpenTextDocumentParams) {
+        if self.as_initialized().is_none() {
+            return;
+        }
         let opened_document_item: TextDocumentItemOwned = params.into_text_document();
+        let opened_uri = opened_document_item.uri().to_string();
 
         #[cfg(debug_assertions)]
         {
@@ -195,99 +979,435 @@ impl Server {
             self.log_message(log_message, Some(log_verbose));
         }
 
-        match self {
-            Self::Initialized(InitializedServerState { documents, .. }) => {
-                // Replace document if already exists
-                let existing_doc_position = documents
-                    .iter()
-                    .position(|doc| doc.borrow_full_document().uri() == opened_document_item.uri());
-
-                let line_seperated_docuemnt = LineSeperatedDocument::from(opened_document_item);
-                match existing_doc_position {
-                    Some(idx) => documents[idx] = line_seperated_docuemnt,
-                    None => documents.push(line_seperated_docuemnt),
-                };
-            }
-            _ => panic!("Cannot handle text document notifications when server not initialized"),
+        let Some(state) = self.as_mut_initialized() else {
+            return;
+        };
+        state.resolve_cache.invalidate(opened_document_item.uri());
+        // Replaces the document if it's already open
+        state.documents.open(opened_document_item);
+
+        self.publish_diagnostics(&opened_uri);
+    }
+
+    /// Handles the `textDocument/didClose` notification
+    ///
+    /// Forgets the document, and clears its diagnostics, as the client now reports the
+    /// problems of the file on disk, if any.
+    pub fn handle_did_close(&mut self, params: DidCloseTextDocumentParams) {
+        let Some(state) = self.as_mut_initialized() else {
+            return;
+        };
+        let uri = params.text_document().uri();
+        state.resolve_cache.invalidate(uri);
+        state.oversized_documents.remove(&normalize_uri(uri));
+        state.cancel_scheduled_diagnostics(uri);
+
+        if let Some(document) = state.documents.remove(uri) {
+            let uri = document.full_document().uri().to_string();
+            state.notify(PublishDiagnosticsParams::new(uri, None, vec![]).into());
+        }
+    }
+
+    /// Handles the `textDocument/didSave` notification
+    ///
+    /// Replaces the stored document with the saved content, if the client sent it and it
+    /// drifted from the document we know of, which brings the document back in sync with
+    /// the client's copy. Then publishes fresh diagnostics, as they may depend on more than
+    /// the document, e.g. the settings files.
+    pub fn handle_did_save(&mut self, params: DidSaveTextDocumentParams) {
+        let Some(state) = self.as_mut_initialized() else {
+            return;
+        };
+        let uri = params.text_document().uri();
+
+        if let Some(text) = params.text()
+            && let Some(document) = state.documents.get(uri)
+            && (document.needs_full_sync() || document.full_document().text() != text)
+        {
+            let (document_uri, language_id, version, _) =
+                document.full_document().clone().into_parts();
+            let saved =
+                TextDocumentItemOwned::new(document_uri, language_id, version, text.to_string());
+            state.resolve_cache.invalidate(uri);
+            state.documents.update(uri, saved.into());
         }
+
+        self.publish_diagnostics(uri);
     }
 
     /// Handles the `textDocument/didChange` notification
+    ///
+    /// Ranged changes are only applied if the document is in sync with the client's copy:
+    /// if a change fails to apply, or the version of the changes doesn't follow the one of
+    /// the document, the document is marked as needing a full sync. It then ignores ranged
+    /// changes, and isn't validated, until a change replaces its whole text.
     pub fn handle_did_change(&mut self, params: DidChangeTextDocumentParams) {
-        let InitializedServerState { documents, .. } = self
-            .as_mut_initialized()
-            .expect("Cannot handle text document notifications when server not initialized");
+        let Some(InitializedServerState {
+            documents,
+            resolve_cache,
+            capabilities,
+            ..
+        }) = self.as_mut_initialized()
+        else {
+            return;
+        };
+        resolve_cache.invalidate(params.text_document().uri());
 
         // Update document if exists
-        let Some(document_lines) = documents
-            .iter_mut()
-            .find(|doc| doc.borrow_full_document().uri() == params.text_document().uri())
-        else {
+        let Some(document_lines) = documents.get(params.text_document().uri()) else {
             return;
         };
 
         // Metadata required for constructing the new TextDocumentItemOwned object
-        let (uri, language_id, ..) = document_lines.borrow_full_document().clone().into_parts();
+        let (uri, language_id, ..) = document_lines.full_document().clone().into_parts();
         let updated_version = params.text_document().version();
 
-        let change_diff: Vec<_> = params
-            .content_changes()
-            .iter()
-            .filter_map(|change| {
-                let range_opt = change.range();
-                let text = change.text();
-                range_opt.map(|range| (range, text))
-            })
-            .collect();
-
-        let diff_applied_text_document = document_lines.apply_diff_to_document(&change_diff);
-
-        let updated_text_document_item = TextDocumentItemOwned::new(
-            uri.to_string(),
-            language_id.to_string(),
-            updated_version,
-            diff_applied_text_document,
-        );
-        *document_lines = LineSeperatedDocument::from(updated_text_document_item);
+        // The ranged changes of a version not following the one of the document were made
+        // to a text we don't know of
+        let was_in_sync = !document_lines.needs_full_sync();
+        let expected_version = document_lines.expected_next_version();
+        let mut desync = match was_in_sync && updated_version != expected_version {
+            true => Some(format!(
+                "expected version {expected_version}, got {updated_version}"
+            )),
+            false => None,
+        };
 
-        #[cfg(debug_assertions)]
-        {
-            let document = document_lines.borrow_full_document();
-            let updated_text = document.text();
-            // Send log with the updated document state
-            let log_verbose = format!("{updated_text}");
-            let log_message = format!("updated document {uri}");
-            self.log_message(log_message, Some(log_verbose));
+        // The changes apply one after the other. A change without a range replaces the
+        // whole document, as sent by clients syncing documents in full.
+        let encoding = capabilities.position_encoding();
+        let mut in_sync = was_in_sync && desync.is_none();
+        let mut updated_document = None;
+        for change in params.content_changes() {
+            let current_document = updated_document.as_ref().unwrap_or(document_lines);
+            let updated_text = match change.range() {
+                None => Ok(change.text().to_string()),
+                // Ignored rather than corrupting the document further
+                Some(_) if !in_sync => continue,
+                Some(range) => {
+                    current_document.apply_diff_to_document(&[(range, change.text())], encoding)
+                }
+            };
+            let updated_text = match updated_text {
+                Ok(text) => text,
+                Err(e) => {
+                    // The edit can't be applied to the document we know of
+                    desync = Some(e.to_string());
+                    in_sync = false;
+                    continue;
+                }
+            };
+            in_sync = true;
+            let updated_item = TextDocumentItemOwned::new(
+                uri.to_string(),
+                language_id.to_string(),
+                updated_version,
+                updated_text,
+            );
+            updated_document = Some(match change.range() {
+                None => LineSeperatedDocument::from(updated_item),
+                // Only the edited part of the syntax tree is parsed again
+                Some(range) => {
+                    let start = current_document.position_to_offset(range.start(), encoding);
+                    let edit = SourceEdit {
+                        start,
+                        old_end: current_document.position_to_offset(range.end(), encoding),
+                        new_end: start + change.text().len(),
+                    };
+                    LineSeperatedDocument::edited(current_document, updated_item, edit)
+                }
+            });
         }
-    }
+
+        if !in_sync {
+            if let Some(document) = documents.get_mut(&uri) {
+                document.mark_needs_full_sync();
+            }
+            match desync {
+                Some(reason) => self.show_error(format!(
+                    "Ignoring change to {uri}: {reason}. Reopen the document to resynchronize it."
+                )),
+                None => self.log_message(
+                    format!("Ignoring change to {uri}, which needs a full sync"),
+                    None,
+                ),
+            }
+            return;
+        }
+        let Some(updated_document) = updated_document else {
+            return;
+        };
+        // Send log with the updated document state
+        #[cfg(debug_assertions)]
+        let log_verbose = updated_document.full_document().text().to_string();
+        documents.update(&uri, updated_document);
+
+        #[cfg(debug_assertions)]
+        {
+            let log_message = format!("updated document {uri}");
+            self.log_message(log_message, Some(log_verbose));
+        }
+
+        self.schedule_diagnostics(&uri);
+    }
+
+    /// Handles the `workspace/didChangeWatchedFiles` notification
+    ///
+    /// Reloads the settings if the [`WORKSPACE_SETTINGS_FILE`] of any workspace folder, or
+    /// the schema file, was created, changed or deleted. The open documents are then
+    /// validated again, as their diagnostics depend on the settings.
+    fn handle_did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) {
+        let Some(InitializedServerState {
+            workspace_folders,
+            settings,
+            ..
+        }) = self.as_initialized()
+        else {
+            return;
+        };
+
+        let is_settings_file_changed = params
+            .changes()
+            .iter()
+            .filter_map(|change| uri_to_path(change.uri()))
+            .any(|path| {
+                settings.schema_file() == Some(path.as_path())
+                    || workspace_folders
+                        .iter()
+                        .any(|folder| path == folder.join(WORKSPACE_SETTINGS_FILE))
+            });
+
+        if is_settings_file_changed {
+            self.reload_settings();
+            self.publish_all_diagnostics();
+        }
+    }
+
+    /// Handles the `workspace/didChangeConfiguration` notification
+    ///
+    /// Replaces the settings previously sent by the client with the `huml` section of its
+    /// settings. Settings missing from the section keep the value resolved from the other
+    /// sources, and the open documents are validated again with the new settings. Invalid
+    /// settings are reported to the user and ignored.
+    fn handle_did_change_configuration(&mut self, params: DidChangeConfigurationParams) {
+        if self.as_initialized().is_none() {
+            return;
+        }
+        let configuration = params
+            .server_settings()
+            .map(PartialServerSettings::from_json)
+            .transpose();
+        match configuration {
+            Ok(configuration) => {
+                let Some(state) = self.as_mut_initialized() else {
+                    return;
+                };
+                state.configuration = configuration.unwrap_or_default();
+                self.reload_settings();
+                self.publish_all_diagnostics();
+            }
+            Err(e) => self.show_error(format!("Ignoring settings: {e}")),
+        }
+    }
 
     /// The main entry point for dispatching all incoming notifications from the client.
     ///
     /// It takes a `ClientServerNotification` and routes it to the appropriate handler.
+    /// Only `exit` is handled before the server is initialized, or once it's shut down:
+    /// the other notifications are ignored, returning the reason why.
+    ///
+    /// Returns [`ControlFlow::Break`] with the code the process must exit with on `exit`,
+    /// after which no message is to be handled.
     pub fn handle_notification(
         &mut self,
         notification: ClientServerNotification,
-    ) -> Result<(), ServerError> {
-        match notification.into_variant() {
+    ) -> Result<ControlFlow<u8>, ServerError> {
+        let variant = notification.into_variant();
+        if !matches!(
+            variant,
+            ClientServerNotificationVariant::Exit | ClientServerNotificationVariant::Unknown
+        ) {
+            self.require_initialized()?;
+        }
+
+        match variant {
             ClientServerNotificationVariant::Initialized(_) => {
                 self.handle_initialized_notification()
             }
-            ClientServerNotificationVariant::Exit => process::exit(0),
+            ClientServerNotificationVariant::Exit => {
+                return Ok(ControlFlow::Break(self.handle_exit()));
+            }
+            // Notifications can't be answered, so unsupported ones are ignored
+            ClientServerNotificationVariant::Unknown => (),
             ClientServerNotificationVariant::SetTrace(params) => self.handle_set_trace(params),
+            ClientServerNotificationVariant::CancelRequest(params) => {
+                if let Some(state) = self.as_mut_initialized() {
+                    state.requests.cancel(params.id());
+                }
+            }
 
             // Text Document Related Notifications
             ClientServerNotificationVariant::DidChange(params) => self.handle_did_change(params),
             ClientServerNotificationVariant::DidOpen(document_sync) => {
                 self.handle_did_open(document_sync)
             }
+            ClientServerNotificationVariant::DidClose(params) => self.handle_did_close(params),
+            ClientServerNotificationVariant::DidSave(params) => self.handle_did_save(params),
+
+            // Workspace Related Notifications
+            ClientServerNotificationVariant::DidChangeWatchedFiles(params) => {
+                self.handle_did_change_watched_files(params)
+            }
+            ClientServerNotificationVariant::DidChangeConfiguration(params) => {
+                self.handle_did_change_configuration(params)
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Handles the `response` of the client to a request sent by the server, which stops
+    /// awaiting it. Errors the client answered with are logged, and the user is told
+    /// about the edits the client didn't apply.
+    ///
+    /// Fails if the server isn't awaiting a response with the id of `response`.
+    pub fn handle_response(&mut self, response: &ClientResponse) -> Result<(), ServerError> {
+        let state = self.require_initialized_mut()?;
+        let method = response
+            .id()
+            .and_then(|id| state.pending_requests.resolve(id))
+            .ok_or(ServerError::UnexpectedResponse(response.id()))?;
+
+        if let Some(message) = response.error_message() {
+            self.log_message(
+                format!("The client failed to handle `{method}`: {message}"),
+                None,
+            );
+            return Ok(());
+        }
+        if method == "workspace/applyEdit"
+            && let Some(result) = response.result().and_then(|result| {
+                serde_json::from_value::<ApplyWorkspaceEditResult>(result.clone()).ok()
+            })
+            && !result.applied()
+        {
+            let reason = result.failure_reason().unwrap_or("no reason given");
+            self.show_error(format!("The client didn't apply the edit: {reason}"));
         }
         Ok(())
     }
 
+    /// Sends the diagnostics of the open document identified by `uri` to the client: its
+    /// syntax errors, along with the issues found by the validation passes.
+    ///
+    /// Documents above the [`max_file_size`] aren't parsed, so their diagnostics are
+    /// cleared, and the user is told once that they aren't validated.
+    ///
+    /// [`max_file_size`]: ServerSettings::max_file_size
+    fn publish_diagnostics(&mut self, uri: &str) {
+        let state = self
+            .as_mut_initialized()
+            .expect("Diagnostics can only be published once the server is initialized");
+        state.cancel_scheduled_diagnostics(uri);
+        // The diagnostics of a text the client doesn't have would point at the wrong places
+        let Some(document) = state
+            .documents
+            .get(uri)
+            .filter(|document| !document.needs_full_sync())
+        else {
+            return;
+        };
+
+        let document_uri = normalize_uri(uri);
+        let full_document = document.full_document();
+        let text = full_document.text();
+        let oversized = text.len() > state.settings.max_file_size();
+        let start = Instant::now();
+        let diagnostics = if oversized {
+            vec![]
+        } else {
+            Diagnostic::lint_parsed(
+                text,
+                document.parsed(),
+                full_document.uri(),
+                &state.settings,
+                state.capabilities.position_encoding(),
+            )
+        };
+        let event = (!oversized && state.settings.enable_telemetry())
+            .then(|| telemetry::lint_event(start.elapsed(), &diagnostics));
+        let params = PublishDiagnosticsParams::new(
+            full_document.uri().to_string(),
+            Some(full_document.version()),
+            diagnostics,
+        );
+        state.notify(params.into());
+        if let Some(event) = event {
+            state.notify(ServerClientNotification::Telemetry(event));
+        }
+
+        if !oversized {
+            state.oversized_documents.remove(&document_uri);
+        } else if state.oversized_documents.insert(document_uri) {
+            let message = format!(
+                "{uri} is larger than {} bytes, and won't be validated",
+                state.settings.max_file_size()
+            );
+            state.notify(ShowMessageParams::new(MessageType::Info, message).into());
+        }
+    }
+
+    /// Sends the diagnostics of the edited document identified by `uri` to the client once
+    /// it hasn't been edited for the configured [`diagnostics_debounce`], so that a burst
+    /// of edits is only linted once.
+    ///
+    /// The diagnostics are published right away if the delay is zero, or if publishing
+    /// involves more than linting, i.e. before the client is initialized, or for
+    /// documents above the [`max_file_size`].
+    ///
+    /// [`diagnostics_debounce`]: ServerSettings::diagnostics_debounce
+    /// [`max_file_size`]: ServerSettings::max_file_size
+    fn schedule_diagnostics(&mut self, uri: &str) {
+        let state = self
+            .as_mut_initialized()
+            .expect("Diagnostics can only be published once the server is initialized");
+        let Some(document) = state.documents.get(uri) else {
+            return;
+        };
+        if document.needs_full_sync() {
+            state.cancel_scheduled_diagnostics(uri);
+            return;
+        }
+        let document = document.full_document();
+        if state.settings.diagnostics_debounce().is_zero()
+            || !state.is_client_initialized
+            || document.text().len() > state.settings.max_file_size()
+        {
+            self.publish_diagnostics(uri);
+            return;
+        }
+
+        let document = document.clone();
+        state.oversized_documents.remove(&normalize_uri(uri));
+        state.schedule_diagnostics(document);
+    }
+
+    /// Sends fresh diagnostics for every open document, e.g. once the settings they are
+    /// validated with changed.
+    fn publish_all_diagnostics(&mut self) {
+        let state = self
+            .as_initialized()
+            .expect("Diagnostics can only be published once the server is initialized");
+        let uris: Vec<_> = state.documents.uris().map(str::to_string).collect();
+        for uri in uris {
+            self.publish_diagnostics(&uri);
+        }
+    }
+
     /// Sends a [`$/logTrace`] notification to the client if tracing is enabled.
     ///
     /// The verbosity of the message is determined by the current `TraceValue`
-    /// set by the client.
+    /// set by the client. It's checked before the notification is queued, as the
+    /// notification writer thread doesn't know about the trace level.
     ///
     /// [`$/logTrace`]: crate::lsp::notification::ServerClientNotification::LogTrace
     fn log_message(&mut self, message: String, verbose: Option<String>) {
@@ -295,53 +1415,144 @@ impl Server {
             .as_mut_initialized()
             .expect("Logging shouldn't happen if the server is not initialized");
 
-        let log_params = match state.trace {
-            TraceValue::Off => return,
-            TraceValue::Message => LogTraceParams::new(message, None),
-            TraceValue::Verbose => LogTraceParams::new(message, verbose),
-        };
-        let _ = state
-            .notification_sender
-            .send(log_params.into())
-            .expect("Notification send failed");
+        if let Some(log_params) =
+            LogTraceParams::new(message, verbose).with_trace_level(state.trace)
+        {
+            state.notify(log_params.into());
+        }
+    }
+
+    /// Sends a [`window/showMessage`] notification asking the client to display `message`
+    /// to the user.
+    ///
+    /// [`window/showMessage`]: crate::lsp::notification::ServerClientNotification::ShowMessage
+    fn show_message(&mut self, kind: MessageType, message: String) {
+        let state = self
+            .as_mut_initialized()
+            .expect("Messages can only be shown once the server is initialized");
+        state.notify(ShowMessageParams::new(kind, message).into());
+    }
+
+    /// Displays the error `message` to the user, for failures they should act upon.
+    fn show_error(&mut self, message: String) {
+        self.show_message(MessageType::Error, message);
+    }
+}
+
+// Settings related methods
+impl Server {
+    /// Resolves the effective [`ServerSettings`] from the [`WORKSPACE_SETTINGS_FILE`] of each
+    /// workspace folder, and the `initializationOptions` and configuration sent by the
+    /// client.
+    ///
+    /// Relative paths sent by the client are resolved from the first workspace folder.
+    /// Settings files that fail to load are logged and skipped, as is a schema file that
+    /// fails to load.
+    fn reload_settings(&mut self) {
+        let state = self
+            .as_mut_initialized()
+            .expect("Settings can only be loaded once the server is initialized");
+
+        let mut layers = Vec::with_capacity(state.workspace_folders.len() + 1);
+        let mut load_errors = vec![];
+        for folder in &state.workspace_folders {
+            match PartialServerSettings::from_workspace_folder(folder) {
+                Ok(Some(workspace_settings)) => layers.push(workspace_settings),
+                Ok(None) => (),
+                Err(e) => load_errors.push(format!(
+                    "Failed to load settings from {}: {e}",
+                    folder.display()
+                )),
+            }
+        }
+        let client_layers = [&state.initialization_options, &state.configuration];
+        layers.extend(
+            client_layers.map(|layer| match state.workspace_folders.first() {
+                Some(folder) => layer.clone().relative_to(folder),
+                None => layer.clone(),
+            }),
+        );
+        state.settings = ServerSettings::resolve(&layers);
+        if let Err(e) = state.settings.load_schema_file() {
+            load_errors.push(e.to_string());
+        }
+
+        for load_error in load_errors {
+            self.show_error(load_error);
+        }
+    }
+}
+
+/// The id of the registration of the files watched by the server.
+const WATCHED_FILES_REGISTRATION_ID: &str = "huml-watched-files";
+
+/// Asks the client to notify the server of changes to the [`WORKSPACE_SETTINGS_FILE`]s
+/// and the schema file, if the client supports registering file watchers dynamically.
+///
+/// The schema file is the one set when the client is initialized, later changes of the
+/// setting aren't registered.
+fn register_watched_files(state: &mut InitializedServerState) {
+    let supports_registration = state
+        .client_capabilities
+        .workspace()
+        .and_then(|workspace| workspace.did_change_watched_files())
+        .is_some_and(|capabilities| capabilities.dynamic_registration());
+    if !supports_registration {
+        return;
     }
+
+    let mut glob_patterns = vec![format!("**/{WORKSPACE_SETTINGS_FILE}")];
+    glob_patterns.extend(
+        state
+            .settings
+            .schema_file()
+            .map(|path| path.display().to_string()),
+    );
+    let registration = Registration::did_change_watched_files(
+        WATCHED_FILES_REGISTRATION_ID.to_string(),
+        glob_patterns,
+    );
+    state.send_request(ServerClientRequestMethod::RegisterCapability(
+        RegistrationParams::new(vec![registration]),
+    ));
 }
 
 #[cfg(test)]
 mod test {
-    use std::sync::mpsc;
+    use std::{
+        env, fs, io,
+        path::{Path, PathBuf},
+        process,
+        sync::mpsc,
+    };
 
     use super::*;
-    use serde_json::json;
+    use serde_json::{Value, json};
 
     use crate::lsp::{
         capabilities::client::ClientCapabilities,
+        common::{
+            diagnostic::DiagnosticSeverity,
+            text_document::{Position, PositionEncodingKind, Range},
+        },
+        notification::{ServerClientNotification, trace::TraceValue},
+        recieved_message::RecievedMessage,
         response::{ResponsePayload, ResponseResult, initialize::InitializeResult},
-        server::InitializedServerState,
+        server::{InitializedServerState, state::parse_count::PARSE_COUNT},
     };
 
     #[test]
     fn should_initialize_server() {
-        let mut server = Server::Uninitialized;
-        let request_str = serde_json::to_string(&json!({
-            "id": 1,
-            "method": "initialize",
-            "params": {
-                "capabilities": {}
-            },
-            "jsonrpc": "2.0"
-        }))
-        .unwrap();
-        let request: Request<'_> = serde_json::from_str(&request_str).unwrap();
-        let response = server.handle_request(&request).unwrap();
+        let mut server = uninitialized_server();
+        let response = request(&mut server, 1, "initialize", json!({ "capabilities": {} }));
         match server {
             Server::Initialized(InitializedServerState {
-                _client_capabilities: client_capabilities,
+                client_capabilities,
                 is_client_initialized,
                 ..
             }) => {
-                assert_eq!(
-                    is_client_initialized, false,
+                assert!(
+                    !is_client_initialized,
                     "Expected is_client_initialized to be false right after initialization"
                 );
 
@@ -355,12 +1566,12 @@ mod test {
                     "Expected client_capabilities to match the value passed in the request"
                 )
             }
-            _ => assert!(false, "Expected the server to be initialized"),
+            _ => panic!("Expected the server to be initialized"),
         }
 
         assert_eq!(
             response.id(),
-            1,
+            Some(1),
             "Expected response id to be same as request id "
         );
 
@@ -374,25 +1585,114 @@ mod test {
     }
 
     #[test]
-    fn test_shutdown() {
-        let request_str = serde_json::to_string(&json!({
-            "id": 2,
-            "method": "shutdown",
-            "jsonrpc": "2.0"
-        }))
-        .unwrap();
-        let request = serde_json::from_str(&request_str).unwrap();
+    fn should_ignore_notifications_before_initialize() {
+        let text_document = json!({ "uri": "file:///config.huml", "version": 2 });
+        let notifications = [
+            json!({ "method": "initialized", "params": {} }),
+            json!({ "method": "$/setTrace", "params": { "value": "verbose" } }),
+            json!({
+                "method": "textDocument/didOpen",
+                "params": {
+                    "textDocument": {
+                        "uri": "file:///config.huml",
+                        "languageId": "huml",
+                        "version": 1,
+                        "text": "key: 1\n"
+                    }
+                }
+            }),
+            json!({
+                "method": "textDocument/didChange",
+                "params": {
+                    "textDocument": text_document,
+                    "contentChanges": [{ "text": "key: 2\n" }]
+                }
+            }),
+            json!({
+                "method": "textDocument/didSave",
+                "params": { "textDocument": text_document, "text": "key: 2\n" }
+            }),
+            json!({
+                "method": "textDocument/didClose",
+                "params": { "textDocument": text_document }
+            }),
+            json!({
+                "method": "workspace/didChangeWatchedFiles",
+                "params": { "changes": [{ "uri": "file:///.huml-lsp.toml", "type": 2 }] }
+            }),
+            json!({
+                "method": "workspace/didChangeConfiguration",
+                "params": { "settings": { "huml": { "indentWidth": 4 } } }
+            }),
+        ];
 
+        let mut server = uninitialized_server();
+        for mut notification in notifications {
+            notification["jsonrpc"] = json!("2.0");
+            let notification_str = notification.to_string();
+            let notification = serde_json::from_str(&notification_str).unwrap();
+
+            let result = server.handle_notification(notification);
+
+            assert!(
+                matches!(
+                    result,
+                    Err(ServerError::Lifecycle(LifecycleError::NotInitialized))
+                ),
+                "Expected {notification_str} to be ignored, got {result:?}"
+            );
+        }
+        assert!(matches!(server, Server::Uninitialized(..)));
+    }
+
+    #[test]
+    fn should_mark_client_initialized_on_initialized_notification() {
+        let mut server = uninitialized_server();
+        request(&mut server, 1, "initialize", json!({ "capabilities": {} }));
+        assert!(!server.as_initialized().unwrap().is_client_initialized);
+
+        notify(&mut server, "initialized", json!({}));
+
+        assert!(
+            server.as_initialized().unwrap().is_client_initialized,
+            "Expected is_client_initialized to be true once the client is initialized"
+        );
+    }
+
+    #[test]
+    fn should_negotiate_position_encoding() {
+        let mut server = uninitialized_server();
+        let response = request(
+            &mut server,
+            1,
+            "initialize",
+            json!({
+                "capabilities": {
+                    "general": { "positionEncodings": ["utf-16", "utf-8"] }
+                }
+            }),
+        );
+
+        let response = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            response["result"]["capabilities"]["positionEncoding"],
+            "utf-8"
+        );
+        assert_eq!(
+            server.capabilities().position_encoding(),
+            PositionEncodingKind::Utf8
+        );
+    }
+
+    #[test]
+    fn test_shutdown() {
         let (notification_sender, _notification_reciever) = mpsc::channel();
-        let mut server = Server::Initialized(InitializedServerState {
-            _client_capabilities: ClientCapabilities::default(),
-            is_client_initialized: true,
-            notification_sender: notification_sender,
-            trace: TraceValue::Off,
-            documents: vec![],
-        });
+        let mut state =
+            InitializedServerState::new(ClientCapabilities::default(), notification_sender);
+        state.is_client_initialized = true;
+        let mut server = Server::Initialized(state);
 
-        let response = server.handle_request(&request).unwrap();
+        let response = request(&mut server, 2, "shutdown", Value::Null);
 
         assert!(
             matches!(server, Server::Shutdown),
@@ -401,7 +1701,7 @@ mod test {
 
         assert_eq!(
             response.id(),
-            2,
+            Some(2),
             "Expected response id to be same as request id "
         );
 
@@ -410,4 +1710,1861 @@ mod test {
             ResponsePayload::Result(ResponseResult::Shutdown)
         ));
     }
+
+    fn shutdown_request() -> Request<'static> {
+        serde_json::from_str(r#"{"id": 7, "method": "shutdown", "jsonrpc": "2.0"}"#).unwrap()
+    }
+
+    fn error_code(response: &ResponseMessage) -> i32 {
+        let ResponsePayload::Error { code, .. } = response.payload() else {
+            panic!("Expected an error response, got {response:?}");
+        };
+        *code
+    }
+
+    #[test]
+    fn should_reject_requests_before_initialize() {
+        let mut server = uninitialized_server();
+
+        let response = server.handle_request(&shutdown_request()).unwrap();
+
+        assert_eq!(response.id(), Some(7));
+        assert_eq!(
+            error_code(&response),
+            ErrorCode::ServerNotInitialized as i32
+        );
+        assert!(matches!(server, Server::Uninitialized(..)));
+    }
+
+    #[test]
+    fn should_answer_shutdown_with_null_result() {
+        let mut server = initialized_server();
+
+        let response = server.handle_request(&shutdown_request()).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"id":7,"result":null,"jsonrpc":"2.0"}"#
+        );
+    }
+
+    #[test]
+    fn should_reject_requests_after_shutdown() {
+        let mut server = initialized_server();
+        server.handle_request(&shutdown_request()).unwrap();
+
+        let response = server.handle_request(&shutdown_request()).unwrap();
+
+        assert_eq!(error_code(&response), ErrorCode::InvalidRequest as i32);
+    }
+
+    #[test]
+    fn should_exit_with_success_only_after_shutdown() {
+        assert_eq!(uninitialized_server().exit_code(), 1);
+        assert_eq!(initialized_server().exit_code(), 1);
+
+        let mut server = initialized_server();
+        server.shut_down();
+
+        assert_eq!(server.exit_code(), 0);
+    }
+
+    /// Creates a workspace folder in the temp directory containing a settings file
+    fn create_workspace(name: &str, settings: &str) -> PathBuf {
+        let folder = env::temp_dir().join(format!("huml-lsp-{name}-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join(WORKSPACE_SETTINGS_FILE), settings).unwrap();
+        folder
+    }
+
+    fn initialize_in_workspace(folder: &Path, initialization_options: Value) -> Server {
+        let mut server = uninitialized_server();
+        request(
+            &mut server,
+            1,
+            "initialize",
+            json!({
+                "capabilities": {},
+                "initializationOptions": initialization_options,
+                "workspaceFolders": [
+                    { "uri": format!("file://{}", folder.display()), "name": "workspace" }
+                ]
+            }),
+        );
+        server
+    }
+
+    fn indent_width(server: &Server) -> usize {
+        server.as_initialized().unwrap().settings.indent_width()
+    }
+
+    #[test]
+    fn should_apply_workspace_settings_file() {
+        let folder = create_workspace("apply-settings", "indent_width = 4\n");
+
+        let server = initialize_in_workspace(&folder, Value::Null);
+
+        assert_eq!(indent_width(&server), 4);
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn should_apply_settings_file_of_root_uri_without_workspace_folders() {
+        let folder = create_workspace("root-uri", "indent_width = 4\n");
+        let mut server = uninitialized_server();
+        request(
+            &mut server,
+            1,
+            "initialize",
+            json!({
+                "capabilities": {},
+                "rootUri": format!("file://{}", folder.display()),
+                "workspaceFolders": null
+            }),
+        );
+
+        assert_eq!(
+            server.as_initialized().unwrap().workspace_folders,
+            std::slice::from_ref(&folder)
+        );
+        assert_eq!(indent_width(&server), 4);
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn should_prefer_initialization_options_over_workspace_file() {
+        let folder = create_workspace("settings-precedence", "indent_width = 4\n");
+
+        let server = initialize_in_workspace(&folder, json!({ "indentWidth": 8 }));
+
+        assert_eq!(indent_width(&server), 8);
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn should_reload_workspace_settings_file_on_change() {
+        let folder = create_workspace("reload-settings", "indent_width = 4\n");
+        let mut server = initialize_in_workspace(&folder, Value::Null);
+
+        fs::write(folder.join(WORKSPACE_SETTINGS_FILE), "indent_width = 3\n").unwrap();
+        notify(
+            &mut server,
+            "workspace/didChangeWatchedFiles",
+            json!({
+                "changes": [{
+                    "uri": format!("file://{}/{WORKSPACE_SETTINGS_FILE}", folder.display()),
+                    "type": 2
+                }]
+            }),
+        );
+
+        assert_eq!(indent_width(&server), 3);
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn should_revalidate_open_documents_on_schema_file_change() {
+        let schema = |kind: &str| json!({ "properties": { "port": { "type": kind } } });
+        let folder = create_workspace("reload-schema", "schema = \"schema.json\"\n");
+        let schema_file = folder.join("schema.json");
+        fs::write(&schema_file, schema("integer").to_string()).unwrap();
+        let (mut server, notifications) = initialized_server_with_notifications();
+        server.as_mut_initialized().unwrap().workspace_folders = vec![folder.clone()];
+        server.reload_settings();
+        let published_messages = || {
+            notifications
+                .try_iter()
+                .filter_map(|notification| match notification {
+                    ServerClientNotification::PublishDiagnostics(params) => Some(
+                        params
+                            .diagnostics()
+                            .iter()
+                            .map(|diagnostic| diagnostic.message().to_string())
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        open_document(&mut server, "file:///tmp/a.huml", "port: \"80\"\n");
+        open_document(&mut server, "file:///tmp/b.huml", "port: 80\n");
+        assert_eq!(
+            published_messages(),
+            [vec!["expected integer, found string".to_string()], vec![]]
+        );
+
+        fs::write(&schema_file, schema("string").to_string()).unwrap();
+        notify(
+            &mut server,
+            "workspace/didChangeWatchedFiles",
+            json!({
+                "changes": [{ "uri": format!("file://{}", schema_file.display()), "type": 2 }]
+            }),
+        );
+
+        assert_eq!(
+            published_messages(),
+            [vec![], vec!["expected string, found integer".to_string()]]
+        );
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    fn change_configuration(server: &mut Server, settings: Value) {
+        notify(
+            server,
+            "workspace/didChangeConfiguration",
+            json!({ "settings": settings }),
+        );
+    }
+
+    #[test]
+    fn should_apply_changed_configuration() {
+        let folder = create_workspace("change-configuration", "indent_width = 4\n");
+        let mut server = initialize_in_workspace(&folder, Value::Null);
+
+        change_configuration(
+            &mut server,
+            json!({ "huml": { "indentWidth": 8, "duplicateKeySeverity": "hint" } }),
+        );
+
+        let settings = &server.as_initialized().unwrap().settings;
+        assert_eq!(settings.indent_width(), 8);
+        assert_eq!(
+            settings.severity(crate::huml::Rule::DuplicateKey),
+            Some(DiagnosticSeverity::Hint)
+        );
+
+        // Settings missing from the configuration fall back to the other sources
+        change_configuration(&mut server, json!({ "editor": { "tabSize": 2 } }));
+
+        assert_eq!(indent_width(&server), 4);
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn should_revalidate_open_documents_on_configuration_change() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/configured.huml";
+        open_document(&mut server, uri, "port:80\n");
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published on open");
+        };
+        assert_eq!(params.diagnostics().len(), 1);
+
+        change_configuration(
+            &mut server,
+            json!({ "huml": { "colonSpacingSeverity": "off" } }),
+        );
+
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published on configuration change");
+        };
+        assert_eq!(params.uri(), uri);
+        assert!(params.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn should_keep_settings_on_invalid_configuration() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        change_configuration(&mut server, json!({ "huml": { "indentWidth": 4 } }));
+
+        change_configuration(&mut server, json!({ "huml": { "indentWidth": "four" } }));
+
+        assert_eq!(indent_width(&server), 4);
+        assert!(matches!(
+            notifications.try_recv(),
+            Ok(ServerClientNotification::ShowMessage(_))
+        ));
+    }
+
+    #[test]
+    fn should_not_validate_documents_above_max_file_size() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        change_configuration(&mut server, json!({ "huml": { "maxFileSize": 4 } }));
+        let uri = "file:///tmp/large.huml";
+        let mut open = |text| {
+            open_document(&mut server, uri, text);
+            notifications.try_iter().collect::<Vec<_>>()
+        };
+
+        // Just at the limit, the document is validated
+        let [ServerClientNotification::PublishDiagnostics(params)] = &open("a:1\n")[..] else {
+            panic!("Expected diagnostics to be published only");
+        };
+        assert_eq!(params.diagnostics().len(), 1);
+
+        // Just over it, validation is skipped, which the user is told once
+        let [
+            ServerClientNotification::PublishDiagnostics(params),
+            ServerClientNotification::ShowMessage(_),
+        ] = &open("a:12\n")[..]
+        else {
+            panic!("Expected diagnostics to be cleared, and the user to be told");
+        };
+        assert!(params.diagnostics().is_empty());
+        let [ServerClientNotification::PublishDiagnostics(params)] = &open("a:123\n")[..] else {
+            panic!("Expected diagnostics to be published only");
+        };
+        assert!(params.diagnostics().is_empty());
+        assert!(
+            server
+                .as_initialized()
+                .unwrap()
+                .documents
+                .get(uri)
+                .is_some(),
+            "Oversized documents should still be stored"
+        );
+    }
+
+    #[test]
+    fn should_parse_unchanged_document_once() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/parsed.huml";
+        let parses_before = PARSE_COUNT.get();
+        open_document(&mut server, uri, "# The port\nport: 80\n");
+        let root = |server: &Server| {
+            let document = server.as_initialized().unwrap().documents.get(uri).unwrap();
+            document.parsed().document.root.clone()
+        };
+        let hover = |server: &mut Server| {
+            request(
+                server,
+                9,
+                "textDocument/hover",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "position": { "line": 1, "character": 1 }
+                }),
+            );
+        };
+
+        hover(&mut server);
+        hover(&mut server);
+
+        // Opening the document parses it to validate it, hovering reuses its syntax tree
+        assert_eq!(PARSE_COUNT.get() - parses_before, 1);
+        let first_root = root(&server);
+
+        // Changing the document drops its syntax tree
+        change_document(&mut server, uri, 2, json!([{ "text": "port: 81\n" }]));
+        assert_ne!(root(&server), first_root);
+    }
+
+    /// Sends the request `method` to `server`, without params if they're `null`, as
+    /// decoded from the message of a client
+    fn request(server: &mut Server, id: i32, method: &str, params: Value) -> ResponseMessage {
+        let mut message = json!({ "id": id, "method": method, "jsonrpc": "2.0" });
+        if !params.is_null() {
+            message["params"] = params;
+        }
+        let request_str = message.to_string();
+        let request = serde_json::from_str(&request_str).unwrap();
+        server.handle_request(&request).unwrap()
+    }
+
+    /// Sends the notification `method` to `server`, without params if they're `null`, as
+    /// decoded from the message of a client, expecting the server to keep running
+    fn notify(server: &mut Server, method: &str, params: Value) {
+        let mut message = json!({ "method": method, "jsonrpc": "2.0" });
+        if !params.is_null() {
+            message["params"] = params;
+        }
+        let notification_str = message.to_string();
+        let notification = serde_json::from_str(&notification_str).unwrap();
+        assert!(
+            server
+                .handle_notification(notification)
+                .unwrap()
+                .is_continue()
+        );
+    }
+
+    fn uninitialized_server() -> Server {
+        Server::new(LspWriter::new(io::sink()), mpsc::channel().0)
+    }
+
+    fn initialized_server() -> Server {
+        let (notification_sender, _notification_reciever) = mpsc::channel();
+        Server::Initialized(InitializedServerState::new(
+            ClientCapabilities::default(),
+            notification_sender,
+        ))
+    }
+
+    /// Creates an initialized server, whose client is initialized as well, along with the
+    /// receiving end of its notifications
+    fn initialized_server_with_notifications() -> (Server, mpsc::Receiver<ServerClientNotification>)
+    {
+        let (notification_sender, notification_reciever) = mpsc::channel();
+        let mut state =
+            InitializedServerState::new(ClientCapabilities::default(), notification_sender);
+        state.is_client_initialized = true;
+        (Server::Initialized(state), notification_reciever)
+    }
+
+    fn open_document(server: &mut Server, uri: &str, text: &str) {
+        notify(
+            server,
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "huml", "version": 1, "text": text }
+            }),
+        );
+    }
+
+    fn change_document(server: &mut Server, uri: &str, version: i32, changes: Value) {
+        notify(
+            server,
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": version },
+                "contentChanges": changes
+            }),
+        );
+    }
+
+    #[test]
+    fn should_return_document_symbols() {
+        let mut server = initialized_server();
+        open_document(
+            &mut server,
+            "file:///tmp/outline.huml",
+            "database::\n  host: \"localhost\"\n  port: 5432\n",
+        );
+        let response = request(
+            &mut server,
+            3,
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": "file:///tmp/outline.huml" } }),
+        );
+
+        let ResponsePayload::Result(ResponseResult::DocumentSymbol(symbols)) = response.payload()
+        else {
+            panic!("Expected a document symbol response");
+        };
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name(), "database");
+        let children: Vec<_> = symbols[0].children().iter().map(|s| s.name()).collect();
+        assert_eq!(children, ["host", "port"]);
+    }
+
+    #[test]
+    fn should_count_symbol_characters_in_negotiated_encoding() {
+        let mut server = initialized_server();
+        open_document(&mut server, "file:///tmp/accents.huml", "\"é\": 1\n");
+        let response = request(
+            &mut server,
+            3,
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": "file:///tmp/accents.huml" } }),
+        );
+
+        let response = serde_json::to_value(&response).unwrap();
+        let symbol = &response["result"][0];
+        assert_eq!(
+            symbol["selectionRange"],
+            json!({
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 0, "character": 3 }
+            }),
+            "Expected `é` to count as a single UTF-16 code unit"
+        );
+        assert_eq!(symbol["range"]["end"], json!({ "line": 0, "character": 6 }));
+    }
+
+    #[test]
+    fn should_reject_second_initialize_without_losing_state() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/kept.huml";
+        open_document(&mut server, uri, "key: 1\n");
+        let response = request(
+            &mut server,
+            5,
+            "initialize",
+            json!({
+                "capabilities": {
+                    "textDocument": { "synchronization": { "didSave": true } }
+                }
+            }),
+        );
+
+        let ResponsePayload::Error { code, .. } = response.payload() else {
+            panic!("Expected the second initialize to fail");
+        };
+        assert_eq!(*code, ErrorCode::InvalidRequest as i32);
+
+        let state = server
+            .as_initialized()
+            .expect("Server should stay initialized");
+        assert!(state.documents.get(uri).is_some());
+        assert_eq!(
+            serde_json::to_value(server.client_capabilities()).unwrap(),
+            serde_json::to_value(ClientCapabilities::default()).unwrap()
+        );
+    }
+
+    fn format_document(server: &mut Server, uri: &str, tab_size: usize) -> Vec<TextEdit> {
+        let response = request(
+            server,
+            4,
+            "textDocument/formatting",
+            json!({
+                "textDocument": { "uri": uri },
+                "options": { "tabSize": tab_size, "insertSpaces": true }
+            }),
+        );
+        let ResponsePayload::Result(ResponseResult::Formatting(edits)) = response.payload() else {
+            panic!("Expected a formatting response");
+        };
+        edits.clone()
+    }
+
+    #[test]
+    fn should_format_whole_document() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/format.huml";
+        open_document(&mut server, uri, "a:   1  \nb::\n    c:  \"x\"\n");
+
+        let edits = format_document(&mut server, uri, 4);
+
+        assert_eq!(
+            edits,
+            [TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(3, 0)),
+                "a: 1\nb::\n    c: \"x\"\n".to_string()
+            )]
+        );
+
+        open_document(&mut server, uri, edits[0].new_text());
+        assert!(format_document(&mut server, uri, 4).is_empty());
+    }
+
+    #[test]
+    fn should_not_format_invalid_document() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/invalid.huml";
+        open_document(&mut server, uri, "a:   value\n");
+
+        assert!(format_document(&mut server, uri, 2).is_empty());
+    }
+
+    fn inlay_hints(server: &mut Server, uri: &str, end: Position) -> Vec<(Position, String)> {
+        let response = request(
+            server,
+            12,
+            "textDocument/inlayHint",
+            json!({
+                "textDocument": { "uri": uri },
+                "range": { "start": { "line": 0, "character": 0 }, "end": end }
+            }),
+        );
+
+        let ResponsePayload::Result(ResponseResult::InlayHint(hints)) = response.payload() else {
+            panic!("Expected an inlay hint response");
+        };
+        hints
+            .iter()
+            .map(|hint| (hint.position(), hint.label().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn should_hint_value_types_within_requested_range() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/hints.huml";
+        open_document(&mut server, uri, "port: 8080\nname: \"é\"\nenabled: true\n");
+        let hint = |line, character, label: &str| (Position::new(line, character), label.into());
+
+        // The `é` string ends after 9 UTF-16 code units, but 10 bytes
+        assert_eq!(
+            inlay_hints(&mut server, uri, Position::new(1, 9)),
+            [hint(0, 10, ": int"), hint(1, 9, ": string")]
+        );
+        assert_eq!(
+            inlay_hints(&mut server, uri, Position::new(1, 8)),
+            [hint(0, 10, ": int")]
+        );
+        assert_eq!(
+            inlay_hints(&mut server, uri, Position::new(42, 0)),
+            [
+                hint(0, 10, ": int"),
+                hint(1, 9, ": string"),
+                hint(2, 13, ": bool")
+            ],
+            "Expected a range past the end of the document to cover it up to its end"
+        );
+    }
+
+    #[test]
+    fn should_report_hex_colors_of_document() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/colors.huml";
+        open_document(&mut server, uri, "theme::\n  accent: \"#ff8800\"\n");
+        let response = request(
+            &mut server,
+            14,
+            "textDocument/documentColor",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+
+        let ResponsePayload::Result(result @ ResponseResult::DocumentColor(_)) = response.payload()
+        else {
+            panic!("Expected a document color response");
+        };
+        assert_eq!(
+            serde_json::to_value(result).unwrap(),
+            json!([{
+                "range": {
+                    "start": { "line": 1, "character": 10 },
+                    "end": { "line": 1, "character": 19 }
+                },
+                "color": { "red": 1.0, "green": 136.0 / 255.0, "blue": 0.0, "alpha": 1.0 }
+            }])
+        );
+    }
+
+    fn code_lenses(server: &mut Server, uri: &str) -> Vec<CodeLens> {
+        let response = request(
+            server,
+            15,
+            "textDocument/codeLens",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+        let ResponsePayload::Result(ResponseResult::CodeLens(lenses)) = response.payload() else {
+            panic!("Expected a code lens response");
+        };
+        lenses.clone()
+    }
+
+    #[test]
+    fn should_summarize_collections_in_code_lenses_after_edits() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/lenses.huml";
+        open_document(&mut server, uri, "name: \"huml\"\nports:: 80, 443\n");
+
+        let lenses = code_lenses(&mut server, uri);
+        assert_eq!(
+            serde_json::to_value(&lenses).unwrap(),
+            json!([{
+                "range": {
+                    "start": { "line": 1, "character": 0 },
+                    "end": { "line": 1, "character": 5 }
+                },
+                "command": { "title": "list: 2 items", "command": "" }
+            }])
+        );
+
+        change_document(
+            &mut server,
+            uri,
+            2,
+            json!([{
+                "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 0 }
+                },
+                "text": "server::\n  host: \"localhost\"\n"
+            }]),
+        );
+
+        let lenses: Vec<_> = code_lenses(&mut server, uri)
+            .iter()
+            .map(|lens| (lens.range().start().line(), lens.title().to_string()))
+            .collect();
+        assert_eq!(
+            lenses,
+            [
+                (0, "dict: 1 key".to_string()),
+                (3, "list: 2 items".to_string())
+            ]
+        );
+    }
+
+    fn definition(server: &mut Server, uri: &str, line: usize, character: usize) -> Value {
+        let response = request(
+            server,
+            13,
+            "textDocument/definition",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character }
+            }),
+        );
+        let ResponsePayload::Result(result @ ResponseResult::Definition(_)) = response.payload()
+        else {
+            panic!("Expected a definition response");
+        };
+        serde_json::to_value(result).unwrap()
+    }
+
+    #[test]
+    fn should_go_from_repeated_key_path_to_first_occurrence() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/definition.huml";
+        let source = "\
+server::
+  port: 80
+  host: \"a\"
+client::
+  port: 1
+server::
+  port: 8080
+";
+        open_document(&mut server, uri, source);
+
+        assert_eq!(
+            definition(&mut server, uri, 6, 3),
+            json!({
+                "uri": uri,
+                "range": {
+                    "start": { "line": 1, "character": 2 },
+                    "end": { "line": 1, "character": 6 }
+                }
+            })
+        );
+        assert_eq!(
+            definition(&mut server, uri, 5, 0)["range"]["start"]["line"],
+            0
+        );
+        // Same key, different path
+        assert_eq!(definition(&mut server, uri, 4, 3), Value::Null);
+        // First occurrence
+        assert_eq!(definition(&mut server, uri, 1, 3), Value::Null);
+    }
+
+    fn references(
+        server: &mut Server,
+        uri: &str,
+        position: (usize, usize),
+        include_declaration: bool,
+    ) -> Vec<(usize, usize)> {
+        let response = request(
+            server,
+            14,
+            "textDocument/references",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": position.0, "character": position.1 },
+                "context": { "includeDeclaration": include_declaration }
+            }),
+        );
+        let ResponsePayload::Result(ResponseResult::References(locations)) = response.payload()
+        else {
+            panic!("Expected a references response");
+        };
+        locations
+            .iter()
+            .map(|location| {
+                assert_eq!(location.uri(), uri);
+                let start = location.range().start();
+                (start.line(), start.character())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_find_references_of_repeated_key_path() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/references.huml";
+        let source = "\
+server::
+  port: 80
+client::
+  port: 1
+server::
+  port: 8080
+server::
+  port: 8081
+";
+        open_document(&mut server, uri, source);
+
+        assert_eq!(
+            references(&mut server, uri, (5, 3), true),
+            [(1, 2), (5, 2), (7, 2)]
+        );
+        assert_eq!(
+            references(&mut server, uri, (1, 3), false),
+            [(5, 2), (7, 2)]
+        );
+        assert_eq!(
+            references(&mut server, uri, (3, 3), true),
+            [(3, 2)],
+            "Expected the same key under another path not to be a reference"
+        );
+        assert!(references(&mut server, uri, (1, 8), true).is_empty());
+    }
+
+    fn signature_help(server: &mut Server, uri: &str, line: usize, character: usize) -> Value {
+        let response = request(
+            server,
+            15,
+            "textDocument/signatureHelp",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character }
+            }),
+        );
+        let ResponsePayload::Result(result @ ResponseResult::SignatureHelp(_)) = response.payload()
+        else {
+            panic!("Expected a signature help response");
+        };
+        serde_json::to_value(result).unwrap()
+    }
+
+    #[test]
+    fn should_hint_value_expected_by_schema() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/signature_help.huml";
+        open_document(&mut server, uri, "server::\n  port: \n");
+
+        assert_eq!(signature_help(&mut server, uri, 1, 8), Value::Null);
+
+        let schema = json!({
+            "properties": {
+                "server": {
+                    "properties": {
+                        "port": { "type": "integer", "description": "The port to listen on." }
+                    }
+                }
+            }
+        });
+        change_configuration(&mut server, json!({ "huml": { "schema": schema } }));
+
+        assert_eq!(
+            signature_help(&mut server, uri, 1, 8),
+            json!({
+                "signatures": [{
+                    "label": "port: integer",
+                    "documentation": { "kind": "markdown", "value": "The port to listen on." }
+                }],
+                "activeSignature": 0
+            })
+        );
+    }
+
+    #[test]
+    fn should_find_workspace_symbols_across_open_documents() {
+        let mut server = initialized_server();
+        open_document(&mut server, "file:///tmp/a.huml", "server::\n  port: 80\n");
+        open_document(
+            &mut server,
+            "file:///tmp/b.huml",
+            "name: \"b\"\nPort: 8080\n",
+        );
+        let response = request(
+            &mut server,
+            16,
+            "workspace/symbol",
+            json!({ "query": "port" }),
+        );
+
+        let ResponsePayload::Result(result @ ResponseResult::WorkspaceSymbol(_)) =
+            response.payload()
+        else {
+            panic!("Expected a workspace symbol response");
+        };
+        assert_eq!(
+            serde_json::to_value(result).unwrap(),
+            json!([
+                {
+                    "name": "port",
+                    "kind": 16,
+                    "location": {
+                        "uri": "file:///tmp/a.huml",
+                        "range": {
+                            "start": { "line": 1, "character": 2 },
+                            "end": { "line": 1, "character": 10 }
+                        }
+                    },
+                    "containerName": "server"
+                },
+                {
+                    "name": "Port",
+                    "kind": 16,
+                    "location": {
+                        "uri": "file:///tmp/b.huml",
+                        "range": {
+                            "start": { "line": 1, "character": 0 },
+                            "end": { "line": 1, "character": 10 }
+                        }
+                    }
+                }
+            ])
+        );
+    }
+
+    fn will_save_wait_until(server: &mut Server, uri: &str) -> Vec<TextEdit> {
+        let response = request(
+            server,
+            9,
+            "textDocument/willSaveWaitUntil",
+            json!({ "textDocument": { "uri": uri }, "reason": 1 }),
+        );
+        let ResponsePayload::Result(ResponseResult::WillSaveWaitUntil(edits)) = response.payload()
+        else {
+            panic!("Expected a willSaveWaitUntil response");
+        };
+        edits.clone()
+    }
+
+    #[test]
+    fn should_format_dirty_document_before_saving() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/save.huml";
+        open_document(&mut server, uri, "a:   1\nb::\n  c: 2\n");
+
+        let edits = will_save_wait_until(&mut server, uri);
+
+        assert_eq!(
+            edits,
+            [TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(3, 0)),
+                "a: 1\nb::\n  c: 2\n".to_string()
+            )]
+        );
+
+        open_document(&mut server, uri, edits[0].new_text());
+        assert!(will_save_wait_until(&mut server, uri).is_empty());
+    }
+
+    #[test]
+    fn should_override_configured_indent_width_for_formatting_request_only() {
+        let mut server = initialized_server();
+        change_configuration(&mut server, json!({ "huml": { "indentWidth": 4 } }));
+        let uri = "file:///tmp/indent.huml";
+        open_document(&mut server, uri, "a::\n  b: 1\n");
+
+        assert!(format_document(&mut server, uri, 2).is_empty());
+
+        let edits = will_save_wait_until(&mut server, uri);
+        assert_eq!(edits[0].new_text(), "a::\n    b: 1\n");
+        assert_eq!(indent_width(&server), 4);
+    }
+
+    #[test]
+    fn should_not_format_before_saving_unless_advertised() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/save.huml";
+        open_document(&mut server, uri, "a:   1\n");
+        let Server::Initialized(state) = &mut server else {
+            unreachable!()
+        };
+        state.capabilities =
+            negotiate_capabilities(&ClientCapabilities::default(), &ServerSettings::default());
+
+        assert!(will_save_wait_until(&mut server, uri).is_empty());
+    }
+
+    fn code_actions(
+        server: &mut Server,
+        uri: &str,
+        diagnostics: &[Diagnostic],
+        only: Option<&[&str]>,
+    ) -> Vec<CodeAction> {
+        let response = request(
+            server,
+            7,
+            "textDocument/codeAction",
+            json!({
+                "textDocument": { "uri": uri },
+                "range": Range::new(Position::new(0, 0), Position::new(1, 0)),
+                "context": { "diagnostics": diagnostics, "only": only }
+            }),
+        );
+        let ResponsePayload::Result(ResponseResult::CodeAction(actions)) = response.payload()
+        else {
+            panic!("Expected a code action response");
+        };
+        actions.clone()
+    }
+
+    #[test]
+    fn should_offer_quick_fixes_and_formatting() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/actions.huml";
+        let text = "a:   1
+";
+        open_document(&mut server, uri, text);
+        let diagnostics = Diagnostic::lint(
+            text,
+            uri,
+            &ServerSettings::default(),
+            PositionEncodingKind::Utf16,
+        );
+
+        let actions = code_actions(&mut server, uri, &diagnostics, None);
+
+        let kinds: Vec<_> = actions.iter().map(CodeAction::kind).collect();
+        assert_eq!(kinds, [QUICK_FIX, FORMAT_DOCUMENT]);
+        assert_eq!(
+            actions[0].edit().changes()[uri],
+            [TextEdit::new(
+                Range::new(Position::new(0, 2), Position::new(0, 5)),
+                " ".to_string()
+            )]
+        );
+        assert_eq!(actions[1].edit().changes()[uri][0].new_text(), "a: 1\n");
+
+        let actions = code_actions(&mut server, uri, &[], None);
+        assert_eq!(actions.len(), 1, "Expected the format action only");
+        let actions = code_actions(&mut server, uri, &diagnostics, Some(&["source"]));
+        assert_eq!(actions[0].kind(), FORMAT_DOCUMENT);
+        assert_eq!(actions.len(), 1);
+    }
+
+    #[test]
+    fn should_offer_to_convert_tab_indentation_to_spaces() {
+        let mut server = initialized_server();
+        change_configuration(&mut server, json!({ "huml": { "indentWidth": 4 } }));
+        let uri = "file:///tmp/tabs.huml";
+        open_document(
+            &mut server,
+            uri,
+            "a::\n\tb: \"\tb\"\n\tc: \"\"\"\n\t\tkept\n\t\"\"\"\n",
+        );
+
+        let actions = code_actions(&mut server, uri, &[], Some(&[CONVERT_INDENTATION]));
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title(), "Convert indentation to spaces");
+        let line_start = |line, tabs| Range::new(Position::new(line, 0), Position::new(line, tabs));
+        assert_eq!(
+            actions[0].edit().changes()[uri],
+            [
+                TextEdit::new(line_start(1, 1), "    ".to_string()),
+                TextEdit::new(line_start(2, 1), "    ".to_string()),
+            ]
+        );
+
+        open_document(&mut server, uri, "a::\n    b: 1\n");
+        let actions = code_actions(&mut server, uri, &[], None);
+        assert!(
+            actions
+                .iter()
+                .all(|action| action.kind() != CONVERT_INDENTATION)
+        );
+    }
+
+    fn rename(
+        server: &mut Server,
+        uri: &str,
+        position: Position,
+        new_name: &str,
+    ) -> ResponseMessage {
+        request(
+            server,
+            8,
+            "textDocument/rename",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": position,
+                "newName": new_name
+            }),
+        )
+    }
+
+    fn execute_command(server: &mut Server, command: &str, argument: &str) -> ResponseMessage {
+        request(
+            server,
+            9,
+            "workspace/executeCommand",
+            json!({ "command": command, "arguments": [argument] }),
+        )
+    }
+
+    #[test]
+    fn should_convert_between_json_and_huml_with_commands() {
+        let mut server = initialized_server();
+        let capabilities = &server.as_initialized().unwrap().capabilities;
+        assert_eq!(
+            capabilities.commands(),
+            ["huml.convertFromJson", "huml.convertToJson"]
+        );
+        let text = |response: &ResponseMessage| {
+            let ResponsePayload::Result(ResponseResult::ExecuteCommand(Some(LSPAny::String(text)))) =
+                response.payload()
+            else {
+                panic!("Expected text, got {response:?}");
+            };
+            text.clone()
+        };
+
+        let response = execute_command(
+            &mut server,
+            "huml.convertFromJson",
+            r#"{ "server": { "port": 80 } }"#,
+        );
+        assert_eq!(text(&response), "server::\n  port: 80\n");
+
+        let uri = "file:///tmp/convert.huml";
+        open_document(&mut server, uri, &text(&response));
+        let response = execute_command(&mut server, "huml.convertToJson", uri);
+        assert_eq!(
+            text(&response),
+            "{\n  \"server\": {\n    \"port\": 80\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn should_reject_commands_with_invalid_input() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/invalid.huml";
+        open_document(&mut server, uri, "port: oops\n");
+
+        for (command, argument) in [
+            ("huml.convertFromJson", "{ oops"),
+            ("huml.convertToJson", uri),
+            ("huml.convertToJson", "file:///tmp/closed.huml"),
+            ("huml.unknown", ""),
+        ] {
+            let response = execute_command(&mut server, command, argument);
+            assert_eq!(error_code(&response), ErrorCode::InvalidParams as i32);
+        }
+    }
+
+    fn prepare_rename(
+        server: &mut Server,
+        uri: &str,
+        position: Position,
+    ) -> Option<PrepareRenameResult> {
+        let response = request(
+            server,
+            8,
+            "textDocument/prepareRename",
+            json!({ "textDocument": { "uri": uri }, "position": position }),
+        );
+        let ResponsePayload::Result(ResponseResult::PrepareRename(result)) = response.payload()
+        else {
+            panic!("Expected a prepare rename response, got {response:?}");
+        };
+        result.clone()
+    }
+
+    #[test]
+    fn should_prepare_renaming_keys_only() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/prepare_rename.huml";
+        open_document(&mut server, uri, "server::\n  port: 80\n");
+
+        let result = prepare_rename(&mut server, uri, Position::new(1, 2));
+
+        let result = result.expect("Key should be renameable");
+        assert_eq!(
+            result.range(),
+            Range::new(Position::new(1, 2), Position::new(1, 6))
+        );
+        assert_eq!(result.placeholder(), "port");
+        assert_eq!(prepare_rename(&mut server, uri, Position::new(1, 9)), None);
+    }
+
+    #[test]
+    fn should_rename_key_at_position() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/rename.huml";
+        open_document(&mut server, uri, "server::\n  port: 80\n");
+
+        let response = rename(&mut server, uri, Position::new(1, 4), "\"listen port\"");
+
+        let ResponsePayload::Result(ResponseResult::Rename(Some(edit))) = response.payload() else {
+            panic!("Expected a workspace edit, got {response:?}");
+        };
+        assert_eq!(
+            edit.changes()[uri],
+            [TextEdit::new(
+                Range::new(Position::new(1, 2), Position::new(1, 6)),
+                "\"listen port\"".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_rename_key_after_multi_byte_characters() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/rename.huml";
+        open_document(&mut server, uri, "prices:: \"€\": 1, rate: 2\n");
+
+        let response = rename(&mut server, uri, Position::new(0, 19), "ratio");
+
+        let ResponsePayload::Result(ResponseResult::Rename(Some(edit))) = response.payload() else {
+            panic!("Expected a workspace edit, got {response:?}");
+        };
+        assert_eq!(
+            edit.changes()[uri],
+            [TextEdit::new(
+                Range::new(Position::new(0, 17), Position::new(0, 21)),
+                "ratio".to_string()
+            )],
+            "Expected `€` to count as a single UTF-16 code unit"
+        );
+    }
+
+    #[test]
+    fn should_not_rename_outside_of_keys() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/rename.huml";
+        open_document(&mut server, uri, "port: 80\n");
+
+        let response = rename(&mut server, uri, Position::new(0, 7), "listen");
+
+        assert!(matches!(
+            response.payload(),
+            ResponsePayload::Result(ResponseResult::Rename(None))
+        ));
+    }
+
+    #[test]
+    fn should_reject_invalid_key_names() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/rename.huml";
+        open_document(&mut server, uri, "port: 80\n");
+
+        let response = rename(&mut server, uri, Position::new(0, 1), "listen port");
+
+        assert_eq!(error_code(&response), ErrorCode::InvalidParams as i32);
+    }
+
+    #[test]
+    fn should_complete_after_multi_byte_characters() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/completion.huml";
+        open_document(&mut server, uri, "\"é☕\": t\n");
+
+        // The cursor is after `t`, 7 UTF-16 code units and 10 bytes into the line
+        let response = request(
+            &mut server,
+            5,
+            "textDocument/completion",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": 0, "character": 7 }
+            }),
+        );
+
+        let ResponsePayload::Result(ResponseResult::Completion(items)) = response.payload() else {
+            panic!("Expected a completion response");
+        };
+        assert!(items.iter().any(|item| item.label() == "true"));
+    }
+
+    fn resolve_completion(server: &mut Server, item: &CompletionItem) -> CompletionItem {
+        let response = request(server, 6, "completionItem/resolve", json!(item));
+        let ResponsePayload::Result(ResponseResult::CompletionResolve(item)) = response.payload()
+        else {
+            panic!("Expected a completion resolve response");
+        };
+        item.clone()
+    }
+
+    #[test]
+    fn should_cache_resolved_completion_items_until_change() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/completion.huml";
+        open_document(
+            &mut server,
+            uri,
+            "- ::\n  port: 80\n- ::\n  port: 81\n- ::\n  host: \"a\"\n  \n",
+        );
+        let response = request(
+            &mut server,
+            5,
+            "textDocument/completion",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": 6, "character": 2 }
+            }),
+        );
+        let ResponsePayload::Result(ResponseResult::Completion(items)) = response.payload() else {
+            panic!("Expected a completion response");
+        };
+        let item = items[0].clone();
+        assert_eq!(item.label(), "port");
+
+        let first = resolve_completion(&mut server, &item);
+        let second = resolve_completion(&mut server, &item);
+
+        assert_eq!(
+            first.documentation().map(|doc| doc.value()),
+            Some("`port` is used 2 times in this document.\n\nValues: `80`, `81`")
+        );
+        assert_eq!(first, second);
+        assert_eq!(server.as_initialized().unwrap().resolve_cache.len(), 1);
+
+        change_document(
+            &mut server,
+            uri,
+            2,
+            json!([{
+                "range": {
+                    "start": { "line": 3, "character": 8 },
+                    "end": { "line": 3, "character": 10 }
+                },
+                "text": "82"
+            }]),
+        );
+
+        assert!(server.as_initialized().unwrap().resolve_cache.is_empty());
+        assert_eq!(
+            resolve_completion(&mut server, &item).documentation(),
+            None,
+            "Items of an outdated version shouldn't be resolved"
+        );
+    }
+
+    #[test]
+    fn should_publish_diagnostics_on_open() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/diagnostics.huml";
+
+        open_document(&mut server, uri, "name:\"huml\"\nport: 80\n");
+
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published");
+        };
+        assert_eq!(params.uri(), uri);
+        assert_eq!(params.version(), Some(1));
+        let ranges: Vec<_> = params.diagnostics().iter().map(|d| d.range()).collect();
+        assert_eq!(
+            ranges,
+            [Range::new(Position::new(0, 5), Position::new(0, 5))]
+        );
+        assert_eq!(
+            params.diagnostics()[0].message(),
+            "expected exactly one space after ':'"
+        );
+    }
+
+    #[test]
+    fn should_link_duplicate_key_to_first_occurrence() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/duplicates.huml";
+
+        open_document(&mut server, uri, "name: \"a\"\nport: 80\nname: \"b\"\n");
+
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published");
+        };
+        let diagnostic = &params.diagnostics()[0];
+        assert_eq!(
+            diagnostic.range(),
+            Range::new(Position::new(2, 0), Position::new(2, 4))
+        );
+        let related = diagnostic.related_information();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location().uri(), uri);
+        assert_eq!(
+            related[0].location().range(),
+            Range::new(Position::new(0, 0), Position::new(0, 4))
+        );
+    }
+
+    #[test]
+    fn should_send_lint_telemetry_only_when_enabled() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/telemetry.huml";
+
+        open_document(&mut server, uri, "a: 1\na: 2\n");
+        let sent: Vec<_> = notifications.try_iter().collect();
+        assert!(
+            sent.iter()
+                .all(|n| !matches!(n, ServerClientNotification::Telemetry(_))),
+            "Expected no telemetry by default, got {sent:?}"
+        );
+
+        change_configuration(&mut server, json!({ "huml": { "enableTelemetry": true } }));
+        open_document(&mut server, uri, "a: 1\na: 2\n");
+        let events: Vec<_> = notifications
+            .try_iter()
+            .filter_map(|notification| match notification {
+                ServerClientNotification::Telemetry(event) => Some(event),
+                _ => None,
+            })
+            .collect();
+        assert!(!events.is_empty(), "Expected a telemetry event");
+        let event = serde_json::to_value(events.last().unwrap()).unwrap();
+        assert_eq!(event["event"], "lint");
+        assert_eq!(event["diagnosticCount"], 1);
+        assert_eq!(event["errorCount"], 1);
+        assert!(event["durationMs"].as_f64().unwrap() >= 0.0);
+        assert_eq!(event.as_object().unwrap().len(), 4);
+    }
+
+    fn close_document(server: &mut Server, uri: &str) {
+        notify(
+            server,
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+    }
+
+    #[test]
+    fn should_replace_reopened_document_in_place() {
+        let mut server = initialized_server();
+        open_document(&mut server, "file:///tmp/a.huml", "a: 1\n");
+        open_document(&mut server, "file:///tmp/b.huml", "b: 1\n");
+
+        // Clients may spell the same URI differently
+        open_document(&mut server, "FILE:///tmp/%61.huml", "a: 2\n");
+
+        let state = server.as_initialized().unwrap();
+        let uris: Vec<_> = state.documents.uris().collect();
+        assert_eq!(uris, ["FILE:///tmp/%61.huml", "file:///tmp/b.huml"]);
+        let document = state.documents.get("file:///tmp/a.huml").unwrap();
+        assert_eq!(document.full_document().text(), "a: 2\n");
+    }
+
+    #[test]
+    fn should_close_document_and_clear_its_diagnostics() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/closed.huml";
+        open_document(&mut server, uri, "a:1\n");
+        notifications
+            .try_recv()
+            .expect("Diagnostics should be published on open");
+
+        close_document(&mut server, uri);
+
+        assert!(
+            server
+                .as_initialized()
+                .unwrap()
+                .documents
+                .get(uri)
+                .is_none()
+        );
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be cleared");
+        };
+        assert_eq!(params.uri(), uri);
+        assert!(params.diagnostics().is_empty());
+
+        // Closing a document that isn't open is a no-op
+        close_document(&mut server, uri);
+        assert!(notifications.try_recv().is_err());
+    }
+
+    fn save_document(server: &mut Server, uri: &str, text: Option<&str>) {
+        notify(
+            server,
+            "textDocument/didSave",
+            json!({ "textDocument": { "uri": uri }, "text": text }),
+        );
+    }
+
+    #[test]
+    fn should_publish_fresh_diagnostics_on_save() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/saved.huml";
+        open_document(&mut server, uri, "a:1\n");
+        notifications
+            .try_recv()
+            .expect("Diagnostics should be published on open");
+
+        save_document(&mut server, uri, None);
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published on save");
+        };
+        assert_eq!(params.diagnostics().len(), 1);
+
+        // The saved content replaces the document if it drifted
+        save_document(&mut server, uri, Some("a: 1\n"));
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published on save");
+        };
+        assert!(params.diagnostics().is_empty());
+        let state = server.as_initialized().unwrap();
+        let document = state.documents.get(uri).unwrap().full_document();
+        assert_eq!(document.text(), "a: 1\n");
+        assert_eq!(document.version(), 1);
+    }
+
+    fn log_with_trace(trace: TraceValue) -> Vec<ServerClientNotification> {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        server.as_mut_initialized().unwrap().trace = trace;
+
+        server.log_message("Logged".to_string(), Some("Details".to_string()));
+
+        notifications.try_iter().collect()
+    }
+
+    #[test]
+    fn should_not_log_trace_when_trace_is_off() {
+        assert!(log_with_trace(TraceValue::Off).is_empty());
+    }
+
+    #[test]
+    fn should_omit_verbose_trace_at_message_level() {
+        let notifications = log_with_trace(TraceValue::Message);
+
+        let [ServerClientNotification::LogTrace(params)] = notifications.as_slice() else {
+            panic!("Expected a single trace, got {notifications:?}");
+        };
+        assert_eq!(params.message(), "Logged");
+        assert_eq!(
+            serde_json::to_value(params).unwrap(),
+            json!({ "message": "Logged" })
+        );
+    }
+
+    #[test]
+    fn should_include_verbose_trace_at_verbose_level() {
+        let notifications = log_with_trace(TraceValue::Verbose);
+
+        let [ServerClientNotification::LogTrace(params)] = notifications.as_slice() else {
+            panic!("Expected a single trace, got {notifications:?}");
+        };
+        assert_eq!(params.verbose(), Some("Details"));
+    }
+
+    fn set_trace(server: &mut Server, value: &str) {
+        notify(server, "$/setTrace", json!({ "value": value }));
+    }
+
+    #[test]
+    fn should_suppress_traces_once_trace_is_set_off() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        set_trace(&mut server, "verbose");
+        server.log_message("Before".to_string(), None);
+
+        set_trace(&mut server, "off");
+        server.log_message("After".to_string(), None);
+
+        let traces: Vec<_> = notifications
+            .try_iter()
+            .map(|notification| match notification {
+                ServerClientNotification::LogTrace(params) => params.message().to_string(),
+                other => panic!("Unexpected notification {other:?}"),
+            })
+            .collect();
+        assert_eq!(traces, ["Before"]);
+    }
+
+    #[test]
+    fn should_keep_order_of_all_uris_when_closing() {
+        let mut server = initialized_server();
+        for name in ["a", "b", "c", "d"] {
+            open_document(&mut server, &format!("file:///tmp/{name}.huml"), "");
+        }
+
+        close_document(&mut server, "file:///tmp/b.huml");
+        open_document(&mut server, "file:///tmp/b.huml", "");
+
+        let uris: Vec<_> = server.as_initialized().unwrap().documents.uris().collect();
+        assert_eq!(
+            uris,
+            [
+                "file:///tmp/a.huml",
+                "file:///tmp/c.huml",
+                "file:///tmp/d.huml",
+                "file:///tmp/b.huml"
+            ]
+        );
+    }
+
+    fn published_todo_diagnostics(flag_todos: bool) -> Vec<Diagnostic> {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let overrides =
+            PartialServerSettings::from_json(&json!({ "flagTodos": flag_todos })).unwrap();
+        server.as_mut_initialized().unwrap().settings = ServerSettings::resolve([&overrides]);
+
+        open_document(
+            &mut server,
+            "file:///tmp/todo.huml",
+            "# TODO: fix this\na: 1\n",
+        );
+
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published");
+        };
+        params.diagnostics().to_vec()
+    }
+
+    #[test]
+    fn should_report_todo_comments_when_enabled() {
+        let diagnostics = published_todo_diagnostics(true);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message(), "TODO: fix this");
+        assert_eq!(
+            diagnostics[0].severity(),
+            Some(DiagnosticSeverity::Information)
+        );
+    }
+
+    #[test]
+    fn should_not_report_todo_comments_when_disabled() {
+        assert!(published_todo_diagnostics(false).is_empty());
+    }
+
+    #[test]
+    fn should_ignore_edits_past_the_end_of_document() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/desync.huml";
+        open_document(&mut server, uri, "port: 80\n");
+        notifications
+            .try_recv()
+            .expect("Diagnostics should be published on open");
+
+        change_document(
+            &mut server,
+            uri,
+            2,
+            json!([{
+                "range": {
+                    "start": { "line": 10, "character": 0 },
+                    "end": { "line": 10, "character": 4 }
+                },
+                "text": "host"
+            }]),
+        );
+
+        let state = server
+            .as_initialized()
+            .expect("Server should stay initialized");
+        let document = state.documents.iter().next().unwrap().full_document();
+        assert_eq!(document.version(), 1);
+        assert_eq!(document.text(), "port: 80\n");
+        assert!(state.documents.get(uri).unwrap().needs_full_sync());
+
+        let Ok(ServerClientNotification::ShowMessage(params)) = notifications.try_recv() else {
+            panic!("Expected the user to be told about the ignored change");
+        };
+        assert_eq!(params.kind(), MessageType::Error);
+        assert!(
+            params
+                .message()
+                .starts_with("Ignoring change to file:///tmp/desync.huml")
+        );
+    }
+
+    #[test]
+    fn should_apply_full_and_ranged_changes_in_order() {
+        let mut server = initialized_server();
+        let uri = "file:///tmp/full.huml";
+        open_document(&mut server, uri, "port: 80\n");
+
+        change_document(
+            &mut server,
+            uri,
+            2,
+            json!([
+                { "text": "host: \"a\"\n" },
+                {
+                    "range": {
+                        "start": { "line": 0, "character": 0 },
+                        "end": { "line": 0, "character": 4 }
+                    },
+                    "text": "name"
+                }
+            ]),
+        );
+
+        let state = server.as_initialized().unwrap();
+        let document = state.documents.get(uri).unwrap().full_document();
+        assert_eq!(document.version(), 2);
+        assert_eq!(document.text().lines().collect::<Vec<_>>(), ["name: \"a\""]);
+    }
+
+    #[test]
+    fn should_resolve_pending_request_with_matching_response() {
+        let mut server = initialized_server();
+        let state = server.as_mut_initialized().unwrap();
+        state.writer = Some(LspWriter::new(io::sink()));
+        let registration =
+            || ServerClientRequestMethod::RegisterCapability(RegistrationParams::new(vec![]));
+        let first = state.send_request(registration()).unwrap();
+        let second = state.send_request(registration()).unwrap();
+        assert_ne!(first, second);
+
+        let body = json!({ "id": first, "result": null, "jsonrpc": "2.0" }).to_string();
+        let Ok(RecievedMessage::Response(response)) = serde_json::from_str(&body) else {
+            panic!("Expected a response");
+        };
+        server.handle_response(&response).unwrap();
+
+        let pending = &server.as_initialized().unwrap().pending_requests;
+        assert!(!pending.is_pending(first));
+        assert!(pending.is_pending(second));
+        assert!(matches!(
+            server.handle_response(&response),
+            Err(ServerError::UnexpectedResponse(Some(id))) if id == first
+        ));
+    }
+
+    /// Publishes the diagnostics scheduled by the edits of documents right away
+    fn flush_diagnostics(server: &Server) {
+        let state = server.as_initialized().unwrap();
+        if let Some(debouncer) = &state.diagnostics_debouncer {
+            debouncer.flush();
+        }
+    }
+
+    #[test]
+    fn should_wait_for_full_sync_after_version_gap() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        let uri = "file:///tmp/gap.huml";
+        open_document(&mut server, uri, "port: 80\n");
+        notifications
+            .try_recv()
+            .expect("Diagnostics should be published on open");
+        let rename_key = json!([{
+            "range": {
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 0, "character": 4 }
+            },
+            "text": "host"
+        }]);
+
+        // Version 2 was never received
+        change_document(&mut server, uri, 3, rename_key.clone());
+        change_document(&mut server, uri, 4, rename_key);
+
+        let document = server.as_initialized().unwrap().documents.get(uri).unwrap();
+        assert!(document.needs_full_sync());
+        assert_eq!(document.full_document().text(), "port: 80\n");
+        let sent: Vec<_> = notifications.try_iter().collect();
+        assert!(
+            matches!(
+                sent.as_slice(),
+                [ServerClientNotification::ShowMessage(params)]
+                    if params.message().contains("expected version 2, got 3")
+            ),
+            "Expected the gap to be reported once, and no diagnostics, got {sent:?}"
+        );
+
+        change_document(&mut server, uri, 5, json!([{ "text": "port:1\n" }]));
+
+        let document = server.as_initialized().unwrap().documents.get(uri).unwrap();
+        assert!(!document.needs_full_sync());
+        assert_eq!(document.full_document().version(), 5);
+        flush_diagnostics(&server);
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published once in sync again");
+        };
+        assert_eq!(params.version(), Some(5));
+    }
+
+    #[test]
+    fn should_publish_diagnostics_once_after_burst_of_edits() {
+        let (mut server, notifications) = initialized_server_with_notifications();
+        change_configuration(
+            &mut server,
+            // Only flushing publishes the diagnostics
+            json!({ "huml": { "diagnosticsDebounceMs": 60_000 } }),
+        );
+        let uri = "file:///tmp/burst.huml";
+        open_document(&mut server, uri, "");
+        notifications
+            .try_recv()
+            .expect("Diagnostics should be published on open");
+
+        for (version, text) in (2..).zip(["p", "po", "port", "port:", "port:1"]) {
+            change_document(&mut server, uri, version, json!([{ "text": text }]));
+        }
+        assert!(
+            notifications.try_recv().is_err(),
+            "Expected no diagnostics to be published while editing"
+        );
+
+        flush_diagnostics(&server);
+        let Ok(ServerClientNotification::PublishDiagnostics(params)) = notifications.try_recv()
+        else {
+            panic!("Expected diagnostics to be published once the edits stopped");
+        };
+        assert_eq!(params.version(), Some(6));
+        assert_eq!(
+            params.diagnostics()[0].message(),
+            "expected exactly one space after ':'"
+        );
+        assert!(
+            notifications.try_recv().is_err(),
+            "Expected the intermediate versions not to be linted"
+        );
+    }
+
+    #[test]
+    fn should_hold_notifications_until_client_is_initialized() {
+        let (notification_sender, notifications) = mpsc::channel();
+        let mut server = Server::Initialized(InitializedServerState::new(
+            ClientCapabilities::default(),
+            notification_sender,
+        ));
+        open_document(&mut server, "file:///tmp/first.huml", "a:1\n");
+        open_document(&mut server, "file:///tmp/second.huml", "b: 2\n");
+
+        assert!(
+            notifications.try_recv().is_err(),
+            "Diagnostics shouldn't be published before the client is initialized"
+        );
+
+        notify(&mut server, "initialized", json!({}));
+
+        let published: Vec<_> = notifications
+            .try_iter()
+            .map(|notification| match notification {
+                ServerClientNotification::PublishDiagnostics(params) => {
+                    (params.uri().to_string(), params.diagnostics().len())
+                }
+                other => panic!("Unexpected notification {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            published,
+            [
+                ("file:///tmp/first.huml".to_string(), 1),
+                ("file:///tmp/second.huml".to_string(), 0)
+            ]
+        );
+        assert!(
+            server
+                .as_initialized()
+                .unwrap()
+                .pending_notifications
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn should_mark_in_flight_request_cancelled() {
+        let mut server = initialized_server();
+        assert!(server.as_mut_initialized().unwrap().requests.register(5));
+
+        notify(&mut server, "$/cancelRequest", json!({ "id": 5 }));
+
+        let requests = &server.as_initialized().unwrap().requests;
+        assert!(requests.cancelled().contains(&5));
+    }
+
+    #[test]
+    fn should_reject_request_reusing_id_in_flight() {
+        let mut server = initialized_server();
+        let request_str = serde_json::to_string(&json!({
+            "id": 7,
+            "method": "textDocument/documentSymbol",
+            "params": { "textDocument": { "uri": "file:///tmp/a.huml" } },
+            "jsonrpc": "2.0"
+        }))
+        .unwrap();
+        let request = serde_json::from_str(&request_str).unwrap();
+        // The first request with the id is still being handled
+        assert!(server.as_mut_initialized().unwrap().requests.register(7));
+
+        let error = server.handle_request(&request).unwrap_err();
+
+        assert!(matches!(error, ServerError::DuplicateRequestId(7)));
+        assert_eq!(JsonRpcError::from(error).code(), ErrorCode::InvalidRequest);
+        // Once the first request is answered, its id can be used again
+        server.as_mut_initialized().unwrap().requests.finish(7);
+        assert!(server.handle_request(&request).is_ok());
+        assert!(server.handle_request(&request).is_ok());
+    }
 }
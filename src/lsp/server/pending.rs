@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use crate::rpc::Integer;
+
+/// Tracks the requests sent to the client whose response is awaited, allocating their
+/// ids.
+///
+/// The response of the client is correlated with its request by id, with
+/// [`PendingRequests::resolve`], telling which method the response answers.
+#[derive(Default, Debug)]
+pub struct PendingRequests {
+    /// The id of the last request sent, ids being allocated in increasing order
+    last_id: Integer,
+    /// The method of every request awaiting a response, by id
+    pending: HashMap<Integer, &'static str>,
+}
+
+impl PendingRequests {
+    /// Allocates the id of a new request of `method`, and awaits its response.
+    pub fn register(&mut self, method: &'static str) -> Integer {
+        self.last_id += 1;
+        self.pending.insert(self.last_id, method);
+        self.last_id
+    }
+
+    /// Stops awaiting the response to the request `id`, returning the method of the
+    /// request, or `None` if no such request is awaiting a response, e.g. because it was
+    /// already answered.
+    pub fn resolve(&mut self, id: Integer) -> Option<&'static str> {
+        self.pending.remove(&id)
+    }
+
+    /// Returns `true` if the response to the request `id` is still awaited.
+    pub fn is_pending(&self, id: Integer) -> bool {
+        self.pending.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_allocate_unique_ids() {
+        let mut requests = PendingRequests::default();
+
+        let first = requests.register("client/registerCapability");
+        let second = requests.register("client/registerCapability");
+
+        assert_ne!(first, second);
+        assert!(requests.is_pending(first));
+        assert!(requests.is_pending(second));
+        assert_eq!(requests.len(), 2);
+    }
+
+    #[test]
+    fn should_resolve_pending_request_once() {
+        let mut requests = PendingRequests::default();
+        let id = requests.register("client/registerCapability");
+
+        assert_eq!(requests.resolve(id + 1), None);
+        assert_eq!(requests.resolve(id), Some("client/registerCapability"));
+        assert_eq!(requests.resolve(id), None);
+        assert!(requests.is_empty());
+    }
+}
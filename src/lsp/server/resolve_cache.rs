@@ -0,0 +1,131 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::rpc::Integer;
+
+/// The number of entries kept by a [`ResolveCache`] by default.
+pub const RESOLVE_CACHE_CAPACITY: usize = 256;
+
+/// Identifies a resolved completion item: its label in a version of a document.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ResolveKey {
+    pub uri: String,
+    pub version: Integer,
+    pub label: String,
+}
+
+/// A bounded cache of the values computed when resolving completion items.
+///
+/// Editors resolve the selected item every time the selection moves, so the same item
+/// is often resolved many times in a row. Once full, the oldest entry is evicted.
+pub struct ResolveCache<V> {
+    entries: HashMap<ResolveKey, V>,
+    /// Keys in insertion order, oldest first
+    order: VecDeque<ResolveKey>,
+    capacity: usize,
+}
+
+impl<V> Default for ResolveCache<V> {
+    fn default() -> Self {
+        Self::new(RESOLVE_CACHE_CAPACITY)
+    }
+}
+
+impl<V> ResolveCache<V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Returns the value cached for `key`, computing it with `compute` if missing.
+    pub fn get_or_insert_with(&mut self, key: ResolveKey, compute: impl FnOnce() -> V) -> &V {
+        if !self.entries.contains_key(&key) {
+            if self.entries.len() >= self.capacity
+                && let Some(oldest) = self.order.pop_front()
+            {
+                self.entries.remove(&oldest);
+            }
+            self.order.push_back(key.clone());
+            self.entries.insert(key.clone(), compute());
+        }
+        &self.entries[&key]
+    }
+
+    /// Drops the values cached for the document identified by `uri`.
+    pub fn invalidate(&mut self, uri: &str) {
+        self.entries.retain(|key, _| key.uri != uri);
+        self.order.retain(|key| key.uri != uri);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(uri: &str, label: &str) -> ResolveKey {
+        ResolveKey {
+            uri: uri.to_string(),
+            version: 1,
+            label: label.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_compute_value_once() {
+        let mut cache = ResolveCache::default();
+        let mut computations = 0;
+
+        for _ in 0..2 {
+            let value = cache.get_or_insert_with(key("file:///a.huml", "port"), || {
+                computations += 1;
+                "documentation"
+            });
+            assert_eq!(*value, "documentation");
+        }
+
+        assert_eq!(computations, 1);
+    }
+
+    #[test]
+    fn should_evict_oldest_entry_when_full() {
+        let mut cache = ResolveCache::new(2);
+        cache.get_or_insert_with(key("file:///a.huml", "a"), || 1);
+        cache.get_or_insert_with(key("file:///a.huml", "b"), || 2);
+        cache.get_or_insert_with(key("file:///a.huml", "c"), || 3);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            *cache.get_or_insert_with(key("file:///a.huml", "a"), || 10),
+            10
+        );
+        assert_eq!(
+            *cache.get_or_insert_with(key("file:///a.huml", "c"), || 30),
+            3
+        );
+    }
+
+    #[test]
+    fn should_invalidate_document_entries() {
+        let mut cache = ResolveCache::default();
+        cache.get_or_insert_with(key("file:///a.huml", "a"), || 1);
+        cache.get_or_insert_with(key("file:///b.huml", "a"), || 2);
+
+        cache.invalidate("file:///a.huml");
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            *cache.get_or_insert_with(key("file:///b.huml", "a"), || 20),
+            2
+        );
+    }
+}
@@ -1,96 +1,414 @@
-use std::sync::mpsc;
-
-use ouroboros::self_referencing;
+use std::{
+    cell::OnceCell, collections::HashSet, path::PathBuf, sync::mpsc, thread::JoinHandle,
+    time::Duration,
+};
 
-use crate::lsp::{
-    capabilities::client::ClientCapabilities,
-    common::text_document::{Range, TextDocumentItemOwned},
-    notification::{ServerClientNotification, trace::TraceValue},
+use crate::{
+    huml::{
+        self, Parsed, SourceEdit,
+        ast::Span,
+        emit::{self, EmitOptions},
+    },
+    lsp::{
+        capabilities::{client::ClientCapabilities, server::ServerCapabilities},
+        common::{
+            markup::MarkupContent,
+            text_document::{
+                Position, PositionEncodingKind, Range, TextDocumentItemOwned, TextEdit,
+            },
+        },
+        error::DocumentSyncError,
+        notification::{ServerClientNotification, trace::TraceValue},
+        request::{ServerClientRequest, ServerClientRequestMethod},
+        server::{
+            debounce::DiagnosticsDebouncer, document_store::DocumentStore,
+            in_flight::InFlightRequests, pending::PendingRequests, resolve_cache::ResolveCache,
+            writer::join_notification_loop,
+        },
+        settings::{PartialServerSettings, ServerSettings},
+    },
+    rpc::{Integer, LspWriter},
 };
 
+// Documents are parsed with a wrapper counting the parses in tests
+#[cfg(not(test))]
+use huml::parse;
+#[cfg(test)]
+use parse_count::parse;
+
 pub struct InitializedServerState {
-    pub _client_capabilities: ClientCapabilities,
+    /// The capabilities declared by the client in the `initialize` request
+    pub client_capabilities: ClientCapabilities,
     pub is_client_initialized: bool,
     pub trace: TraceValue,
+    /// The capabilities advertised to the client
+    pub capabilities: ServerCapabilities,
     pub notification_sender: mpsc::Sender<ServerClientNotification>,
-    pub documents: Vec<LineSeperatedDocument>,
+    /// The thread writing the notifications sent with `notification_sender`, if any
+    pub notification_thread: Option<JoinHandle<()>>,
+    /// The writer reaching the client, which requests are sent with, if any
+    pub writer: Option<LspWriter>,
+    /// The requests sent to the client, awaiting its response
+    pub pending_requests: PendingRequests,
+    /// Notifications waiting for the client to be initialized, oldest first
+    pub pending_notifications: Vec<ServerClientNotification>,
+    /// The open documents
+    pub documents: DocumentStore,
+    /// The URIs of the open documents too large to be validated, whose user was told so
+    pub oversized_documents: HashSet<String>,
+    /// The effective settings, resolved from all the settings sources
+    pub settings: ServerSettings,
+    /// Settings sent by the client as `initializationOptions`
+    pub initialization_options: PartialServerSettings,
+    /// Settings sent by the client with `workspace/didChangeConfiguration`
+    pub configuration: PartialServerSettings,
+    /// Local paths of the workspace folders opened in the client
+    pub workspace_folders: Vec<PathBuf>,
+    /// Documentation of the resolved completion items
+    pub resolve_cache: ResolveCache<MarkupContent>,
+    /// The requests being handled
+    pub requests: InFlightRequests,
+    /// The thread publishing the diagnostics of edited documents, once first needed
+    pub diagnostics_debouncer: Option<DiagnosticsDebouncer>,
+}
+
+impl InitializedServerState {
+    pub fn new(
+        client_capabilities: ClientCapabilities,
+        notification_sender: mpsc::Sender<ServerClientNotification>,
+    ) -> Self {
+        Self {
+            capabilities: ServerCapabilities::default()
+                .with_position_encoding(client_capabilities.position_encoding()),
+            client_capabilities,
+            is_client_initialized: false,
+            trace: TraceValue::Off,
+            notification_sender,
+            notification_thread: None,
+            writer: None,
+            pending_requests: PendingRequests::default(),
+            pending_notifications: vec![],
+            documents: DocumentStore::default(),
+            oversized_documents: HashSet::new(),
+            settings: ServerSettings::default(),
+            initialization_options: PartialServerSettings::default(),
+            configuration: PartialServerSettings::default(),
+            workspace_folders: vec![],
+            resolve_cache: ResolveCache::default(),
+            requests: InFlightRequests::default(),
+            diagnostics_debouncer: None,
+        }
+    }
+
+    /// Sends `notification` to the client, or queues it until the client is initialized,
+    /// as the client isn't ready to receive notifications before.
+    pub fn notify(&mut self, notification: ServerClientNotification) {
+        if self.is_client_initialized {
+            // Nobody is left to report to once the client stopped listening
+            let _ = self.notification_sender.send(notification);
+        } else {
+            self.pending_notifications.push(notification);
+        }
+    }
+
+    /// Sends the request `method` to the client, with a new id, returning the id its
+    /// response is awaited with. Without a writer to reach the client, the request is
+    /// dropped.
+    pub fn send_request(&mut self, method: ServerClientRequestMethod) -> Option<Integer> {
+        let writer = self.writer.as_ref()?;
+        let id = self.pending_requests.register(method.name());
+        // Nobody is left to ask once the client stopped listening
+        let _ = writer.send_request(&ServerClientRequest::new(id, method));
+        Some(id)
+    }
+
+    /// Marks the client as initialized, and sends the notifications queued until then.
+    pub fn mark_client_initialized(&mut self) {
+        self.is_client_initialized = true;
+        for notification in self.pending_notifications.drain(..) {
+            let _ = self.notification_sender.send(notification);
+        }
+    }
+
+    /// Publishes the diagnostics of `document` once it hasn't been edited for the
+    /// configured [`diagnostics_debounce`](ServerSettings::diagnostics_debounce).
+    pub fn schedule_diagnostics(&mut self, document: TextDocumentItemOwned) {
+        let notification_sender = &self.notification_sender;
+        let debouncer = self
+            .diagnostics_debouncer
+            .get_or_insert_with(|| DiagnosticsDebouncer::spawn(notification_sender.clone()));
+        debouncer.schedule(
+            document,
+            self.settings.clone(),
+            self.capabilities.position_encoding(),
+            self.settings.diagnostics_debounce(),
+        );
+    }
+
+    /// Drops the diagnostics scheduled for the document identified by `uri`, if any.
+    pub fn cancel_scheduled_diagnostics(&self, uri: &str) {
+        if let Some(debouncer) = &self.diagnostics_debouncer {
+            debouncer.cancel(uri);
+        }
+    }
+
+    /// Releases the state, waiting up to `timeout` for the notifications sent so far to be
+    /// written. Returns whether they were all written in time.
+    ///
+    /// The notifications still waiting for the client to be initialized are dropped, along
+    /// with the scheduled diagnostics.
+    pub fn close(self, timeout: Duration) -> bool {
+        let Self {
+            notification_sender,
+            notification_thread,
+            diagnostics_debouncer,
+            ..
+        } = self;
+        // The debouncer holds a sender of the notification channel as well
+        if let Some(debouncer) = diagnostics_debouncer {
+            debouncer.close();
+        }
+        // Closing the channel lets the thread finish once it's empty
+        drop(notification_sender);
+        notification_thread.is_none_or(|thread| join_notification_loop(thread, timeout))
+    }
 }
 
-#[self_referencing]
 pub struct LineSeperatedDocument {
-    pub full_document: TextDocumentItemOwned,
-    #[borrows(full_document)]
-    #[covariant]
-    pub lines: Vec<&'this str>,
+    full_document: TextDocumentItemOwned,
+    /// The byte offset at which each line of the document starts
+    line_starts: Vec<usize>,
+    /// The document parsed, once requested
+    parsed: OnceCell<Parsed>,
+    /// Whether the document drifted from the client's copy, so that only a change
+    /// replacing its whole text can be applied
+    needs_full_sync: bool,
 }
 
 impl LineSeperatedDocument {
-    pub fn into_full_document(self) -> TextDocumentItemOwned {
-        self.into_heads().full_document
+    /// Creates the document resulting from `edit` of `previous`. If `previous` was parsed,
+    /// only the edited part of its syntax tree is parsed again.
+    pub fn edited(previous: &Self, document: TextDocumentItemOwned, edit: SourceEdit) -> Self {
+        let edited = Self::from(document);
+        if let Some(parsed) = previous.parsed.get() {
+            let reparsed = huml::reparse(parsed, edited.full_document.text(), edit);
+            let _ = edited.parsed.set(reparsed);
+        }
+        edited
     }
 
-    pub fn apply_diff_to_document(&self, diff: &[(Range, &str)]) -> String {
-        let mut document = String::new();
-        for (range, replace_with) in diff {
-            let (start_line, start_pos) = (range.start().line(), range.start().character());
-            let (end_line, end_pos) = (range.end().line(), range.end().character());
-            document = self.with_lines(|lines| {
-                if start_line > lines.len() || end_line > lines.len() {
-                    panic!("Document out of sync. Changes suggested outside range")
-                }
+    /// Returns the document parsed, parsing it on the first call only.
+    ///
+    /// Every version of a document is a new [`LineSeperatedDocument`], so the cached
+    /// syntax tree always matches the text of the document.
+    pub fn parsed(&self) -> &Parsed {
+        self.parsed.get_or_init(|| parse(self.full_document.text()))
+    }
 
-                let before_start = &lines[..start_line];
-                let stale_lines = &lines[start_line..=end_line];
+    /// Returns the byte offset of `position`, whose `character` offset is counted in
+    /// `encoding`.
+    ///
+    /// Positions past the end of their line are clamped to the end of the line, before
+    /// its `\n` or `\r\n` line ending, and positions past the last line to the end of the
+    /// document.
+    pub fn position_to_offset(&self, position: Position, encoding: PositionEncodingKind) -> usize {
+        let Some(line) = self.line(position.line()) else {
+            return self.full_document.text().len();
+        };
+        let start = self.line_starts[position.line()];
+        start + encoding.byte_offset(line, position.character())
+    }
 
-                // Ignore the empty eof line
-                let eof_pos = lines.len() - 1;
-                let after_end = &lines.get((end_line + 1)..eof_pos).unwrap_or(&[]);
+    /// Returns the byte offset of `position` in its line, its `character` offset being
+    /// counted in `encoding`, or `None` if the position is past the last line.
+    pub fn byte_column(&self, position: Position, encoding: PositionEncodingKind) -> Option<usize> {
+        let line = self.line(position.line())?;
+        Some(encoding.byte_offset(line, position.character()))
+    }
 
-                let mut changed_region = String::new();
+    /// Returns the position of the byte `offset`, with its `character` offset counted in
+    /// `encoding`. The inverse of [`position_to_offset`](Self::position_to_offset).
+    ///
+    /// Offsets past the end of the document are clamped to its end, and offsets inside a
+    /// line ending to the end of their line.
+    pub fn offset_to_position(&self, offset: usize, encoding: PositionEncodingKind) -> Position {
+        let offset = offset.min(self.full_document.text().len());
+        // The first line always starts at 0, so a line starts at or before any offset
+        let line_number = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line_number];
+        let line = self.line(line_number).unwrap_or_default();
+        Position::new(line_number, encoding.character_offset(line, offset - start))
+    }
 
-                // Add the unchanged bits from stale first line into
-                if let Some(stale_line_first) = stale_lines.first() {
-                    changed_region.push_str(&stale_line_first[..start_pos]);
-                }
+    /// Returns the range of `span`, with its `character` offsets counted in `encoding`.
+    pub fn span_to_range(&self, span: Span, encoding: PositionEncodingKind) -> Range {
+        Range::new(
+            self.offset_to_position(span.start.offset, encoding),
+            self.offset_to_position(span.end.offset, encoding),
+        )
+    }
+
+    /// Returns the text of line `line_number`, without its line ending.
+    fn line(&self, line_number: usize) -> Option<&str> {
+        let text = self.full_document.text();
+        let line_starts = &self.line_starts;
+        let start = *line_starts.get(line_number)?;
+        match line_starts.get(line_number + 1) {
+            // Any line but the last one ends with `\n`, or `\r\n`
+            Some(&next) => {
+                let line = &text[start..next - 1];
+                Some(line.strip_suffix('\r').unwrap_or(line))
+            }
+            None => Some(&text[start..]),
+        }
+    }
 
-                changed_region.push_str(replace_with);
+    /// Returns the range covering the whole document, with its `character` offsets
+    /// counted in `encoding`.
+    pub fn full_range(&self, encoding: PositionEncodingKind) -> Range {
+        let end = self.full_document.text().len();
+        Range::new(Position::new(0, 0), self.offset_to_position(end, encoding))
+    }
 
-                // Push unchanged bits fromo the stale last line into the updated last line
-                if let Some(stale_line_last) = stale_lines.last() {
-                    changed_region.push_str(&stale_line_last[end_pos..]);
-                }
+    /// Returns the edit formatting the whole document with `options`, or `None` if the
+    /// document is already formatted or has syntax errors. The range of the edit is
+    /// counted in `encoding`.
+    pub fn formatting_edit(
+        &self,
+        options: &EmitOptions,
+        encoding: PositionEncodingKind,
+    ) -> Option<TextEdit> {
+        let text = self.full_document.text();
+        let formatted = emit::format(text, options)?;
+        (formatted != text).then(|| TextEdit::new(self.full_range(encoding), formatted))
+    }
 
-                // Combine the channged and the unchanged parts of the documeent
-                let updated_document = [before_start, &[&changed_region], after_end]
-                    .concat()
-                    .join("\n");
+    /// Returns the edits replacing the tabs indenting the lines of the document with
+    /// spaces, using `indent_width` columns per tab. See [`emit::expand_indentation_tabs`].
+    pub fn indentation_edits(
+        &self,
+        indent_width: usize,
+        encoding: PositionEncodingKind,
+    ) -> Vec<TextEdit> {
+        let text = self.full_document.text();
+        emit::expand_indentation_tabs(text, indent_width)
+            .into_iter()
+            .map(|fix| TextEdit::new(self.span_to_range(fix.span, encoding), fix.text))
+            .collect()
+    }
 
-                updated_document
-            })
+    /// The version the next change of the document brings it to. Ranged changes made to
+    /// any other version don't apply to the text of the document.
+    pub fn expected_next_version(&self) -> i32 {
+        self.full_document.version() + 1
+    }
+
+    /// Returns `true` if the document is out of sync with the client's copy, until a
+    /// change replaces its whole text.
+    pub fn needs_full_sync(&self) -> bool {
+        self.needs_full_sync
+    }
+
+    /// Marks the document as out of sync with the client's copy, e.g. after a change
+    /// failed to apply.
+    pub fn mark_needs_full_sync(&mut self) {
+        self.needs_full_sync = true;
+    }
+
+    pub fn full_document(&self) -> &TextDocumentItemOwned {
+        &self.full_document
+    }
+
+    pub fn into_full_document(self) -> TextDocumentItemOwned {
+        self.full_document
+    }
+
+    /// Applies the edits of `diff` to the document, returning the updated text. The
+    /// `character` offsets of the ranges are counted in `encoding`, and clamped to the end
+    /// of their line.
+    ///
+    /// Like the edits of a `TextEdit[]`, the ranges all refer to the document before any
+    /// edit is applied, so they can come in any order, e.g. from the end of the document to
+    /// its start. Edits inserting text at the same position are applied in order. The text
+    /// outside of the edited ranges is kept as is, line endings included.
+    ///
+    /// Fails if a range points at a line past the end of the document, ends before it
+    /// starts, or overlaps another range, leaving the document out of sync with the client.
+    pub fn apply_diff_to_document(
+        &self,
+        diff: &[(Range, &str)],
+        encoding: PositionEncodingKind,
+    ) -> Result<String, DocumentSyncError> {
+        let text = self.full_document.text();
+        // A line starts after every `\n`, so a trailing `\n` starts a last, empty line
+        let line_count = self.line_starts.len();
+        let mut edits = Vec::with_capacity(diff.len());
+        for &(range, replace_with) in diff {
+            if let Some(line) = [range.start().line(), range.end().line()]
+                .into_iter()
+                .find(|&line| line >= line_count)
+            {
+                return Err(DocumentSyncError::LineOutOfRange { line, line_count });
+            }
+            let start = self.position_to_offset(range.start(), encoding);
+            let end = self.position_to_offset(range.end(), encoding);
+            if start > end {
+                return Err(DocumentSyncError::InvertedRange);
+            }
+            edits.push((start, end, replace_with));
+        }
+
+        // The sort is stable, keeping the order of insertions at the same position
+        edits.sort_by_key(|&(start, end, _)| (start, end));
+        let mut document = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (start, end, replace_with) in edits {
+            if start < copied {
+                return Err(DocumentSyncError::OverlappingRanges);
+            }
+            document.push_str(&text[copied..start]);
+            document.push_str(replace_with);
+            copied = end;
         }
-        document
+        document.push_str(&text[copied..]);
+        Ok(document)
     }
 }
 
 impl From<TextDocumentItemOwned> for LineSeperatedDocument {
     fn from(value: TextDocumentItemOwned) -> Self {
-        LineSeperatedDocumentBuilder {
+        let line_starts = std::iter::once(0)
+            .chain(value.text().match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self {
             full_document: value,
-            lines_builder: |document| {
-                let mut lines: Vec<_> = document.text().lines().collect();
-                // Add an empty eof line
-                lines.push("");
-                lines
-            },
+            line_starts,
+            parsed: OnceCell::new(),
+            needs_full_sync: false,
         }
-        .build()
     }
 }
 
 #[cfg(test)]
-mod tests {
-    use crate::lsp::common::text_document::Position;
+pub mod parse_count {
+    use std::cell::Cell;
+
+    use crate::huml::{self, Parsed};
 
+    thread_local! {
+        /// The number of documents parsed by the current thread
+        pub static PARSE_COUNT: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Parses `source` with [`huml::parse`], counting the parse in [`PARSE_COUNT`].
+    pub fn parse(source: &str) -> Parsed {
+        PARSE_COUNT.with(|count| count.set(count.get() + 1));
+        huml::parse(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
 
     const TEST_TEXT: &str = r#"Hello, I'm developer.
@@ -171,8 +489,7 @@ i work at Torchwood."#;
         let mut test_text = TEST_TEXT.to_string();
         let last_substr_line = substr
             .lines()
-            .rev()
-            .next()
+            .next_back()
             .expect("Atleast one line should be present");
 
         let found_at = test_text
@@ -218,7 +535,9 @@ i work at Torchwood."#;
         let line_seperated_document = LineSeperatedDocument::from(document);
         let (range, expected_text) = generate_op(substr, replace_with);
         let diff = [(range, replace_with)];
-        let updated_text = line_seperated_document.apply_diff_to_document(&diff);
+        let updated_text = line_seperated_document
+            .apply_diff_to_document(&diff, PositionEncodingKind::Utf8)
+            .unwrap();
         (updated_text, expected_text)
     }
 
@@ -315,6 +634,12 @@ i work at Torchwood."#;
         assert_eq!(updated_text, expected_text);
     }
 
+    #[test]
+    fn should_delete_single_word() {
+        let (updated_text, expected_text) = handle_delete_test("developer");
+        assert_eq!(updated_text, expected_text);
+    }
+
     #[test]
     fn should_delete_first_word() {
         let (updated_text, expected_text) =
@@ -322,4 +647,222 @@ i work at Torchwood."#;
 
         assert_eq!(updated_text, expected_text);
     }
+
+    fn apply_to(text: &str, range: Range, replace_with: &str) -> String {
+        let document = TextDocumentItemOwned::new(
+            "uri://file".to_string(),
+            "huml".to_string(),
+            1,
+            text.to_string(),
+        );
+        LineSeperatedDocument::from(document)
+            .apply_diff_to_document(&[(range, replace_with)], PositionEncodingKind::Utf16)
+            .unwrap()
+    }
+
+    #[test]
+    fn should_apply_utf16_edits_after_emoji() {
+        // The crab is 4 bytes long, but 2 UTF-16 code units
+        let text = "emoji: \"🦀 crab\"\nnext: 1";
+        let range = Range::new(Position::new(0, 11), Position::new(0, 15));
+
+        assert_eq!(
+            apply_to(text, range, "ferris"),
+            "emoji: \"🦀 ferris\"\nnext: 1"
+        );
+    }
+
+    #[test]
+    fn should_apply_utf16_edits_after_accented_characters() {
+        let text = "café: \"crème brûlée\"";
+        let range = Range::new(Position::new(0, 7), Position::new(0, 12));
+
+        assert_eq!(apply_to(text, range, "creme"), "café: \"creme brûlée\"");
+    }
+
+    #[test]
+    fn should_reject_edits_past_the_last_line() {
+        let document = LineSeperatedDocument::from(build_document());
+        let range = Range::new(Position::new(7, 0), Position::new(8, 0));
+
+        assert_eq!(
+            document.apply_diff_to_document(&[(range, "x")], PositionEncodingKind::Utf16),
+            Err(DocumentSyncError::LineOutOfRange {
+                line: 7,
+                line_count: 3
+            })
+        );
+        // The text doesn't end with `\n`, so it has no empty line after its last one
+        let range = Range::new(Position::new(3, 0), Position::new(3, 0));
+        assert_eq!(
+            document.apply_diff_to_document(&[(range, "x")], PositionEncodingKind::Utf16),
+            Err(DocumentSyncError::LineOutOfRange {
+                line: 3,
+                line_count: 3
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_inverted_ranges() {
+        let document = LineSeperatedDocument::from(build_document());
+        let range = Range::new(Position::new(1, 4), Position::new(0, 2));
+
+        assert_eq!(
+            document.apply_diff_to_document(&[(range, "x")], PositionEncodingKind::Utf16),
+            Err(DocumentSyncError::InvertedRange)
+        );
+    }
+
+    #[test]
+    fn should_apply_edits_in_descending_order() {
+        let document = document_of("a: 1\nb: 2\nc: 3\n");
+        let range =
+            |line, start, end| Range::new(Position::new(line, start), Position::new(line, end));
+        let diff = [
+            (range(2, 3, 4), "30"),
+            (range(1, 0, 1), "beta"),
+            (range(0, 3, 4), "10"),
+            (range(0, 3, 3), "+"),
+        ];
+
+        assert_eq!(
+            document.apply_diff_to_document(&diff, PositionEncodingKind::Utf16),
+            Ok("a: +10\nbeta: 2\nc: 30\n".to_string())
+        );
+    }
+
+    #[test]
+    fn should_reject_overlapping_ranges() {
+        let document = document_of("name: \"huml\"\nport: 80\n");
+        let diff = [
+            (
+                Range::new(Position::new(1, 0), Position::new(1, 8)),
+                "port: 8080",
+            ),
+            (
+                Range::new(Position::new(0, 6), Position::new(1, 4)),
+                "\"x\"\nhost",
+            ),
+        ];
+
+        assert_eq!(
+            document.apply_diff_to_document(&diff, PositionEncodingKind::Utf16),
+            Err(DocumentSyncError::OverlappingRanges)
+        );
+    }
+
+    #[test]
+    fn should_clamp_edits_past_the_end_of_line() {
+        let range = Range::new(Position::new(0, 4), Position::new(0, 100));
+
+        assert_eq!(apply_to("key: 1\nnext: 2", range, " 2"), "key: 2\nnext: 2");
+    }
+
+    #[test]
+    fn should_keep_crlf_line_endings() {
+        let text = "name: \"huml\"\r\nport: 80\r\nhost: \"localhost\"\r\n";
+        let range = Range::new(Position::new(1, 6), Position::new(1, 8));
+
+        assert_eq!(
+            apply_to(text, range, "8080"),
+            "name: \"huml\"\r\nport: 8080\r\nhost: \"localhost\"\r\n"
+        );
+    }
+
+    #[test]
+    fn should_replace_crlf_line_endings_in_range() {
+        let text = "a: 1\r\nb: 2\r\n";
+        let range = Range::new(Position::new(0, 4), Position::new(1, 0));
+
+        assert_eq!(apply_to(text, range, "\r\n\r\n"), "a: 1\r\n\r\nb: 2\r\n");
+        assert_eq!(apply_to(text, range, ""), "a: 1b: 2\r\n");
+    }
+
+    fn document_of(text: &str) -> LineSeperatedDocument {
+        TextDocumentItemOwned::new(
+            "uri://file".to_string(),
+            "huml".to_string(),
+            1,
+            text.to_string(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn should_convert_positions_at_line_starts_and_ends() {
+        let document = document_of("a: 1\nbb: 2\n");
+        let utf16 = PositionEncodingKind::Utf16;
+
+        for (position, offset) in [
+            (Position::new(0, 0), 0),
+            (Position::new(0, 4), 4),
+            (Position::new(1, 0), 5),
+            (Position::new(1, 5), 10),
+            (Position::new(2, 0), 11),
+        ] {
+            assert_eq!(document.position_to_offset(position, utf16), offset);
+            assert_eq!(document.offset_to_position(offset, utf16), position);
+        }
+        // Clamped to the end of the line, and of the document
+        assert_eq!(document.position_to_offset(Position::new(0, 42), utf16), 4);
+        assert_eq!(document.position_to_offset(Position::new(7, 0), utf16), 11);
+        assert_eq!(document.offset_to_position(42, utf16), Position::new(2, 0));
+    }
+
+    #[test]
+    fn should_convert_positions_of_crlf_documents() {
+        let document = document_of("a: 1\r\nbb: 2\r\nc: 🦀");
+        let utf16 = PositionEncodingKind::Utf16;
+
+        assert_eq!(document.position_to_offset(Position::new(0, 4), utf16), 4);
+        assert_eq!(document.position_to_offset(Position::new(0, 42), utf16), 4);
+        assert_eq!(document.position_to_offset(Position::new(1, 0), utf16), 6);
+        assert_eq!(document.position_to_offset(Position::new(2, 5), utf16), 20);
+
+        assert_eq!(document.offset_to_position(6, utf16), Position::new(1, 0));
+        assert_eq!(document.offset_to_position(11, utf16), Position::new(1, 5));
+        // Inside the `\r\n` line ending
+        assert_eq!(document.offset_to_position(12, utf16), Position::new(1, 5));
+        assert_eq!(document.offset_to_position(20, utf16), Position::new(2, 5));
+    }
+
+    #[test]
+    fn should_end_formatting_edit_in_encoding() {
+        let document = document_of("name:   \"café ☕\"");
+        let options = EmitOptions::default();
+
+        let utf16 = document
+            .formatting_edit(&options, PositionEncodingKind::Utf16)
+            .expect("Document should need formatting");
+        let utf8 = document
+            .formatting_edit(&options, PositionEncodingKind::Utf8)
+            .expect("Document should need formatting");
+
+        assert_eq!(utf16.range().end(), Position::new(0, 16));
+        assert_eq!(utf8.range().end(), Position::new(0, 19));
+    }
+
+    #[test]
+    fn should_reparse_edited_documents_like_new_ones() {
+        let previous = document_of("a: 1\nb::\n  c: 2\n");
+        let text = "a: 1\nb::\n  c: 3\n  d: 4\n";
+        let edit = SourceEdit {
+            start: 14,
+            old_end: 15,
+            new_end: 22,
+        };
+        let document = || {
+            TextDocumentItemOwned::new("uri://file".to_string(), "huml".to_string(), 2, text.into())
+        };
+
+        // Documents that weren't parsed stay unparsed until requested
+        let unparsed = LineSeperatedDocument::edited(&previous, document(), edit);
+        assert!(unparsed.parsed.get().is_none());
+
+        previous.parsed();
+        let edited = LineSeperatedDocument::edited(&previous, document(), edit);
+        let parsed = edited.parsed.get().expect("Expected a reparsed tree");
+        assert_eq!(parsed.document, huml::parse(text).document);
+    }
 }
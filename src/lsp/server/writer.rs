@@ -1,41 +1,95 @@
-use std::{io, sync::mpsc, thread};
+use std::{
+    io,
+    sync::mpsc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-use crate::{lsp::notification::ServerClientNotification, rpc::jsonrpc_encode};
+use crate::{
+    lsp::notification::ServerClientNotification,
+    rpc::{LspWriter, SendError},
+};
 
-pub fn initialize_notification_loop<WriteOutput>(
-    mut write_output: WriteOutput,
-) -> mpsc::Sender<ServerClientNotification>
+/// How long the server waits, on exit, for the notifications left to be written.
+pub const NOTIFICATION_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Spawns the thread writing the notifications sent to the returned channel with
+/// `writer`, returning the channel along with the handle of the thread.
+///
+/// The thread finishes once every sender of the channel is dropped and the
+/// notifications sent before are written. If writing fails with an error other than a
+/// transient one, the thread stops and calls `on_failure`, since the client can't be
+/// reached anymore.
+pub fn initialize_notification_loop<OnFailure>(
+    writer: LspWriter,
+    on_failure: OnFailure,
+) -> (mpsc::Sender<ServerClientNotification>, JoinHandle<()>)
 where
-    WriteOutput: FnMut(&str) -> io::Result<()> + Send + 'static,
+    OnFailure: FnOnce(io::Error) + Send + 'static,
 {
     let (msg_sender, msg_reciever) = mpsc::channel::<ServerClientNotification>();
-    thread::spawn(move || {
+    let handle = thread::spawn(move || {
         for msg in msg_reciever {
-            let payload = jsonrpc_encode(&msg).unwrap();
-            let _ = write_output(&payload);
+            match writer.send_notification(&msg) {
+                Ok(()) => (),
+                // Only this notification is lost, the next ones may still be encoded
+                Err(SendError::Encode(_)) => (),
+                Err(SendError::Io(e)) => {
+                    on_failure(e);
+                    return;
+                }
+            }
         }
     });
-    msg_sender
+    (msg_sender, handle)
+}
+
+/// Waits up to `timeout` for the notification thread of `handle` to finish, returning
+/// whether it did. The senders of its channel must be dropped for it to finish.
+pub fn join_notification_loop(handle: JoinHandle<()>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    handle.join().is_ok()
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Read;
+    use std::{
+        io::{ErrorKind, Read, Write},
+        time::Duration,
+    };
 
-    use crate::lsp::notification::trace::LogTraceParams;
+    use crate::{lsp::notification::trace::LogTraceParams, rpc::jsonrpc_encode};
 
     use super::*;
-    use io::Write;
+
+    /// A writer whose output is gone
+    struct BrokenWriter;
+
+    impl Write for BrokenWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
 
     #[test]
     fn should_write_notification() {
-        let (mut reader, mut writer) = io::pipe().unwrap();
+        let (mut reader, writer) = io::pipe().unwrap();
         let notification =
             ServerClientNotification::from(LogTraceParams::new("Hello World".to_string(), None));
 
         // Send message and drop sender to close channel
         {
-            let sender = initialize_notification_loop(move |msg| write!(writer, "{msg}"));
+            let (sender, _) = initialize_notification_loop(LspWriter::new(writer), |_| ());
             sender
                 .send(notification.clone())
                 .expect("Sender shouldn't fail");
@@ -48,4 +102,74 @@ mod tests {
             jsonrpc_encode::<ServerClientNotification>(&notification).unwrap();
         assert_eq!(actual_content_written, expected_jsonrpc_payload);
     }
+
+    /// A writer taking a while to write anything, whose output is kept
+    struct SlowWriter(mpsc::Sender<Vec<u8>>);
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            thread::sleep(Duration::from_millis(20));
+            self.0.send(buf.to_vec()).unwrap();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn should_write_queued_notifications_before_finishing() {
+        let (output_sender, output) = mpsc::channel();
+        let (sender, handle) =
+            initialize_notification_loop(LspWriter::new(SlowWriter(output_sender)), |_| ());
+        let messages = ["first", "second", "third"];
+        for message in messages {
+            sender
+                .send(LogTraceParams::new(message.to_string(), None).into())
+                .expect("Sender shouldn't fail");
+        }
+
+        drop(sender);
+
+        assert!(join_notification_loop(handle, Duration::from_secs(5)));
+        let written = String::from_utf8(output.try_iter().flatten().collect()).unwrap();
+        let expected: String = messages
+            .into_iter()
+            .map(|message| {
+                let notification: ServerClientNotification =
+                    LogTraceParams::new(message.to_string(), None).into();
+                jsonrpc_encode(&notification).unwrap()
+            })
+            .collect();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn should_give_up_joining_after_timeout() {
+        let (output_sender, _output) = mpsc::channel();
+        let (sender, handle) =
+            initialize_notification_loop(LspWriter::new(SlowWriter(output_sender)), |_| ());
+
+        // The thread keeps waiting for notifications while a sender is alive
+        assert!(!join_notification_loop(handle, Duration::from_millis(50)));
+        drop(sender);
+    }
+
+    #[test]
+    fn should_report_fatal_write_errors() {
+        let (failure_sender, failure_reciever) = mpsc::channel();
+        let (sender, _) = initialize_notification_loop(LspWriter::new(BrokenWriter), move |e| {
+            failure_sender.send(e.kind()).unwrap();
+        });
+
+        sender
+            .send(LogTraceParams::new("Hello World".to_string(), None).into())
+            .expect("Sender shouldn't fail");
+
+        assert_eq!(
+            failure_reciever.recv_timeout(Duration::from_secs(5)),
+            Ok(ErrorKind::BrokenPipe)
+        );
+    }
 }
@@ -0,0 +1,441 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    huml::{Rule, schema::Schema},
+    lsp::{common::diagnostic::DiagnosticSeverity, error::SettingsError},
+};
+
+/// Name of the file, looked up in the root of every workspace folder, that teams can
+/// commit to share server settings.
+pub const WORKSPACE_SETTINGS_FILE: &str = ".huml-lsp.toml";
+
+/// The effective settings used by the server.
+///
+/// Settings are resolved by layering [`PartialServerSettings`] on top of the defaults,
+/// with the following precedence (highest first):
+/// 1. The `huml` settings sent by the client with `workspace/didChangeConfiguration`
+/// 2. `initializationOptions` sent by the client in the `initialize` request
+/// 3. The [`WORKSPACE_SETTINGS_FILE`] of each workspace folder
+/// 4. The defaults
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerSettings {
+    /// The number of spaces used for each level of indentation.
+    indent_width: usize,
+    /// The severity of the diagnostics reporting the spacing after `:` indicators.
+    colon_spacing_severity: RuleSeverity,
+    /// The severity of the diagnostics reporting duplicate keys.
+    duplicate_key_severity: RuleSeverity,
+    /// The severity of the diagnostics reporting misspelled booleans.
+    boolean_spelling_severity: RuleSeverity,
+    /// The severity of the diagnostics reporting blocks indented inconsistently.
+    indent_step_severity: RuleSeverity,
+    /// The schema documents are checked against, if any.
+    schema: Option<Schema>,
+    /// The file the schema is loaded from, if it's not set inline.
+    schema_file: Option<PathBuf>,
+    /// The severity of the diagnostics reporting the mismatches with the schema.
+    schema_severity: RuleSeverity,
+    /// Whether comments with a `TODO` or `FIXME` marker are reported as diagnostics.
+    flag_todos: bool,
+    /// Whether completion is offered to the client.
+    enable_completion: bool,
+    /// The size, in bytes, above which documents aren't validated.
+    max_file_size: usize,
+    /// Whether anonymous metrics, e.g. how long linting took, are sent to the client as
+    /// telemetry events.
+    enable_telemetry: bool,
+    /// How long, in milliseconds, a document must go unedited before its diagnostics are
+    /// published. Diagnostics are published on every edit if `0`.
+    diagnostics_debounce_ms: u64,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            indent_width: 2,
+            colon_spacing_severity: RuleSeverity::Error,
+            duplicate_key_severity: RuleSeverity::Error,
+            boolean_spelling_severity: RuleSeverity::Warning,
+            indent_step_severity: RuleSeverity::Error,
+            schema: None,
+            schema_file: None,
+            schema_severity: RuleSeverity::Error,
+            flag_todos: false,
+            enable_completion: true,
+            max_file_size: 1024 * 1024,
+            enable_telemetry: false,
+            diagnostics_debounce_ms: 300,
+        }
+    }
+}
+
+impl ServerSettings {
+    /// Resolves the effective settings by applying each layer, in order, over the defaults.
+    /// Later layers take precedence over earlier ones.
+    pub fn resolve<'a>(layers: impl IntoIterator<Item = &'a PartialServerSettings>) -> Self {
+        let mut settings = Self::default();
+        for layer in layers {
+            settings.apply(layer);
+        }
+        settings
+    }
+
+    /// Overrides the settings with the values present in `overrides`.
+    pub fn apply(&mut self, overrides: &PartialServerSettings) {
+        if let Some(indent_width) = overrides.indent_width {
+            self.indent_width = indent_width;
+        }
+        if let Some(severity) = overrides.colon_spacing_severity {
+            self.colon_spacing_severity = severity;
+        }
+        if let Some(severity) = overrides.duplicate_key_severity {
+            self.duplicate_key_severity = severity;
+        }
+        if let Some(severity) = overrides.boolean_spelling_severity {
+            self.boolean_spelling_severity = severity;
+        }
+        if let Some(severity) = overrides.indent_step_severity {
+            self.indent_step_severity = severity;
+        }
+        match &overrides.schema {
+            Some(SchemaSetting::Inline(schema)) => {
+                self.schema = Some(schema.clone());
+                self.schema_file = None;
+            }
+            // Loaded by `load_schema_file`, once every layer is applied
+            Some(SchemaSetting::File(path)) => {
+                self.schema = None;
+                self.schema_file = Some(path.clone());
+            }
+            None => (),
+        }
+        if let Some(severity) = overrides.schema_severity {
+            self.schema_severity = severity;
+        }
+        if let Some(flag_todos) = overrides.flag_todos {
+            self.flag_todos = flag_todos;
+        }
+        if let Some(enable_completion) = overrides.enable_completion {
+            self.enable_completion = enable_completion;
+        }
+        if let Some(max_file_size) = overrides.max_file_size {
+            self.max_file_size = max_file_size;
+        }
+        if let Some(enable_telemetry) = overrides.enable_telemetry {
+            self.enable_telemetry = enable_telemetry;
+        }
+        if let Some(debounce_ms) = overrides.diagnostics_debounce_ms {
+            self.diagnostics_debounce_ms = debounce_ms;
+        }
+    }
+
+    pub fn indent_width(&self) -> usize {
+        self.indent_width
+    }
+
+    /// Returns the schema documents are checked against, or `None` if schema validation
+    /// is off.
+    pub fn schema(&self) -> Option<&Schema> {
+        self.schema.as_ref()
+    }
+
+    /// Returns the file the schema is loaded from, if it's set as a path.
+    pub fn schema_file(&self) -> Option<&Path> {
+        self.schema_file.as_deref()
+    }
+
+    /// Loads the schema from the [`schema_file`](Self::schema_file), if any. Schema
+    /// validation stays off if the file can't be loaded.
+    pub fn load_schema_file(&mut self) -> Result<(), SettingsError> {
+        let Some(path) = &self.schema_file else {
+            return Ok(());
+        };
+        let schema = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| serde_json::from_str(&source).map_err(|e| e.to_string()));
+        match schema {
+            Ok(schema) => {
+                self.schema = Some(schema);
+                Ok(())
+            }
+            Err(reason) => {
+                self.schema = None;
+                Err(SettingsError::Schema {
+                    path: path.clone(),
+                    reason,
+                })
+            }
+        }
+    }
+
+    pub fn flag_todos(&self) -> bool {
+        self.flag_todos
+    }
+
+    pub fn enable_completion(&self) -> bool {
+        self.enable_completion
+    }
+
+    pub fn max_file_size(&self) -> usize {
+        self.max_file_size
+    }
+
+    pub fn enable_telemetry(&self) -> bool {
+        self.enable_telemetry
+    }
+
+    /// Returns how long a document must go unedited before its diagnostics are published.
+    pub fn diagnostics_debounce(&self) -> Duration {
+        Duration::from_millis(self.diagnostics_debounce_ms)
+    }
+
+    /// Returns `true` if the issues found by `rule` are reported.
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        self.severity(rule).is_some()
+    }
+
+    /// Returns the severity of the diagnostics reporting the issues found by `rule`, or
+    /// `None` if they aren't reported.
+    pub fn severity(&self, rule: Rule) -> Option<DiagnosticSeverity> {
+        match rule {
+            Rule::ColonSpacing => self.colon_spacing_severity.diagnostic_severity(),
+            Rule::DuplicateKey => self.duplicate_key_severity.diagnostic_severity(),
+            Rule::TodoComment => self.flag_todos.then_some(DiagnosticSeverity::Information),
+            Rule::BooleanSpelling => self.boolean_spelling_severity.diagnostic_severity(),
+            Rule::IndentStep => self.indent_step_severity.diagnostic_severity(),
+            Rule::Schema => self.schema_severity.diagnostic_severity(),
+        }
+    }
+}
+
+/// The severity with which the issues found by a configurable rule are reported.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+    /// The issues aren't reported.
+    Off,
+}
+
+impl RuleSeverity {
+    /// Returns the severity of the diagnostics reporting the issues, or `None` if they
+    /// aren't reported.
+    pub fn diagnostic_severity(self) -> Option<DiagnosticSeverity> {
+        match self {
+            Self::Error => Some(DiagnosticSeverity::Error),
+            Self::Warning => Some(DiagnosticSeverity::Warning),
+            Self::Information => Some(DiagnosticSeverity::Information),
+            Self::Hint => Some(DiagnosticSeverity::Hint),
+            Self::Off => None,
+        }
+    }
+}
+
+/// The `schema` setting: either the path of a JSON file holding the schema, or the schema
+/// itself.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum SchemaSetting {
+    File(PathBuf),
+    Inline(Schema),
+}
+
+/// A set of settings where every key is optional.
+///
+/// This is the shape in which settings are received from the client or read from a
+/// [`WORKSPACE_SETTINGS_FILE`]. Keys are accepted in either `camelCase` or `snake_case`.
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialServerSettings {
+    #[serde(default, alias = "indent_width")]
+    indent_width: Option<usize>,
+    #[serde(default, alias = "colon_spacing_severity")]
+    colon_spacing_severity: Option<RuleSeverity>,
+    #[serde(default, alias = "duplicate_key_severity")]
+    duplicate_key_severity: Option<RuleSeverity>,
+    #[serde(default, alias = "boolean_spelling_severity")]
+    boolean_spelling_severity: Option<RuleSeverity>,
+    #[serde(default, alias = "indent_step_severity")]
+    indent_step_severity: Option<RuleSeverity>,
+    #[serde(default)]
+    schema: Option<SchemaSetting>,
+    #[serde(default, alias = "schema_severity")]
+    schema_severity: Option<RuleSeverity>,
+    #[serde(default, alias = "flag_todos")]
+    flag_todos: Option<bool>,
+    #[serde(default, alias = "enable_completion")]
+    enable_completion: Option<bool>,
+    #[serde(default, alias = "max_file_size")]
+    max_file_size: Option<usize>,
+    #[serde(default, alias = "enable_telemetry")]
+    enable_telemetry: Option<bool>,
+    #[serde(default, alias = "diagnostics_debounce_ms")]
+    diagnostics_debounce_ms: Option<u64>,
+}
+
+impl PartialServerSettings {
+    /// Parses settings from the contents of a [`WORKSPACE_SETTINGS_FILE`].
+    pub fn from_toml(source: &str) -> Result<Self, SettingsError> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Parses settings from a JSON value, e.g. the `initializationOptions`.
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, SettingsError> {
+        Ok(Self::deserialize(value)?)
+    }
+
+    /// Resolves the relative paths of the settings, e.g. of the schema file, from `dir`.
+    pub fn relative_to(mut self, dir: &Path) -> Self {
+        if let Some(SchemaSetting::File(path)) = &mut self.schema
+            && path.is_relative()
+        {
+            *path = dir.join(&*path);
+        }
+        self
+    }
+
+    /// Reads the [`WORKSPACE_SETTINGS_FILE`] located at the root of `folder`. Relative
+    /// paths are resolved from `folder`.
+    ///
+    /// Returns `Ok(None)` if the folder has no settings file.
+    pub fn from_workspace_folder(folder: &Path) -> Result<Option<Self>, SettingsError> {
+        match fs::read_to_string(folder.join(WORKSPACE_SETTINGS_FILE)) {
+            Ok(source) => {
+                Self::from_toml(&source).map(|settings| Some(settings.relative_to(folder)))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_parse_toml_settings() {
+        let overrides = PartialServerSettings::from_toml("indent_width = 4").unwrap();
+        assert_eq!(overrides.indent_width, Some(4));
+    }
+
+    #[test]
+    fn should_parse_json_settings() {
+        let overrides = PartialServerSettings::from_json(&json!({ "indentWidth": 8 })).unwrap();
+        assert_eq!(overrides.indent_width, Some(8));
+    }
+
+    #[test]
+    fn should_parse_rule_severity() {
+        let overrides =
+            PartialServerSettings::from_json(&json!({ "duplicateKeySeverity": "warning" }))
+                .unwrap();
+        let settings = ServerSettings::resolve([&overrides]);
+
+        assert_eq!(
+            settings.severity(Rule::DuplicateKey),
+            Some(DiagnosticSeverity::Warning)
+        );
+        assert_eq!(
+            ServerSettings::default().severity(Rule::DuplicateKey),
+            Some(DiagnosticSeverity::Error)
+        );
+    }
+
+    #[test]
+    fn should_turn_rule_off() {
+        let overrides =
+            PartialServerSettings::from_toml("colon_spacing_severity = \"off\"").unwrap();
+        let settings = ServerSettings::resolve([&overrides]);
+
+        assert!(!settings.is_enabled(Rule::ColonSpacing));
+        assert_eq!(settings.severity(Rule::ColonSpacing), None);
+        assert!(ServerSettings::default().is_enabled(Rule::ColonSpacing));
+    }
+
+    #[test]
+    fn should_ignore_unknown_keys() {
+        let overrides = PartialServerSettings::from_toml("unknown = true").unwrap();
+        assert_eq!(overrides, PartialServerSettings::default());
+    }
+
+    #[test]
+    fn should_fail_on_invalid_value() {
+        assert!(PartialServerSettings::from_toml("indent_width = \"four\"").is_err());
+    }
+
+    #[test]
+    fn should_parse_schema() {
+        let overrides = PartialServerSettings::from_json(&json!({
+            "schema": { "type": "object", "required": ["port"] }
+        }))
+        .unwrap();
+
+        assert!(ServerSettings::default().schema().is_none());
+        assert!(ServerSettings::resolve([&overrides]).schema().is_some());
+        let toml = "[schema]\ntype = \"object\"\nproperties = { port = { type = \"integer\" } }\n";
+        assert!(
+            PartialServerSettings::from_toml(toml)
+                .unwrap()
+                .schema
+                .is_some()
+        );
+        assert!(PartialServerSettings::from_json(&json!({ "schema": { "type": "int" } })).is_err());
+    }
+
+    #[test]
+    fn should_load_schema_from_file_relative_to_folder() {
+        let folder = std::env::temp_dir().join(format!("huml-lsp-schema-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("schema.json"), r#"{ "required": ["port"] }"#).unwrap();
+        let overrides = PartialServerSettings::from_json(&json!({ "schema": "schema.json" }))
+            .unwrap()
+            .relative_to(&folder);
+
+        let mut settings = ServerSettings::resolve([&overrides]);
+        assert_eq!(
+            settings.schema_file(),
+            Some(folder.join("schema.json").as_path())
+        );
+        assert!(settings.schema().is_none());
+        settings.load_schema_file().unwrap();
+        assert!(settings.schema().is_some());
+
+        fs::write(folder.join("schema.json"), "not json").unwrap();
+        assert!(matches!(
+            settings.load_schema_file(),
+            Err(SettingsError::Schema { .. })
+        ));
+        assert!(settings.schema().is_none());
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn should_resolve_layers_by_precedence() {
+        let workspace = PartialServerSettings {
+            indent_width: Some(4),
+            ..Default::default()
+        };
+        let initialization_options = PartialServerSettings {
+            indent_width: Some(8),
+            ..Default::default()
+        };
+
+        assert_eq!(ServerSettings::resolve([]).indent_width(), 2);
+        assert_eq!(ServerSettings::resolve([&workspace]).indent_width(), 4);
+        assert_eq!(
+            ServerSettings::resolve([&workspace, &initialization_options]).indent_width(),
+            8
+        );
+    }
+}
@@ -1,88 +1,855 @@
+mod check;
+mod format;
+mod logger;
+
 use huml_lsp::{
-    lsp::{recieved_message::RecievedMessage, server::Server},
-    rpc::{RPCMessageStream, jsonrpc_decode, jsonrpc_encode},
+    lsp::{
+        properties::ServerInfo,
+        recieved_message::{RecievedMessage, RecievedMessages},
+        response::{ErrorCode, ResponseMessage, ResponsePayload},
+        server::Server,
+    },
+    rpc::{DecodeError, LspWriter, RPCMessageStream, SendError},
 };
-use serde_json::Value;
+use logger::Logger;
+use serde::Serialize;
 use std::{
     env,
     error::Error,
-    fs::File,
-    io::{self, Write},
-    panic,
+    fmt::Debug,
+    io::{self, Read},
+    net::{Ipv4Addr, TcpStream},
+    ops::ControlFlow,
+    process::ExitCode,
+    sync::mpsc,
 };
 
-fn build_logger() -> impl FnMut(&str) -> () {
-    let log_file_path_result = env::var("HUML_LOG_PATH");
-    let log_path = log_file_path_result.as_deref().unwrap_or("/tmp/huml.log");
-    let mut log_file = File::create(log_path).unwrap();
-    let logger = move |message: &str| {
-        let _ = writeln!(log_file, "{message}");
+const USAGE: &str = "Usage: huml-lsp [--stdio | --socket <port>]";
+
+/// How the server exchanges messages with the client.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Transport {
+    /// Messages are read from stdin and written to stdout.
+    Stdio,
+    /// Messages are exchanged over a TCP connection to the client, listening on `port`
+    /// of localhost.
+    Socket { port: u16 },
+}
+
+impl Transport {
+    /// Parses the transport from the command line `args`, e.g. `--socket 5007` or
+    /// `--socket=5007`. Messages go through stdio by default.
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut transport = Self::Stdio;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let port = match arg.as_str() {
+                "--stdio" => {
+                    transport = Self::Stdio;
+                    continue;
+                }
+                "--socket" => args.next().map(String::as_str),
+                arg => match arg.strip_prefix("--socket=") {
+                    Some(port) => Some(port),
+                    None => return Err(format!("Unknown argument `{arg}`")),
+                },
+            };
+            let port = port.ok_or("Missing the port of `--socket`")?;
+            let port = port
+                .parse()
+                .map_err(|e| format!("Invalid port `{port}`: {e}"))?;
+            transport = Self::Socket { port };
+        }
+        Ok(transport)
+    }
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args
+        .first()
+        .is_some_and(|arg| matches!(arg.as_str(), "--check" | "check"))
+    {
+        return Ok(check::run(&args[1..]));
+    }
+    if args
+        .first()
+        .is_some_and(|arg| matches!(arg.as_str(), "--format" | "format"))
+    {
+        return Ok(format::run(&args[1..]));
+    }
+    if args.first().is_some_and(|arg| arg == "--version") {
+        let info = ServerInfo::default();
+        println!("{} {}", info.name(), info.version());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let transport = match Transport::from_args(&args) {
+        Ok(transport) => transport,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return Ok(ExitCode::from(2));
+        }
     };
-    logger
+
+    let mut logger = Logger::from_env();
+
+    logger.info(format_args!(
+        "Started Server over {transport:?}. Waiting for Messages..."
+    ));
+    let exit_code = serve_over(transport, logger)?;
+    Ok(exit_code.map_or(ExitCode::SUCCESS, ExitCode::from))
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut log = build_logger();
-    let mut server = Server::new();
+/// Connects to the client with `transport`, and handles its messages until it
+/// disconnects. See [`serve`].
+fn serve_over(transport: Transport, logger: Logger) -> io::Result<Option<u8>> {
+    match transport {
+        Transport::Stdio => serve(io::stdin().lock(), LspWriter::new(io::stdout()), logger),
+        Transport::Socket { port } => {
+            let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+            let writer = LspWriter::new(stream.try_clone()?);
+            serve(stream, writer, logger)
+        }
+    }
+}
 
-    let stdin_reader = io::stdin().lock();
-    let rpc_reader = RPCMessageStream::new(stdin_reader);
+/// Handles the messages read from `reader` until it ends or the client asks the server
+/// to exit, sending the responses and notifications with `writer`. The messages of a
+/// batch are answered with a batch of responses.
+///
+/// Returns the code the process must exit with if the client sent `exit`, the messages
+/// following it being left unhandled, or `None` if `reader` ended first. Either way, the
+/// notifications sent so far are written before returning.
+///
+/// A message that can't be decoded or handled never stops the server: requests are
+/// answered with an error response, and notifications are only logged. Failing to read
+/// from `reader`, or to write with `writer`, does, returning the error.
+fn serve(reader: impl Read, writer: LspWriter, mut logger: Logger) -> io::Result<Option<u8>> {
+    let (write_failures_sender, write_failures) = mpsc::channel();
+    let mut server = Server::new(writer.clone(), write_failures_sender);
+    let mut rpc_reader = RPCMessageStream::new(reader).typed();
 
-    log("Started Server. Waiting for Messages...");
-    for message_result in rpc_reader {
-        let message_string = match message_result {
-            Ok(s) => s,
+    while let Some(message_result) = rpc_reader.next_message::<RecievedMessages>() {
+        // Read and parse / recieve the message
+        let messages = match message_result {
+            Ok(messages) => messages,
+            // The client may be waiting for an answer, but its id can't be known
+            Err(e @ DecodeError::JsonError(_)) => {
+                logger.warn(format_args!("Error decoding message: {e}"));
+                let response = ResponseMessage::error_for_undecoded(
+                    None,
+                    ErrorCode::ParseError,
+                    e.to_string(),
+                );
+                write_response(&writer, &response, &mut logger)?;
+                continue;
+            }
+            // Nothing more can be read from the client
+            Err(DecodeError::Io(e)) => {
+                logger.error(format_args!("Error reading message: {e}"));
+                return Err(e);
+            }
             Err(e) => {
-                log(&format!("Error reading from stream: {}", e));
+                logger.warn(format_args!("Error reading message: {e}"));
                 continue; // Skip to the next message on read error
             }
         };
 
-        // Debug logging to inspect requests
-        #[cfg(debug_assertions)]
-        {
-            if let Ok(json_value) = jsonrpc_decode::<Value>(&message_string) {
-                if let Ok(pretty_json) = serde_json::to_string_pretty(&json_value) {
-                    log(&format!("Message: {}", pretty_json));
+        let mut responses = vec![];
+        let mut exit_code = None;
+        for body in messages.bodies() {
+            match handle_message(&mut server, body, &mut logger) {
+                ControlFlow::Continue(response) => responses.extend(response),
+                ControlFlow::Break(code) => {
+                    exit_code = Some(code);
+                    break;
                 }
             }
         }
 
-        // Parse / recieve the message
-        let parsed_message: RecievedMessage =
-            match jsonrpc_decode::<RecievedMessage>(&message_string) {
-                Ok(msg) => msg,
-                Err(decode_err) => {
-                    log(&format!("Error parsing message: {decode_err}"));
-                    continue;
+        if !messages.is_batch() {
+            for response in &responses {
+                write_response(&writer, response, &mut logger)?;
+            }
+        } else if messages.bodies().is_empty() {
+            let response = ResponseMessage::error_for_undecoded(
+                None,
+                ErrorCode::InvalidRequest,
+                "Empty batch".to_string(),
+            );
+            write_response(&writer, &response, &mut logger)?;
+        } else if !responses.is_empty() {
+            write_response(&writer, &responses, &mut logger)?;
+        }
+
+        check_notifications_written(&write_failures, &mut logger)?;
+        if exit_code.is_some() {
+            return Ok(exit_code);
+        }
+    }
+
+    // The client is gone, but the notifications sent so far are still written, as on `exit`
+    server.shut_down();
+    check_notifications_written(&write_failures, &mut logger)?;
+    Ok(None)
+}
+
+/// Returns the error of the thread writing notifications, which stops on failure, if it
+/// failed to write one.
+fn check_notifications_written(
+    write_failures: &mpsc::Receiver<io::Error>,
+    logger: &mut Logger,
+) -> io::Result<()> {
+    match write_failures.try_recv() {
+        Ok(e) => {
+            logger.error(format_args!("Failed to write notification: {e}"));
+            Err(e)
+        }
+        Err(_) => Ok(()),
+    }
+}
+
+/// Handles the message `body`, returning the response to send if it's a request, or
+/// [`ControlFlow::Break`] with the exit code of the process if it's `exit`.
+fn handle_message(
+    server: &mut Server,
+    body: &str,
+    logger: &mut Logger,
+) -> ControlFlow<u8, Option<ResponseMessage>> {
+    let response = match serde_json::from_str::<RecievedMessage>(body) {
+        Ok(RecievedMessage::Request(req)) => {
+            // Debug logging to inspect requests
+            logger.debug(format_args!("Request: {req:#?}"));
+
+            let response = server.handle_request(&req).unwrap_or_else(|e| {
+                logger.warn(format_args!("Failed to handle request: {e}"));
+                ResponseMessage::new_for(&req, ResponsePayload::from(e))
+            });
+            Some(response)
+        }
+        Ok(RecievedMessage::Response(response)) => {
+            logger.debug(format_args!("Response from client: {response:#?}"));
+
+            if let Err(e) = server.handle_response(&response) {
+                logger.warn(format_args!("Failed to handle response: {e}"));
+            }
+            None
+        }
+        // Notifications can't be answered, even with a method not found error
+        Ok(RecievedMessage::UnknownNotification(notification)) => {
+            logger.info(format_args!(
+                "Ignoring unsupported notification `{}`",
+                notification.method()
+            ));
+            None
+        }
+        Ok(RecievedMessage::Notification(notification)) => {
+            logger.debug(format_args!("Notification: {notification:#?}"));
+
+            match server.handle_notification(notification) {
+                Ok(ControlFlow::Break(code)) => return ControlFlow::Break(code),
+                Ok(ControlFlow::Continue(())) => (),
+                Err(e) => logger.warn(format_args!("Failed to handle notification: {e}")),
+            }
+            None
+        }
+        Err(e) => {
+            logger.warn(format_args!("Error decoding message: {e}"));
+            RecievedMessage::decode_error_response(body, &e)
+        }
+    };
+    ControlFlow::Continue(response)
+}
+
+/// Sends `response` with `writer`. Responses that fail to encode are logged and
+/// dropped.
+fn write_response(
+    writer: &LspWriter,
+    response: &(impl Serialize + Debug),
+    logger: &mut Logger,
+) -> io::Result<()> {
+    logger.debug(format_args!("Response: {response:#?}"));
+
+    match writer.send_response(response) {
+        Ok(()) => Ok(()),
+        Err(SendError::Encode(e)) => {
+            logger.error(format_args!("Failed to encode response: {e}"));
+            Ok(())
+        }
+        Err(SendError::Io(e)) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::LogLevel;
+    use huml_lsp::rpc::jsonrpc_decode;
+    use serde_json::{Value, json};
+    use std::{
+        io::{Cursor, Write},
+        net::{Shutdown, TcpListener},
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    /// An output whose content stays readable by the test once written to
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn quiet_logger() -> Logger {
+        Logger::new(LogLevel::Error, io::sink())
+    }
+
+    fn frame(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{body}", body.len())
+    }
+
+    /// Serves `input`, returning the bodies of the responses written
+    fn serve_input(input: String) -> Vec<Value> {
+        serve_transcript(input).messages
+    }
+
+    /// Serves `input`, returning what the server wrote in answer
+    fn serve_transcript(input: String) -> Transcript {
+        let output = SharedOutput::default();
+        let exit_code = serve(
+            Cursor::new(input),
+            LspWriter::new(output.clone()),
+            quiet_logger(),
+        )
+        .expect("Serving shouldn't fail");
+
+        let output = output.0.lock().unwrap().clone();
+        let messages = RPCMessageStream::new(Cursor::new(output))
+            .map(|message| jsonrpc_decode(&message.unwrap()).unwrap())
+            .collect();
+        Transcript {
+            exit_code,
+            messages,
+        }
+    }
+
+    /// The messages of a client to the server, framed and served through the same
+    /// decoding as the messages of a real client once the conversation is written
+    #[derive(Default)]
+    struct Conversation {
+        input: String,
+        last_id: i32,
+    }
+
+    impl Conversation {
+        /// Sends the request `method`, without params if they're `null`, returning its id
+        fn request(&mut self, method: &str, params: Value) -> i32 {
+            self.last_id += 1;
+            self.send(json!({ "id": self.last_id, "method": method }), params);
+            self.last_id
+        }
+
+        /// Sends the notification `method`, without params if they're `null`
+        fn notify(&mut self, method: &str, params: Value) {
+            self.send(json!({ "method": method }), params);
+        }
+
+        /// Answers the request `id` of the server with `result`
+        fn respond(&mut self, id: i32, result: Value) {
+            let response = json!({ "id": id, "result": result, "jsonrpc": "2.0" });
+            self.input.push_str(&frame(&response.to_string()));
+        }
+
+        fn send(&mut self, mut message: Value, params: Value) {
+            message["jsonrpc"] = json!("2.0");
+            if !params.is_null() {
+                message["params"] = params;
+            }
+            self.input.push_str(&frame(&message.to_string()));
+        }
+
+        fn serve(self) -> Transcript {
+            serve_transcript(self.input)
+        }
+    }
+
+    /// What the server wrote in answer to the messages of a client
+    struct Transcript {
+        /// The code the server exits with, if the client sent `exit`
+        exit_code: Option<u8>,
+        /// The bodies of the messages written, in the order they were written
+        messages: Vec<Value>,
+    }
+
+    impl Transcript {
+        /// Returns the response to the request with `id`
+        fn response(&self, id: i32) -> &Value {
+            self.messages
+                .iter()
+                .find(|message| message["id"] == id && message.get("method").is_none())
+                .unwrap_or_else(|| panic!("No response to {id} in {:?}", self.messages))
+        }
+
+        /// Returns the notifications `method` sent by the server, in the order they were
+        /// sent
+        fn notifications(&self, method: &str) -> Vec<&Value> {
+            self.messages
+                .iter()
+                .filter(|message| message["method"] == method && message.get("id").is_none())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn should_go_through_whole_lifecycle() {
+        let uri = "file:///tmp/lifecycle.huml";
+        let mut conversation = Conversation::default();
+        let initialize = conversation.request("initialize", json!({ "capabilities": {} }));
+        conversation.notify("initialized", json!({}));
+        conversation.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": "huml",
+                    "version": 1,
+                    "text": "key: 1\nkey: 2\n"
                 }
-            };
+            }),
+        );
+        let shutdown = conversation.request("shutdown", Value::Null);
+        conversation.notify("exit", Value::Null);
+        let after_exit = conversation.request("shutdown", Value::Null);
 
-        let response = match parsed_message {
-            RecievedMessage::Request(req) => server.handle_request(&req),
-            RecievedMessage::Notification(notification) => {
-                server.handle_notification(notification).unwrap();
-                continue;
+        let transcript = conversation.serve();
+
+        assert_eq!(transcript.exit_code, Some(0));
+        let capabilities = &transcript.response(initialize)["result"]["capabilities"];
+        assert!(capabilities.is_object());
+        let diagnostics = transcript.notifications("textDocument/publishDiagnostics");
+        assert_eq!(
+            diagnostics.len(),
+            1,
+            "Unexpected diagnostics: {diagnostics:?}"
+        );
+        assert_eq!(diagnostics[0]["params"]["uri"], uri);
+        let diagnostic = &diagnostics[0]["params"]["diagnostics"][0];
+        assert_eq!(diagnostic["range"]["start"]["line"], 1);
+        assert_eq!(transcript.response(shutdown)["result"], Value::Null);
+        assert!(
+            transcript
+                .messages
+                .iter()
+                .all(|message| message["id"] != after_exit),
+            "Expected the messages following `exit` not to be handled"
+        );
+    }
+
+    #[test]
+    fn should_write_notifications_when_client_disconnects() {
+        /// An output slow to write notifications, which are still being written once the
+        /// client is gone
+        #[derive(Clone, Default)]
+        struct SlowNotificationOutput(SharedOutput);
+
+        impl Write for SlowNotificationOutput {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if String::from_utf8_lossy(buf).contains("\"method\"") {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                self.0.write(buf)
             }
-        };
 
-        let encoded_response = match response.map(|msg| jsonrpc_encode(&msg)) {
-            Ok(Ok(res)) => res,
-            Err(e) => {
-                log(&format!("Failed to handle request: {e}"));
-                panic!("Request Handlingg Error: {e}")
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
             }
-            Ok(Err(e)) => {
-                log(&format!("Failed to encode response: {e}"));
-                panic!("Response encoding faileed: {e}")
+        }
+
+        let uri = "file:///tmp/disconnect.huml";
+        let mut conversation = Conversation::default();
+        conversation.request("initialize", json!({ "capabilities": {} }));
+        conversation.notify("initialized", json!({}));
+        conversation.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": "huml",
+                    "version": 1,
+                    "text": "key: 1\nkey: 2\n"
+                }
+            }),
+        );
+        let output = SlowNotificationOutput::default();
+
+        // The conversation ends without `shutdown` nor `exit`
+        let exit_code = serve(
+            Cursor::new(conversation.input),
+            LspWriter::new(output.clone()),
+            quiet_logger(),
+        )
+        .unwrap();
+
+        assert_eq!(exit_code, None);
+        let output = output.0.0.lock().unwrap().clone();
+        let diagnostics: Vec<Value> = RPCMessageStream::new(Cursor::new(output))
+            .map(|message| jsonrpc_decode(&message.unwrap()).unwrap())
+            .filter(|message: &Value| message["method"] == "textDocument/publishDiagnostics")
+            .collect();
+        assert_eq!(
+            diagnostics.len(),
+            1,
+            "Unexpected diagnostics: {diagnostics:?}"
+        );
+        assert_eq!(diagnostics[0]["params"]["uri"], uri);
+    }
+
+    #[test]
+    fn should_exit_with_error_without_shutdown() {
+        let mut conversation = Conversation::default();
+        conversation.request("initialize", json!({ "capabilities": {} }));
+        conversation.notify("exit", Value::Null);
+
+        assert_eq!(conversation.serve().exit_code, Some(1));
+    }
+
+    #[test]
+    fn should_keep_serving_after_garbage() {
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "capabilities": {} }
+        });
+        let input = [
+            frame("{ this is not json"),
+            "garbage without a header\r\n".to_string(),
+            frame(r#"{"jsonrpc":"2.0","method":"$/unknownNotification"}"#),
+            frame(&initialize.to_string()),
+        ]
+        .concat();
+
+        let responses = serve_input(input);
+
+        assert_eq!(responses.len(), 2, "Unexpected responses: {responses:?}");
+        assert_eq!(responses[0]["id"], Value::Null);
+        assert_eq!(responses[0]["error"]["code"], ErrorCode::ParseError as i32);
+        assert_eq!(responses[1]["id"], 1);
+        assert!(responses[1]["result"]["capabilities"].is_object());
+    }
+
+    #[test]
+    fn should_answer_initialize_with_server_info() {
+        let initialize =
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#;
+
+        let responses = serve_input(frame(initialize));
+
+        let server_info = &responses[0]["result"]["serverInfo"];
+        assert_eq!(server_info["name"], env!("CARGO_PKG_NAME"));
+        assert_eq!(server_info["version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn should_register_watched_files_without_answering_client_response() {
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "capabilities": {
+                    "workspace": { "didChangeWatchedFiles": { "dynamicRegistration": true } }
+                }
             }
+        });
+        let input = [
+            frame(&initialize.to_string()),
+            frame(r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#),
+            frame(r#"{"jsonrpc":"2.0","id":1,"result":null}"#),
+        ]
+        .concat();
+
+        let messages = serve_input(input);
+
+        assert_eq!(messages.len(), 2, "Unexpected messages: {messages:?}");
+        assert_eq!(messages[1]["method"], "client/registerCapability");
+        assert_eq!(messages[1]["id"], 1);
+        let registration = &messages[1]["params"]["registrations"][0];
+        assert_eq!(registration["method"], "workspace/didChangeWatchedFiles");
+        assert_eq!(
+            registration["registerOptions"]["watchers"][0]["globPattern"],
+            "**/.huml-lsp.toml"
+        );
+    }
+
+    #[test]
+    fn should_apply_converted_document_with_workspace_edit() {
+        let uri = "file:///tmp/convert.huml";
+        let mut conversation = Conversation::default();
+        conversation.request(
+            "initialize",
+            json!({ "capabilities": { "workspace": { "applyEdit": true } } }),
+        );
+        conversation.notify("initialized", json!({}));
+        conversation.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": "huml",
+                    "version": 1,
+                    "text": "port: 80\n"
+                }
+            }),
+        );
+        let convert = conversation.request(
+            "workspace/executeCommand",
+            json!({ "command": "huml.convertToJson", "arguments": [uri] }),
+        );
+        // The first request of the server
+        conversation.respond(1, json!({ "applied": false, "failureReason": "read-only" }));
+        conversation.request("shutdown", Value::Null);
+        conversation.notify("exit", Value::Null);
+
+        let transcript = conversation.serve();
+
+        let json = "{\n  \"port\": 80\n}";
+        assert_eq!(transcript.response(convert)["result"], json);
+        let apply_edit = transcript
+            .messages
+            .iter()
+            .find(|message| message["method"] == "workspace/applyEdit")
+            .expect("Expected the client to be asked to apply the edit");
+        assert_eq!(apply_edit["id"], 1);
+        assert_eq!(
+            apply_edit["params"]["edit"],
+            json!({
+                "changes": {
+                    uri: [{
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 1, "character": 0 }
+                        },
+                        "newText": json
+                    }]
+                }
+            })
+        );
+        let messages = transcript.notifications("window/showMessage");
+        assert_eq!(messages.len(), 1, "Unexpected messages: {messages:?}");
+        assert_eq!(
+            messages[0]["params"]["message"],
+            "The client didn't apply the edit: read-only"
+        );
+    }
+
+    #[test]
+    fn should_ignore_unsupported_notifications() {
+        let unknown = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/unknownNotify",
+            "params": { "textDocument": { "uri": "file:///config.huml" } }
+        });
+        let shutdown = r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#;
+        let input = [frame(&unknown.to_string()), frame(shutdown)].concat();
+
+        let responses = serve_input(input);
+
+        // Only the request following the notification is answered
+        assert_eq!(responses.len(), 1, "Unexpected responses: {responses:?}");
+        assert_eq!(responses[0]["id"], 1);
+    }
+
+    #[test]
+    fn should_stop_serving_on_read_error() {
+        struct BrokenReader;
+
+        impl Read for BrokenReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        let result = serve(BrokenReader, LspWriter::new(io::sink()), quiet_logger());
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn should_stop_serving_on_notification_write_error() {
+        /// An output refusing notifications, as if the client left once answered
+        struct NotificationRefusingOutput;
+
+        impl Write for NotificationRefusingOutput {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if String::from_utf8_lossy(buf).contains("\"method\"") {
+                    return Err(io::ErrorKind::BrokenPipe.into());
+                }
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut conversation = Conversation::default();
+        conversation.request("initialize", json!({ "capabilities": {} }));
+        conversation.notify("initialized", json!({}));
+        // The diagnostics of the document are published right away
+        conversation.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": "file:///tmp/a.huml",
+                    "languageId": "huml",
+                    "version": 1,
+                    "text": "port: 80\n"
+                }
+            }),
+        );
+        conversation.request("shutdown", Value::Null);
+        conversation.notify("exit", Value::Null);
+
+        let result = serve(
+            Cursor::new(conversation.input),
+            LspWriter::new(NotificationRefusingOutput),
+            quiet_logger(),
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn should_parse_transport_from_args() {
+        let transport = |args: &[&str]| {
+            let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+            Transport::from_args(&args)
         };
 
-        log(encoded_response.as_ref());
+        assert_eq!(transport(&[]), Ok(Transport::Stdio));
+        assert_eq!(transport(&["--stdio"]), Ok(Transport::Stdio));
+        assert_eq!(
+            transport(&["--socket", "5007"]),
+            Ok(Transport::Socket { port: 5007 })
+        );
+        assert_eq!(
+            transport(&["--socket=5007"]),
+            Ok(Transport::Socket { port: 5007 })
+        );
+        assert!(transport(&["--socket"]).is_err());
+        assert!(transport(&["--socket", "http"]).is_err());
+        assert!(transport(&["--pipe"]).is_err());
+    }
+
+    #[test]
+    fn should_serve_over_socket() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || serve_over(Transport::Socket { port }, quiet_logger()));
+        let (mut client, _) = listener.accept().unwrap();
+
+        let input = [
+            frame(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#),
+            frame(r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#),
+            frame(r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#),
+        ]
+        .concat();
+        client.write_all(input.as_bytes()).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        server.join().unwrap().expect("Serving shouldn't fail");
+
+        let responses: Vec<Value> = RPCMessageStream::new(client)
+            .map(|message| jsonrpc_decode(&message.unwrap()).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 2, "Unexpected responses: {responses:?}");
+        assert_eq!(responses[0]["id"], 1);
+        assert!(responses[0]["result"]["capabilities"].is_object());
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["result"], Value::Null);
+    }
+
+    #[test]
+    fn should_answer_unknown_request_with_method_not_found() {
+        let unknown = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "textDocument/foo",
+            "params": { "textDocument": { "uri": "file:///tmp/foo.huml" } }
+        });
+        let input = [
+            frame(&unknown.to_string()),
+            frame(r#"{"jsonrpc":"2.0","id":4,"method":"unknown/method"}"#),
+        ]
+        .concat();
+
+        let responses = serve_input(input);
+
+        assert_eq!(responses[0]["id"], 3);
+        assert_eq!(
+            responses[0]["error"]["code"],
+            ErrorCode::MethodNotFound as i32
+        );
+        assert_eq!(responses[1]["id"], 4);
+        assert_eq!(
+            responses[1]["error"]["code"],
+            ErrorCode::MethodNotFound as i32
+        );
+    }
+
+    #[test]
+    fn should_answer_batch_with_batch() {
+        let batch = json!([
+            {
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "capabilities": {} }
+            },
+            { "jsonrpc": "2.0", "method": "initialized", "params": {} }
+        ]);
+
+        let responses = serve_input(frame(&batch.to_string()));
+
+        assert_eq!(responses.len(), 1, "Expected a single frame");
+        let batch_response = responses[0].as_array().expect("Expected a batch response");
+        assert_eq!(batch_response.len(), 1);
+        assert_eq!(batch_response[0]["id"], 1);
+        assert!(batch_response[0]["result"]["capabilities"].is_object());
+    }
+
+    #[test]
+    fn should_isolate_invalid_messages_of_batch() {
+        let batch = json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "unknown/method" },
+            { "jsonrpc": "2.0", "method": "$/unknownNotification" },
+            {
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "initialize",
+                "params": { "capabilities": {} }
+            }
+        ]);
+
+        let responses = serve_input(frame(&batch.to_string()));
 
-        let mut stdout_writer = io::stdout().lock();
-        stdout_writer.write_all(encoded_response.as_bytes())?;
-        stdout_writer.flush()?;
+        let batch_response = responses[0].as_array().expect("Expected a batch response");
+        assert_eq!(batch_response.len(), 2);
+        assert_eq!(batch_response[0]["id"], 1);
+        assert_eq!(
+            batch_response[0]["error"]["code"],
+            ErrorCode::MethodNotFound as i32
+        );
+        assert_eq!(batch_response[1]["id"], 2);
+        assert!(batch_response[1]["result"]["capabilities"].is_object());
     }
-    Ok(())
 }
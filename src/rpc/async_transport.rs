@@ -0,0 +1,157 @@
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::rpc::{DecodeError, SendError, framing::MessageFramer, jsonrpc_encode};
+
+use super::transport::READ_CHUNK_SIZE;
+
+/// A stream of messages parsed from an asynchronous reader, e.g. the stdin of tokio.
+///
+/// Messages are framed the same way as by [`RPCMessageStream`](super::RPCMessageStream),
+/// without blocking the thread while waiting for the client, so that other requests can
+/// be handled in the meantime.
+pub struct AsyncRPCMessageStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    reader: R,
+    framer: MessageFramer,
+    /// The bytes of the last read, before they're framed
+    read_chunk: Box<[u8]>,
+}
+
+impl<R> AsyncRPCMessageStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            framer: MessageFramer::default(),
+            read_chunk: vec![0; READ_CHUNK_SIZE].into_boxed_slice(),
+        }
+    }
+
+    /// Reads the next message, returning `None` once the reader reaches its end between
+    /// two messages.
+    ///
+    /// Errors are the same as the ones of
+    /// [`RPCMessageStream::get_message_from_reader`](super::RPCMessageStream::get_message_from_reader),
+    /// and reading can go on after any of them.
+    pub async fn next_message(&mut self) -> Option<Result<String, DecodeError>> {
+        let message_len = loop {
+            match self.framer.next_message_len() {
+                Ok(Some(message_len)) => break message_len,
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            let bytes_read = match self.reader.read(&mut self.read_chunk).await {
+                Ok(bytes_read) => bytes_read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(DecodeError::Io(e))),
+            };
+            if bytes_read == 0 {
+                return self.framer.finish().err().map(Err);
+            }
+            self.framer.push(&self.read_chunk[..bytes_read]);
+        };
+
+        let message = self.framer.message(message_len).map(str::to_string);
+        if message.is_ok() {
+            self.framer.consume(message_len);
+        }
+        Some(message)
+    }
+}
+
+/// Writes the outgoing messages to an asynchronous output reaching the client.
+pub struct AsyncLspWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    output: W,
+}
+
+impl<W> AsyncLspWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+
+    /// Frames and writes `message`, a response, a batch of responses or a notification,
+    /// and flushes the output.
+    pub async fn send(&mut self, message: &impl Serialize) -> Result<(), SendError> {
+        let payload = jsonrpc_encode(message)?;
+        self.output.write_all(payload.as_bytes()).await?;
+        self.output.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+    use tokio::io::duplex;
+
+    use super::*;
+    use crate::rpc::jsonrpc_decode;
+
+    #[tokio::test]
+    async fn should_decode_multiple_messages_from_duplex_stream() {
+        // Smaller than a message, so that each one takes several reads
+        let (client, server) = duplex(16);
+        let messages = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+            json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+        ];
+
+        let send = async {
+            let mut writer = AsyncLspWriter::new(client);
+            for message in &messages {
+                writer.send(message).await.unwrap();
+            }
+            // Dropping the writer ends the stream
+        };
+        let receive = async {
+            let mut stream = AsyncRPCMessageStream::new(server);
+            let mut received = vec![];
+            while let Some(message) = stream.next_message().await {
+                received.push(jsonrpc_decode::<Value>(&message.unwrap()).unwrap());
+            }
+            received
+        };
+        let ((), received) = tokio::join!(send, receive);
+
+        assert_eq!(received, messages);
+    }
+
+    #[tokio::test]
+    async fn should_recover_from_invalid_data_and_truncated_messages() {
+        let (mut client, server) = duplex(1024);
+        let body = r#"{"jsonrpc":"2.0","id":1}"#;
+        let input = format!(
+            "garbage\r\nContent-Length: {}\r\n\r\n{body}Content-Length: 100\r\n\r\n{{",
+            body.len()
+        );
+
+        client.write_all(input.as_bytes()).await.unwrap();
+        drop(client);
+
+        let mut stream = AsyncRPCMessageStream::new(server);
+        assert!(matches!(
+            stream.next_message().await,
+            Some(Err(DecodeError::MissingOrInvalidHeader))
+        ));
+        let message = stream.next_message().await.unwrap().unwrap();
+        assert_eq!(jsonrpc_decode::<Value>(&message).unwrap()["id"], 1);
+        assert!(matches!(
+            stream.next_message().await,
+            Some(Err(DecodeError::UnexpectedEof))
+        ));
+        assert!(stream.next_message().await.is_none());
+    }
+}
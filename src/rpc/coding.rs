@@ -4,6 +4,10 @@ use crate::rpc::{DecodeError, EncodeError};
 
 pub const RPC_HEADER_PREFIX: &str = "Content-Length: ";
 pub const RPC_HEADER_LEN: usize = RPC_HEADER_PREFIX.len();
+/// The optional header describing the encoding of the body, which is always UTF-8.
+pub const RPC_CONTENT_TYPE_PREFIX: &str = "Content-Type: ";
+/// The separator between the header and the body of a message.
+pub const RPC_HEADER_END: &str = "\r\n\r\n";
 
 /// Encode a json serializable object as per the BASE_PROTOCOL specified
 /// in the LSP specification
@@ -11,11 +15,13 @@ pub const RPC_HEADER_LEN: usize = RPC_HEADER_PREFIX.len();
 /// SEE [BASE_PROTOCOL](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#baseProtocol)
 pub fn jsonrpc_encode<DType: Serialize>(data: &DType) -> Result<String, EncodeError> {
     let json = serde_json::to_string(data)?;
+    // The header counts bytes, not characters, which `str::len` returns
     let content_length = json.len();
 
     Ok(format!("Content-Length: {content_length}\r\n\r\n{json}"))
 }
 
+/// Decode a message framed as per the BASE_PROTOCOL specified
 /// in the LSP specification
 ///
 /// SEE [BASE_PROTOCOL](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#baseProtocol)
@@ -24,23 +30,10 @@ where
     DType: Deserialize<'de>,
 {
     // Split header and body
-    let mut split_data_iter = data.split("\r\n\r\n");
-
-    // Extract header and body
-    let header = split_data_iter
-        .next()
-        .ok_or(DecodeError::MissingOrInvalidHeader)?;
-    let body = split_data_iter.next().ok_or(DecodeError::IncompleteData)?;
-
-    // Prase Content-Length from header
-    if !header.starts_with(RPC_HEADER_PREFIX) {
-        return Err(DecodeError::MissingOrInvalidHeader);
-    }
-    let content_length_str = &header[RPC_HEADER_LEN..];
-    let content_length: usize = content_length_str
-        .trim()
-        .parse()
-        .map_err(|e| DecodeError::ContentLengthNotNumber(e))?;
+    let (header, body) = data
+        .split_once(RPC_HEADER_END)
+        .ok_or(DecodeError::IncompleteData)?;
+    let content_length = parse_content_length(header)?;
 
     // Validate body length
     if body.len() != content_length {
@@ -53,11 +46,40 @@ where
     Ok(deserialized_data)
 }
 
+/// Parses the header lines of a message, without the blank line ending them, and
+/// returns the length of the body given by its `Content-Length` header.
+///
+/// The optional `Content-Type` header is ignored, as the body is always UTF-8 encoded
+/// JSON. Header names are matched ignoring their case.
+pub fn parse_content_length(header: &str) -> Result<usize, DecodeError> {
+    let mut content_length = None;
+    for line in header.split("\r\n") {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or(DecodeError::MissingOrInvalidHeader)?;
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            let length = value
+                .trim()
+                .parse()
+                .map_err(DecodeError::ContentLengthNotNumber)?;
+            content_length = Some(length);
+        } else if !name.eq_ignore_ascii_case("Content-Type") {
+            return Err(DecodeError::MissingOrInvalidHeader);
+        }
+    }
+    content_length.ok_or(DecodeError::MissingOrInvalidHeader)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use serde::{Deserialize, Serialize};
 
-    use crate::rpc::jsonrpc_decode;
+    use crate::rpc::{
+        DecodeError, EncodeError, jsonrpc_decode, jsonrpc_encode, parse_content_length,
+    };
 
     #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
     struct TestStruct {
@@ -82,4 +104,60 @@ mod tests {
         };
         assert_eq!(decoded_value, expected_value);
     }
+
+    #[test]
+    fn should_decode_with_content_type_in_either_order() {
+        let content_type = "Content-Type: application/vscode-jsonrpc; charset=utf-8";
+        let body = "{\"jsonrpc\":\"2.0\"}";
+        let messages = [
+            format!("Content-Length: 17\r\n{content_type}\r\n\r\n{body}"),
+            format!("{content_type}\r\nContent-Length: 17\r\n\r\n{body}"),
+        ];
+
+        for message in messages {
+            let decoded_value: TestStruct = jsonrpc_decode(&message).expect("Decoding failed");
+            assert_eq!(decoded_value.jsonrpc, "2.0");
+        }
+    }
+
+    #[test]
+    fn should_require_content_length() {
+        assert!(matches!(
+            parse_content_length("Content-Type: application/vscode-jsonrpc; charset=utf-8"),
+            Err(DecodeError::MissingOrInvalidHeader)
+        ));
+        assert!(matches!(
+            parse_content_length("Content-Length: 17\r\nX-Unknown: 1"),
+            Err(DecodeError::MissingOrInvalidHeader)
+        ));
+        assert_eq!(parse_content_length("content-length:17").unwrap(), 17);
+    }
+
+    #[test]
+    fn should_fail_to_encode_unserializable_value() {
+        // JSON objects can't have non-string keys
+        let data = HashMap::from([((1, 2), 3)]);
+
+        let error: EncodeError = jsonrpc_encode(&data).expect_err("Encoding succeeded");
+
+        assert!(error.to_string().contains("key must be a string"));
+    }
+
+    #[test]
+    fn should_round_trip_multibyte_payload() {
+        let test_data = TestStruct {
+            jsonrpc: "2.0 🦀 é".to_string(),
+        };
+        let body = "{\"jsonrpc\":\"2.0 🦀 é\"}";
+
+        let encoded = jsonrpc_encode(&test_data).expect("Encoding failed");
+
+        assert_eq!(body.len(), body.chars().count() + 4);
+        assert_eq!(
+            encoded,
+            format!("Content-Length: {}\r\n\r\n{body}", body.len())
+        );
+        let decoded_value: TestStruct = jsonrpc_decode(&encoded).expect("Decoding failed");
+        assert_eq!(decoded_value, test_data);
+    }
 }
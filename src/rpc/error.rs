@@ -1,4 +1,4 @@
-use std::{num::ParseIntError, str::Utf8Error};
+use std::{io, num::ParseIntError, str::Utf8Error};
 
 #[derive(thiserror::Error, Debug)]
 pub enum CodingError {
@@ -12,6 +12,15 @@ pub enum CodingError {
 #[error("Encode failed due to JSON error: {0}")]
 pub struct EncodeError(#[from] serde_json::Error);
 
+/// The reasons an outgoing message can't be sent.
+#[derive(thiserror::Error, Debug)]
+pub enum SendError {
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+    #[error("Failed to write message: {0}")]
+    Io(#[from] io::Error),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum DecodeError {
     #[error("Missing or invalid header in the data.")]
@@ -20,8 +29,14 @@ pub enum DecodeError {
     InvalidContentLengthEncoding(Utf8Error),
     #[error("Error converting content length to usize. {0}")]
     ContentLengthNotNumber(ParseIntError),
+    #[error("Error converting message to utf8. {0}")]
+    InvalidMessageEncoding(Utf8Error),
     #[error("Data length does not match Content-Length")]
     IncompleteData,
+    #[error("Reached the end of the stream in the middle of a message")]
+    UnexpectedEof,
+    #[error("Failed to read message: {0}")]
+    Io(#[from] io::Error),
     #[error("JSON deserialization error: {0}")]
     JsonError(#[from] serde_json::Error),
 }
@@ -0,0 +1,143 @@
+use crate::rpc::{
+    DecodeError, RPC_CONTENT_TYPE_PREFIX, RPC_HEADER_END, RPC_HEADER_LEN, RPC_HEADER_PREFIX,
+    parse_content_length,
+};
+
+/// Splits the bytes read from the client into messages, independently of how they're
+/// read.
+///
+/// The bytes scanned for the end of the header, and the header once parsed, are
+/// remembered, so that each byte is only scanned once however many reads a message
+/// takes.
+#[derive(Default)]
+pub(super) struct MessageFramer {
+    buffer: Vec<u8>,
+    /// How much of the message at the start of the buffer was framed
+    frame: FrameState,
+}
+
+/// The progress made framing the message at the start of the buffer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FrameState {
+    /// The end of the header wasn't found in the first `scanned` bytes.
+    Header { scanned: usize },
+    /// The header was parsed, and the message ends at the byte `end`.
+    Body { end: usize },
+}
+
+impl Default for FrameState {
+    fn default() -> Self {
+        Self::Header { scanned: 0 }
+    }
+}
+
+impl MessageFramer {
+    /// Appends `bytes` read from the client.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Returns the length of the message at the start of the buffer, or `None` if it
+    /// hasn't been fully received yet.
+    ///
+    /// Data that doesn't start with a valid header is discarded, up to the next header
+    /// if one is buffered, so that framing can go on with the next message.
+    pub fn next_message_len(&mut self) -> Result<Option<usize>, DecodeError> {
+        self.buffered_message_len().inspect_err(|_| {
+            self.discard_invalid_header();
+        })
+    }
+
+    /// Returns the buffered message of `len` bytes, as returned by
+    /// [`next_message_len`](Self::next_message_len).
+    ///
+    /// Messages are framed on the raw bytes read, as `Content-Length` counts bytes and a
+    /// read may end in the middle of a character. The message is only converted to UTF-8
+    /// once it's complete, failing with [`DecodeError::InvalidMessageEncoding`], and
+    /// discarded, if it isn't valid UTF-8.
+    pub fn message(&mut self, len: usize) -> Result<&str, DecodeError> {
+        if let Err(e) = str::from_utf8(&self.buffer[..len]) {
+            self.consume(len);
+            return Err(DecodeError::InvalidMessageEncoding(e));
+        }
+        Ok(str::from_utf8(&self.buffer[..len]).expect("Message was checked to be valid utf8"))
+    }
+
+    /// Drops the first `len` bytes of the buffer, the start of the next message being
+    /// framed from scratch.
+    pub fn consume(&mut self, len: usize) {
+        self.buffer.drain(..len);
+        self.frame = FrameState::default();
+    }
+
+    /// Ends the framing once the client has nothing more to send. Fails with
+    /// [`DecodeError::UnexpectedEof`] if a message was incomplete, in which case it's
+    /// discarded.
+    pub fn finish(&mut self) -> Result<(), DecodeError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.consume(self.buffer.len());
+        Err(DecodeError::UnexpectedEof)
+    }
+
+    /// Drops the data at the start of the buffer, which doesn't start with a valid header,
+    /// up to the next header if one is buffered.
+    fn discard_invalid_header(&mut self) {
+        let next_header = (1..self.buffer.len())
+            .find(|&position| {
+                let rest = &self.buffer[position..];
+                rest.starts_with(RPC_HEADER_PREFIX.as_bytes())
+                    || rest.starts_with(RPC_CONTENT_TYPE_PREFIX.as_bytes())
+            })
+            .unwrap_or(self.buffer.len());
+        self.consume(next_header);
+    }
+
+    fn buffered_message_len(&mut self) -> Result<Option<usize>, DecodeError> {
+        let scanned = match self.frame {
+            FrameState::Body { end } => return Ok((end <= self.buffer.len()).then_some(end)),
+            FrameState::Header { scanned } => scanned,
+        };
+
+        // Ensure we have enough bytes to test for header
+        if self.buffer.len() <= RPC_HEADER_LEN {
+            return Ok(None);
+        }
+
+        // Check for header presence a the beginning of the message, either
+        // `Content-Length: <number>` or the optional `Content-Type: <type>`
+        if !self.buffer.starts_with(RPC_HEADER_PREFIX.as_bytes())
+            && !self.buffer.starts_with(RPC_CONTENT_TYPE_PREFIX.as_bytes())
+        {
+            return Err(DecodeError::MissingOrInvalidHeader);
+        }
+
+        // Find index of the double crlf, i.e. (\r\n\r\n) to find the header boundary. It
+        // may have started at the end of the bytes already scanned.
+        let scan_start = scanned.saturating_sub(RPC_HEADER_END.len() - 1);
+        let Some(double_crlf_loc) = self.buffer[scan_start..]
+            .windows(RPC_HEADER_END.len())
+            .position(|window| window == RPC_HEADER_END.as_bytes())
+            .map(|position| scan_start + position)
+        else {
+            // Have not recieved enough bytes yet.
+            self.frame = FrameState::Header {
+                scanned: self.buffer.len(),
+            };
+            return Ok(None);
+        };
+
+        // Calculate the length of the body
+        let header = str::from_utf8(&self.buffer[..double_crlf_loc])
+            .map_err(DecodeError::InvalidContentLengthEncoding)?;
+        let content_length = parse_content_length(header)?;
+
+        // The body is the content after the double crlf
+        let body_start_pos = double_crlf_loc + RPC_HEADER_END.len();
+        self.frame = FrameState::Body {
+            end: body_start_pos + content_length,
+        };
+        self.buffered_message_len()
+    }
+}
@@ -10,20 +10,33 @@
 //! and deserialization between JSON and Rust structs, and reading from/writing to
 //! the underlying I/O streams (typically `stdin` and `stdout`).
 
+/// Reads and writes messages with tokio, without blocking the thread.
+#[cfg(feature = "async")]
+mod async_transport;
+
 /// Handles the encoding and decoding of JSON-RPC messages.
 mod coding;
 
 /// Defines errors specific to the JSON-RPC communication layer.
 mod error;
 
+/// Splits the bytes read from the client into messages.
+mod framing;
+
 /// Manages the transport layer for sending and receiving messages.
 mod transport;
 
 /// Defines the core data structures of a JSON-RPC message.
 mod types;
 
+/// Serializes the writes of outgoing messages to the client.
+mod writer;
+
 // Re-export the public items from the submodules for easier access.
+#[cfg(feature = "async")]
+pub use async_transport::*;
 pub use coding::*;
 pub use error::*;
 pub use transport::*;
 pub use types::*;
+pub use writer::*;
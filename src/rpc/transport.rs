@@ -1,5 +1,9 @@
-use crate::rpc::{DecodeError, RPC_HEADER_LEN, RPC_HEADER_PREFIX};
-use std::io::Read;
+use crate::rpc::{DecodeError, framing::MessageFramer, jsonrpc_decode};
+use serde::Deserialize;
+use std::io::{self, Read};
+
+/// The maximum number of bytes read at once.
+pub(super) const READ_CHUNK_SIZE: usize = 64 * 1024;
 
 /// A stream of messages parsed from a reader
 pub struct RPCMessageStream<R>
@@ -7,7 +11,9 @@ where
     R: Read,
 {
     reader: R,
-    read_buffer: Vec<u8>,
+    framer: MessageFramer,
+    /// The bytes of the last read, before they're framed
+    read_chunk: Box<[u8]>,
 }
 
 impl<R> RPCMessageStream<R>
@@ -17,70 +23,56 @@ where
     pub fn new(reader: R) -> Self {
         Self {
             reader,
-            read_buffer: Vec::with_capacity(1024),
+            framer: MessageFramer::default(),
+            read_chunk: vec![0; READ_CHUNK_SIZE].into_boxed_slice(),
+        }
+    }
+
+    /// Turns the stream into a [`TypedMessageStream`], which decodes the body of each
+    /// message as it's read.
+    pub fn typed(self) -> TypedMessageStream<R> {
+        TypedMessageStream {
+            messages: self,
+            message: String::new(),
         }
     }
 
-    pub fn get_message_from_reader(&mut self) -> Result<&str, DecodeError>
+    /// Reads from the reader until a whole message is buffered, and returns it.
+    ///
+    /// Returns `Ok(None)` once the reader reaches its end between two messages, and
+    /// [`DecodeError::UnexpectedEof`] if it ends in the middle of a message, in which case
+    /// the incomplete message is discarded.
+    ///
+    /// Messages are framed on the raw bytes read, as `Content-Length` counts bytes and a
+    /// read may end in the middle of a character. The message is only converted to UTF-8
+    /// once it's complete, failing with [`DecodeError::InvalidMessageEncoding`] if it
+    /// isn't valid UTF-8.
+    ///
+    /// Invalid data is discarded as well, up to the next header, so that reading can go
+    /// on with the next message. Reads failing with anything but
+    /// [`io::ErrorKind::Interrupted`] fail with [`DecodeError::Io`], keeping the data
+    /// buffered so far.
+    pub fn get_message_from_reader(&mut self) -> Result<Option<&str>, DecodeError>
     where
         R: Read,
     {
-        let message_end_index: usize;
-        loop {
-            let mut read_buf = [0; 400];
-            let Ok(bytes_read) = self.reader.read(&mut read_buf) else {
-                continue;
-            };
-            self.read_buffer.extend_from_slice(&read_buf[..bytes_read]);
-
-            // Ensure we have enough bytes to test for header
-            if self.read_buffer.len() <= RPC_HEADER_LEN {
-                continue;
-            }
-
-            // Check for header presence a the beginning of the message
-            // RPC_HEADER_PREFIX - Content-Length: <number>
-            if !self.read_buffer.starts_with(RPC_HEADER_PREFIX.as_bytes()) {
-                return Err(DecodeError::MissingOrInvalidHeader);
+        let message_len = loop {
+            if let Some(message_len) = self.framer.next_message_len()? {
+                break message_len;
             }
 
-            // Find index of crlf, i.e. (\r\n\r\n) to find the header boundary
-            let Some(content_length_digits) = self.read_buffer[RPC_HEADER_LEN..]
-                .iter()
-                .position(|&byte| byte == b'\r')
-            else {
-                // Have not recieved enough bytes yet.
-                continue;
+            let bytes_read = match self.reader.read(&mut self.read_chunk) {
+                Ok(bytes_read) => bytes_read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(DecodeError::Io(e)),
             };
-
-            // Calculate the length of the body
-            let double_crlf_loc = RPC_HEADER_LEN + content_length_digits;
-            let content_length_str =
-                str::from_utf8(&self.read_buffer[RPC_HEADER_LEN..double_crlf_loc])
-                    .map_err(|e| DecodeError::InvalidContentLengthEncoding(e))?;
-
-            let content_length: usize = content_length_str
-                .trim()
-                .parse()
-                .map_err(|e| DecodeError::ContentLengthNotNumber(e))?;
-
-            // Check the presence of body, i.e. the content after the double crlf
-            let body_start_pos = double_crlf_loc + "\r\n\r\n".len();
-            let body_end_pos = body_start_pos + content_length;
-
-            // Enough of the body is not recieved yet
-            if body_end_pos > self.read_buffer.len() {
-                continue;
+            if bytes_read == 0 {
+                return self.framer.finish().map(|()| None);
             }
+            self.framer.push(&self.read_chunk[..bytes_read]);
+        };
 
-            message_end_index = body_end_pos;
-            break;
-        }
-
-        let message = str::from_utf8(&self.read_buffer[..message_end_index].as_ref())
-            .expect("Invalid Message Format - Conversion to utf8 failed");
-
-        Ok(message)
+        self.framer.message(message_len).map(Some)
     }
 }
 
@@ -93,28 +85,76 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let message = self
             .get_message_from_reader()
-            .map(|message| message.to_string())
+            .map(|message| message.map(str::to_string))
             .inspect(|message| {
-                self.read_buffer.drain(..message.len());
+                if let Some(message) = message {
+                    self.framer.consume(message.len());
+                }
             });
 
-        Some(message)
+        message.transpose()
+    }
+}
+
+/// A stream of messages parsed from a reader, with their bodies decoded.
+///
+/// Decoded messages may borrow from the stream, so they are read with
+/// [`TypedMessageStream::next_message`] rather than through an [`Iterator`].
+pub struct TypedMessageStream<R>
+where
+    R: Read,
+{
+    messages: RPCMessageStream<R>,
+    /// The last message read, which the decoded message borrows from
+    message: String,
+}
+
+impl<R> TypedMessageStream<R>
+where
+    R: Read,
+{
+    /// Reads the next message and decodes its body as a `T`.
+    pub fn next_message<'a, T>(&'a mut self) -> Option<Result<T, DecodeError>>
+    where
+        T: Deserialize<'a>,
+    {
+        self.message = match self.messages.next()? {
+            Ok(message) => message,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(jsonrpc_decode(&self.message))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::rpc::{DecodeError, RPCMessageStream};
+    use crate::{
+        lsp::{recieved_message::RecievedMessage, request::RequestMethod},
+        rpc::{DecodeError, RPCMessageStream},
+    };
     use std::{
-        io::{self, Cursor, Write},
+        io::{self, Cursor, Read, Write},
         thread,
-        time::Duration,
+        time::{Duration, Instant},
     };
 
+    /// A reader returning at most `chunk_size` bytes per read
+    struct ChunkedReader {
+        data: Cursor<Vec<u8>>,
+        chunk_size: usize,
+    }
+
+    impl Read for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.chunk_size);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
     #[test]
     fn should_deserialize_from_buf_with_payload() {
         let json_str =
-            format!("Content-Length: 35\r\n\r\n{{\"jsonrpc\":\"2.0\",\"message\":\"Hello\"}}");
+            "Content-Length: 35\r\n\r\n{\"jsonrpc\":\"2.0\",\"message\":\"Hello\"}".to_string();
 
         let json_buf = Cursor::new(json_str.clone());
         let mut rpc_stream = RPCMessageStream::new(json_buf);
@@ -136,17 +176,33 @@ mod tests {
         assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg2);
     }
 
+    #[test]
+    fn should_read_messages_with_content_type_header() {
+        let body = "{\"jsonrpc\":\"2.0\"}";
+        let content_type = "Content-Type: application/vscode-jsonrpc; charset=utf-8";
+        let json_msg1 = format!("Content-Length: 17\r\n{content_type}\r\n\r\n{body}");
+        let json_msg2 = format!("{content_type}\r\nContent-Length: 17\r\n\r\n{body}");
+        let json_msg3 = format!("Content-Length: 17\r\n\r\n{body}");
+        let json_buf = Cursor::new(format!("{json_msg1}{json_msg2}{json_msg3}"));
+        let mut rpc_stream = RPCMessageStream::new(json_buf);
+
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg1);
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg2);
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg3);
+        assert!(rpc_stream.next().is_none());
+    }
+
     #[test]
     fn should_wait_till_payload_ready() {
         let json_str =
-            format!("Content-Length: 35\r\n\r\n{{\"jsonrpc\":\"2.0\",\"message\":\"Hello\"}}");
+            "Content-Length: 35\r\n\r\n{\"jsonrpc\":\"2.0\",\"message\":\"Hello\"}".to_string();
 
         let (reader, mut writer) = io::pipe().unwrap();
         thread::spawn({
             let json_str = json_str.clone();
             move || {
                 for string_chunk in json_str.as_bytes().chunks(5) {
-                    writer.write(string_chunk).unwrap();
+                    writer.write_all(string_chunk).unwrap();
 
                     thread::sleep(Duration::from_millis(100));
                 }
@@ -158,9 +214,45 @@ mod tests {
         assert_eq!(rpc_stream.next().unwrap().unwrap(), json_str);
     }
 
+    #[test]
+    fn should_frame_multibyte_characters_split_across_reads() {
+        let body = r#"{"jsonrpc":"2.0","message":"héllo wörld ✓ 🦀"}"#;
+        let json_msg = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+        let reader = ChunkedReader {
+            data: Cursor::new(json_msg.repeat(2).into_bytes()),
+            chunk_size: 5,
+        };
+        let mut rpc_stream = RPCMessageStream::new(reader);
+
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg);
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg);
+        assert!(rpc_stream.next().is_none());
+    }
+
+    #[test]
+    fn should_frame_multi_megabyte_messages_promptly() {
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","text":"{}"}}"#,
+            "a".repeat(8 * 1024 * 1024)
+        );
+        let json_msg = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+        let reader = ChunkedReader {
+            data: Cursor::new(format!("{json_msg}{json_msg}").into_bytes()),
+            chunk_size: 4096,
+        };
+        let mut rpc_stream = RPCMessageStream::new(reader);
+        let start = Instant::now();
+
+        assert!(rpc_stream.next().unwrap().unwrap() == json_msg);
+        assert!(rpc_stream.next().unwrap().unwrap() == json_msg);
+        assert!(rpc_stream.next().is_none());
+        // Generous enough for slow machines, but far below rescanning the buffer
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
     #[test]
     fn should_err_for_invalid_header() {
-        let json_str = format!("{{\"jsonrpc\":\"2.0\",\"message\":\"Hello\"}}");
+        let json_str = "{\"jsonrpc\":\"2.0\",\"message\":\"Hello\"}".to_string();
         let json_buf = Cursor::new(json_str);
         let mut rpc_stream = RPCMessageStream::new(json_buf);
 
@@ -168,5 +260,130 @@ mod tests {
             rpc_stream.next().unwrap(),
             Err(DecodeError::MissingOrInvalidHeader)
         ));
+        assert!(rpc_stream.next().is_none());
+    }
+
+    #[test]
+    fn should_skip_to_next_header_after_invalid_data() {
+        let json_msg = "Content-Length: 17\r\n\r\n{\"jsonrpc\":\"2.0\"}";
+        let json_buf = Cursor::new(format!("garbage\r\n{json_msg}"));
+        let mut rpc_stream = RPCMessageStream::new(json_buf);
+
+        assert!(matches!(
+            rpc_stream.next().unwrap(),
+            Err(DecodeError::MissingOrInvalidHeader)
+        ));
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg);
+    }
+
+    #[test]
+    fn should_err_for_message_not_in_utf8() {
+        let mut data = b"Content-Length: 2\r\n\r\n\xff\xfe".to_vec();
+        data.extend_from_slice(b"Content-Length: 2\r\n\r\n{}");
+        let mut rpc_stream = RPCMessageStream::new(Cursor::new(data));
+
+        assert!(matches!(
+            rpc_stream.next().unwrap(),
+            Err(DecodeError::InvalidMessageEncoding(_))
+        ));
+        assert_eq!(
+            rpc_stream.next().unwrap().unwrap(),
+            "Content-Length: 2\r\n\r\n{}"
+        );
+    }
+
+    #[test]
+    fn should_end_with_reader() {
+        let json_msg = "Content-Length: 17\r\n\r\n{\"jsonrpc\":\"2.0\"}";
+        let mut rpc_stream = RPCMessageStream::new(Cursor::new(json_msg));
+
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg);
+        assert!(rpc_stream.next().is_none());
+    }
+
+    #[test]
+    fn should_err_for_eof_in_the_middle_of_a_message() {
+        let json_str = "Content-Length: 35\r\n\r\n{\"jsonrpc\":\"2.0\"";
+        let mut rpc_stream = RPCMessageStream::new(Cursor::new(json_str));
+
+        assert!(matches!(
+            rpc_stream.next().unwrap(),
+            Err(DecodeError::UnexpectedEof)
+        ));
+        assert!(rpc_stream.next().is_none());
+    }
+
+    /// A reader returning `data`, then failing with `error`
+    struct FailingReader {
+        data: Cursor<Vec<u8>>,
+        error: io::ErrorKind,
+    }
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.data.read(buf)? {
+                0 => Err(self.error.into()),
+                bytes_read => Ok(bytes_read),
+            }
+        }
+    }
+
+    #[test]
+    fn should_err_for_failed_reads() {
+        let reader = FailingReader {
+            data: Cursor::new(b"Content-Length: 17\r\n\r\n{".to_vec()),
+            error: io::ErrorKind::BrokenPipe,
+        };
+        let mut rpc_stream = RPCMessageStream::new(reader);
+
+        let Some(Err(DecodeError::Io(e))) = rpc_stream.next() else {
+            panic!("Expected the read error");
+        };
+        assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    /// A reader interrupted before every successful read
+    struct InterruptedReader {
+        data: Cursor<Vec<u8>>,
+        interrupted: bool,
+    }
+
+    impl Read for InterruptedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let len = buf.len().min(5);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn should_retry_interrupted_reads() {
+        let json_msg = "Content-Length: 17\r\n\r\n{\"jsonrpc\":\"2.0\"}";
+        let reader = InterruptedReader {
+            data: Cursor::new(json_msg.as_bytes().to_vec()),
+            interrupted: false,
+        };
+        let mut rpc_stream = RPCMessageStream::new(reader);
+
+        assert_eq!(rpc_stream.next().unwrap().unwrap(), json_msg);
+        assert!(rpc_stream.next().is_none());
+    }
+
+    #[test]
+    fn should_decode_typed_messages() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#;
+        let framed = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+        let mut messages = RPCMessageStream::new(Cursor::new(framed)).typed();
+
+        let message = messages.next_message::<RecievedMessage>().unwrap();
+
+        let Ok(RecievedMessage::Request(request)) = message else {
+            panic!("Expected an initialize request, got {message:?}");
+        };
+        assert_eq!(request.id(), 1);
+        assert!(matches!(request.method(), RequestMethod::Initialize(_)));
     }
 }
@@ -5,14 +5,17 @@ use serde::{Deserialize, Serialize};
 // Define type aliases for all the base types
 pub type Integer = i32;
 pub type UInteger = usize;
-pub type Decimal = u64;
+pub type Decimal = f64;
 pub type LSPArray = Vec<LSPAny>;
 pub type LSPObject = HashMap<String, LSPAny>;
 
 /// This enum represents any usable value in the JSON rpc specification
 /// that is not null. This type is not in itself part of the spec,
 /// but allows for marking types that would never be nullable.
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// Numbers are read as the first variant able to hold them, so whole numbers are
+/// integers, and only fractional or out of range numbers are decimals.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(untagged)]
 pub enum LSPAny {
     LSPObject(LSPObject),
@@ -23,3 +26,32 @@ pub enum LSPAny {
     Decimal(Decimal),
     Boolean(bool),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Any fractional number, not an approximation of pi
+    #[allow(clippy::approx_constant)]
+    fn should_deserialize_numbers_into_narrowest_variant() {
+        let numbers: Vec<LSPAny> = serde_json::from_str("[3.14, -2, 10, 3000000000]").unwrap();
+
+        assert_eq!(
+            numbers,
+            [
+                LSPAny::Decimal(3.14),
+                LSPAny::Integer(-2),
+                LSPAny::Integer(10),
+                LSPAny::UInteger(3_000_000_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_serialize_decimals_as_floats() {
+        let json = serde_json::to_string(&LSPAny::Decimal(0.5)).unwrap();
+
+        assert_eq!(json, "0.5");
+    }
+}
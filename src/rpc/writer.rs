@@ -0,0 +1,195 @@
+use std::{
+    io::{self, ErrorKind, Write},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::rpc::{SendError, jsonrpc_encode};
+
+/// How long to wait before retrying a write to an output that isn't ready.
+const WOULD_BLOCK_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+/// Writes the outgoing messages to the output reaching the client.
+///
+/// The output is shared by the clones of the writer, e.g. by the thread answering
+/// requests and the one sending notifications. Each message is written as a whole
+/// while holding the output, so the frames of concurrent messages never interleave.
+#[derive(Clone)]
+pub struct LspWriter {
+    output: Arc<Mutex<dyn Write + Send>>,
+}
+
+impl LspWriter {
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+        }
+    }
+
+    /// Frames and writes the response to a request, or a batch of responses.
+    pub fn send_response(&self, response: &impl Serialize) -> Result<(), SendError> {
+        self.send(response)
+    }
+
+    /// Frames and writes a notification.
+    pub fn send_notification(&self, notification: &impl Serialize) -> Result<(), SendError> {
+        self.send(notification)
+    }
+
+    /// Frames and writes a request sent to the client.
+    pub fn send_request(&self, request: &impl Serialize) -> Result<(), SendError> {
+        self.send(request)
+    }
+
+    fn send(&self, message: &impl Serialize) -> Result<(), SendError> {
+        let payload = jsonrpc_encode(message)?;
+        // A panic while writing leaves nothing to protect, the next frame starts anew
+        let mut output = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+        write_payload(&mut *output, payload.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Writes the whole `payload` to `output` and flushes it.
+///
+/// Partial writes are continued, and writes interrupted or refused because the output
+/// isn't ready are retried.
+fn write_payload(output: &mut (impl Write + ?Sized), mut payload: &[u8]) -> io::Result<()> {
+    while !payload.is_empty() {
+        match output.write(payload) {
+            Ok(0) => return Err(ErrorKind::WriteZero.into()),
+            Ok(written) => payload = &payload[written..],
+            Err(e) => wait_before_retry(e)?,
+        }
+    }
+    loop {
+        match output.flush() {
+            Ok(()) => return Ok(()),
+            Err(e) => wait_before_retry(e)?,
+        }
+    }
+}
+
+/// Waits until an operation that failed with `error` can be retried, or returns the
+/// error if it isn't transient.
+fn wait_before_retry(error: io::Error) -> io::Result<()> {
+    match error.kind() {
+        ErrorKind::Interrupted => Ok(()),
+        ErrorKind::WouldBlock => {
+            thread::sleep(WOULD_BLOCK_RETRY_DELAY);
+            Ok(())
+        }
+        _ => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::{Value, json};
+
+    use crate::rpc::{RPCMessageStream, jsonrpc_decode};
+
+    use super::*;
+
+    /// A writer failing with the errors of `failures` before accepting a few bytes at a
+    /// time.
+    struct FlakyWriter {
+        written: Vec<u8>,
+        failures: Vec<ErrorKind>,
+    }
+
+    impl Write for FlakyWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if let Some(kind) = self.failures.pop() {
+                return Err(kind.into());
+            }
+            let written = buf.len().min(5);
+            self.written.extend_from_slice(&buf[..written]);
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A writer accepting a single byte at a time, leaving other threads the time to
+    /// interleave their writes, into a buffer shared with the test.
+    struct SlowWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            thread::yield_now();
+            let Some(&byte) = buf.first() else {
+                return Ok(0);
+            };
+            self.0.lock().unwrap().push(byte);
+            Ok(1)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn should_retry_interrupted_and_partial_writes() {
+        let mut writer = FlakyWriter {
+            written: vec![],
+            failures: vec![ErrorKind::Interrupted, ErrorKind::WouldBlock],
+        };
+        let payload = "Content-Length: 17\r\n\r\n{\"jsonrpc\":\"2.0\"}";
+
+        write_payload(&mut writer, payload.as_bytes()).expect("Write should be retried");
+
+        assert!(writer.failures.is_empty());
+        assert_eq!(writer.written, payload.as_bytes());
+    }
+
+    #[test]
+    fn should_report_fatal_write_errors() {
+        let writer = LspWriter::new(FlakyWriter {
+            written: vec![],
+            failures: vec![ErrorKind::BrokenPipe],
+        });
+
+        let result = writer.send_notification(&json!({ "jsonrpc": "2.0", "method": "exit" }));
+
+        assert!(matches!(result, Err(SendError::Io(e)) if e.kind() == ErrorKind::BrokenPipe));
+    }
+
+    #[test]
+    fn should_not_interleave_concurrent_messages() {
+        let output = Arc::new(Mutex::new(vec![]));
+        let writer = LspWriter::new(SlowWriter(output.clone()));
+        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": "response" });
+        let notification = json!({ "jsonrpc": "2.0", "method": "notification" });
+
+        let notifier = thread::spawn({
+            let writer = writer.clone();
+            let notification = notification.clone();
+            move || {
+                for _ in 0..20 {
+                    writer.send_notification(&notification).unwrap();
+                }
+            }
+        });
+        for _ in 0..20 {
+            writer.send_response(&response).unwrap();
+        }
+        notifier.join().unwrap();
+
+        let output = output.lock().unwrap().clone();
+        let messages: Vec<Value> = RPCMessageStream::new(Cursor::new(output))
+            .map(|message| jsonrpc_decode(&message.expect("Frames should be intact")).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 40);
+        assert_eq!(messages.iter().filter(|m| **m == response).count(), 20);
+        assert_eq!(messages.iter().filter(|m| **m == notification).count(), 20);
+    }
+}